@@ -2,6 +2,11 @@
 # required explicite API exports
 #
 from .evaluation import (
+    AltoReader,
+    DocumentStatistics,
+    EvalConfig,
+    PairResult,
+    evaluate_pair,
     Evaluator,
     find_groundtruth,
     gather_candidates,
@@ -9,7 +14,25 @@ from .evaluation import (
     Evaluator,
 )
 
+from .errors import (
+    EvalError,
+    EvalIOError,
+    EvalParseError,
+    EvalUnsupportedError,
+    EvalMetricError,
+    EvalMatchingError,
+    EvalTimeoutError,
+)
+
+from .formats import (
+    FormatReader,
+    register_reader,
+)
+
 from .metrics import (
+    EvalContext,
+    Metric,
+    MetricOutcome,
     MetricCA,
     MetricLA,
     MetricWA,
@@ -17,15 +40,41 @@ from .metrics import (
     MetricPre,
     MetricRec,
     MetricFM,
+    register_metric,
 )
 
 from .model import (
     Piece,
     PieceType,
+    Separators,
+    separators_from_spec,
     to_pieces,
+    to_alto,
+    write_alto,
+    to_page,
+    write_page,
+    validate_alto,
+    validate_page,
     OCRToken,
     OCRWord,
     OCRWordLine,
     OCRRegion,
     BoundingBox,
 )
+
+from .results import (
+    read_results,
+    write_results,
+    write_ocrd_eval,
+    merge_results,
+)
+
+from .layout import (
+    LayoutProfile,
+    load_layout_profile,
+)
+
+from .sinks import (
+    ResultSink,
+    sink_from_spec,
+)
@@ -0,0 +1,523 @@
+# -*- coding: utf-8 -*-
+"""Alignment Module
+
+Align reference and candidate words by minimal edit
+distance to spot which candidate word corresponds to
+which reference word and export this for visualization
+"""
+
+import bisect
+import html
+import os
+import string
+import unicodedata
+
+from collections import (
+    Counter,
+)
+from concurrent.futures import (
+    ProcessPoolExecutor,
+)
+from multiprocessing import (
+    cpu_count,
+)
+
+from typing import (
+    List,
+    Tuple,
+)
+
+from rapidfuzz.distance import (
+    Levenshtein,
+)
+
+from .model import (
+    PieceType,
+    _bounding_box,
+    _leaf_regions,
+    _write_xml,
+    to_page,
+)
+
+from .normalization import (
+    HYPHENS,
+    UC_NORMALIZATION,
+)
+
+# error classes of aligned words
+ALIGN_MATCH = 'match'
+ALIGN_SUBSTITUTION = 'substitution'
+ALIGN_INSERTION = 'insertion'
+ALIGN_DELETION = 'deletion'
+# substitutions which differ only by case or punctuation
+ALIGN_CASE = 'case'
+ALIGN_PUNCTUATION = 'punctuation'
+# one reference word split into several candidate words,
+# several reference words merged into one candidate word
+# or either due to hyphenation at line end, like 'Fu⸗ chs'
+ALIGN_SPLIT = 'split'
+ALIGN_MERGE = 'merge'
+ALIGN_HYPHENATION = 'hyphenation'
+# error classes in order of report
+ALIGN_ERRORS = [ALIGN_SUBSTITUTION, ALIGN_INSERTION, ALIGN_DELETION, ALIGN_SPLIT,
+                ALIGN_MERGE, ALIGN_CASE, ALIGN_PUNCTUATION, ALIGN_HYPHENATION]
+# max number of parts of split or merged words
+SPLIT_MAX_PARTS = 4
+# blocks of unmatched words with more cells (reference
+# times candidate words) keep their pairs as aligned,
+# since grouping them takes quadratic time
+SPLIT_MAX_CELLS = 10000
+# markers of edit operations in terminal view
+ALIGN_MARKERS = {ALIGN_MATCH: ' ', ALIGN_SUBSTITUTION: 'S', ALIGN_INSERTION: 'I', ALIGN_DELETION: 'D'}
+
+# formats of alignment export
+ALIGNMENT_PAGE = 'page'
+ALIGNMENT_HOCR = 'hocr'
+ALIGNMENT_FORMATS = {ALIGNMENT_PAGE: '.alignment.xml', ALIGNMENT_HOCR: '.alignment.hocr'}
+
+# sequences up to this length get aligned at once,
+# longer ones get split at anchors first
+ANCHOR_MIN_LENGTH = 200
+# anchored sequences longer than this get their segments
+# aligned in parallel, since alignment is CPU bound,
+# this uses processes rather than threads
+PARALLEL_MIN_LENGTH = 5000
+
+# chars which must be escaped in PAGE custom attributes
+_CUSTOM_ESCAPES = {c: f'\\u{ord(c):04x}' for c in '\\;{}:'}
+
+
+def _normed(word) -> str:
+    return unicodedata.normalize(UC_NORMALIZATION, word)
+
+
+def _align_opcodes(reference: List[str], candidate: List[str]) -> List[Tuple]:
+    """Align two sequences with minimal edit distance
+    by edit operations of rapidfuzz
+
+    Returns pairs of indices (reference, candidate),
+    with None marking deleted or inserted items
+    """
+
+    pairs = []
+    for (_tag, _i1, _i2, _j1, _j2) in Levenshtein.opcodes(reference, candidate):
+        if _tag == 'delete':
+            pairs += [(_i, None) for _i in range(_i1, _i2)]
+        elif _tag == 'insert':
+            pairs += [(None, _j) for _j in range(_j1, _j2)]
+        else:
+            pairs += zip(range(_i1, _i2), range(_j1, _j2))
+    return pairs
+
+
+def anchors(reference: List[str], candidate: List[str]) -> List[Tuple]:
+    """Pairs of indices of rare items, i.e. items occuring
+    exactly once in both sequences, which keep their order
+    (longest increasing subsequence like patience diff)"""
+
+    _counts_ref = Counter(reference)
+    _counts_can = Counter(candidate)
+    _positions = {_item: _j for (_j, _item) in enumerate(candidate) if _counts_can[_item] == 1}
+    _pairs = [(_i, _positions[_item]) for (_i, _item) in enumerate(reference)
+              if _counts_ref[_item] == 1 and _item in _positions]
+    # patience sorting by candidate index
+    _tails = []
+    _tail_pairs = []
+    _predecessors = {}
+    for _pair in _pairs:
+        _k = bisect.bisect_left(_tails, _pair[1])
+        _predecessors[_pair] = _tail_pairs[_k - 1] if _k > 0 else None
+        if _k == len(_tails):
+            _tails.append(_pair[1])
+            _tail_pairs.append(_pair)
+        else:
+            _tails[_k] = _pair[1]
+            _tail_pairs[_k] = _pair
+    result = []
+    _pair = _tail_pairs[-1] if _tail_pairs else None
+    while _pair is not None:
+        result.append(_pair)
+        _pair = _predecessors[_pair]
+    result.reverse()
+    return result
+
+
+def segments(reference: List[str], candidate: List[str]) -> List[Tuple]:
+    """Split sequences at anchors into independent segments
+
+    Returns ranges ((ref start, ref end), (can start, can end))
+    between anchors, anchors itself are left out
+    """
+
+    result = []
+    (_i, _j) = (0, 0)
+    for (_a, _b) in anchors(reference, candidate):
+        result.append(((_i, _a), (_j, _b)))
+        (_i, _j) = (_a + 1, _b + 1)
+    result.append(((_i, len(reference)), (_j, len(candidate))))
+    return result
+
+
+def _align_offset(job) -> List[Tuple]:
+    (_reference, _candidate, _i1, _j1) = job
+    return [(_i + _i1 if _i is not None else None, _j + _j1 if _j is not None else None)
+            for (_i, _j) in _align_opcodes(_reference, _candidate)]
+
+
+def _default_workers(reference, candidate) -> int:
+    if max(len(reference), len(candidate)) <= PARALLEL_MIN_LENGTH:
+        return 1
+    _cpus = cpu_count()
+    return _cpus - 1 if _cpus > 3 else 1
+
+
+def align(reference: List[str], candidate: List[str], workers=None) -> List[Tuple]:
+    """Align two sequences with minimal edit distance
+
+    Long sequences get anchored at rare items common to both
+    first and only the segments in between get aligned,
+    which is much faster and keeps distant repetitions of
+    frequent items from being matched across the text.
+    Segments are independent, so for huge pages they're
+    aligned by several workers (default: all but one CPU
+    beyond PARALLEL_MIN_LENGTH items, otherwise 1)
+
+    Returns pairs of indices (reference, candidate),
+    with None marking deleted or inserted items
+    """
+
+    if max(len(reference), len(candidate)) <= ANCHOR_MIN_LENGTH:
+        return _align_opcodes(reference, candidate)
+    _segments = segments(reference, candidate)
+    _jobs = [(reference[_i1:_i2], candidate[_j1:_j2], _i1, _j1)
+             for ((_i1, _i2), (_j1, _j2)) in _segments]
+    if workers is None:
+        workers = _default_workers(reference, candidate)
+    if workers > 1 and len(_jobs) > 1:
+        with ProcessPoolExecutor(max_workers=workers) as executor:
+            _aligned = list(executor.map(_align_offset, _jobs, chunksize=max(1, len(_jobs) // (4 * workers))))
+    else:
+        _aligned = [_align_offset(_job) for _job in _jobs]
+    pairs = []
+    for (_n, _segment) in enumerate(_segments):
+        pairs += _aligned[_n]
+        if _n < len(_segments) - 1:
+            pairs.append((_segment[0][1], _segment[1][1]))
+    return pairs
+
+
+def token_alignment(reference: List[str], candidate: List[str]) -> List[Tuple]:
+    """Edit operations of aligned tokens like word accuracy
+    counts them, i.e. (operation, reference token, candidate
+    token) with operation match, substitution, insertion or
+    deletion and the missing side None. Case or punctuation
+    differences are substitutions, too.
+
+    Aligned by single process, since evaluation runs
+    in pooled processes already
+    """
+
+    alignment = []
+    for (_i, _j) in align(reference, candidate, workers=1):
+        (_ref, _can) = (reference[_i] if _i is not None else None, candidate[_j] if _j is not None else None)
+        if _ref is None:
+            alignment.append((ALIGN_INSERTION, None, _can))
+        elif _can is None:
+            alignment.append((ALIGN_DELETION, _ref, None))
+        else:
+            alignment.append((ALIGN_MATCH if _ref == _can else ALIGN_SUBSTITUTION, _ref, _can))
+    return alignment
+
+
+def format_token_alignment(alignment, gap='∅') -> List[str]:
+    """Aligned tokens as marker, reference and candidate
+    columns for terminal, with gap standing in for the
+    missing side of insertions and deletions"""
+
+    _rows = [(ALIGN_MARKERS.get(_op, '?'), _ref if _ref is not None else gap, _can if _can is not None else gap)
+             for (_op, _ref, _can) in alignment]
+    _width = max((len(_ref) for (_, _ref, _) in _rows), default=0)
+    return [f'{_marker} {_ref:<{_width}}  {_can}' for (_marker, _ref, _can) in _rows]
+
+
+def classify(reference_word, candidate_word) -> str:
+    """Error class of aligned pair of words"""
+
+    if candidate_word is None:
+        return ALIGN_DELETION
+    if reference_word is None:
+        return ALIGN_INSERTION
+    _ref = _normed(reference_word)
+    _can = _normed(candidate_word)
+    if _ref == _can:
+        return ALIGN_MATCH
+    if _ref.lower() == _can.lower():
+        return ALIGN_CASE
+    _strip = str.maketrans('', '', string.punctuation)
+    if _ref.translate(_strip) == _can.translate(_strip):
+        return ALIGN_PUNCTUATION
+    return ALIGN_SUBSTITUTION
+
+
+def _rejoined(word, parts) -> str:
+    """Error class if parts make up word, either plainly
+    or with hyphens at end of parts dropped, else None
+    (all normalized already)"""
+
+    _length = sum(len(_p) for _p in parts)
+    if not len(word) <= _length < len(word) + len(parts):
+        return None
+    if ''.join(parts) == word:
+        return ALIGN_SPLIT
+    _hyphenated = [bool(_p) and _p[-1] in HYPHENS for _p in parts[:-1]]
+    if any(_hyphenated):
+        _stripped = [_p[:-1] if _h else _p for (_p, _h) in zip(parts, _hyphenated)]
+        if ''.join(_stripped + parts[-1:]) == word:
+            return ALIGN_HYPHENATION
+    return None
+
+
+def _block_groups(refs, cans, reference_words, candidate_words, normed) -> List[Tuple]:
+    """Group block of unmatched words with fewest errors,
+    where a word split into (or merged from) up to
+    SPLIT_MAX_PARTS words counts as single error"""
+
+    (_n, _m) = (len(refs), len(cans))
+    _steps = [(1, 1), (1, 0), (0, 1)] + [(1, _k) for _k in range(2, SPLIT_MAX_PARTS + 1)] \
+        + [(_k, 1) for _k in range(2, SPLIT_MAX_PARTS + 1)]
+    _cost = [[_n + _m + 1] * (_m + 1) for _ in range(_n + 1)]
+    _back = [[None] * (_m + 1) for _ in range(_n + 1)]
+    _cost[0][0] = 0
+    for _a in range(_n + 1):
+        for _b in range(_m + 1):
+            for (_da, _db) in _steps:
+                if _a + _da > _n or _b + _db > _m or _cost[_a][_b] + 1 >= _cost[_a + _da][_b + _db]:
+                    continue
+                if _db > 1:
+                    _error = _rejoined(normed[0][refs[_a]], [normed[1][_j] for _j in cans[_b:_b + _db]])
+                elif _da > 1:
+                    _error = _rejoined(normed[1][cans[_b]], [normed[0][_i] for _i in refs[_a:_a + _da]])
+                    _error = ALIGN_MERGE if _error == ALIGN_SPLIT else _error
+                else:
+                    _error = classify(reference_words[refs[_a]] if _da else None,
+                                      candidate_words[cans[_b]] if _db else None)
+                if _error:
+                    _cost[_a + _da][_b + _db] = _cost[_a][_b] + 1
+                    _back[_a + _da][_b + _db] = (_da, _db, _error)
+    # trace back
+    groups = []
+    (_a, _b) = (_n, _m)
+    while _a > 0 or _b > 0:
+        (_da, _db, _error) = _back[_a][_b]
+        (_a, _b) = (_a - _da, _b - _db)
+        _pairs = [(refs[_a] if _da else None, cans[_b] if _db else None)]
+        _pairs += [(None, _j) for _j in cans[_b + 1:_b + _db]] + [(_i, None) for _i in refs[_a + 1:_a + _da]]
+        groups.append((_error, _pairs))
+    groups.reverse()
+    return groups
+
+
+def word_groups(reference_words: List[str], candidate_words: List[str]) -> List[Tuple]:
+    """Align words, compared after unicode normalization, and
+    group unmatched words in between matches anew, so words
+    split or merged make up single error
+
+    Returns (error class, pairs of indices) for each group,
+    with None marking deleted or inserted words, blocks
+    exceeding SPLIT_MAX_CELLS keep pairs as aligned
+    """
+
+    _normed_words = ([_normed(w) for w in reference_words], [_normed(w) for w in candidate_words])
+    _pairs = align(*_normed_words)
+    groups = []
+    (_refs, _cans, _block) = ([], [], [])
+    for (_i, _j) in _pairs + [(None, None)]:
+        _match = _i is not None and _j is not None and _normed_words[0][_i] == _normed_words[1][_j]
+        if not _match and (_i is not None or _j is not None):
+            _refs += [_i] if _i is not None else []
+            _cans += [_j] if _j is not None else []
+            _block.append((_i, _j))
+            continue
+        if len(_refs) * len(_cans) > SPLIT_MAX_CELLS:
+            groups += [(classify(reference_words[_i] if _i is not None else None,
+                                 candidate_words[_j] if _j is not None else None), [(_i, _j)])
+                       for (_i, _j) in _block]
+        else:
+            groups += _block_groups(_refs, _cans, reference_words, candidate_words, _normed_words)
+        (_refs, _cans, _block) = ([], [], [])
+        if _match:
+            groups.append((ALIGN_MATCH, [(_i, _j)]))
+    return groups
+
+
+def align_words(reference_words: List[str], candidate_words: List[str]) -> List[Tuple]:
+    """Align words, compared after unicode normalization
+
+    Returns (reference word, candidate index, error class)
+    for each pair, with missing sides set to None, pairs
+    of split or merged words share error class
+    """
+
+    alignment = []
+    for (_error, _pairs) in word_groups(reference_words, candidate_words):
+        for (_i, _j) in _pairs:
+            alignment.append((reference_words[_i] if _i is not None else None, _j, _error))
+    return alignment
+
+
+def word_errors(reference_words: List[str], candidate_words: List[str]) -> Counter:
+    """Count word errors by class, where words split
+    or merged count as single error"""
+
+    return Counter(_error for (_error, _) in word_groups(reference_words, candidate_words)
+                   if _error != ALIGN_MATCH)
+
+
+def candidate_words(piece) -> List:
+    """Word pieces of page in reading order,
+    the same order as they get written"""
+
+    _words = [_w for _region in _leaf_regions(piece)
+              for _line in _region.pieces if _line.type == PieceType.LINE
+              for _w in _line.pieces if _w.type == PieceType.WORD]
+    if not _words:
+        raise RuntimeError(f"ID={piece.id}: no words to align, requires word level data!")
+    return _words
+
+
+def confidence_errors(reference_words: List[str], candidate_words: List[str], words: List[Tuple]) -> List[Tuple]:
+    """Confidence (0 - 1) of each candidate word and whether
+    it's erroneous. Candidate words are aligned with words
+    read (text, confidence) first, since they may differ
+    by normalization, so only words read aligned with
+    candidate words and with confidence count."""
+
+    _errors = {_j: _error for (_, _j, _error) in align_words(reference_words, candidate_words)
+               if _j is not None}
+    _pairs = align([_normed(w) for w in candidate_words], [_normed(_t) for (_t, _) in words])
+    _confidences = [(words[_k][1], _errors[_j] != ALIGN_MATCH) for (_j, _k) in _pairs
+                    if _j is not None and _k is not None and words[_k][1] is not None]
+    # some engines report percent
+    if _confidences and max(_c for (_c, _) in _confidences) > 1:
+        _confidences = [(_c / 100, _e) for (_c, _e) in _confidences]
+    return _confidences
+
+
+def word_annotations(reference_words: List[str], words: List) -> List[dict]:
+    """Annotate each candidate word piece with it's aligned
+    reference word, error class and reference words
+    missing in front of it (trailing ones go to last word)
+
+    Split words refer to the whole reference word each,
+    merged words to all reference words, separated by space
+    """
+
+    _groups = word_groups(reference_words, [_w.transcription for _w in words])
+    annotations = [{'reference': None, 'error': ALIGN_INSERTION, 'deleted': []} for _ in words]
+    _deleted = []
+    for (_error, _pairs) in _groups:
+        _refs = [reference_words[_i] for (_i, _) in _pairs if _i is not None]
+        _cans = [_j for (_, _j) in _pairs if _j is not None]
+        if not _cans:
+            _deleted += _refs
+            continue
+        for _j in _cans:
+            annotations[_j] = {'reference': ' '.join(_refs) if _refs else None, 'error': _error,
+                               'deleted': _deleted}
+            _deleted = []
+    if _deleted and annotations:
+        annotations[-1]['deleted_after'] = _deleted
+    return annotations
+
+
+def _escape_custom(value) -> str:
+    return ''.join(_CUSTOM_ESCAPES.get(c, c) for c in value)
+
+
+def to_alignment_page(piece, reference_words: List[str]):
+    """PAGE 2019 document of candidate with alignment as
+    custom attribute of each word, like
+    'alignment {reference:Fuchs;error:substitution;}'"""
+
+    _words = candidate_words(piece)
+    _annotations = word_annotations(reference_words, _words)
+    document = to_page(piece, step_description='word alignment')
+    _elements = document.documentElement.getElementsByTagNameNS('*', 'Word')
+    if len(_elements) != len(_words):
+        raise RuntimeError(f"ID={piece.id}: {len(_elements)} written words vs. {len(_words)} aligned")
+    for (_element, _annotation) in zip(_elements, _annotations):
+        _custom = f"error:{_annotation['error']};"
+        if _annotation['reference'] is not None:
+            _custom = f"reference:{_escape_custom(_annotation['reference'])};" + _custom
+        if _annotation['deleted']:
+            _custom += f"deletedBefore:{_escape_custom(' '.join(_annotation['deleted']))};"
+        if _annotation.get('deleted_after'):
+            _custom += f"deletedAfter:{_escape_custom(' '.join(_annotation['deleted_after']))};"
+        _element.setAttribute('custom', f'alignment {{{_custom}}}')
+    return document
+
+
+def _hocr_bbox(piece) -> str:
+    (_x1, _y1, _x2, _y2) = _bounding_box(piece.dimensions)
+    return f'bbox {_x1} {_y1} {_x2} {_y2}'
+
+
+def to_alignment_hocr(piece, reference_words: List[str], image_name=None) -> str:
+    """hOCR document of candidate with alignment as data
+    attributes of each word (data-reference, data-error,
+    data-deleted-before, data-deleted-after)"""
+
+    _words = candidate_words(piece)
+    _annotations = dict(zip([id(_w) for _w in _words], word_annotations(reference_words, _words)))
+    _image = image_name or (piece.metadata and piece.metadata.image_name) or piece.id
+    _out = ['<!DOCTYPE html>', '<html>', '<head>', '<meta charset="utf-8">',
+            f'<title>{html.escape(str(_image))}</title>',
+            '<meta name="ocr-system" content="digital-eval">',
+            '<meta name="ocr-capabilities" content="ocr_page ocr_carea ocr_line ocrx_word">',
+            '</head>', '<body>',
+            f'<div class="ocr_page" id="page_1" title="image &quot;{html.escape(str(_image))}&quot;; '
+            f'{_hocr_bbox(piece)}">']
+    for (_r, _region) in enumerate(_leaf_regions(piece), 1):
+        _out.append(f'<div class="ocr_carea" id="block_{_r}" title="{_hocr_bbox(_region)}">')
+        _lines = [_l for _l in _region.pieces if _l.type == PieceType.LINE]
+        for (_l, _line) in enumerate(_lines, 1):
+            _out.append(f'<span class="ocr_line" id="line_{_r}_{_l}" title="{_hocr_bbox(_line)}">')
+            _line_words = [_w for _w in _line.pieces if _w.type == PieceType.WORD]
+            for (_w, _word) in enumerate(_line_words, 1):
+                _annotation = _annotations[id(_word)]
+                _title = _hocr_bbox(_word)
+                if _word.confidence is not None:
+                    _title += f'; x_wconf {round(100 * _word.confidence)}'
+                _attributes = f'data-error="{_annotation["error"]}"'
+                if _annotation['reference'] is not None:
+                    _attributes += f' data-reference="{html.escape(_annotation["reference"])}"'
+                if _annotation['deleted']:
+                    _attributes += f' data-deleted-before="{html.escape(" ".join(_annotation["deleted"]))}"'
+                if _annotation.get('deleted_after'):
+                    _attributes += f' data-deleted-after="{html.escape(" ".join(_annotation["deleted_after"]))}"'
+                _out.append(f'<span class="ocrx_word" id="word_{_r}_{_l}_{_w}" title="{_title}" '
+                            f'{_attributes}>{html.escape(_word.transcription)}</span>')
+            _out.append('</span>')
+        _out.append('</div>')
+    _out += ['</div>', '</body>', '</html>']
+    return '\n'.join(_out) + '\n'
+
+
+def write_alignment(piece, reference_words: List[str], path_out, export_format=ALIGNMENT_PAGE):
+    """Write candidate page with word alignment
+    as PAGE or hOCR file"""
+
+    if export_format == ALIGNMENT_PAGE:
+        _write_xml(to_alignment_page(piece, reference_words), path_out)
+    elif export_format == ALIGNMENT_HOCR:
+        with open(path_out, mode='w', encoding='utf-8') as _handle:
+            _handle.write(to_alignment_hocr(piece, reference_words))
+    else:
+        raise ValueError(f"unknown alignment format '{export_format}', expected one of {list(ALIGNMENT_FORMATS)}")
+
+
+def alignment_path(dir_out, path_candidate, export_format) -> str:
+    """Name export after candidate file"""
+
+    _name = os.path.splitext(os.path.basename(str(path_candidate)))[0]
+    return os.path.join(dir_out, _name + ALIGNMENT_FORMATS[export_format])
@@ -0,0 +1,833 @@
+# -*- coding: utf-8 -*-
+"""Analysis Module
+
+Additional inspections of evaluated corpora, which
+help to interpret the actual metrics
+"""
+
+import hashlib
+import os
+import statistics
+import unicodedata
+
+from collections import (
+    Counter
+)
+
+from typing import (
+    List,
+    Tuple,
+)
+
+from .alignment import (
+    ALIGN_ERRORS,
+    ALIGN_HYPHENATION,
+    ALIGN_MATCH,
+    candidate_words,
+    word_annotations,
+    word_errors,
+    word_groups,
+)
+
+from .evaluation import (
+    get_bbox_data,
+)
+
+from .metrics import (
+    UC_NORMALIZATION,
+    EvalContext,
+    MetricBoW,
+    MetricScore,
+    MetricWA,
+    correlation,
+)
+
+from .model import (
+    _bounding_box,
+    to_pieces,
+)
+
+from .normalization import (
+    NORM_DEHYPHENATE,
+    NORM_LINE_BREAKS,
+    normalize_text,
+)
+
+# analysis of unicode character categories
+ANALYSIS_UNICODE = 'unicode'
+# analysis of suspicious groundtruth data
+ANALYSIS_GT_SANITY = 'gt-sanity'
+# analysis of error rates by zone of page
+ANALYSIS_POSITION = 'position'
+# analysis of errors due to line segmentation
+ANALYSIS_LINE_BREAKS = 'line-breaks'
+# analysis of character accuracies per line
+ANALYSIS_LINES = 'lines'
+# analysis of word errors by class
+ANALYSIS_WORD_ERRORS = 'word-errors'
+# analysis of word errors due to hyphenation at line end
+ANALYSIS_HYPHENATION = 'hyphenation'
+# analysis of word errors by confidence
+ANALYSIS_CONFIDENCE = 'confidence'
+# analysis of correlations between metrics
+ANALYSIS_CORRELATION = 'correlation'
+# analysis of duplicate candidate texts
+ANALYSIS_DUPLICATES = 'duplicates'
+ANALYSES = [ANALYSIS_UNICODE, ANALYSIS_GT_SANITY, ANALYSIS_POSITION, ANALYSIS_LINE_BREAKS,
+            ANALYSIS_LINES, ANALYSIS_WORD_ERRORS, ANALYSIS_HYPHENATION, ANALYSIS_CONFIDENCE,
+            ANALYSIS_CORRELATION, ANALYSIS_DUPLICATES]
+
+# label of line based metric and number
+# of worst lines to report over all pages
+LINES_METRIC = 'LCA'
+LINES_TOP_WORST = 10
+
+# label of confidence metric and upper bounds
+# of confidence ranges to report error rates for
+CONFIDENCE_METRIC = 'CWA'
+CONFIDENCE_BOUNDS = [0.5, 0.7, 0.9, 1.0]
+
+# group unicode general categories by their major class
+UC_CATEGORY_GROUPS = {
+    'L': 'letters',
+    'N': 'digits',
+    'P': 'punctuation',
+    'S': 'symbols',
+    'M': 'marks',
+    'Z': 'separators',
+    'C': 'other',
+}
+UC_CATEGORY_ORDER = ['letters', 'digits', 'punctuation', 'symbols', 'marks', 'separators', 'other']
+
+# number of deviating codepoints to report
+UC_TOP_DEVIATIONS = 10
+
+# groundtruth considered too short if it has less
+# characters than this ratio of the candidate's
+GT_MIN_LENGTH_RATIO = 0.5
+GT_SUSPICION_SHORT = 'short'
+GT_SUSPICION_NO_LETTERS = 'no letters'
+GT_SUSPICION_DUPLICATE = 'duplicate'
+GT_SUSPICION_COPY = 'identical to candidate'
+
+# candidates estimated this similar (jaccard of char
+# shingles by minhash) count as near-identical, their
+# signatures consist of bands of rows, pages sharing
+# any band get compared (locality sensitive hashing)
+DUPLICATE_SIMILARITY = 0.9
+DUPLICATE_SHINGLE = 5
+DUPLICATE_BANDS = 16
+DUPLICATE_ROWS = 4
+DUPLICATE_IDENTICAL = 'identical'
+DUPLICATE_NEAR = 'near-identical'
+# mersenne prime for universal hashing of shingles
+_MINHASH_PRIME = (1 << 61) - 1
+
+# hints at causes of pages removed as outliers
+HINT_EMPTY_CANDIDATE = 'empty candidate'
+HINT_SHORT_GROUNDTRUTH = 'very short groundtruth'
+HINT_READING_ORDER = 'suspected reading order issue'
+HINT_PARSE_WARNINGS = 'parse warnings'
+# groundtruth with less chars is very short
+HINT_SHORT_GROUNDTRUTH_CHARS = 50
+# bag of words better than word accuracy by this
+# suggests words recognized but read out of order
+HINT_READING_ORDER_GAP = 20.0
+
+# page zones: rows by thirds of page height, columns by
+# halves of page width except outer stripes as margins
+POSITION_ROWS = ['top', 'middle', 'bottom']
+POSITION_COLUMNS = ['margin-left', 'left', 'right', 'margin-right']
+POSITION_MARGIN_RATIO = 0.1
+# shades of heatmap cells by error rate (percent)
+POSITION_SHADES = [(5.0, ' '), (15.0, '░'), (30.0, '▒'), (50.0, '▓'), (100.0, '█')]
+
+
+def parse_analyses(label) -> List[str]:
+    """Parse comma-separated analysis names"""
+
+    _analyses = [a.strip() for a in str(label).split(',') if a.strip()]
+    _unknown = [a for a in _analyses if a not in ANALYSES]
+    if _unknown:
+        raise ValueError(f"unknown analysis {_unknown}, expected one of {ANALYSES}")
+    return _analyses
+
+
+def unicode_category_group(char) -> str:
+    """Map character to group of it's general category
+    Whitespace like linebreaks count as separators, too."""
+
+    if char.isspace():
+        return 'separators'
+    return UC_CATEGORY_GROUPS.get(unicodedata.category(char)[0], 'other')
+
+
+class UnicodeStatistics:
+    """Count categories and codepoints of texts"""
+
+    def __init__(self):
+        self.n_chars = 0
+        self.categories = Counter()
+        self.codepoints = Counter()
+
+    def add(self, text):
+        """Add text, normalized the same way as metrics do"""
+
+        if not text:
+            return
+        if isinstance(text, list):
+            text = ' '.join(text)
+        _normed = unicodedata.normalize(UC_NORMALIZATION, text)
+        self.n_chars += len(_normed)
+        self.codepoints.update(_normed)
+        self.categories.update(unicode_category_group(c) for c in _normed)
+
+    def ratio(self, category) -> float:
+        """Percentage of category among all characters"""
+
+        if not self.n_chars:
+            return 0.0
+        return 100 * self.categories[category] / self.n_chars
+
+    def frequency(self, char) -> float:
+        if not self.n_chars:
+            return 0.0
+        return self.codepoints[char] / self.n_chars
+
+
+def unicode_statistics(entries):
+    """Gather statistics for reference and candidate
+    texts of all evaluated entries"""
+
+    stats_reference = UnicodeStatistics()
+    stats_candidate = UnicodeStatistics()
+    for _entry in entries:
+        stats_reference.add(_entry.txt_reference)
+        stats_candidate.add(_entry.txt_candidate)
+    return (stats_reference, stats_candidate)
+
+
+def deviating_codepoints(stats_reference, stats_candidate, top=UC_TOP_DEVIATIONS):
+    """Codepoints which frequencies differ most between
+    reference and candidate corpus, i.e. when groundtruth
+    contains 'ſ' which OCR always reads as 's'
+
+    Skip regular letters and spaces that occour in both
+    corpora with just different frequencies.
+    """
+
+    _all = set(stats_reference.codepoints) | set(stats_candidate.codepoints)
+    _deviations = []
+    for _char in _all:
+        _n_ref = stats_reference.codepoints[_char]
+        _n_can = stats_candidate.codepoints[_char]
+        if _n_ref and _n_can and (_char.isspace() or _is_ascii_alnum(_char)):
+            continue
+        _delta = abs(stats_reference.frequency(_char) - stats_candidate.frequency(_char))
+        _deviations.append((_delta, _char, _n_ref, _n_can))
+    _deviations.sort(key=lambda d: (-d[0], d[1]))
+    return [(c, n_ref, n_can) for (_, c, n_ref, n_can) in _deviations[:top]]
+
+
+def _is_ascii_alnum(char) -> bool:
+    return ord(char) < 128 and char.isalnum()
+
+
+def _describe_codepoint(char) -> str:
+    _name = unicodedata.name(char, 'UNKNOWN')
+    _visible = char if unicodedata.category(char)[0] not in 'CZM' else ' '
+    return f"U+{ord(char):04X} '{_visible}' {_name}"
+
+
+def report_unicode_statistics(entries, top=UC_TOP_DEVIATIONS) -> List[str]:
+    """Render unicode statistics as report lines"""
+
+    (stats_ref, stats_can) = unicode_statistics(entries)
+    lines = [f'Unicode categories\treference ({stats_ref.n_chars})\tcandidate ({stats_can.n_chars})']
+    for _category in UC_CATEGORY_ORDER:
+        lines.append(f'  {_category:<12}\t{stats_ref.ratio(_category):6.2f}%\t{stats_can.ratio(_category):6.2f}%')
+    _deviations = deviating_codepoints(stats_ref, stats_can, top)
+    if _deviations:
+        lines.append('Top deviating codepoints\treference\tcandidate')
+        for (_char, _n_ref, _n_can) in _deviations:
+            lines.append(f'  {_describe_codepoint(_char)}\t{_n_ref}\t{_n_can}')
+    return lines
+
+
+def _as_text(text) -> str:
+    if not text:
+        return ''
+    if isinstance(text, list):
+        text = ' '.join(text)
+    return ' '.join(unicodedata.normalize(UC_NORMALIZATION, text).split())
+
+
+def suspicious_groundtruth(entries, min_length_ratio=GT_MIN_LENGTH_RATIO):
+    """Inspect groundtruth texts for common data problems:
+    * groundtruth suspiciously short compared to candidate
+    * groundtruth without any letters
+    * groundtruth with same text as other groundtruth file
+    * groundtruth with same text as candidate (propably copied)
+
+    Texts are compared after whitespace was collapsed.
+    Returns list of entries with reasons, each
+    """
+
+    _by_hash = {}
+    for _entry in entries:
+        _text = _as_text(_entry.txt_reference)
+        if _text:
+            _hash = hashlib.sha1(_text.encode('utf-8')).hexdigest()
+            _by_hash.setdefault(_hash, []).append(_entry)
+
+    suspicious = []
+    for _entry in entries:
+        _reasons = []
+        _ref = _as_text(_entry.txt_reference)
+        _can = _as_text(_entry.txt_candidate)
+        if len(_ref) < min_length_ratio * len(_can):
+            _reasons.append(f'{GT_SUSPICION_SHORT} ({len(_ref)} vs. {len(_can)} chars)')
+        if not any(c.isalpha() for c in _ref):
+            _reasons.append(GT_SUSPICION_NO_LETTERS)
+        if _ref:
+            _hash = hashlib.sha1(_ref.encode('utf-8')).hexdigest()
+            _others = [os.path.basename(str(e.path_g)) for e in _by_hash[_hash] if e is not _entry]
+            if _others:
+                _reasons.append(f'{GT_SUSPICION_DUPLICATE} of {", ".join(_others)}')
+        if _ref and _ref == _can:
+            _reasons.append(GT_SUSPICION_COPY)
+        if _reasons:
+            suspicious.append((_entry, _reasons))
+    return suspicious
+
+
+def report_suspicious_groundtruth(entries, min_length_ratio=GT_MIN_LENGTH_RATIO) -> List[str]:
+    """Render suspicious groundtruth as report lines"""
+
+    _suspicious = suspicious_groundtruth(entries, min_length_ratio)
+    lines = [f'Suspicious groundtruth\t{len(_suspicious)} of {len(entries)}']
+    for (_entry, _reasons) in _suspicious:
+        lines.append(f'  {_entry.path_g}\t{"; ".join(_reasons)}')
+    return lines
+
+
+def minhash_signature(text, n_hashes=DUPLICATE_BANDS * DUPLICATE_ROWS, shingle=DUPLICATE_SHINGLE) -> List[int]:
+    """MinHash signature of text's char shingles, where
+    the share of equal positions of two signatures
+    estimates the jaccard similarity of their shingles
+
+    Hash functions are seeded deterministically, so
+    signatures of different runs compare, too
+    """
+
+    _text = _as_text(text)
+    _shingles = {_text[_i:_i + shingle] for _i in range(max(len(_text) - shingle + 1, 1))}
+    _hashes = [int.from_bytes(hashlib.sha1(_s.encode('utf-8')).digest()[:8], 'big') for _s in _shingles]
+    signature = []
+    for _i in range(n_hashes):
+        _seed = hashlib.sha1(f'minhash{_i}'.encode('utf-8')).digest()
+        (_a, _b) = (int.from_bytes(_seed[:8], 'big') | 1, int.from_bytes(_seed[8:16], 'big'))
+        signature.append(min((_a * _h + _b) % _MINHASH_PRIME for _h in _hashes))
+    return signature
+
+
+def _similarity(signature_a, signature_b) -> float:
+    return sum(1 for (_a, _b) in zip(signature_a, signature_b) if _a == _b) / len(signature_a)
+
+
+def duplicate_candidates(entries, min_similarity=DUPLICATE_SIMILARITY) -> List[Tuple[List, str, float]]:
+    """Clusters of candidates with identical or near-identical
+    text, which suggests duplicate scans or copy errors
+
+    Identical texts (after whitespace was collapsed) are
+    found by hash, near-identical ones by MinHash signatures
+    sharing any band and estimated at least min_similarity
+    similar. Clusters are transitive. Returns list of
+    entries of each cluster, whether all of them are
+    identical and least estimated similarity of a pair
+    """
+
+    _texts = {}
+    for _entry in entries:
+        _text = _as_text(_entry.txt_candidate)
+        if _text:
+            _texts.setdefault(hashlib.sha1(_text.encode('utf-8')).hexdigest(), []).append(_entry)
+
+    # near-identical texts by signatures of distinct texts only
+    _keys = list(_texts)
+    _signatures = [minhash_signature(_texts[_k][0].txt_candidate) for _k in _keys]
+    _buckets = {}
+    for (_i, _signature) in enumerate(_signatures):
+        for _band in range(DUPLICATE_BANDS):
+            _rows = tuple(_signature[_band * DUPLICATE_ROWS:(_band + 1) * DUPLICATE_ROWS])
+            _buckets.setdefault((_band, _rows), set()).add(_i)
+    _parents = list(range(len(_keys)))
+
+    def _root(i):
+        while _parents[i] != i:
+            _parents[i] = _parents[_parents[i]]
+            i = _parents[i]
+        return i
+
+    _similarities = {}
+    for _members in _buckets.values():
+        _members = sorted(_members)
+        for (_n, _i) in enumerate(_members):
+            for _j in _members[_n + 1:]:
+                if (_i, _j) in _similarities:
+                    continue
+                _similarities[(_i, _j)] = _similarity(_signatures[_i], _signatures[_j])
+                if _similarities[(_i, _j)] >= min_similarity:
+                    _parents[_root(_j)] = _root(_i)
+
+    (_clusters, _least) = ({}, {})
+    for _i in range(len(_keys)):
+        _clusters.setdefault(_root(_i), []).append(_i)
+    for ((_i, _), _value) in _similarities.items():
+        if _value >= min_similarity:
+            _least[_root(_i)] = min(_least.get(_root(_i), 1.0), _value)
+    clusters = []
+    for (_root_i, _members) in _clusters.items():
+        _entries = [_e for _i in _members for _e in _texts[_keys[_i]]]
+        if len(_entries) > 1:
+            _kind = DUPLICATE_IDENTICAL if len(_members) == 1 else DUPLICATE_NEAR
+            clusters.append((_entries, _kind, _least.get(_root_i, 1.0)))
+    return clusters
+
+
+def report_duplicate_candidates(entries, min_similarity=DUPLICATE_SIMILARITY) -> List[str]:
+    """Render clusters of duplicate candidates as report lines"""
+
+    _clusters = duplicate_candidates(entries, min_similarity)
+    _n_pages = sum(len(_c[0]) for _c in _clusters)
+    lines = [f'Duplicate candidates\t{len(_clusters)} clusters, {_n_pages} of {len(entries)} pages']
+    for (_entries, _kind, _least) in _clusters:
+        _label = _kind if _kind == DUPLICATE_IDENTICAL else f'{_kind} (>= {_least:.2f})'
+        lines.append(f'  {_label}\t{", ".join(str(_e.path_c) for _e in _entries)}')
+    return lines
+
+
+def page_zone(point, page_box) -> Tuple[str, str]:
+    """Row and column of page zone containing point,
+    page_box as (x0, y0, x1, y1)"""
+
+    (_x0, _y0, _x1, _y1) = page_box
+    _rel_x = (point[0] - _x0) / max(_x1 - _x0, 1)
+    _rel_y = (point[1] - _y0) / max(_y1 - _y0, 1)
+    _row = POSITION_ROWS[min(max(int(_rel_y * len(POSITION_ROWS)), 0), len(POSITION_ROWS) - 1)]
+    if _rel_x < POSITION_MARGIN_RATIO:
+        _column = POSITION_COLUMNS[0]
+    elif _rel_x >= 1 - POSITION_MARGIN_RATIO:
+        _column = POSITION_COLUMNS[-1]
+    else:
+        _column = POSITION_COLUMNS[1] if _rel_x < 0.5 else POSITION_COLUMNS[2]
+    return (_row, _column)
+
+
+class PositionStatistics:
+    """Count aligned words and their errors by page zone
+
+    Reference words missing in candidate count for
+    zone of following candidate word, since they
+    have no geometry of their own
+    """
+
+    def __init__(self):
+        self.n_pages = 0
+        self.n_words = Counter()
+        self.n_errors = Counter()
+        self.skipped = []
+
+    def add(self, zone, n_words=1, n_errors=0):
+        self.n_words[zone] += n_words
+        self.n_errors[zone] += n_errors
+
+    def error_rate(self, zone) -> float:
+        """Percentage of erroneous words in zone,
+        None if zone contains no words at all"""
+
+        if not self.n_words[zone]:
+            return None
+        return 100 * self.n_errors[zone] / self.n_words[zone]
+
+
+def _centroid(dimensions) -> Tuple[float, float]:
+    (_x0, _y0, _x1, _y1) = _bounding_box(dimensions)
+    return ((_x0 + _x1) / 2, (_y0 + _y1) / 2)
+
+
+def position_statistics(entries) -> PositionStatistics:
+    """Align candidate words of each entry with reference
+    words and gather errors by zone of candidate page
+
+    Words outside of groundtruth frame are ignored, entries
+    without word geometry are skipped
+    """
+
+    stats = PositionStatistics()
+    for _entry in entries:
+        try:
+            _page = to_pieces(str(_entry.path_c))
+            _words = candidate_words(_page)
+            _frame = get_bbox_data(str(_entry.path_g))
+        except Exception as exc:
+            stats.skipped.append((_entry, str(exc)))
+            continue
+        if _frame:
+            ((_fx0, _fy0), (_fx1, _fy1)) = _frame
+            _words = [_w for _w in _words
+                      if _fx0 <= _centroid(_w.dimensions)[0] <= _fx1
+                      and _fy0 <= _centroid(_w.dimensions)[1] <= _fy1]
+        if not _words:
+            stats.skipped.append((_entry, 'no candidate words within groundtruth frame'))
+            continue
+        _reference = _entry.txt_reference
+        if isinstance(_reference, list):
+            _reference = ' '.join(_reference)
+        _page_box = _bounding_box(_page.dimensions)
+        for (_word, _annotation) in zip(_words, word_annotations(_reference.split(), _words)):
+            _n_deleted = len(_annotation['deleted']) + len(_annotation.get('deleted_after', []))
+            _n_errors = _n_deleted + (0 if _annotation['error'] == ALIGN_MATCH else 1)
+            stats.add(page_zone(_centroid(_word.dimensions), _page_box), 1 + _n_deleted, _n_errors)
+        stats.n_pages += 1
+    return stats
+
+
+def _shade(error_rate) -> str:
+    for (_threshold, _shade) in POSITION_SHADES:
+        if error_rate < _threshold:
+            return _shade
+    return POSITION_SHADES[-1][1]
+
+
+def report_position_statistics(entries) -> List[str]:
+    """Render error rates by page zone as heatmap table,
+    each cell with error rate and number of words"""
+
+    stats = position_statistics(entries)
+    _header = '\t'.join(f'{_c:>16}' for _c in POSITION_COLUMNS)
+    lines = [f'Word error rates by page zone ({stats.n_pages} pages)\t{_header}']
+    for _row in POSITION_ROWS:
+        _cells = []
+        for _column in POSITION_COLUMNS:
+            _rate = stats.error_rate((_row, _column))
+            if _rate is None:
+                _cells.append(f'{"-":>16}')
+            else:
+                _n_words = stats.n_words[(_row, _column)]
+                _cells.append(f'{_shade(_rate)} {_rate:6.2f}%({_n_words})'.rjust(16))
+        lines.append(f'  {_row:<12}\t' + '\t'.join(_cells))
+    for (_entry, _reason) in stats.skipped:
+        lines.append(f'  skipped {_entry.path_c}: {_reason}')
+    return lines
+
+
+def _joined(text) -> str:
+    """Text as is, keeping line breaks"""
+
+    return ' '.join(text) if isinstance(text, list) else (text or '')
+
+
+def _scores_normalized(entries, normalizations) -> List[Tuple[str, float, float]]:
+    """Mean of each metric as evaluated (before) and
+    recalculated with both texts normalized (after).
+    Composite scores are skipped as well as metrics
+    requiring more than both texts, like lines, words
+    read or corpus data, since that's not kept"""
+
+    (_before, _after) = ({}, {})
+    for _entry in entries:
+        (_reference, _candidate) = (_joined(_entry.txt_reference), _joined(_entry.txt_candidate))
+        _ctx = EvalContext(normalize_text(_reference, normalizations),
+                           normalize_text(_candidate, normalizations))
+        for _metric in _entry.metrics:
+            if isinstance(_metric, MetricScore) or _metric.value is None:
+                continue
+            if _metric.requires_context or _metric.corpus_based:
+                continue
+            _before.setdefault(_metric.label, []).append(_metric.value)
+            _after.setdefault(_metric.label, []).append(_metric.calculate(_ctx).value)
+    return [(_label, sum(_values) / len(_values), sum(_after[_label]) / len(_after[_label]))
+            for (_label, _values) in _before.items()]
+
+
+def line_break_scores(entries) -> Tuple[int, List[Tuple[str, float, float]]]:
+    """Mean of each metric as evaluated (before) and
+    recalculated with line breaks collapsed into spaces
+    (after), so their difference is due to line
+    segmentation only. Composite scores are skipped.
+
+    Returns number of entries with line breaks and
+    label, before and after per metric
+    """
+
+    _n_breaks = sum(1 for _e in entries
+                    if '\n' in _joined(_e.txt_reference) or '\n' in _joined(_e.txt_candidate))
+    return (_n_breaks, _scores_normalized(entries, [NORM_LINE_BREAKS]))
+
+
+def report_line_break_scores(entries) -> List[str]:
+    """Render metric means before and after
+    collapsing line breaks with their difference"""
+
+    (_n_breaks, _scores) = line_break_scores(entries)
+    lines = [f'Line segmentation ({_n_breaks} of {len(entries)} pages with line breaks)']
+    if not _n_breaks:
+        lines.append('  texts contain no line breaks, evaluate with extra \'keep_line_breaks\'')
+    for (_label, _before, _after) in _scores:
+        lines.append(f'  {_label:<8}\tbefore {_before:6.2f}\tafter {_after:6.2f}\t({_after - _before:+.2f})')
+    return lines
+
+
+def line_statistics(entries, top=LINES_TOP_WORST) -> Tuple[List[float], List[Tuple]]:
+    """Character accuracies of all reference lines and worst
+    lines over all entries (accuracy, entry, reference line,
+    candidate line), from outcomes of line metric"""
+
+    (_accuracies, _worst) = ([], [])
+    for _entry in entries:
+        for _metric in _entry.metrics:
+            if _metric.label == LINES_METRIC and 'accuracies' in _metric.diagnostics:
+                _accuracies += _metric.diagnostics['accuracies']
+                _worst += [(_a, _entry, _r, _c) for (_a, _r, _c) in _metric.diagnostics['worst']]
+    _worst.sort(key=lambda w: w[0])
+    return (_accuracies, _worst[:top])
+
+
+def report_line_statistics(entries, top=LINES_TOP_WORST) -> List[str]:
+    """Render mean and median of line accuracies
+    followed by worst lines"""
+
+    (_accuracies, _worst) = line_statistics(entries, top)
+    if not _accuracies:
+        return [f'Line accuracies: no lines, requires metric \'{LINES_METRIC}\'']
+    lines = [f'Line accuracies ({len(_accuracies)} lines): mean {statistics.mean(_accuracies):.2f}, '
+             f'median {statistics.median(_accuracies):.2f}']
+    for (_accuracy, _entry, _reference, _candidate) in _worst:
+        lines.append(f'  {_accuracy:6.2f}\t{os.path.basename(str(_entry.path_c))}\t'
+                     f'"{_reference}" vs. "{_candidate}"')
+    return lines
+
+
+def word_error_statistics(entries) -> Tuple[int, Counter]:
+    """Number of reference words and word errors by
+    class over all entries, from words aligned anew"""
+
+    (_n_words, _errors) = (0, Counter())
+    for _entry in entries:
+        _reference = _entry.txt_reference
+        if isinstance(_reference, list):
+            _reference = ' '.join(_reference)
+        _reference_words = (_reference or '').split()
+        _n_words += len(_reference_words)
+        _errors += word_errors(_reference_words, (_entry.txt_candidate or '').split())
+    return (_n_words, _errors)
+
+
+def report_word_error_statistics(entries) -> List[str]:
+    """Render counts of word errors by class with
+    their share of all errors"""
+
+    (_n_words, _errors) = word_error_statistics(entries)
+    _n_errors = sum(_errors.values())
+    lines = [f'Word errors ({_n_words} reference words, {_n_errors} errors)']
+    for _error in ALIGN_ERRORS:
+        _share = 100 * _errors[_error] / _n_errors if _n_errors else 0.0
+        lines.append(f'  {_error:<12}\t{_errors[_error]:6d}\t{_share:6.2f}%')
+    return lines
+
+
+def confidence_statistics(entries) -> Tuple[List[Tuple], float]:
+    """Word errors by confidence range over all entries
+    (upper bound, number of words, error rate) and overall
+    correlation of confidence and errors, which is clearly
+    negative if low confidence actually indicates errors"""
+
+    _confidences = []
+    for _entry in entries:
+        for _metric in _entry.metrics:
+            if _metric.label == CONFIDENCE_METRIC and 'confidences' in _metric.diagnostics:
+                _confidences += _metric.diagnostics['confidences']
+    _ranges = []
+    _lower = -1
+    for _bound in CONFIDENCE_BOUNDS:
+        _errors = [_e for (_c, _e) in _confidences if _lower < _c <= _bound]
+        _ranges.append((_bound, len(_errors), sum(_errors) / len(_errors) if _errors else None))
+        _lower = _bound
+    _correlation = correlation([_c for (_c, _) in _confidences], [float(_e) for (_, _e) in _confidences])
+    return (_ranges, _correlation)
+
+
+def report_confidence_statistics(entries) -> List[str]:
+    """Render error rates by confidence range
+    and correlation of confidences and errors"""
+
+    (_ranges, _correlation) = confidence_statistics(entries)
+    _n_words = sum(_n for (_, _n, _) in _ranges)
+    if not _n_words:
+        return [f'Word confidences: no words, requires metric \'{CONFIDENCE_METRIC}\'']
+    _coefficient = f'{_correlation:+.2f}' if _correlation is not None else 'n.a.'
+    lines = [f'Word confidences ({_n_words} words): correlation with errors {_coefficient}']
+    _lower = 0.0
+    for (_bound, _n, _rate) in _ranges:
+        _error_rate = f'{100 * _rate:6.2f}%' if _rate is not None else '   n.a.'
+        lines.append(f'  {_lower:.1f} - {_bound:.1f}\t{_n:6d} words\terrors {_error_rate}')
+        _lower = _bound
+    return lines
+
+
+def metric_correlations(entries) -> List[Tuple[str, str, int, float]]:
+    """Correlation (Pearson) of each pair of metrics over
+    all entries having values of both, strongest first,
+    regardless of sign, since error rates decrease where
+    accuracies increase. Composite scores are skipped.
+
+    Returns both labels, number of entries and
+    coefficient (None, if either doesn't vary) per pair
+    """
+
+    (_labels, _values) = ([], [])
+    for _entry in entries:
+        _by_label = {}
+        for _metric in _entry.metrics:
+            if isinstance(_metric, MetricScore) or _metric.value is None:
+                continue
+            _by_label[_metric.label] = _metric.value
+            if _metric.label not in _labels:
+                _labels.append(_metric.label)
+        _values.append(_by_label)
+    _pairs = []
+    for (_i, _first) in enumerate(_labels):
+        for _second in _labels[_i + 1:]:
+            _both = [(_v[_first], _v[_second]) for _v in _values if _first in _v and _second in _v]
+            _coefficient = correlation([_x for (_x, _) in _both], [_y for (_, _y) in _both])
+            _pairs.append((_first, _second, len(_both), _coefficient))
+    return sorted(_pairs, key=lambda _p: -abs(_p[3]) if _p[3] is not None else 1)
+
+
+def report_metric_correlations(entries) -> List[str]:
+    """Render correlation of each pair of metrics, where
+    strong correlation means one may stand in for the other"""
+
+    _pairs = metric_correlations(entries)
+    if not _pairs:
+        return ['Metric correlations: requires at least 2 metrics']
+    lines = [f'Metric correlations ({len(entries)} pages)']
+    for (_first, _second, _n, _coefficient) in _pairs:
+        _label = f'{_first} ~ {_second}'
+        _value = f'{_coefficient:+.2f}' if _coefficient is not None else ' n.a.'
+        lines.append(f'  {_label:<16}	{_value}	({_n} pages)')
+    return lines
+
+
+def _line_end_words(text) -> Tuple[List[str], set]:
+    """Words of text and indices of those ending a line"""
+
+    (_words, _ends) = ([], set())
+    for _line in text.split('\n'):
+        _words += _line.split()
+        if _words and _line.split():
+            _ends.add(len(_words) - 1)
+    return (_words, _ends)
+
+
+def _is_line_end_hyphenation(pairs, line_ends) -> bool:
+    """Whether all parts of word broken by hyphenation, but
+    the last, end a line (candidate or reference side)"""
+
+    (_refs, _cans) = ([_i for (_i, _) in pairs if _i is not None], [_j for (_, _j) in pairs if _j is not None])
+    (_parts, _ends) = (_cans, line_ends[1]) if len(_cans) > 1 else (_refs, line_ends[0])
+    return all(_p in _ends for _p in _parts[:-1])
+
+
+def hyphenation_statistics(entries) -> Tuple[int, int, int, List[Tuple[str, float, float]]]:
+    """Word errors due to words broken by hyphen at line end
+    in either candidate or reference, but not in the other,
+    and mean of each metric as evaluated (before) and
+    recalculated with both texts dehyphenated (after)
+
+    Returns number of entries with line breaks, number of
+    word errors, those due to hyphenation at line end and
+    label, before and after per metric
+    """
+
+    (_n_breaks, _n_errors, _n_hyphenation) = (0, 0, 0)
+    for _entry in entries:
+        (_reference, _candidate) = (_joined(_entry.txt_reference), _joined(_entry.txt_candidate))
+        if '\n' in _reference or '\n' in _candidate:
+            _n_breaks += 1
+        ((_ref_words, _ref_ends), (_can_words, _can_ends)) = (_line_end_words(_reference),
+                                                              _line_end_words(_candidate))
+        for (_error, _pairs) in word_groups(_ref_words, _can_words):
+            if _error == ALIGN_MATCH:
+                continue
+            _n_errors += 1
+            if _error == ALIGN_HYPHENATION and _is_line_end_hyphenation(_pairs, (_ref_ends, _can_ends)):
+                _n_hyphenation += 1
+    return (_n_breaks, _n_errors, _n_hyphenation, _scores_normalized(entries, [NORM_DEHYPHENATE]))
+
+
+def report_hyphenation_statistics(entries) -> List[str]:
+    """Render word errors due to hyphenation and metric
+    means before and after dehyphenation"""
+
+    (_n_breaks, _n_errors, _n_hyphenation, _scores) = hyphenation_statistics(entries)
+    _share = 100 * _n_hyphenation / _n_errors if _n_errors else 0.0
+    lines = [f'Hyphenation ({_n_breaks} of {len(entries)} pages with line breaks): {_n_hyphenation} of '
+             f'{_n_errors} word errors ({_share:.2f}%) due to words broken at line end']
+    if not _n_breaks:
+        lines.append('  texts contain no line breaks, evaluate with extra \'keep_line_breaks\'')
+    for (_label, _before, _after) in _scores:
+        lines.append(f'  {_label:<8}\tbefore {_before:6.2f}\tdehyphenated {_after:6.2f}\t({_after - _before:+.2f})')
+    return lines
+
+
+def outlier_hints(entry) -> List[str]:
+    """Hints at causes of entry's poor results, derived
+    from it's texts, if kept, and warnings evaluating
+    and parsing it"""
+
+    _hints = []
+    if entry.txt_reference is not None and entry.txt_candidate is not None:
+        (_ref, _can) = (_as_text(entry.txt_reference), _as_text(entry.txt_candidate))
+        if not _can:
+            _hints.append(HINT_EMPTY_CANDIDATE)
+        if len(_ref) < HINT_SHORT_GROUNDTRUTH_CHARS or len(_ref) < GT_MIN_LENGTH_RATIO * len(_can):
+            _hints.append(f'{HINT_SHORT_GROUNDTRUTH} ({len(_ref)} vs. {len(_can)} chars)')
+        if _ref and _can:
+            _ctx = EvalContext(_ref, _can)
+            (_wa, _bow) = (MetricWA().calculate(_ctx).value, MetricBoW().calculate(_ctx).value)
+            if _bow - _wa >= HINT_READING_ORDER_GAP:
+                _hints.append(f'{HINT_READING_ORDER} (bag of words {_bow:.2f} vs. words {_wa:.2f})')
+    if entry.parse_warnings:
+        _hints.append(f'{HINT_PARSE_WARNINGS} ({len(entry.parse_warnings)})')
+    return _hints + list(entry.warnings)
+
+
+def outliers(results, entries) -> List[Tuple]:
+    """Entries removed as outliers from any aggregate,
+    in order of entries, with labels of metrics they're
+    outlying by and hints at causes"""
+
+    _labels = {}
+    for _result in results:
+        for _path in _result.outliers:
+            _label = _result.eval_key.split('@')[0]
+            _labels.setdefault(_path, [])
+            if _label not in _labels[_path]:
+                _labels[_path].append(_label)
+    return [(_entry, _labels[_entry.path_c], outlier_hints(_entry))
+            for _entry in entries if _entry.path_c in _labels]
+
+
+def report_outliers(results, entries) -> List[str]:
+    """Render outliers with hints, nothing if none"""
+
+    _outliers = outliers(results, entries)
+    if not _outliers:
+        return []
+    lines = [f'Outliers ({len(_outliers)} of {len(entries)} pages)']
+    for (_entry, _labels, _hints) in _outliers:
+        _hints = '; '.join(_hints) if _hints else 'no hints'
+        lines.append(f'  {_entry.path_c}\t{", ".join(_labels)}\t{_hints}')
+    return lines
@@ -2,9 +2,18 @@
 """OCR QA Evaluation CLI"""
 
 import argparse
+import importlib
+import os
+import sqlite3
 import sys
+import time
 
 from datetime import date
+from pathlib import Path
+from typing import (
+    List,
+    Tuple,
+)
 
 from digital_eval import (
     find_groundtruth,
@@ -13,18 +22,521 @@ from digital_eval import (
     Evaluator,
 )
 
+from digital_eval.i18n import (
+    REPORT_LANG_DEFAULT,
+    REPORT_LANGS,
+    label,
+)
+
+from digital_eval.config import (
+    eras_from_config,
+    read_config,
+    score_from_config,
+)
+
+from digital_eval.compat import (
+    COMPAT_DINGLEHOPPER,
+    COMPAT_METRICS,
+    ERROR_RATES,
+    compat_metrics,
+)
+
+from digital_eval.evaluation import (
+    AVERAGE_BOTH,
+    AVERAGE_MACRO,
+    AVERAGE_MICRO,
+    AVERAGES,
+    DIAGNOSTIC_ALIGNMENT,
+    MISSING_CONTENT_MODES,
+    MISSING_CONTENT_SKIP,
+    NOT_SET,
+    AltoReader,
+    EvalConfig,
+    SELF_CHECK_SKIP,
+    evaluate_pair,
+    filter_shard,
+    parse_shard,
+    parse_timeout,
+    parse_warning_counts,
+    resource_usage,
+    self_check,
+)
+
+from digital_eval.corruption import (
+    CORRUPT_RATE,
+    CORRUPT_SEED,
+    CORRUPTIONS,
+    CORRUPT_SEPARATOR,
+    corrupt_all,
+    parse_corruptions,
+    read_confusions,
+)
+
+from digital_eval.errors import (
+    EvalMatchingError,
+)
+
+from digital_eval.estimation import (
+    LT_LANGUAGE,
+)
+
+from digital_eval.formats import (
+    FORMAT_AUTO,
+    reader_names,
+    register_reader,
+)
+
+from digital_eval.layout import (
+    LAYOUT_IOU_THRESHOLD,
+    ORDER_AUTHORITIES,
+    ORDER_CANDIDATE,
+    ORDER_EACH,
+    ORDER_REFERENCE,
+    TABLE_CONTENT_MODES,
+    region_texts,
+)
+
+from digital_eval.model import (
+    SEPARATOR_NAMES,
+    separators_from_spec,
+    to_pieces,
+)
+
+from digital_eval.normalization import (
+    NORMALIZATIONS,
+    parse_normalizations,
+    read_abbreviations,
+    read_equivalences,
+)
+
+from digital_eval.metrics import (
+    BLEU_ORDER,
+    CASE_IGNORE,
+    CASE_MODES,
+    CHRF_BETA,
+    CHRF_ORDER,
+    ERROR_RATE_METRICS,
+    GAPS_MODES,
+    LENGTH_MODES,
+    MARKS_IGNORE,
+    MARKS_MODES,
+    METRICS,
+    METRICS_BAG_BASED,
+    METRICS_CHAR_BASED,
+    METRICS_DEFAULT,
+    METRICS_EDIT_DISTANCE,
+    METRICS_IR,
+    METRICS_LAYOUT,
+    METRICS_STOPWORDS,
+    METRICS_UNIT_RANGE,
+    REPORT_ERROR_RATE,
+    SIMILARITY_MODES,
+    STOPWORDS_ISO,
+    TOKEN_FILTERS,
+    TOKENIZE_MODES,
+    UNITS_CODEPOINTS,
+    UNITS_MODES,
+    WEIGHTING_MODES,
+    SCORE_LABEL_DEFAULT,
+    metric_from_spec,
+    parse_languages,
+    score_from_spec,
+)
+
+from digital_eval.alignment import (
+    ALIGNMENT_FORMATS,
+    ALIGNMENT_PAGE,
+    alignment_path,
+    format_token_alignment,
+    write_alignment,
+)
+
+from digital_eval.analysis import (
+    ANALYSES,
+    ANALYSIS_CONFIDENCE,
+    ANALYSIS_CORRELATION,
+    ANALYSIS_DUPLICATES,
+    ANALYSIS_GT_SANITY,
+    ANALYSIS_HYPHENATION,
+    ANALYSIS_LINE_BREAKS,
+    ANALYSIS_LINES,
+    ANALYSIS_POSITION,
+    ANALYSIS_UNICODE,
+    ANALYSIS_WORD_ERRORS,
+    CONFIDENCE_METRIC,
+    LINES_METRIC,
+    outliers,
+    parse_analyses,
+    report_confidence_statistics,
+    report_duplicate_candidates,
+    report_hyphenation_statistics,
+    report_line_break_scores,
+    report_line_statistics,
+    report_metric_correlations,
+    report_outliers,
+    report_position_statistics,
+    report_suspicious_groundtruth,
+    report_unicode_statistics,
+    report_word_error_statistics,
+)
+
+from digital_eval.sinks import (
+    SINK_KINDS,
+    sink_from_spec,
+)
+
+from digital_eval.results import (
+    BASELINE_IMPROVEMENT,
+    BASELINE_REGRESSION,
+    BASELINE_THRESHOLD,
+    RESULTS_FORMAT_DIGITAL_EVAL,
+    RESULTS_FORMAT_OCRD,
+    RESULTS_FORMATS,
+    aggregate_baseline,
+    compare_results,
+    format_resource_usage,
+    merge_results,
+    write_html_report,
+    write_ocrd_eval,
+    write_results,
+)
+
 DEFAULT_VERBOSITY = 0
 EVAL_VERBOSITY = DEFAULT_VERBOSITY
 
+# subcommand to merge (partial) result files
+SUBCOMMAND_MERGE = 'merge-results'
+# subcommand to inject errors into groundtruth
+SUBCOMMAND_CORRUPT = 'corrupt'
+# subcommand to list registered metrics
+SUBCOMMAND_METRICS = 'list-metrics'
+# subcommand to debug single pair
+SUBCOMMAND_INSPECT = 'inspect'
+
+# exit code if composite score falls below --fail-under
+EXIT_FAIL_UNDER = 1
+# exit code if any result regressed against --baseline
+EXIT_REGRESSION = 1
+# exit code if groundtruth evaluated against itself isn't perfect
+EXIT_SELF_CHECK = 1
+
+# colorize terminal output only if it's a terminal (auto)
+COLOR_AUTO = 'auto'
+COLOR_ALWAYS = 'always'
+COLOR_NEVER = 'never'
+COLORS = [COLOR_AUTO, COLOR_ALWAYS, COLOR_NEVER]
+# ANSI escape sequences
+ANSI_GREEN = '\033[32m'
+ANSI_YELLOW = '\033[33m'
+ANSI_RED = '\033[31m'
+ANSI_BOLD = '\033[1m'
+ANSI_RESET = '\033[0m'
+# quality thresholds (accuracy 0 - 100) for green and yellow,
+# anything below is red
+QUALITY_GOOD = 95.0
+QUALITY_FAIR = 80.0
+
+# presets set options like evaluation frameworks specify
+# them, i.e. OCR-D (ocrd_eval.md): CER and WER on grapheme
+# clusters and unicode words after NFC, pooled over all
+# reference units and written as OCR-D evaluation report
+PRESET_OCRD = 'ocrd'
+PRESETS = {
+    PRESET_OCRD: {'compat': COMPAT_DINGLEHOPPER, 'average': AVERAGE_MICRO,
+                  'results_format': RESULTS_FORMAT_OCRD},
+}
+
+
+def use_color(mode=COLOR_AUTO, stream=None) -> bool:
+    """Colorize if forced or output is terminal which
+    supports colors and NO_COLOR is not set"""
+
+    if mode == COLOR_ALWAYS:
+        return True
+    if mode == COLOR_NEVER:
+        return False
+    stream = stream or sys.stdout
+    if 'NO_COLOR' in os.environ or os.environ.get('TERM') == 'dumb':
+        return False
+    return hasattr(stream, 'isatty') and stream.isatty()
+
+
+def _paint(text, ansi, colored) -> str:
+    return f'{ansi}{text}{ANSI_RESET}' if colored else text
+
+
+def _quality_color(eval_key, value) -> str:
+    """Color by quality, respecting metric's range
+    and error rates, which are better if lower"""
+
+    _label = eval_key.split('@')[0]
+    _accuracy = 100 * value if _label in METRICS_UNIT_RANGE else value
+    if _label in ERROR_RATES:
+        _accuracy = 100 - _accuracy
+    if _accuracy >= QUALITY_GOOD:
+        return ANSI_GREEN
+    if _accuracy >= QUALITY_FAIR:
+        return ANSI_YELLOW
+    return ANSI_RED
+
+
+def _averages(result, average, colored=False, lang=REPORT_LANG_DEFAULT) -> str:
+    """Means followed by number of reference units,
+    like entry's metrics, i.e. '∅: 97.48(2481)'"""
+
+    _n_refs = int(result.n_chars)
+    _means = []
+    if average in (AVERAGE_MACRO, AVERAGE_BOTH):
+        _mean = _paint(f'{result.mean:.2f}', _quality_color(result.eval_key, result.mean), colored)
+        _means.append(f'∅: {_mean}({_n_refs})')
+    if average in (AVERAGE_MICRO, AVERAGE_BOTH):
+        _mean = _paint(f'{result.micro_mean:.2f}', _quality_color(result.eval_key, result.micro_mean), colored)
+        _means.append(f'{label("micro", lang)} ∅: {_mean}({_n_refs})')
+    return '\t'.join(_means)
+
 
-def _main(pcandidates, preference, verbosity, xtra):
+def _operations(result, lang=REPORT_LANG_DEFAULT) -> str:
+    """Pooled edit operations, if known, i.e.
+    ', substitutions: 12, insertions: 3, deletions: 4'"""
+
+    return ''.join(f', {label(_op, lang)}: {_n}' for (_op, _n) in (result.operations or {}).items())
+
+
+def _print_summary(results, label_candidates, label_reference, average=AVERAGE_BOTH, colored=False,
+                   lang=REPORT_LANG_DEFAULT):
+    evaluation_date = date.today().isoformat()
+    _header = label('summary', lang, candidates=label_candidates, reference=label_reference, date=evaluation_date)
+    (_items, _std, _median) = (label('items', lang), label('std', lang), label('median', lang))
+    print(f'[INFO ] {_paint(_header, ANSI_BOLD, colored)}')
+    for result in results:
+        (gt_type, n_total, _, med, _) = result.get_defaults()
+        add_stats = f', {_std}: {result.std:.2f}, {_median}: {med:.2f}' if n_total > 1 else ''
+        print(f'[INFO ] "{gt_type}"\t{_averages(result, average, colored, lang)}\t{n_total} {_items}{add_stats}'
+              f'{_operations(result, lang)}')
+        if result.cleared_result:
+            (_, n_t2, _, med2, _) = result.cleared_result.get_defaults()
+            ccr_std = result.cleared_result.std
+            drops = n_total - n_t2
+            if drops > 0:
+                print(f'[INFO ] "{gt_type}"\t{_averages(result.cleared_result, average, colored, lang)}\t{n_t2} {_items} (-{drops}), {_std}: {ccr_std:.2f}, {_median}: {med2:.2f}'
+                      f'{_operations(result.cleared_result, lang)}')
+
+
+def _print_analyses(analyses, entries):
+    if ANALYSIS_UNICODE in analyses:
+        for _line in report_unicode_statistics(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_GT_SANITY in analyses:
+        for _line in report_suspicious_groundtruth(entries):
+            print(f'[WARN ] {_line}')
+    if ANALYSIS_POSITION in analyses:
+        for _line in report_position_statistics(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_LINE_BREAKS in analyses:
+        for _line in report_line_break_scores(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_LINES in analyses:
+        for _line in report_line_statistics(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_WORD_ERRORS in analyses:
+        for _line in report_word_error_statistics(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_HYPHENATION in analyses:
+        for _line in report_hyphenation_statistics(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_CONFIDENCE in analyses:
+        for _line in report_confidence_statistics(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_CORRELATION in analyses:
+        for _line in report_metric_correlations(entries):
+            print(f'[INFO ] {_line}')
+    if ANALYSIS_DUPLICATES in analyses:
+        for _line in report_duplicate_candidates(entries):
+            print(f'[WARN ] {_line}')
+
+
+def _check_fail_under(results, score_key, threshold, average=AVERAGE_MACRO, lang=REPORT_LANG_DEFAULT):
+    _scores = [r for r in results if r.eval_key == score_key]
+    if not _scores:
+        print(f'[WARN ] no composite score "{score_key}" to check against {threshold:.2f}')
+        sys.exit(EXIT_FAIL_UNDER)
+    _mean = _scores[0].get_mean(average)
+    if _mean < threshold:
+        print(f'[WARN ] "{score_key}" ∅: {_mean:.2f} {label("fails_under", lang)} {threshold:.2f}')
+        sys.exit(EXIT_FAIL_UNDER)
+    print(f'[INFO ] "{score_key}" ∅: {_mean:.2f} {label("passes", lang)} {threshold:.2f}')
+
+
+def _check_baseline(results, path_baseline, threshold, average=AVERAGE_MACRO, colored=False,
+                    lang=REPORT_LANG_DEFAULT, fingerprint=None, force=False):
+    try:
+        _results_baseline = aggregate_baseline(path_baseline, fingerprint, force)
+    except EvalMatchingError as exc:
+        print(f'[ERROR] {exc}, pass --force to compare anyway')
+        sys.exit(1)
+    _comparison = compare_results(results, _results_baseline, threshold, average)
+    _n_regressions = 0
+    _n_improvements = 0
+    for (_key, _mean_base, _mean, _delta, _status) in _comparison:
+        _line = f'"{_key}"\t{label(_status, lang)}: {_mean_base:.2f} => {_mean:.2f} ({_delta:+.2f})'
+        if _status == BASELINE_REGRESSION:
+            _n_regressions += 1
+            print(f'[WARN ] {_paint(_line, ANSI_RED, colored)}')
+        elif _status == BASELINE_IMPROVEMENT:
+            _n_improvements += 1
+            print(f'[INFO ] {_paint(_line, ANSI_GREEN, colored)}')
+    _n_unchanged = len(_comparison) - _n_regressions - _n_improvements
+    _summary = label('baseline_summary', lang, n=len(_comparison), path=path_baseline,
+                     n_regressions=_n_regressions, n_improvements=_n_improvements,
+                     n_unchanged=_n_unchanged, threshold=threshold)
+    print(f'[INFO ] {_summary}')
+    if _n_regressions:
+        sys.exit(EXIT_REGRESSION)
+
+
+def read_region_ids(ids=None, path_ids=None) -> List[str]:
+    """Region IDs from comma-separated list and/or
+    file with one ID per line, in order, without
+    duplicates"""
+
+    _ids = [_i.strip() for _i in ids.split(',')] if ids else []
+    if path_ids:
+        with open(path_ids, mode='r', encoding='utf-8') as _handle:
+            _ids += [_l.strip() for _l in _handle if not _l.strip().startswith('#')]
+    region_ids = []
+    for _id in _ids:
+        if _id and _id not in region_ids:
+            region_ids.append(_id)
+    if not region_ids:
+        raise ValueError("no region IDs given")
+    return region_ids
+
+
+def _export_alignments(entries, dir_out, export_format):
+    os.makedirs(dir_out, exist_ok=True)
+    _n_written = 0
+    for _entry in entries:
+        _path_out = alignment_path(dir_out, _entry.path_c, export_format)
+        try:
+            _reference = _entry.txt_reference
+            if isinstance(_reference, list):
+                _reference = ' '.join(_reference)
+            write_alignment(to_pieces(str(_entry.path_c)), _reference.split(), _path_out, export_format)
+            _n_written += 1
+        except Exception as exc:
+            print(f"[WARN ] {_entry.path_c}: can't export alignment: {exc}")
+    print(f'[INFO ] wrote {_n_written} word alignments ({export_format}) to "{dir_out}"')
+
+
+def _inspected(path_inspect, *paths) -> bool:
+    _inspect = os.path.abspath(path_inspect)
+    return any(os.path.abspath(_p) == _inspect for _p in paths if _p)
+
+
+def _print_token_alignment(name, alignment):
+    if alignment is None:
+        print(f'[WARN ] [{name}] no token alignment to inspect, requires word accuracy metric')
+        return
+    print(f'[TRACE] [{name}] token alignment reference | candidate')
+    for _line in format_token_alignment(alignment):
+        print(f'[TRACE] [{name}] {_line}')
+
+
+def _main_pair(path_candidate, path_reference, verbosity, xtra, metrics=None, score=None,
+               fail_under=None, region_ids=None, lang=REPORT_LANG_DEFAULT, data_format=None,
+               normalizations=None, eras=None, abbreviations=None, timeout_per_file=None,
+               by_region=False, separators=None, strict_parse=False, count_units=UNITS_CODEPOINTS,
+               order_authority=ORDER_EACH, equivalences=None, project_candidate=False, inspect=None,
+               min_word_confidence=None):
+    """Evaluate single candidate file versus single reference file"""
+
+    _config = EvalConfig(metrics, score, xtra, region_ids, verbosity, data_format, normalizations, eras,
+                         abbreviations, timeout_per_file, by_region, separators, strict_parse, count_units,
+                         order_authority, equivalences, project_candidate, min_word_confidence)
+    try:
+        _result = evaluate_pair(path_candidate, path_reference, _config)
+    except Exception as exc:
+        print(f'[ERROR] {exc}')
+        sys.exit(1)
+    print(f'[INFO ] [{os.path.basename(path_candidate)}] [{_result}]')
+    for _region in _result.regions or []:
+        _candidate = f' ~ {_region.candidate_id} (IoU {_region.iou:.2f})' if _region.candidate_id else ''
+        print(f'[INFO ] [{_region.id}]{_candidate} [{_region}]')
+    if verbosity >= 1:
+        _timings = ', '.join(f'{_k} {_v:.3f}s' for _k, _v in _result.timings.items())
+        print(f'[DEBUG] [{os.path.basename(path_candidate)}] {_timings}')
+        for (_kind, _n) in parse_warning_counts(_result.parse_warnings).items():
+            print(f'[DEBUG] [{os.path.basename(path_candidate)}] {_n} parse warnings {_kind}')
+    if inspect and verbosity >= 2 and _inspected(inspect, path_candidate, path_reference):
+        _print_token_alignment(os.path.basename(path_candidate), _result.word_alignment)
+    if fail_under is not None:
+        _score = _result.values[score.label]
+        if _score < fail_under:
+            print(f'[WARN ] "{score.label}": {_score:.2f} {label("fails_under", lang)} {fail_under:.2f}')
+            sys.exit(EXIT_FAIL_UNDER)
+        print(f'[INFO ] "{score.label}": {_score:.2f} {label("passes", lang)} {fail_under:.2f}')
+
+
+def _main_self_check(path_groundtruth, verbosity, xtra, metrics=None, data_format=None,
+                     normalizations=None, abbreviations=None, separators=None):
+    """Evaluate each groundtruth file against itself"""
+
+    _config = EvalConfig(metrics, None, xtra, None, verbosity, data_format, normalizations,
+                         abbreviations=abbreviations, separators=separators)
+    (_n_checked, _deviations) = self_check(path_groundtruth, _config)
+    for (_path, _reason) in _deviations:
+        print(f'[WARN ] [{_path}] {_reason}')
+    print(f'[INFO ] self-check of {_n_checked} groundtruth files in "{path_groundtruth}": '
+          f'{len(_deviations)} deviations')
+    if _deviations:
+        sys.exit(EXIT_SELF_CHECK)
+
+
+def _main(pcandidates, preference, verbosity, xtra, path_results=None, shard=None, analyses=None,
+          metrics=None, path_html=None, score=None, fail_under=None,
+          path_baseline=None, baseline_threshold=BASELINE_THRESHOLD,
+          alignment_dir=None, alignment_format=ALIGNMENT_PAGE, average=AVERAGE_BOTH,
+          colored=False, lang=REPORT_LANG_DEFAULT, region_ids=None, data_format=None,
+          normalizations=None, eras=None, abbreviations=None, timeout_per_file=None, by_region=False,
+          sinks=None, separators=None, strict_parse=False, count_units=UNITS_CODEPOINTS, layout_boxes=False,
+          order_authority=ORDER_EACH, equivalences=None, project_candidate=False, inspect=None, force=False,
+          min_word_confidence=None, results_format=RESULTS_FORMAT_DIGITAL_EVAL):
+    _started = time.perf_counter()
     (path_candidates, path_ref) = validate_paths(pcandidates, preference)
 
     if verbosity >= 2:
         args = f"{path_candidates}, {path_ref}, {verbosity}"
         print(f'[DEBUG] call with {args}')
 
+    # single pair of files
+    if os.path.isfile(path_candidates) and os.path.isfile(path_ref):
+        _main_pair(path_candidates, path_ref, verbosity, xtra, metrics, score, fail_under, region_ids, lang,
+                   data_format, normalizations, eras, abbreviations, timeout_per_file, by_region, separators,
+                   strict_parse, count_units, order_authority, equivalences, project_candidate, inspect,
+                   min_word_confidence)
+        return
+
     evaluator = Evaluator(path_ref, verbosity, xtra)
+    if metrics:
+        evaluator.metrics = metrics
+    evaluator.score = score
+    evaluator.region_ids = region_ids
+    evaluator.data_format = data_format
+    evaluator.normalizations = normalizations or []
+    evaluator.eras = eras or []
+    evaluator.abbreviations = abbreviations or {}
+    evaluator.timeout_per_file = timeout_per_file
+    evaluator.by_region = by_region
+    evaluator.strict_parse = strict_parse
+    evaluator.count_units = count_units
+    evaluator.layout_boxes = layout_boxes
+    evaluator.order_authority = order_authority
+    evaluator.equivalences = equivalences or {}
+    evaluator.project_candidate = project_candidate
+    evaluator.min_word_confidence = min_word_confidence
+    evaluator.sinks = sinks or []
+    if separators:
+        evaluator.separators = separators_from_spec(separators, evaluator.separators)
 
     # gather structure information
     candidates = gather_candidates(path_candidates)
@@ -32,55 +544,367 @@ def _main(pcandidates, preference, verbosity, xtra):
         print(f"[WARN] no ocr data (.*xml) in any dir starting from '{path_candidates}' ! exit.")
         sys.exit(0)
 
+    # optional: restrict to this job's shard
+    if shard:
+        (shard_index, n_shards) = parse_shard(shard)
+        n_gathered = len(candidates)
+        candidates = filter_shard(candidates, path_candidates, shard_index, n_shards)
+        print(f'[INFO ] shard {shard_index}/{n_shards}: picked "{len(candidates)}" from "{n_gathered}" candidates')
+        if not candidates:
+            print(f"[WARN] no candidates for shard {shard} ! exit.")
+            sys.exit(0)
+
     # match groundtruth
     for entry in candidates:
         gt = find_groundtruth(entry.path_c, path_candidates, path_ref)
         if gt:
             entry.path_g = gt
 
-    # remove all paths where no groundtruth exists
+    # remove all paths where no groundtruth exists, unless
+    # reference-free metrics evaluate them on their own
     gt_entries = [c for c in candidates if c.path_g]
     n_entries = len(candidates)
     n_diff = n_entries - len(gt_entries)
     gt_missing = set(gt_entries) ^ set (candidates)
     rnd_str = f" ({gt_missing})" if gt_missing else ""
-    print(f'[INFO ] from "{n_entries}" filtered "{n_diff}" candidates missing groundtruth{rnd_str}')
+    _reference_free = [_m.label for _m in evaluator.metrics if not _m.requires_reference]
+    if _reference_free and gt_missing:
+        print(f'[INFO ] from "{n_entries}" evaluate "{n_diff}" candidates missing groundtruth '
+              f'by {_reference_free} only{rnd_str}')
+        gt_entries = candidates
+    else:
+        print(f'[INFO ] from "{n_entries}" filtered "{n_diff}" candidates missing groundtruth{rnd_str}')
 
     # trigger actual evaluation
     evaluator.eval_all(gt_entries)
+    resources = resource_usage(_started)
+
+    if evaluator.timed_out_entries:
+        print(f'[WARN ] {len(evaluator.timed_out_entries)} candidates timed out, excluded from aggregation')
+
+    # optional: pairwise view of selected entry
+    if inspect and verbosity >= 2:
+        _inspects = [_e for _e in evaluator.evaluation_entries if _inspected(inspect, _e.path_c, _e.path_g)]
+        if not _inspects:
+            print(f'[WARN ] no evaluated entry "{inspect}" to inspect')
+        for _entry in _inspects:
+            _print_token_alignment(os.path.basename(_entry.path_c), _entry.word_alignment)
+
+    # optional: store evaluated entries, including timed out
+    if path_results:
+        _entries = sorted(evaluator.evaluation_entries + evaluator.timed_out_entries, key=lambda e: e.path_c)
+        if results_format == RESULTS_FORMAT_OCRD:
+            write_ocrd_eval(path_results, _entries, path_candidates, path_ref, resources)
+        else:
+            write_results(path_results, _entries, path_candidates, path_ref, shard, resources)
+        print(f'[INFO ] wrote {len(_entries)} results to "{path_results}"')
+    if alignment_dir:
+        _export_alignments(evaluator.evaluation_entries, alignment_dir, alignment_format)
+    if not evaluator.evaluation_entries and evaluator.timed_out_entries:
+        for _sink in evaluator.sinks:
+            _sink.close()
+        print(f"[WARN] all candidates timed out after {timeout_per_file}s ! exit.")
+        sys.exit(0)
 
     # aggregate
-    evaluator.aggregate(by_type=True)
+    by_metrics = list(range(len(metrics))) if metrics else [0, 1, 2, 3]
+    if score:
+        # composite score follows all calculated metrics
+        by_metrics.append(len(evaluator.metrics))
+    evaluator.aggregate(by_type=True, by_metrics=by_metrics)
 
     # evaluator.evaluate()
     evaluator.eval_map()
 
     # get results
     results = evaluator.get_results()
-    evaluation_date = date.today().isoformat()
+    for _sink in evaluator.sinks:
+        _sink.aggregate(results)
+        _sink.close()
+    if path_html:
+        write_html_report(path_html, evaluator.evaluation_entries,
+                          f'Evaluation "{path_candidates}" vs. "{path_ref}"', lang, resources,
+                          outliers(results, evaluator.evaluation_entries))
+        print(f'[INFO ] wrote html report to "{path_html}"')
+    _print_summary(results, path_candidates, path_ref, average, colored, lang)
+    _usage = ', '.join(f'{_l}: {_v}' for (_l, _v) in format_resource_usage(resources, lang))
+    print(f'[INFO ] {label("resources", lang)}: {_usage}')
+    for _line in report_outliers(results, evaluator.evaluation_entries):
+        print(f'[INFO ] {_line}')
+
+    # optional: additional analyses, which compare
+    # candidates with their groundtruth
+    if analyses:
+        _print_analyses(analyses, [_e for _e in evaluator.evaluation_entries if _e.path_g])
+
+    # optional: gate by composite score
+    if fail_under is not None:
+        _check_fail_under(results, f"{score.label}@{Path(path_ref).parts[-1]}", fail_under, average, lang)
+
+    # optional: gate by regressions against baseline
+    if path_baseline:
+        _check_baseline(results, path_baseline, baseline_threshold, average, colored, lang,
+                        evaluator.fingerprint(), force)
+
+
+def _main_merge(paths_results, verbosity, path_results=None, average=AVERAGE_BOTH, colored=False,
+                lang=REPORT_LANG_DEFAULT, force=False):
+    try:
+        (root_candidates, root_ref, entries) = merge_results(paths_results, force)
+    except EvalMatchingError as exc:
+        print(f'[ERROR] {exc}, pass --force to merge anyway')
+        sys.exit(1)
+    print(f'[INFO ] merged {len(entries)} results from {len(paths_results)} files')
+    if not entries:
+        print(f"[WARN] no results in '{paths_results}' ! exit.")
+        sys.exit(0)
+
+    if path_results:
+        write_results(path_results, entries, root_candidates, root_ref)
+        print(f'[INFO ] wrote {len(entries)} results to "{path_results}"')
+
+    _evaluated = [e for e in entries if not e.timed_out]
+    if len(_evaluated) < len(entries):
+        print(f'[WARN ] {len(entries) - len(_evaluated)} results timed out, excluded from aggregation')
+    if not _evaluated:
+        print(f"[WARN] all results in '{paths_results}' timed out ! exit.")
+        sys.exit(0)
+    evaluator = Evaluator(root_ref, verbosity)
+    evaluator.evaluation_entries = _evaluated
+    evaluator.aggregate(by_type=True, by_metrics=list(range(len(_evaluated[0].metrics))))
+    evaluator.eval_map()
+    _print_summary(evaluator.get_results(), root_candidates, root_ref, average, colored, lang)
+    for _line in report_outliers(evaluator.get_results(), evaluator.evaluation_entries):
+        print(f'[INFO ] {_line}')
+
+
+def main_merge(argv):
+    PARSER = argparse.ArgumentParser(prog=f"digital-eval {SUBCOMMAND_MERGE}",
+                                     description="Merge (partial) evaluation results into one aggregated report")
+    PARSER.add_argument("results", nargs='+', help="Result files (JSON) to merge")
+    PARSER.add_argument("-v", "--verbosity", action='count', default=DEFAULT_VERBOSITY,
+                        required=False, help="Verbosity (optional, default: '')")
+    PARSER.add_argument("--results-file", required=False,
+                        help="Write merged results into this file (JSON, optional)")
+    PARSER.add_argument("--average", required=False, default=AVERAGE_BOTH, choices=AVERAGES,
+                        help=f"Report micro, macro or both means (optional, default: {AVERAGE_BOTH})")
+    PARSER.add_argument("--color", required=False, default=COLOR_AUTO, choices=COLORS,
+                        help=f"Colorize report (optional, default: {COLOR_AUTO}, i.e. only on terminals)")
+    PARSER.add_argument("--report-lang", required=False, default=REPORT_LANG_DEFAULT, choices=REPORT_LANGS,
+                        help=f"Language of report labels (optional, default: {REPORT_LANG_DEFAULT})")
+    PARSER.add_argument("--force", required=False, action='store_true',
+                        help="Merge results evaluated with differing configurations, too (optional)")
+
+    ARGS = vars(PARSER.parse_args(argv))
+    _main_merge(ARGS["results"], ARGS["verbosity"], ARGS["results_file"], ARGS["average"],
+                use_color(ARGS["color"]), ARGS["report_lang"], ARGS["force"])
+
+
+def main_corrupt(argv):
+    PARSER = argparse.ArgumentParser(prog=f"digital-eval {SUBCOMMAND_CORRUPT}",
+                                     description="Inject errors into groundtruth to get synthetic candidates")
+    PARSER.add_argument("groundtruth", help="Groundtruth file or root directory (ALTO, PAGE, plain text)")
+    PARSER.add_argument("-o", "--output", required=True,
+                        help="Directory to write corrupted candidates into, keeping sub directories")
+    PARSER.add_argument("--rate", required=False, type=float, default=CORRUPT_RATE,
+                        help=f"Errors per char of groundtruth (optional, default: {CORRUPT_RATE})")
+    PARSER.add_argument("--corruptions", required=False, default=CORRUPT_SEPARATOR.join(CORRUPTIONS),
+                        help=f"Comma-separated kinds of errors (optional, default: all of {CORRUPTIONS})")
+    PARSER.add_argument("--confusions", required=False,
+                        help="File with substitutions 'original<TAB>replacement' per line "
+                             "(optional, default: common OCR confusions)")
+    PARSER.add_argument("--seed", required=False, type=int, default=CORRUPT_SEED,
+                        help=f"Seed of random errors, same seed same candidates (optional, default: {CORRUPT_SEED})")
+
+    ARGS = vars(PARSER.parse_args(argv))
+    if not 0 <= ARGS["rate"] <= 1:
+        PARSER.error(f"--rate must be within [0, 1], got {ARGS['rate']}")
+    try:
+        _corruptions = parse_corruptions(ARGS["corruptions"])
+    except ValueError as _exc:
+        PARSER.error(str(_exc))
+    _confusions = None
+    if ARGS["confusions"]:
+        try:
+            _confusions = read_confusions(ARGS["confusions"])
+        except (OSError, ValueError) as _exc:
+            PARSER.error(f"can't read --confusions: {_exc}")
+    if not os.path.exists(ARGS["groundtruth"]):
+        PARSER.error(f"groundtruth '{ARGS['groundtruth']}' doesn't exist")
+    (_n_written, _counts) = corrupt_all(ARGS["groundtruth"], ARGS["output"], ARGS["rate"],
+                                        _corruptions, ARGS["seed"], _confusions)
+    print(f'[INFO ] corrupted {_n_written} files into "{ARGS["output"]}"')
+    for _corruption in _corruptions:
+        print(f'[INFO ] {_corruption}: {_counts[_corruption]} errors')
+
+
+def load_plugins(modules):
+    """Import modules which register further
+    metrics or readers when imported"""
+
+    for _module in modules or []:
+        importlib.import_module(_module)
+
+
+def main_list_metrics(argv):
+    PARSER = argparse.ArgumentParser(prog=f"digital-eval {SUBCOMMAND_METRICS}",
+                                     description="List labels and names of registered metrics")
+    PARSER.add_argument("--plugin", required=False, action='append',
+                        help="Import this module registering further metrics first (optional, repeatable)")
+
+    ARGS = vars(PARSER.parse_args(argv))
+    try:
+        load_plugins(ARGS["plugin"])
+    except ImportError as _exc:
+        PARSER.error(f"can't load --plugin: {_exc}")
+    for (_label, _factory) in METRICS.items():
+        _default = ' (default)' if _label in METRICS_DEFAULT else ''
+        print(f'{_label}\t{_factory().name}{_default}')
+
+
+def match_reference(path_candidate, path_reference) -> Tuple[str, str, List[str]]:
+    """Groundtruth file of single candidate like evaluation
+    of directories picks it, how it got matched and further
+    files which would match, too"""
+
+    if os.path.isfile(path_reference):
+        return (path_reference, 'given as file', [])
+    _root_candidates = os.path.dirname(os.path.abspath(path_candidate))
+    _path_gt = find_groundtruth(os.path.abspath(path_candidate), _root_candidates, path_reference)
+    if not _path_gt:
+        return (None, None, [])
+    _stem = os.path.splitext(os.path.basename(path_candidate))[0]
+    _how = f"by name '{_stem}.xml'" if os.path.basename(_path_gt) == f'{_stem}.xml' \
+        else f"by first file starting with '{_stem}'"
+    _others = sorted(os.path.join(path_reference, _f) for _f in os.listdir(path_reference)
+                     if _f.startswith(_stem) and os.path.splitext(_f)[1] in ('.xml', '.txt')
+                     and os.path.join(path_reference, _f) != _path_gt)
+    return (_path_gt, _how, _others)
+
+
+def _region_ids(path_in) -> List[str]:
+    try:
+        return [_r[0] for _r in region_texts(to_pieces(str(path_in)))]
+    except Exception:
+        return []
 
-    print(f'[INFO ] Evaluation Summary for "{path_candidates}" vs. "{path_ref} ({evaluation_date})')
-    for result in results:
-        (gt_type, n_total, mean_total, med, _n_refs) = result.get_defaults()
-        add_stats = f', std: {result.std:.2f}, median: {med:.2f}' if n_total > 1 else ''
-        print(f'[INFO ] "{gt_type}"\t∅: {mean_total:.2f}\t{n_total} items, {_n_refs} refs{add_stats}')
-        if result.cleared_result:
-            (_, n_t2, mean2, med2, n_c2) = result.cleared_result.get_defaults()
-            ccr_std = result.cleared_result.std
-            drops = n_total - n_t2
-            if drops > 0:
-                print(f'[INFO ] "{gt_type}"\t∅: {mean2:.2f}\t{n_t2} items (-{drops}), {n_c2} refs, std: {ccr_std:.2f}, median: {med2:.2f}')
+
+def main_inspect(argv):
+    PARSER = argparse.ArgumentParser(prog=f"digital-eval {SUBCOMMAND_INSPECT}",
+                                     description="Show how single candidate gets matched and evaluated")
+    PARSER.add_argument("candidate", help="Candidate file")
+    PARSER.add_argument("-r", "-ref", "--reference", required=True,
+                        help="Groundtruth directory to match candidate in by name, or groundtruth file")
+    PARSER.add_argument("-m", "--metric", required=False, action='append',
+                        help=f"Metric to calculate, repeatable (optional, default: {METRICS_DEFAULT})")
+    PARSER.add_argument("-x", "--extra", required=False,
+                        help="Additional information to evaluation, like 'keep_line_breaks' (optional)")
+    PARSER.add_argument("--format", required=False, default=FORMAT_AUTO, choices=[FORMAT_AUTO] + reader_names(),
+                        help=f"Format of candidate (optional, default: {FORMAT_AUTO})")
+    PARSER.add_argument("--normalize", required=False,
+                        help=f"Comma-separated normalizations of {list(NORMALIZATIONS)} (optional)")
+    PARSER.add_argument("--order-authority", required=False, default=ORDER_EACH, choices=ORDER_AUTHORITIES,
+                        help=f"Whose reading order serializes regions (optional, default: {ORDER_EACH})")
+    PARSER.add_argument("--project-candidate", required=False, action='store_true',
+                        help="Re-assemble candidate lines in regions of reference (optional)")
+    PARSER.add_argument("--plugin", required=False, action='append',
+                        help="Import this module registering further metrics first (optional, repeatable)")
+
+    ARGS = vars(PARSER.parse_args(argv))
+    try:
+        load_plugins(ARGS["plugin"])
+    except ImportError as _exc:
+        PARSER.error(f"can't load --plugin: {_exc}")
+    _path_c = ARGS["candidate"]
+    if not os.path.isfile(_path_c):
+        PARSER.error(f"candidate '{_path_c}' doesn't exist")
+    if not os.path.exists(ARGS["reference"]):
+        PARSER.error(f"reference '{ARGS['reference']}' doesn't exist")
+    try:
+        _metrics = [metric_from_spec(m) for m in ARGS["metric"]] if ARGS["metric"] else None
+        _normalizations = parse_normalizations(ARGS["normalize"]) if ARGS["normalize"] else None
+    except ValueError as _exc:
+        PARSER.error(str(_exc))
+    _data_format = ARGS["format"] if ARGS["format"] != FORMAT_AUTO else None
+
+    # matching
+    (_path_g, _how, _others) = match_reference(_path_c, ARGS["reference"])
+    print(f'[INFO ] candidate "{_path_c}"')
+    if not _path_g:
+        print(f'[ERROR] no groundtruth named like "{os.path.splitext(os.path.basename(_path_c))[0]}" '
+              f'in "{ARGS["reference"]}"')
+        sys.exit(1)
+    print(f'[INFO ] groundtruth "{_path_g}" ({_how})')
+    for _other in _others:
+        print(f'[WARN ] groundtruth "{_other}" matches, too, but isn\'t used')
+
+    # evaluation
+    _config = EvalConfig(_metrics, extras=ARGS["extra"], data_format=_data_format, normalizations=_normalizations,
+                         order_authority=ARGS["order_authority"], project_candidate=ARGS["project_candidate"])
+    try:
+        _result = evaluate_pair(_path_c, _path_g, _config)
+    except Exception as exc:
+        print(f'[ERROR] {exc}')
+        sys.exit(1)
+    if _result.gt_type != NOT_SET:
+        print(f'[INFO ] groundtruth type "{_result.gt_type}"')
+    for _warning in _result.parse_warnings:
+        print(f'[WARN ] parse warning {_warning}')
+
+    # texts and region order
+    print(f'[INFO ] groundtruth text :: "{_result.txt_reference}"')
+    print(f'[INFO ] candidate text   :: "{_result.txt_candidate}"')
+    if _result.region_order:
+        (_order_g, _order_c) = _result.region_order
+        _kind = 'projected into groundtruth' if ARGS["project_candidate"] else f'by {ARGS["order_authority"]}'
+        print(f'[INFO ] region order {_kind}')
+    else:
+        (_order_g, _order_c) = (_region_ids(_path_g), _region_ids(_path_c))
+        print("[INFO ] region order each file's own")
+    print(f'[INFO ]   groundtruth: {", ".join(str(_i) for _i in _order_g) or NOT_SET}')
+    print(f'[INFO ]   candidate:   {", ".join(str(_i) for _i in _order_c) or NOT_SET}')
+
+    # metrics with diagnostics
+    for _metric in _result.metrics:
+        _operations = ''.join(f', {_op} {_n}' for (_op, _n) in (_metric.operations or {}).items())
+        print(f'[INFO ] {_metric.label} ({_metric.name}): {_metric.value:.2f} ({_metric.n_ref}){_operations}')
+        for (_key, _value) in _metric.diagnostics.items():
+            if _key == DIAGNOSTIC_ALIGNMENT:
+                for _line in format_token_alignment(_value):
+                    print(f'[INFO ]   {_line}')
+            else:
+                print(f'[INFO ]   {_key}: {_value}')
 
 
 ########
 # MAIN #
 ########
 def main():
-    PARSER = argparse.ArgumentParser(description="Evaluate Digital Data")
+    if len(sys.argv) > 1 and sys.argv[1] == SUBCOMMAND_MERGE:
+        main_merge(sys.argv[2:])
+        return
+    if len(sys.argv) > 1 and sys.argv[1] == SUBCOMMAND_CORRUPT:
+        main_corrupt(sys.argv[2:])
+        return
+    if len(sys.argv) > 1 and sys.argv[1] == SUBCOMMAND_METRICS:
+        main_list_metrics(sys.argv[2:])
+        return
+    if len(sys.argv) > 1 and sys.argv[1] == SUBCOMMAND_INSPECT:
+        main_inspect(sys.argv[2:])
+        return
+
+    PARSER = argparse.ArgumentParser(description="Evaluate Digital Data",
+                                     epilog=f"use 'digital-eval {SUBCOMMAND_MERGE} -h' to merge stored results, "
+                                            f"'digital-eval {SUBCOMMAND_CORRUPT} -h' to corrupt groundtruth, "
+                                            f"'digital-eval {SUBCOMMAND_METRICS}' to list metrics, "
+                                            f"'digital-eval {SUBCOMMAND_INSPECT} -h' to debug single pair")
     PARSER.add_argument(
-                        "candidates", help="Root Directory to inspect")
+                        "candidates", help="Root Directory to inspect, or single candidate file")
     PARSER.add_argument("-ref", "--reference", required=False,
-                        help="Root Reference directory for Groundtruth or alike (optional)")
+                        help="""
+                        Root Reference directory for Groundtruth or alike (optional),
+                        or single reference file to evaluate single candidate file
+                        """)
     PARSER.add_argument("-v", "--verbosity", action='count', default=DEFAULT_VERBOSITY,
                         required=False, help="""
                         Verbosity. 
@@ -91,14 +915,594 @@ def main():
                         pass additional information to evaluation, like
                         * 'ignore_geometry' 
                         compare only textual contents without respect to coords
+                        * 'best_window'
+                        groundtruth is snippet of candidate page, evaluate
+                        only best matching window of candidate tokens
+                        * 'keep_line_breaks'
+                        join lines by line breaks rather than spaces,
+                        so differing line segmentation counts as error
+                        (several extras separated by comma)
+                        """)
+    PARSER.add_argument("--separators", required=False,
+                        help=f"""
+                        Strings joining words to lines ('word'), lines to regions
+                        ('line') and regions to page ('region') when reading
+                        texts (optional), like 'line=newline:region=none',
+                        each one of {list(SEPARATOR_NAMES)} or custom string.
+                        Default: space, unless extra 'keep_line_breaks' joins lines
+                        and regions by newline
+                        """)
+    PARSER.add_argument("--plugin", required=False, action='append',
+                        help=f"""
+                        Import this module first, which registers further
+                        metrics by digital_eval.register_metric or readers
+                        by digital_eval.register_reader when imported
+                        (optional, repeatable). List metrics by
+                        'digital-eval {SUBCOMMAND_METRICS} --plugin <module>'
+                        """)
+    PARSER.add_argument("-m", "--metric", required=False, action='append',
+                        help=f"""
+                        Metric to calculate, repeat for several metrics
+                        (optional, default: {METRICS_DEFAULT}, pick 'LCA'
+                        for mean character accuracy of aligned lines or
+                        'QCA:model=<model>.onnx' for accuracy estimated by
+                        learned model without reference, with optional
+                        'dictionary' and char model training text 'lm',
+                        or 'DICT:api=<url>' for ratio of candidate words
+                        known by LanguageTool server, with optional
+                        'language' (default: {LT_LANGUAGE}), or
+                        'DICT:dictionary=<de_DE.dic>,<words.txt>' for
+                        ratio found in local hunspell dictionaries or
+                        word lists,
+                        or 'ChrF' for F-score of char n-grams ignoring
+                        whitespace, with max order 'n' (default:
+                        {CHRF_ORDER}) and recall weight 'beta' (default:
+                        {CHRF_BETA}), like 'ChrF:n=3:beta=1', or 'BLEU'
+                        for precision of word n-grams with brevity
+                        penalty, with max order 'n' (default:
+                        {BLEU_ORDER}), like 'BLEU:n=2', or 'CWA' for
+                        word accuracy weighted by word confidences of
+                        candidate, like ALTO 'WC', or 'CaseER' for
+                        percentage of reference words which differ only by
+                        case, or 'DiaER' for percentage of reference chars
+                        which differ only by diacritics, or 'SegER' for
+                        percentage of word edits due to words split or
+                        merged, or 'WWAs' for word accuracy of content
+                        words, i.e. without stopwords of 'language', or
+                        'KWRec:terms=<names.txt>' for percentage of terms,
+                        one per line, occuring in reference and found in
+                        candidate, or 'CapRec' for percentage of reference
+                        words capitalized, but not at sentence start, found
+                        in candidate).
+                        Token based metrics accept option 'drop' with
+                        token classes {list(TOKEN_FILTERS)} to ignore,
+                        like 'WWA:drop=punct' or 'WBoW:drop=punct,digits',
+                        and option 'tokenize' with {TOKENIZE_MODES}
+                        to split off or strip punctuation consistently,
+                        like 'WWA:tokenize=split-punct'.
+                        All metrics accept option 'case' with {CASE_MODES},
+                        like 'CCA:case=ignore', and option 'marks' with
+                        {MARKS_MODES} to strip combining marks like accents,
+                        like 'CCA:marks=ignore'. Bag of words metrics
+                        {METRICS_BAG_BASED} accept option 'fuzzy' with max
+                        edits or edits per char of tokens to still match,
+                        like 'WBoW:fuzzy=1' or 'IRRec:fuzzy=0.2'.
+                        Frequency bag of words 'BoWf' compares token counts
+                        of both texts, with option 'similarity' of
+                        {SIMILARITY_MODES}, like 'BoWf:similarity=cosine'.
+                        Layout metrics {METRICS_LAYOUT} match regions of
+                        ALTO or PAGE files by intersection over union (IoU)
+                        of their bounding boxes, at least option 'iou'
+                        (default: {LAYOUT_IOU_THRESHOLD}), and report percentage
+                        of candidate regions matched ('RegPre'), reference
+                        regions matched ('RegRec') and mean IoU of matched
+                        regions ('RegIoU'), like 'RegRec:iou=0.7'. Option
+                        'profile' weights regions by type and error kind,
+                        like 'RegRec:profile=newspaper.xml'.
+                        'TblSim' compares tables of PAGE files by their grid
+                        of rows and cells, with option 'content' of
+                        {TABLE_CONTENT_MODES} to compare cell texts, too,
+                        like 'TblSim:content=ignore'.
+                        IR metrics {METRICS_IR} accept option 'weighting'
+                        with {WEIGHTING_MODES} to weight terms by tf-idf over
+                        all groundtruth, like 'IRRec:weighting=tfidf'.
+                        Edit distance metrics {METRICS_EDIT_DISTANCE} accept
+                        option 'gaps' with {GAPS_MODES} to skip text not
+                        covered by partial groundtruth, like 'CCA:gaps=auto',
+                        and option 'length' with {LENGTH_MODES} to normalize
+                        edits by reference or longer text, like 'CCA:length=max'.
+                        Character based metrics {METRICS_CHAR_BASED} accept
+                        option 'units' with {UNITS_MODES} to count
+                        user-perceived chars, like 'CCA:units=graphemes'
+                        """)
+    PARSER.add_argument("--format", required=False, default=FORMAT_AUTO,
+                        choices=[FORMAT_AUTO] + reader_names(),
+                        help=f"""
+                        Format of candidates (optional, default: {FORMAT_AUTO}, i.e.
+                        detected per file). Groundtruth format is always detected
+                        """)
+    PARSER.add_argument("--alto-missing-content", required=False, default=MISSING_CONTENT_SKIP,
+                        choices=MISSING_CONTENT_MODES,
+                        help=f"""
+                        Handling of ALTO String without CONTENT (optional, default:
+                        {MISSING_CONTENT_SKIP}): skip it, keep it as 'empty' word
+                        without text, read it's 'subs' content SUBS_CONTENT
+                        instead or fail with 'error'. Each occurrence is counted
+                        as parse warning
+                        """)
+    PARSER.add_argument("--normalize", required=False,
+                        help=f"""
+                        Normalize reference and candidate texts before calculating
+                        any metric (optional), comma-separated list of {list(NORMALIZATIONS)}.
+                        Eras defined by --config add further normalizations for pages
+                        published in them
+                        """)
+    PARSER.add_argument("--abbreviations", required=False,
+                        help="""
+                        Expand abbreviations in reference and candidate texts
+                        before normalizations (optional), read from this file
+                        with one 'abbreviation<TAB>expansion' per line
+                        """)
+    PARSER.add_argument("--equivalences", required=False,
+                        help="""
+                        Replace variants of chars or tokens by their canonical
+                        forms in reference and candidate texts after
+                        normalizations (optional), read from this file with
+                        one 'variant<TAB>canonical' per line or, if it ends
+                        with '.json', from JSON object like {"ß": "ss"}
+                        """)
+    PARSER.add_argument("--count-units", required=False, default=UNITS_CODEPOINTS, choices=UNITS_MODES,
+                        help=f"""
+                        Units to count chars by (optional, default: {UNITS_CODEPOINTS}),
+                        both for reference and candidate size and as denominator
+                        of character based metrics {METRICS_CHAR_BASED}, like
+                        option 'units' of each metric. Pick 'graphemes' to count
+                        user-perceived chars, like text editors do
+                        """)
+    PARSER.add_argument("--ignore-case", required=False, action='store_true',
+                        help=f"""
+                        Lowercase reference and candidate texts before calculating
+                        any metric (optional), like option 'case={CASE_IGNORE}' of
+                        each metric
+                        """)
+    PARSER.add_argument("--ignore-marks", required=False, action='store_true',
+                        help=f"""
+                        Strip combining marks, like accents, from decomposed
+                        reference and candidate texts before calculating any
+                        metric (optional), like option 'marks={MARKS_IGNORE}'
+                        of each metric
+                        """)
+    PARSER.add_argument("--as-error-rate", required=False, action='store_true',
+                        help=f"""
+                        Report error rates (percent) rather than accuracies (optional),
+                        i.e. edits per reference unit, which may exceed 100, for
+                        {list(ERROR_RATE_METRICS)} as {[_l for (_l, _) in ERROR_RATE_METRICS.values()]},
+                        like option 'report={REPORT_ERROR_RATE}' of each metric
+                        """)
+    PARSER.add_argument("--lt-api-url", required=False,
+                        help="""
+                        LanguageTool server API to check candidate's words with,
+                        like 'http://localhost:8081/v2' (optional), adds reference-free
+                        metric 'DICT' for ratio of words known, like it's option 'api'
+                        """)
+    PARSER.add_argument("-l", "--language", required=False,
+                        help=f"""
+                        Languages of stopwords dropped by metrics {METRICS_STOPWORDS},
+                        comma-separated ISO 639-2 codes like 'deu,lat' (optional,
+                        default: 'deu,eng,ara,rus'), one of {list(STOPWORDS_ISO)},
+                        like option 'language' of each of these metrics
+                        """)
+    PARSER.add_argument("--compat", required=False, choices=list(COMPAT_METRICS),
+                        help="""
+                        Calculate metrics exactly like other evaluation tool
+                        to get directly comparable numbers (optional):
+                        * 'dinglehopper' CER and WER (percent) on grapheme clusters
+                        and unicode word segments after NFC normalization
+                        * 'ocrevaluation' CER, WER and order independent WER (percent)
+                        like ocrevalUAtion, best used with --html-report
+                        """)
+    PARSER.add_argument("--preset", required=False, choices=list(PRESETS),
+                        help=f"""
+                        Set options like evaluation framework specifies them
+                        (optional): '{PRESET_OCRD}' calculates CER and WER like
+                        OCR-D's evaluation, i.e. --compat {COMPAT_DINGLEHOPPER}
+                        with --average {AVERAGE_MICRO}, and writes --results-file
+                        as OCR-D evaluation report (--results-format {RESULTS_FORMAT_OCRD})
+                        """)
+    PARSER.add_argument("--config", required=False,
+                        help="""
+                        Read settings from this config file (INI, optional).
+                        Section [score] defines a composite score as weighted
+                        sum of metrics, like 'weights = 0.6*CCA + 0.4*WWA'
+                        with optional 'label' (default: 'Score'). Sections like
+                        [era:pre-1900] with 'from' and/or 'until' (years) define
+                        publication eras to aggregate by and their 'normalize',
+                        assigned by date of METS next to candidates
+                        """)
+    PARSER.add_argument("--score", required=False,
+                        help=f"""
+                        Composite score as weighted sum of metrics, like
+                        '0.6*CCA + 0.4*WWA' (optional), labeled '{SCORE_LABEL_DEFAULT}',
+                        overrides [score] from --config
+                        """)
+    PARSER.add_argument("--self-check", required=False, action='store_true',
+                        help=f"""
+                        Evaluate each groundtruth file of --reference (or candidates,
+                        if none) against itself rather than candidates (optional),
+                        with metrics picked (default: all but {SELF_CHECK_SKIP}),
+                        and exit with code {EXIT_SELF_CHECK} if any isn't perfect, which
+                        reveals problems of parsing or normalization
+                        """)
+    PARSER.add_argument("--fail-under", required=False, type=float,
+                        help=f"""
+                        Exit with code {EXIT_FAIL_UNDER} if mean composite score
+                        of all candidates is less than this (optional,
+                        requires --score or [score] from --config)
+                        """)
+    PARSER.add_argument("--baseline", required=False,
+                        help=f"""
+                        Compare mean of each metric and domain with stored results
+                        of previous run (JSON from --results-file, optional) and
+                        exit with code {EXIT_REGRESSION} if any of them regressed
+                        """)
+    PARSER.add_argument("--baseline-threshold", required=False, type=float,
+                        default=BASELINE_THRESHOLD,
+                        help=f"""
+                        Minimal change of mean to count as regression or
+                        improvement against --baseline, in points of 0 - 100,
+                        scaled for metrics {METRICS_UNIT_RANGE} ranging 0 - 1
+                        (optional, default: {BASELINE_THRESHOLD})
+                        """)
+    PARSER.add_argument("--force", required=False, action='store_true',
+                        help="""
+                        Compare with --baseline evaluated with differing
+                        configuration, i.e. fingerprint stored with each
+                        result, too (optional)
+                        """)
+    PARSER.add_argument("--average", required=False, default=AVERAGE_BOTH, choices=AVERAGES,
+                        help=f"""
+                        Report aggregated means pooled over all reference units of
+                        a set ('{AVERAGE_MICRO}'), averaged per page ('{AVERAGE_MACRO}') or
+                        both (optional, default: {AVERAGE_BOTH}). Gates by --fail-under
+                        and --baseline use micro means only for '{AVERAGE_MICRO}'
+                        """)
+    PARSER.add_argument("--color", required=False, default=COLOR_AUTO, choices=COLORS,
+                        help=f"""
+                        Colorize report by quality, green from {QUALITY_GOOD:.0f}, yellow
+                        from {QUALITY_FAIR:.0f}, red below (accuracy, optional, default:
+                        {COLOR_AUTO}, i.e. only on terminals and without NO_COLOR set)
+                        """)
+    PARSER.add_argument("--report-lang", required=False, default=REPORT_LANG_DEFAULT, choices=REPORT_LANGS,
+                        help=f"""
+                        Language of report labels in summary and HTML report
+                        (optional, default: {REPORT_LANG_DEFAULT})
+                        """)
+    PARSER.add_argument("--html-report", required=False,
+                        help="""
+                        Write per-document summary with metrics and
+                        spotted differences into this file (HTML, optional)
+                        """)
+    PARSER.add_argument("--alignment-dir", required=False,
+                        help="""
+                        Export word alignment of each candidate into this directory
+                        (optional). Each candidate word carries it's aligned reference
+                        word and error class, requires candidates with word level data
+                        """)
+    PARSER.add_argument("--alignment-format", required=False, default=ALIGNMENT_PAGE,
+                        choices=list(ALIGNMENT_FORMATS),
+                        help=f"""
+                        Format of exported word alignment (optional, default: {ALIGNMENT_PAGE}):
+                        PAGE 2019 with custom attribute or hOCR with data attributes
+                        """)
+    PARSER.add_argument("--inspect", required=False,
+                        help="""
+                        Print aligned reference and candidate tokens of word
+                        accuracy for this candidate or reference file in columns,
+                        marked S(ubstitution), I(nsertion) or D(eletion), to verify
+                        tokenization and metrics manually (optional, requires '-vv')
+                        """)
+    PARSER.add_argument("--region-ids", required=False,
+                        help="""
+                        Evaluate only groundtruth regions with these IDs, comma-separated
+                        (optional). Candidates get restricted to the frame covering them,
+                        pages without any of them are skipped
+                        """)
+    PARSER.add_argument("--region-id-file", required=False,
+                        help="""
+                        Like --region-ids, but read IDs from this file, one per line,
+                        ignoring empty lines and lines starting with '#' (optional)
+                        """)
+    PARSER.add_argument("--results-file", required=False,
+                        help="""
+                        Write evaluated entries into this file (JSON, optional).
+                        Result files of several runs can be combined
+                        afterwards with 'digital-eval merge-results'
+                        """)
+    PARSER.add_argument("--results-format", required=False, choices=RESULTS_FORMATS,
+                        default=RESULTS_FORMAT_DIGITAL_EVAL,
+                        help=f"""
+                        Layout of --results-file (optional, default: '{RESULTS_FORMAT_DIGITAL_EVAL}'):
+                        '{RESULTS_FORMAT_OCRD}' writes evaluation report of OCR-D, i.e. 'cer'
+                        and 'wer' (fractions) by page and document wide, which can't be merged
+                        """)
+    PARSER.add_argument("--shard", required=False,
+                        help="""
+                        Evaluate only shard 'i/n' of all candidates (like '3/8', optional).
+                        Candidates are partitioned deterministically by path, therefore
+                        n jobs with shards 1/n .. n/n cover all candidates exactly once
+                        """)
+    PARSER.add_argument("--timeout-per-file", required=False,
+                        help="""
+                        Abort evaluation of single candidate after this duration
+                        (like '60s' or '2m', optional). Timed out candidates are
+                        marked in results file and excluded from aggregation
+                        """)
+    PARSER.add_argument("--by-region", required=False, action='store_true',
+                        help="""
+                        Evaluate each region of structured groundtruth, too,
+                        against candidate region matched by IoU (optional).
+                        Region rows go to html report and results file and
+                        are printed with '-v' or for single pair of files
+                        """)
+    PARSER.add_argument("--order-authority", required=False, default=ORDER_EACH, choices=ORDER_AUTHORITIES,
+                        help=f"""
+                        Whose reading order serializes regions of both texts
+                        (optional, default: {ORDER_EACH}, i.e. each text's own).
+                        With '{ORDER_REFERENCE}' candidate regions follow the
+                        reference regions matched by IoU, unmatched ones stay
+                        behind their predecessor, '{ORDER_CANDIDATE}' vice versa.
+                        Requires ALTO or PAGE files, ignored with region IDs
+                        """)
+    PARSER.add_argument("--project-candidate", required=False, action='store_true',
+                        help="""
+                        Re-assemble candidate lines in regions of reference
+                        (optional), each line going to the region covering
+                        most of it, so differing segmentation of engines,
+                        like regions merged or split, doesn't count. Requires
+                        ALTO or PAGE files, ignored with region IDs
+                        """)
+    PARSER.add_argument("--min-word-confidence", required=False, type=float,
+                        help="""
+                        Drop candidate words with confidence below this
+                        threshold within [0, 1], like ALTO 'WC', PAGE 'conf'
+                        or hOCR 'x_wconf' (percent), before evaluation, so
+                        they count as deletions (optional). Words without
+                        confidence are kept. Not with '--by-region'
+                        """)
+    PARSER.add_argument("--layout-boxes", required=False, action='store_true',
+                        help="""
+                        Keep bounding boxes of structured groundtruth's regions
+                        and lines with each entry of results file and JSON sink
+                        (optional), together with region metrics of '--by-region'
+                        and line accuracies of metric 'LCA', if evaluated, to draw
+                        them over page images
+                        """)
+    PARSER.add_argument("--strict-parse", required=False, action='store_true',
+                        help="""
+                        Fail each pair with any parse warning, like ALTO
+                        String without CONTENT or PAGE line without text,
+                        rather than skip such elements (optional). Failed
+                        pairs are reported and excluded from aggregation
+                        """)
+    PARSER.add_argument("--sink", required=False, action='append',
+                        help=f"""
+                        Stream each evaluated entry as soon as it's done and
+                        finally the aggregates to this sink (optional, repeatable),
+                        'kind:path' with kind of {SINK_KINDS}, like 'csv:results.csv',
+                        'sqlite:results.db', 'json:results.jsonl' (JSON lines),
+                        'prometheus:digital_eval.prom' (text format, progress
+                        and means) or just 'stdout'
+                        """)
+    PARSER.add_argument("--analysis", required=False,
+                        help=f"""
+                        Report additional analyses of evaluated corpus (optional),
+                        comma-separated list of {ANALYSES}:
+                        * '{ANALYSIS_UNICODE}' distribution of unicode character categories
+                        and top deviating codepoints in reference and candidate texts
+                        * '{ANALYSIS_GT_SANITY}' list suspicious groundtruth, i.e. much shorter than
+                        candidate, without letters, duplicate of other groundtruth or candidate
+                        * '{ANALYSIS_POSITION}' word error rates by page zone (rows top, middle,
+                        bottom and columns margins, left, right) as heatmap table, requires
+                        candidates with word level data
+                        * '{ANALYSIS_LINE_BREAKS}' metric means before and after collapsing
+                        line breaks into spaces, i.e. errors due to line segmentation,
+                        requires extra 'keep_line_breaks'
+                        * '{ANALYSIS_LINES}' mean and median character accuracy of lines
+                        and worst lines over all pages, requires metric 'LCA'
+                        * '{ANALYSIS_WORD_ERRORS}' word errors by class, i.e. substitution,
+                        insertion, deletion, split, merge and those differing only by case,
+                        punctuation or hyphenation
+                        * '{ANALYSIS_HYPHENATION}' word errors due to words broken by hyphen at
+                        line end and metric means before and after dehyphenation, requires
+                        extra 'keep_line_breaks'
+                        * '{ANALYSIS_CONFIDENCE}' word error rates by confidence range and
+                        correlation of confidences with errors, requires metric 'CWA'
+                        * '{ANALYSIS_CORRELATION}' correlation of each pair of metrics over
+                        all pages, i.e. whether a cheaper metric may stand in for another
+                        * '{ANALYSIS_DUPLICATES}' clusters of candidates with identical or
+                        near-identical text, i.e. probable duplicate scans or copy errors
                         """)
 
     ARGS = vars(PARSER.parse_args())
+    try:
+        load_plugins(ARGS["plugin"])
+    except ImportError as exc:
+        PARSER.error(f"can't load --plugin: {exc}")
+    if ARGS["preset"]:
+        for (_key, _value) in PRESETS[ARGS["preset"]].items():
+            if ARGS[_key] != PARSER.get_default(_key):
+                PARSER.error(f"--preset already sets --{_key}, don't use with --{_key}")
+            ARGS[_key] = _value
+    if ARGS["alto_missing_content"] != MISSING_CONTENT_SKIP:
+        register_reader(AltoReader(ARGS["alto_missing_content"]))
     path_candidates = ARGS["candidates"]
     path_ref = ARGS["reference"]
     verbosity = ARGS["verbosity"]
     xtra = ARGS["extra"]
-    _main(path_candidates, path_ref, verbosity, xtra)
+    path_results = ARGS["results_file"]
+    shard = ARGS["shard"]
+    if shard:
+        try:
+            parse_shard(shard)
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    sinks = []
+    for _spec in ARGS["sink"] or []:
+        try:
+            sinks.append(sink_from_spec(_spec))
+        except (OSError, ValueError, sqlite3.Error) as exc:
+            PARSER.error(str(exc))
+    timeout_per_file = None
+    if ARGS["timeout_per_file"]:
+        try:
+            timeout_per_file = parse_timeout(ARGS["timeout_per_file"])
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    analyses = None
+    if ARGS["analysis"]:
+        try:
+            analyses = parse_analyses(ARGS["analysis"])
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    metrics = None
+    if ARGS["metric"]:
+        try:
+            metrics = [metric_from_spec(m) for m in ARGS["metric"]]
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    if ARGS["compat"]:
+        if metrics:
+            PARSER.error("--compat already defines metrics, don't use with --metric")
+        metrics = compat_metrics(ARGS["compat"])
+    if ARGS["ignore_case"]:
+        if ARGS["compat"]:
+            PARSER.error("--compat already defines metrics, don't use with --ignore-case")
+        metrics = metrics or [METRICS[_label]() for _label in METRICS_DEFAULT]
+        for _metric in metrics:
+            _metric.case = CASE_IGNORE
+    if ARGS["ignore_marks"]:
+        if ARGS["compat"]:
+            PARSER.error("--compat already defines metrics, don't use with --ignore-marks")
+        metrics = metrics or [METRICS[_label]() for _label in METRICS_DEFAULT]
+        for _metric in metrics:
+            _metric.marks = MARKS_IGNORE
+    if ARGS["count_units"] != UNITS_CODEPOINTS:
+        if ARGS["compat"]:
+            PARSER.error("--compat already defines metrics, don't use with --count-units")
+    if ARGS["as_error_rate"]:
+        if ARGS["compat"]:
+            PARSER.error("--compat already reports error rates, don't use with --as-error-rate")
+        metrics = metrics or [METRICS[_label]() for _label in METRICS_DEFAULT]
+        for _metric in metrics:
+            if _metric.label in ERROR_RATE_METRICS:
+                _metric.as_error_rate()
+    if ARGS["language"]:
+        try:
+            _languages = parse_languages(ARGS["language"])
+        except ValueError as exc:
+            PARSER.error(str(exc))
+        metrics = metrics or [METRICS[_label]() for _label in METRICS_DEFAULT]
+        for _metric in metrics:
+            if _metric.label in METRICS_STOPWORDS and not _metric.languages:
+                _metric.languages = _languages
+    if ARGS["lt_api_url"]:
+        metrics = metrics or [METRICS[_label]() for _label in METRICS_DEFAULT]
+        if not any(_m.label == 'DICT' for _m in metrics):
+            metrics.append(METRICS['DICT']())
+        for _metric in metrics:
+            if _metric.label == 'DICT' and not _metric.api_url and not _metric.path_dictionary:
+                _metric.api_url = ARGS["lt_api_url"]
+    if any(_m.label == 'DICT' and not _m.api_url and not _m.path_dictionary for _m in metrics or []):
+        PARSER.error("metric 'DICT' requires option 'api', --lt-api-url or option 'dictionary'")
+    if analyses and ANALYSIS_LINES in analyses:
+        if not any(_m.label == LINES_METRIC for _m in metrics or []):
+            PARSER.error(f"--analysis {ANALYSIS_LINES} requires metric '{LINES_METRIC}'")
+    if analyses and ANALYSIS_CONFIDENCE in analyses:
+        if not any(_m.label == CONFIDENCE_METRIC for _m in metrics or []):
+            PARSER.error(f"--analysis {ANALYSIS_CONFIDENCE} requires metric '{CONFIDENCE_METRIC}'")
+    score = None
+    eras = None
+    if ARGS["config"]:
+        try:
+            _config = read_config(ARGS["config"])
+            score = score_from_config(_config)
+            eras = eras_from_config(_config)
+        except (RuntimeError, ValueError) as exc:
+            PARSER.error(str(exc))
+    if ARGS["score"]:
+        try:
+            score = score_from_spec(ARGS["score"])
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    normalizations = None
+    if ARGS["normalize"]:
+        try:
+            normalizations = parse_normalizations(ARGS["normalize"])
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    abbreviations = None
+    if ARGS["abbreviations"]:
+        try:
+            abbreviations = read_abbreviations(ARGS["abbreviations"])
+        except (OSError, ValueError) as exc:
+            PARSER.error(str(exc))
+    equivalences = None
+    if ARGS["equivalences"]:
+        try:
+            equivalences = read_equivalences(ARGS["equivalences"])
+        except (OSError, ValueError) as exc:
+            PARSER.error(str(exc))
+    separators = ARGS["separators"]
+    if separators:
+        try:
+            separators_from_spec(separators)
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    if score:
+        _labels = [m.label for m in metrics] if metrics else METRICS_DEFAULT
+        if score.label in _labels:
+            PARSER.error(f"score label '{score.label}' collides with metric")
+        try:
+            score.validate(_labels)
+        except ValueError as exc:
+            PARSER.error(str(exc))
+    fail_under = ARGS["fail_under"]
+    if fail_under is not None and not score:
+        PARSER.error("--fail-under requires composite score from --score or --config")
+    path_baseline = ARGS["baseline"]
+    if path_baseline and not os.path.isfile(path_baseline):
+        PARSER.error(f"baseline '{path_baseline}' doesn't exist")
+    _min_confidence = ARGS["min_word_confidence"]
+    if _min_confidence is not None:
+        if not 0 <= _min_confidence <= 1:
+            PARSER.error(f"--min-word-confidence must be within [0, 1], got {_min_confidence}")
+        if ARGS["by_region"]:
+            PARSER.error("--min-word-confidence filters page text only, don't use with --by-region")
+    if ARGS["project_candidate"] and ARGS["order_authority"] == ORDER_CANDIDATE:
+        PARSER.error("--project-candidate serializes in reference order, don't use with --order-authority candidate")
+    if ARGS["inspect"] and verbosity < 2:
+        PARSER.error("--inspect prints with verbosity '-vv' only")
+    path_html = ARGS["html_report"]
+    region_ids = None
+    if ARGS["region_ids"] or ARGS["region_id_file"]:
+        try:
+            region_ids = read_region_ids(ARGS["region_ids"], ARGS["region_id_file"])
+        except (OSError, ValueError) as exc:
+            PARSER.error(str(exc))
+    if ARGS["self_check"]:
+        _main_self_check(path_ref or path_candidates, verbosity, xtra, metrics,
+                          ARGS["format"] if ARGS["format"] != FORMAT_AUTO else None, normalizations,
+                         abbreviations, separators)
+        return
+    _main(path_candidates, path_ref, verbosity, xtra, path_results, shard, analyses,
+          metrics, path_html, score, fail_under, path_baseline, ARGS["baseline_threshold"],
+          ARGS["alignment_dir"], ARGS["alignment_format"], ARGS["average"],
+          use_color(ARGS["color"]), ARGS["report_lang"], region_ids,
+          ARGS["format"] if ARGS["format"] != FORMAT_AUTO else None, normalizations, eras,
+          abbreviations, timeout_per_file, ARGS["by_region"], sinks, separators, ARGS["strict_parse"],
+          ARGS["count_units"], ARGS["layout_boxes"], ARGS["order_authority"],
+          equivalences, ARGS["project_candidate"], ARGS["inspect"], ARGS["force"],
+          ARGS["min_word_confidence"], ARGS["results_format"])
 
 
 if __name__ == "__main__":
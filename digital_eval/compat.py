@@ -0,0 +1,227 @@
+# -*- coding: utf-8 -*-
+"""Compatibility Module
+
+Metrics which follow the definitions of other
+OCR evaluation tools, to make results directly
+comparable with them
+"""
+
+import math
+import re
+import unicodedata
+
+from collections import (
+    Counter
+)
+
+from typing import (
+    List,
+)
+
+from rapidfuzz.string_metric import (
+    levenshtein
+)
+
+from uniseg.wordbreak import (
+    words as _words
+)
+
+from .metrics import (
+    SCORE_LABELS_EXTRA,
+    EvalContext,
+    Metric,
+    MetricOutcome,
+    grapheme_clusters,
+)
+
+# dinglehopper (https://github.com/qurator-spk/dinglehopper)
+COMPAT_DINGLEHOPPER = 'dinglehopper'
+# ocrevalUAtion (https://github.com/impactcentre/ocrevalUAtion)
+COMPAT_OCREVALUATION = 'ocrevaluation'
+
+# equivalences applied by dinglehopper's default
+# normalization (NFC_SBB) after NFC, except for the
+# MUFI codepoints from unicode private use area
+DINGLEHOPPER_EQUIVALENCES = {
+    '==': '–',
+    '—': '–',
+    'ﬁ': 'fi',
+    'ﬀ': 'ff',
+    'ﬂ': 'fl',
+    'ﬃ': 'ffi',
+    '’': "'",
+    '⸗': '-',
+    'uͤ': 'ü',
+    'aͤ': 'ä',
+    'oͤ': 'ö',
+    '°': '⁰',
+    'ϑ': 'θ',
+}
+
+
+def dinglehopper_normalize(text) -> str:
+    """NFC plus dinglehopper's equivalences"""
+
+    text = unicodedata.normalize('NFC', text)
+    for (_from, _to) in DINGLEHOPPER_EQUIVALENCES.items():
+        text = text.replace(_from, _to)
+    return unicodedata.normalize('NFC', text)
+
+
+def _is_unwanted(char) -> bool:
+    """Whitespace, punctuation, symbols, marks
+    and control characters don't make a word"""
+
+    _category = unicodedata.category(char)
+    return _category[0] in 'OMPZS' or _category in ['Cc', 'Cf']
+
+
+def dinglehopper_words(text) -> List[str]:
+    """Split text at word boundaries following
+    unicode text segmentation (UAX #29) and drop
+    words which consist only of unwanted chars"""
+
+    return [w for w in _words(text) if not all(_is_unwanted(c) for c in w)]
+
+
+def error_rate(reference: List[str], candidate: List[str]):
+    """Error rate as dinglehopper and ocrevalUAtion define it:
+    edit distance (S+I+D) normalized by reference length N,
+    which can exceed 1.0 and even be infinite, if
+    there's no reference at all"""
+
+    _distance = levenshtein(reference, candidate)
+    _n_ref = len(reference)
+    if _n_ref == 0:
+        _rate = 0.0 if len(candidate) == 0 else math.inf
+    else:
+        _rate = _distance / _n_ref
+    return (_rate, _distance, _n_ref)
+
+
+class MetricDinglehopperCER(Metric):
+    """Character error rate (percent) on grapheme clusters"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'CER'
+        self.name = 'Character Error Rate (dinglehopper)'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = ctx.get(('dinglehopper', 'graphemes'),
+                                           lambda t: grapheme_clusters(dinglehopper_normalize(t)))
+        (_rate, _distance, _n_ref) = error_rate(_reference, _candidate)
+        return MetricOutcome(100 * _rate, _distance, _n_ref, _reference, _candidate)
+
+
+class MetricDinglehopperWER(Metric):
+    """Word error rate (percent) on unicode word segments"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'WER'
+        self.name = 'Word Error Rate (dinglehopper)'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = ctx.get(('dinglehopper', 'words'),
+                                           lambda t: dinglehopper_words(dinglehopper_normalize(t)))
+        (_rate, _distance, _n_ref) = error_rate(_reference, _candidate)
+        return MetricOutcome(100 * _rate, _distance, _n_ref, _reference, _candidate)
+
+
+def ocreval_normalize(text) -> str:
+    """NFC with whitespace collapsed to single spaces"""
+
+    return ' '.join(unicodedata.normalize('NFC', text).split())
+
+
+def ocreval_words(text) -> List[str]:
+    """Words are maximal sequences of letters and digits,
+    everything else only separates words"""
+
+    return re.findall(r'[^\W_]+', text)
+
+
+def ocreval_bag_error_rate(reference: List[str], candidate: List[str]):
+    """Order independent word error rate: compare words
+    regardless of their position, errors are the larger
+    number of either missing or spurious words"""
+
+    _bag_ref = Counter(reference)
+    _bag_can = Counter(candidate)
+    _missing = sum((_bag_ref - _bag_can).values())
+    _spurious = sum((_bag_can - _bag_ref).values())
+    _distance = max(_missing, _spurious)
+    _n_ref = len(reference)
+    if _n_ref == 0:
+        _rate = 0.0 if len(candidate) == 0 else math.inf
+    else:
+        _rate = _distance / _n_ref
+    return (_rate, _distance, _n_ref)
+
+
+class MetricOcrevalCER(Metric):
+    """Character error rate (percent)"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'CER'
+        self.name = 'Character Error Rate (ocrevalUAtion)'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = ctx.get(('ocreval', 'text'), ocreval_normalize)
+        (_rate, _distance, _n_ref) = error_rate(_reference, _candidate)
+        return MetricOutcome(100 * _rate, _distance, _n_ref, _reference, _candidate)
+
+
+class MetricOcrevalWER(Metric):
+    """Word error rate (percent)"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'WER'
+        self.name = 'Word Error Rate (ocrevalUAtion)'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = ctx.get(('ocreval', 'words'),
+                                           lambda t: ocreval_words(ocreval_normalize(t)))
+        (_rate, _distance, _n_ref) = error_rate(_reference, _candidate)
+        return MetricOutcome(100 * _rate, _distance, _n_ref, _reference, _candidate)
+
+
+class MetricOcrevalWERBag(Metric):
+    """Order independent word error rate (percent)"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'WERoi'
+        self.name = 'Word Error Rate, order independent (ocrevalUAtion)'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = ctx.get(('ocreval', 'words'),
+                                           lambda t: ocreval_words(ocreval_normalize(t)))
+        (_rate, _distance, _n_ref) = ocreval_bag_error_rate(_reference, _candidate)
+        return MetricOutcome(100 * _rate, _distance, _n_ref, _reference, _candidate)
+
+
+# labels of metrics where lower values are better
+ERROR_RATES = ['CER', 'LER', 'WER', 'WERoi', 'BoWER', 'CaseER', 'DiaER', 'SegER']
+
+# metrics for each compatibility mode
+COMPAT_METRICS = {
+    COMPAT_DINGLEHOPPER: [MetricDinglehopperCER, MetricDinglehopperWER],
+    COMPAT_OCREVALUATION: [MetricOcrevalCER, MetricOcrevalWER, MetricOcrevalWERBag],
+}
+
+
+def compat_metrics(mode) -> List[Metric]:
+    """Create metrics of compatibility mode"""
+
+    if mode not in COMPAT_METRICS:
+        raise ValueError(f"unknown compatibility mode '{mode}', expected one of {list(COMPAT_METRICS)}")
+    return [_metric() for _metric in COMPAT_METRICS[mode]]
+
+
+# compatibility metrics may be weighted by scores, too
+SCORE_LABELS_EXTRA.extend(sorted({_metric().label for _metrics in COMPAT_METRICS.values()
+                                  for _metric in _metrics}))
@@ -0,0 +1,112 @@
+# -*- coding: utf-8 -*-
+"""Configuration Module
+
+Read evaluation settings from INI-style config file, like
+
+    [score]
+    label = Score
+    weights = 0.6*CCA + 0.4*WWA
+
+    [era:pre-1900]
+    until = 1899
+    normalize = long-s
+"""
+
+import configparser
+import os
+
+from typing import (
+    List,
+)
+
+from .metrics import (
+    SCORE_LABEL_DEFAULT,
+    MetricScore,
+    score_from_spec,
+)
+
+from .normalization import (
+    parse_normalizations,
+)
+
+# section of composite score
+CONFIG_SECTION_SCORE = 'score'
+CONFIG_SCORE_LABEL = 'label'
+CONFIG_SCORE_WEIGHTS = 'weights'
+
+# sections of publication eras, like [era:pre-1900]
+CONFIG_SECTION_ERA_PREFIX = 'era:'
+CONFIG_ERA_FROM = 'from'
+CONFIG_ERA_UNTIL = 'until'
+CONFIG_ERA_NORMALIZE = 'normalize'
+
+
+class Era:
+    """Range of publication years (both inclusive, open
+    if None) with normalizations for it's material"""
+
+    def __init__(self, label, start=None, end=None, normalizations=None):
+        self.label = label
+        self.start = start
+        self.end = end
+        self.normalizations = normalizations or []
+
+    def contains(self, year) -> bool:
+        if year is None:
+            return False
+        return (self.start is None or year >= self.start) and (self.end is None or year <= self.end)
+
+
+def era_of(year, eras: List[Era]) -> Era:
+    """First era containing year, None if none does"""
+
+    for _era in eras:
+        if _era.contains(year):
+            return _era
+    return None
+
+
+def read_config(path_in) -> configparser.ConfigParser:
+    """Read config file"""
+
+    if not os.path.isfile(path_in):
+        raise RuntimeError(f"config file '{path_in}' doesn't exist")
+    config = configparser.ConfigParser()
+    try:
+        config.read(path_in, encoding='utf-8')
+    except configparser.Error as _err:
+        raise RuntimeError(f"{path_in}: invalid config: {_err}") from _err
+    return config
+
+
+def score_from_config(config) -> MetricScore:
+    """Create composite score if config defines one"""
+
+    if not config.has_section(CONFIG_SECTION_SCORE):
+        return None
+    _section = config[CONFIG_SECTION_SCORE]
+    if CONFIG_SCORE_WEIGHTS not in _section:
+        raise ValueError(f"section [{CONFIG_SECTION_SCORE}] misses '{CONFIG_SCORE_WEIGHTS}'")
+    _label = _section.get(CONFIG_SCORE_LABEL, SCORE_LABEL_DEFAULT)
+    return score_from_spec(_section[CONFIG_SCORE_WEIGHTS], _label)
+
+
+def eras_from_config(config) -> List[Era]:
+    """Create eras in order of their sections"""
+
+    eras = []
+    for _name in config.sections():
+        if not _name.startswith(CONFIG_SECTION_ERA_PREFIX):
+            continue
+        _section = config[_name]
+        _label = _name[len(CONFIG_SECTION_ERA_PREFIX):].strip()
+        try:
+            _start = _section.getint(CONFIG_ERA_FROM)
+            _end = _section.getint(CONFIG_ERA_UNTIL)
+        except ValueError as _err:
+            raise ValueError(f"section [{_name}]: invalid year: {_err}") from _err
+        if _start is None and _end is None:
+            raise ValueError(f"section [{_name}] requires '{CONFIG_ERA_FROM}' or '{CONFIG_ERA_UNTIL}'")
+        _normalizations = parse_normalizations(_section.get(CONFIG_ERA_NORMALIZE, ''))
+        eras.append(Era(_label, _start, _end, _normalizations))
+    return eras
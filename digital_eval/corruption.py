@@ -0,0 +1,277 @@
+# -*- coding: utf-8 -*-
+"""Error Injection Module
+
+Corrupt groundtruth on purpose to get synthetic candidates
+with errors of known kind and rate, i.e. to benchmark how
+sensitive metrics are or to train quality estimation
+models. Corruptions:
+
+* substitute: replace chars by confusions typical for OCR
+  of historical prints, like 'e' by 'c' or 'rn' by 'm'
+* split: insert whitespace into words
+* merge: drop whitespace between words of same line
+* drop-diacritics: drop combining marks, like 'ü' to 'u'
+
+Structured groundtruth (ALTO, PAGE) gets corrupted line
+by line and written in it's format again, plain text
+keeps it's line breaks.
+"""
+
+import os
+import random
+import re
+import unicodedata
+
+from collections import (
+    Counter,
+)
+
+from typing import (
+    Dict,
+    List,
+    Tuple,
+)
+
+from .errors import (
+    EvalUnsupportedError,
+)
+
+from .formats import (
+    detect_reader,
+)
+
+from .model import (
+    Piece,
+    PieceType,
+    to_pieces,
+    write_alto,
+    write_page,
+)
+
+CORRUPT_SUBSTITUTE = 'substitute'
+CORRUPT_SPLIT = 'split'
+CORRUPT_MERGE = 'merge'
+CORRUPT_DIACRITICS = 'drop-diacritics'
+CORRUPTIONS = [CORRUPT_SUBSTITUTE, CORRUPT_SPLIT, CORRUPT_MERGE, CORRUPT_DIACRITICS]
+CORRUPT_SEPARATOR = ','
+# errors per char of groundtruth
+CORRUPT_RATE = 0.02
+# fixed seed, so corrupted candidates can be reproduced
+CORRUPT_SEED = 1
+# groundtruth files to corrupt
+CORRUPT_SUFFIXES = ['.txt', '.xml']
+# format of plain text and writers of structured
+# formats, by name of reader detecting them
+CORRUPT_FORMAT_TEXT = 'text'
+CORRUPT_WRITERS = {'alto': write_alto, 'page': write_page}
+
+# common confusions of OCR on (Fraktur) prints
+CONFUSIONS = {
+    'e': ['c'], 'c': ['e'], 'n': ['u'], 'u': ['n'], 'h': ['b'], 'b': ['h'],
+    'f': ['ſ'], 'ſ': ['f'], 's': ['f'], 'l': ['I', '1'], 'I': ['l'], 'i': ['l'],
+    'r': ['t'], 't': ['r'], 'v': ['y'], 'y': ['v'], 'o': ['0'], 'O': ['0'],
+    'rn': ['m'], 'm': ['rn'], 'B': ['V'], 'V': ['B'], 'W': ['M'], 'M': ['W'],
+}
+CONFUSION_SEPARATOR = '\t'
+CONFUSION_COMMENT = '#'
+
+
+def parse_corruptions(label) -> List[str]:
+    """Corruptions from comma-separated label"""
+
+    _names = [_n.strip() for _n in str(label).split(CORRUPT_SEPARATOR) if _n.strip()]
+    _unknown = [_n for _n in _names if _n not in CORRUPTIONS]
+    if _unknown:
+        raise ValueError(f"unknown corruptions {_unknown}, expected any of {CORRUPTIONS}")
+    return _names
+
+
+def read_confusions(path_in) -> Dict[str, List[str]]:
+    """Read confusions 'original<TAB>replacement',
+    one per line, an original may occur repeatedly"""
+
+    _confusions = {}
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        for (_n, _line) in enumerate(_handle, 1):
+            _line = _line.rstrip('\r\n')
+            if not _line.strip() or _line.lstrip().startswith(CONFUSION_COMMENT):
+                continue
+            if CONFUSION_SEPARATOR not in _line:
+                raise ValueError(f"{path_in}:{_n}: expected 'original<TAB>replacement', got '{_line}'")
+            (_original, _replacement) = _line.split(CONFUSION_SEPARATOR, 1)
+            if not _original:
+                raise ValueError(f"{path_in}:{_n}: empty original")
+            _confusions.setdefault(_original, []).append(_replacement)
+    return _confusions
+
+
+def _places(text, corruptions, confusions) -> List[Tuple[int, int, str, List[str]]]:
+    """Places (start, end, corruption, replacements) of
+    text applicable to corruptions"""
+
+    _found = []
+    if CORRUPT_SUBSTITUTE in corruptions:
+        for (_original, _replacements) in confusions.items():
+            for _match in re.finditer(re.escape(_original), text):
+                _found.append((_match.start(), _match.end(), CORRUPT_SUBSTITUTE, _replacements))
+    if CORRUPT_SPLIT in corruptions:
+        for _i in range(1, len(text)):
+            if text[_i - 1].isalnum() and text[_i].isalnum():
+                _found.append((_i, _i, CORRUPT_SPLIT, [' ']))
+    if CORRUPT_MERGE in corruptions:
+        for _match in re.finditer(r'(?<=\S)[ \t]+(?=\S)', text):
+            _found.append((_match.start(), _match.end(), CORRUPT_MERGE, ['']))
+    if CORRUPT_DIACRITICS in corruptions:
+        for (_i, _char) in enumerate(text):
+            _decomposed = unicodedata.normalize('NFD', _char)
+            _base = ''.join(_c for _c in _decomposed if not unicodedata.combining(_c))
+            if _base != _decomposed:
+                _found.append((_i, _i + 1, CORRUPT_DIACRITICS, [_base]))
+    return _found
+
+
+def corrupt_text(text, rate=CORRUPT_RATE, corruptions=None, rng=None,
+                 confusions=None, n_errors=None) -> Tuple[str, Counter]:
+    """Text with about rate errors per char (or n_errors,
+    if set), as far as it offers places for corruptions
+    (default: all), and number of errors by corruption
+
+    Each error picks it's corruption at random first,
+    so all get about the same share, whatever number
+    of places. Errors don't overlap, each costs at
+    least one edit"""
+
+    _rng = rng or random.Random(CORRUPT_SEED)
+    _corruptions = corruptions or CORRUPTIONS
+    _by_corruption = {_c: [] for _c in _corruptions}
+    for _place in _places(text, _corruptions, CONFUSIONS if confusions is None else confusions):
+        _by_corruption[_place[2]].append(_place)
+    for _places_of in _by_corruption.values():
+        _rng.shuffle(_places_of)
+    _n_errors = round(rate * len(text)) if n_errors is None else n_errors
+    (_picked, _used) = ([], set())
+    while len(_picked) < _n_errors:
+        _left = [_c for _c in _corruptions if _by_corruption[_c]]
+        if not _left:
+            break
+        (_start, _end, _corruption, _replacements) = _by_corruption[_rng.choice(_left)].pop()
+        # insertions occupy their neighbours
+        _span = set(range(_start, _end)) if _end > _start else {_start - 1, _start}
+        if _span & _used:
+            continue
+        _used |= _span
+        _picked.append((_start, _end, _corruption, _rng.choice(_replacements)))
+    _chars = list(text)
+    for (_start, _end, _, _replacement) in sorted(_picked, reverse=True):
+        _chars[_start:_end] = list(_replacement)
+    return (''.join(_chars), Counter(_c for (_, _, _c, _) in _picked))
+
+
+def _set_text(piece: Piece, text):
+    piece._transcriptions = []
+    piece.transcription = text
+
+
+def _lines(piece: Piece) -> List[Piece]:
+    if piece.type == PieceType.LINE:
+        return [piece]
+    return [_l for _p in piece.pieces for _l in _lines(_p)]
+
+
+def _corrupt_line(line: Piece, corrupt) -> Counter:
+    """Corrupt line's text, words which got split or
+    merged share line's box evenly"""
+
+    _words = [_p for _p in line.pieces if _p.type == PieceType.WORD]
+    if not _words:
+        (_text, _counts) = corrupt(line.transcription)
+        _set_text(line, _text)
+        return _counts
+    (_text, _counts) = corrupt(' '.join(_w.transcription for _w in _words))
+    _tokens = _text.split()
+    if len(_tokens) != len(_words):
+        _xs = [_p[0] for _p in line.dimensions]
+        _ys = [_p[1] for _p in line.dimensions]
+        (_x0, _y0, _x1, _y1) = (min(_xs), min(_ys), max(_xs), max(_ys))
+        _width = max((_x1 - _x0) // max(len(_tokens), 1), 1)
+        _words = []
+        for _i in range(len(_tokens)):
+            _word = Piece(f'{line.id}_w{_i + 1}')
+            _word.type = PieceType.WORD
+            _word.parent = line
+            (_left, _right) = (_x0 + _i * _width, _x0 + (_i + 1) * _width)
+            _word.dimensions = [(_left, _y0), (_right, _y0), (_right, _y1), (_left, _y1)]
+            _words.append(_word)
+        line.pieces = [_p for _p in line.pieces if _p.type != PieceType.WORD] + _words
+    for (_word, _token) in zip(_words, _tokens):
+        _set_text(_word, _token)
+    return _counts
+
+
+def candidate_name(name_groundtruth) -> str:
+    """Name of corrupted candidate which matches
+    groundtruth, i.e. without '.gt'"""
+
+    return name_groundtruth.replace('.gt', '')
+
+
+def corrupt_file(path_in, path_out, rate=CORRUPT_RATE, corruptions=None, rng=None,
+                 confusions=None) -> Counter:
+    """Write corrupted groundtruth to path_out, plain text
+    or in it's format, and return errors by corruption,
+    rate applies to whole file, not each line"""
+
+    _rng = rng or random.Random(CORRUPT_SEED)
+    # errors owed to file, since short lines alone
+    # would round to none
+    _owed = 0.0
+
+    def _corrupt(text):
+        nonlocal _owed
+        _owed += rate * len(text)
+        (_text, _counts) = corrupt_text(text, rate, corruptions, _rng, confusions, round(_owed))
+        _owed -= sum(_counts.values())
+        return (_text, _counts)
+
+    _format = detect_reader(str(path_in)).name
+    if _format == CORRUPT_FORMAT_TEXT:
+        with open(path_in, mode='r', encoding='utf-8') as _handle:
+            (_text, _counts) = _corrupt(_handle.read())
+        with open(path_out, mode='w', encoding='utf-8') as _handle:
+            _handle.write(_text)
+        return _counts
+    if _format not in CORRUPT_WRITERS:
+        raise EvalUnsupportedError(f"{path_in}: can only corrupt ALTO, PAGE or plain text")
+    _write = CORRUPT_WRITERS[_format]
+    _page = to_pieces(str(path_in))
+    _counts = Counter()
+    for _line in _lines(_page):
+        _counts.update(_corrupt_line(_line, _corrupt))
+    _write(_page, str(path_out), step_description='corrupt groundtruth')
+    return _counts
+
+
+def corrupt_all(path_groundtruth, dir_out, rate=CORRUPT_RATE, corruptions=None,
+                seed=CORRUPT_SEED, confusions=None) -> Tuple[int, Counter]:
+    """Corrupt single groundtruth file or all below directory
+    into dir_out, keeping sub directories, and return number
+    of files written and errors by corruption"""
+
+    _rng = random.Random(seed)
+    if os.path.isdir(path_groundtruth):
+        _paths = sorted(os.path.join(_dir, _f) for (_dir, _, _files) in os.walk(path_groundtruth)
+                        for _f in _files if _f.endswith(tuple(CORRUPT_SUFFIXES)))
+        _root = path_groundtruth
+    else:
+        (_paths, _root) = ([path_groundtruth], os.path.dirname(path_groundtruth))
+    (_n_written, _counts) = (0, Counter())
+    for _path in _paths:
+        _rel = os.path.relpath(_path, _root)
+        _path_out = os.path.join(dir_out, os.path.dirname(_rel), candidate_name(os.path.basename(_rel)))
+        os.makedirs(os.path.dirname(_path_out), exist_ok=True)
+        try:
+            _counts.update(corrupt_file(_path, _path_out, rate, corruptions, _rng, confusions))
+            _n_written += 1
+        except Exception as exc:
+            print(f"[WARN ] {_path}: can't corrupt: {exc}")
+    return (_n_written, _counts)
@@ -0,0 +1,47 @@
+# -*- coding: utf-8 -*-
+"""Errors Module
+
+Typed errors of public API, so callers can tell i.e.
+broken groundtruth from groundtruth without text.
+Each also derives from the builtin error raised
+before, therefore existing handlers keep working.
+"""
+
+
+class EvalError(Exception):
+    """Base of all evaluation errors"""
+
+
+class EvalIOError(EvalError, IOError):
+    """Data can't be read or written"""
+
+
+class EvalParseError(EvalError, RuntimeError):
+    """Data is inconsistent with it's format
+
+    data_format: format of data, like 'xml', 'alto' or 'page'
+    position: (line, column) if known, None otherwise
+    """
+
+    def __init__(self, message, data_format=None, position=None):
+        super().__init__(message)
+        self.data_format = data_format
+        self.position = position
+
+
+class EvalUnsupportedError(EvalError, RuntimeError):
+    """Data format or version isn't supported"""
+
+
+class EvalMetricError(EvalError, RuntimeError):
+    """Metric can't be calculated, like for
+    groundtruth without any text"""
+
+
+class EvalMatchingError(EvalError, RuntimeError):
+    """Candidates and groundtruth don't match"""
+
+
+class EvalTimeoutError(EvalError, RuntimeError):
+    """Evaluation of single pair exceeded its
+    time budget, like due to pathological alignment"""
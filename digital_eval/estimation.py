@@ -0,0 +1,347 @@
+# -*- coding: utf-8 -*-
+"""Quality Estimation Module
+
+Reference-free estimation of OCR quality by a learned
+model, which predicts the character error rate of a
+candidate from features extracted from it alone.
+Models are provided by users in ONNX format and require
+the optional feature 'onnx', i.e.
+
+    pip install digital-eval[onnx]
+
+Each model must take a single float tensor of shape
+(1, len(QE_FEATURES)) with features in order of
+QE_FEATURES and output the predicted CER (percent).
+Without model, the ratio of words a LanguageTool server
+or local dictionaries know indicates quality, too.
+"""
+
+import functools
+import json
+import math
+import os
+import re
+import statistics
+import unicodedata
+import urllib.error
+import urllib.parse
+import urllib.request
+
+from collections import (
+    Counter
+)
+
+from typing import (
+    Dict,
+    List,
+    Tuple,
+)
+
+import numpy as np
+
+from .errors import (
+    EvalIOError,
+    EvalUnsupportedError,
+)
+
+from .model import (
+    PieceType,
+    to_pieces,
+)
+
+# features in order of model input
+QE_FEATURES = [
+    'conf_mean',        # mean word confidence (0 - 1)
+    'conf_min',         # least word confidence
+    'conf_std',         # standard deviation of word confidences
+    'conf_low_ratio',   # ratio of words below CONF_LOW
+    'dict_rate',        # ratio of tokens found in dictionary
+    'lm_perplexity',    # perplexity by char trigram model
+]
+# features which can't be determined are set to this
+QE_MISSING = 0.0
+# word confidences below are considered low
+CONF_LOW = 0.5
+# order of char language model
+LM_ORDER = 3
+# language of LanguageTool checks, guessed by default
+LT_LANGUAGE = 'auto'
+# LanguageTool's issue type of unknown words
+LT_MISSPELLING = 'misspelling'
+# seconds to wait for LanguageTool server
+LT_TIMEOUT = 60
+
+
+def word_confidences(path_candidate) -> List[float]:
+    """Confidences of all words of candidate, scaled
+    to 0 - 1, empty if format carries none"""
+
+    try:
+        _stack = [to_pieces(path_candidate)]
+    except Exception:
+        return []
+    _confidences = []
+    while _stack:
+        _piece = _stack.pop()
+        if _piece.type == PieceType.WORD and _piece.confidence is not None:
+            _confidences.append(_piece.confidence)
+        _stack += _piece.pieces
+    # some engines report percent
+    if _confidences and max(_confidences) > 1:
+        _confidences = [_c / 100 for _c in _confidences]
+    return _confidences
+
+
+@functools.lru_cache(maxsize=None)
+def read_words(path_in) -> frozenset:
+    """Dictionary of words, one per line"""
+
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        return frozenset(_l.strip() for _l in _handle if _l.strip())
+
+
+def _hunspell_flags(flags, flag_type) -> List[str]:
+    if flag_type == 'long':
+        return [flags[_i:_i + 2] for _i in range(0, len(flags), 2)]
+    if flag_type == 'num':
+        return [_f.strip() for _f in flags.split(',') if _f.strip()]
+    return list(flags)
+
+
+def _read_affixes(path_aff) -> Tuple[str, str, Dict[str, tuple]]:
+    """Encoding, flag type and affix rules by flag of hunspell
+    affix file, each rule (strip, add, condition) and rules
+    of a flag (kind, cross product, rules)"""
+
+    with open(path_aff, mode='rb') as _handle:
+        _lines = _handle.read().decode('latin-1').splitlines()
+    _encoding = 'latin-1'
+    for _line in _lines:
+        if _line.startswith('SET '):
+            _encoding = _line.split()[1]
+            break
+    (_flag_type, _affixes) = ('short', {})
+    for _line in _lines:
+        _line = _line.encode('latin-1').decode(_encoding, errors='replace')
+        _fields = _line.split()
+        if len(_fields) >= 2 and _fields[0] == 'FLAG':
+            _flag_type = _fields[1].lower()
+        elif len(_fields) == 4 and _fields[0] in ['PFX', 'SFX'] and _fields[1] not in _affixes \
+                and _fields[3].isdigit():
+            _affixes[_fields[1]] = (_fields[0], _fields[2] == 'Y', [])
+        elif len(_fields) >= 4 and _fields[0] in ['PFX', 'SFX']:
+            (_kind, _flag, _strip, _add) = _fields[:4]
+            _condition = _fields[4] if len(_fields) > 4 else '.'
+            # drop continuation flags of two-fold affixes
+            (_strip, _add) = ('' if _strip == '0' else _strip, _add.split('/')[0])
+            _add = '' if _add == '0' else _add
+            _pattern = f'^{_condition}' if _kind == 'PFX' else f'{_condition}$'
+            _affixes[_flag][2].append((_strip, _add, re.compile(_pattern)))
+    return (_encoding, _flag_type, _affixes)
+
+
+def _affixed(word, kind, rules) -> List[str]:
+    _words = []
+    for (_strip, _add, _condition) in rules:
+        if not _condition.search(word):
+            continue
+        if kind == 'PFX' and word.startswith(_strip):
+            _words.append(_add + word[len(_strip):])
+        elif kind == 'SFX' and word.endswith(_strip):
+            _words.append(word[:len(word) - len(_strip)] + _add)
+    return _words
+
+
+def read_hunspell(path_dic) -> frozenset:
+    """Words of hunspell dictionary, expanded by prefix and
+    suffix rules of affix file next to it (if any), without
+    compounds and two-fold affixes"""
+
+    _path_aff = os.path.splitext(path_dic)[0] + '.aff'
+    (_encoding, _flag_type, _affixes) = ('utf-8', 'short', {})
+    if os.path.isfile(_path_aff):
+        (_encoding, _flag_type, _affixes) = _read_affixes(_path_aff)
+    with open(path_dic, mode='r', encoding=_encoding, errors='replace') as _handle:
+        _lines = _handle.read().splitlines()
+    _words = set()
+    # first line holds number of entries
+    for _line in _lines[1:]:
+        _entry = _line.split()[0] if _line.split() else ''
+        (_word, _, _flags) = _entry.partition('/')
+        if not _word:
+            continue
+        _words.add(_word)
+        _rules = [_affixes[_f] for _f in _hunspell_flags(_flags, _flag_type) if _f in _affixes]
+        _prefixed = []
+        for (_kind, _cross, _affix_rules) in _rules:
+            _forms = _affixed(_word, _kind, _affix_rules)
+            _words.update(_forms)
+            if _kind == 'PFX' and _cross:
+                _prefixed += _forms
+        # cross product of prefixes and suffixes
+        for (_kind, _cross, _affix_rules) in _rules:
+            if _kind == 'SFX' and _cross:
+                for _form in _prefixed:
+                    _words.update(_affixed(_form, _kind, _affix_rules))
+    return frozenset(_words)
+
+
+@functools.lru_cache(maxsize=None)
+def read_dictionary(paths) -> frozenset:
+    """Lowercase words of hunspell dictionaries ('.dic')
+    or word lists (one word per line), comma-separated"""
+
+    _words = set()
+    for _path in [_p.strip() for _p in paths.split(',') if _p.strip()]:
+        _read = read_hunspell if _path.endswith('.dic') else read_words
+        _words.update(_w.lower() for _w in _read(_path))
+    return frozenset(_words)
+
+
+def _strip_punctuation(token) -> str:
+    _chars = list(token)
+    while _chars and unicodedata.category(_chars[0]).startswith('P'):
+        _chars.pop(0)
+    while _chars and unicodedata.category(_chars[-1]).startswith('P'):
+        _chars.pop()
+    return ''.join(_chars)
+
+
+def dictionary_rate(tokens, words) -> float:
+    """Ratio of tokens found in dictionary, ignoring
+    punctuation at both ends and case"""
+
+    (_n_tokens, _n_hits) = dictionary_hits(tokens, {_w.lower() for _w in words})
+    if not _n_tokens:
+        return QE_MISSING
+    return _n_hits / _n_tokens
+
+
+def dictionary_hits(tokens, words) -> Tuple[int, int]:
+    """Number of tokens and of those found in dictionary
+    of lowercase words, ignoring punctuation at both ends
+    and case"""
+
+    _tokens = [_strip_punctuation(_t) for _t in tokens]
+    _tokens = [_t for _t in _tokens if _t]
+    return (len(_tokens), sum(1 for _t in _tokens if _t.lower() in words))
+
+
+def languagetool_misspellings(text, api_url, language=LT_LANGUAGE) -> List[Tuple[int, int]]:
+    """Spans (start, end) of words LanguageTool server
+    at api_url, like 'http://localhost:8081/v2', doesn't
+    know, i.e. matches of spelling rules"""
+
+    _url = api_url.rstrip('/') + '/check'
+    _data = urllib.parse.urlencode({'text': text, 'language': language}).encode('utf-8')
+    try:
+        with urllib.request.urlopen(_url, data=_data, timeout=LT_TIMEOUT) as _response:
+            _result = json.loads(_response.read().decode('utf-8'))
+    except (urllib.error.URLError, OSError, ValueError) as exc:
+        raise EvalIOError(f"LanguageTool server {_url} failed: {exc}") from exc
+    return [(_m['offset'], _m['offset'] + _m['length'])
+            for _m in _result.get('matches', [])
+            if _m.get('rule', {}).get('issueType') == LT_MISSPELLING]
+
+
+def languagetool_rate(text, api_url, language=LT_LANGUAGE) -> Tuple[float, int, int]:
+    """Ratio of tokens known by LanguageTool, ignoring
+    punctuation at both ends, with number of tokens
+    and of those unknown"""
+
+    _spans = []
+    for _match in re.finditer(r'\S+', text):
+        _token = _match.group()
+        _word = _strip_punctuation(_token)
+        if _word:
+            _start = _match.start() + _token.index(_word)
+            _spans.append((_start, _start + len(_word)))
+    if not _spans:
+        return (QE_MISSING, 0, 0)
+    _unknown = languagetool_misspellings(text, api_url, language)
+    _n_unknown = sum(1 for (_s, _e) in _spans
+                     if any(_s < _ue and _us < _e for (_us, _ue) in _unknown))
+    return ((len(_spans) - _n_unknown) / len(_spans), len(_spans), _n_unknown)
+
+
+class CharLanguageModel:
+    """Char n-gram model with add-one smoothing"""
+
+    def __init__(self, order=LM_ORDER):
+        self.order = order
+        self.ngrams = Counter()
+        self.contexts = Counter()
+        self.alphabet = set()
+
+    def train(self, text):
+        """Count n-grams of text"""
+
+        _padded = ' ' * (self.order - 1) + text
+        self.alphabet.update(text)
+        for _i in range(len(text)):
+            _ngram = _padded[_i:_i + self.order]
+            self.ngrams[_ngram] += 1
+            self.contexts[_ngram[:-1]] += 1
+        return self
+
+    def perplexity(self, text) -> float:
+        """Perplexity of text, higher for text
+        less likely to be seen in training"""
+
+        if not text:
+            return QE_MISSING
+        _padded = ' ' * (self.order - 1) + text
+        _n_chars = len(self.alphabet | set(text))
+        _log_prob = 0.0
+        for _i in range(len(text)):
+            _ngram = _padded[_i:_i + self.order]
+            _log_prob += math.log((self.ngrams[_ngram] + 1) / (self.contexts[_ngram[:-1]] + _n_chars))
+        return math.exp(-_log_prob / len(text))
+
+
+@functools.lru_cache(maxsize=None)
+def read_language_model(path_in, order=LM_ORDER) -> CharLanguageModel:
+    """Char model trained on plain text file"""
+
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        return CharLanguageModel(order).train(' '.join(_handle.read().split()))
+
+
+def extract_features(path_candidate, text, path_dictionary=None, path_lm=None) -> Dict[str, float]:
+    """Features of candidate by name, those without
+    data (like confidences of plain text) QE_MISSING"""
+
+    _features = dict.fromkeys(QE_FEATURES, QE_MISSING)
+    _confidences = word_confidences(path_candidate)
+    if _confidences:
+        _features['conf_mean'] = statistics.mean(_confidences)
+        _features['conf_min'] = min(_confidences)
+        _features['conf_std'] = statistics.pstdev(_confidences)
+        _features['conf_low_ratio'] = sum(1 for _c in _confidences if _c < CONF_LOW) / len(_confidences)
+    if path_dictionary:
+        _features['dict_rate'] = dictionary_rate(text.split(), read_words(path_dictionary))
+    if path_lm:
+        _features['lm_perplexity'] = read_language_model(path_lm).perplexity(' '.join(text.split()))
+    return _features
+
+
+@functools.lru_cache(maxsize=None)
+def load_model(path_model):
+    """ONNX inference session of model, loaded once
+    per process since sessions can't be pickled"""
+
+    try:
+        import onnxruntime
+    except ImportError as exc:
+        raise EvalUnsupportedError("quality estimation requires optional feature 'onnx', "
+                                   "i.e. 'pip install digital-eval[onnx]'") from exc
+    return onnxruntime.InferenceSession(str(path_model))
+
+
+def predict_cer(path_model, features: Dict[str, float]) -> float:
+    """Predicted character error rate (percent)"""
+
+    _session = load_model(path_model)
+    _input = np.array([[features[_f] for _f in QE_FEATURES]], dtype=np.float32)
+    _outputs = _session.run(None, {_session.get_inputs()[0].name: _input})
+    return float(np.ravel(_outputs[0])[0])
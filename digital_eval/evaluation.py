@@ -1,11 +1,20 @@
 # -*- coding: utf-8 -*-
 """OCR Evaluation Module"""
 
+import copy
+import hashlib
+import json
 import os
 import re
+import signal
 import sys
+import threading
+import time
 import xml.dom.minidom
 import xml.etree.ElementTree as ET
+from collections import (
+    Counter,
+)
 from concurrent.futures import (
     ProcessPoolExecutor
 )
@@ -22,7 +31,52 @@ from pathlib import (
 
 import numpy as np
 
+from .alignment import (
+    token_alignment,
+)
+
+from .compat import (
+    ERROR_RATES,
+)
+
+from .config import (
+    CONFIG_SECTION_ERA_PREFIX,
+    era_of,
+)
+
+from .errors import (
+    EvalError,
+    EvalIOError,
+    EvalMatchingError,
+    EvalMetricError,
+    EvalParseError,
+    EvalTimeoutError,
+    EvalUnsupportedError,
+)
+
+from .formats import (
+    FormatReader,
+    detect_reader,
+    get_reader,
+    reader_suffixes,
+    register_reader,
+)
+
+from .layout import (
+    ORDER_EACH,
+    line_boxes,
+    match_regions,
+    order_regions,
+    project_lines,
+    region_texts,
+)
+
+from .mets import (
+    page_year,
+)
+
 from .metrics import (
+    Metric,
     MetricCA,
     MetricLA,
     MetricWA,
@@ -30,9 +84,26 @@ from .metrics import (
     MetricPre,
     MetricRec,
     MetricFM,
+    EvalContext,
+    METRICS,
+    METRICS_CHAR_BASED,
+    METRICS_UNIT_RANGE,
+    OPERATIONS,
+    OPTION_DOC_FREQUENCIES,
+    OPTION_LINES,
+    OPTION_PATH_CANDIDATE,
+    OPTION_PATH_REFERENCE,
+    OPTION_WORDS_CANDIDATE,
+    UNITS_CODEPOINTS,
+    best_window,
+    count_units,
+    document_frequencies,
 )
 
 from .model import (
+    HOCR_CLASS_WORD,
+    HOCR_CLASSES_LINE,
+    _bounding_box,
     BoundingBox,
     OCRWord,
     OCRWordLine,
@@ -40,6 +111,14 @@ from .model import (
     to_pieces,
     Piece,
     PieceType,
+    Separators,
+    separators_from_spec,
+)
+
+from .normalization import (
+    apply_equivalences,
+    expand_abbreviations,
+    normalize_text,
 )
 
 
@@ -50,8 +129,63 @@ XML_NS = {'alto': 'http://www.loc.gov/standards/alto/ns-v3#',
 # just use textual information for evaluation
 # do *not* respect any geometrics
 EVAL_EXTRA_IGNORE_GEOMETRY = 'ignore_geometry'
+# groundtruth is snippet of candidate text, like a
+# single paragraph of a page, so evaluate only the
+# best matching window of candidate tokens
+EVAL_EXTRA_BEST_WINDOW = 'best_window'
+# join lines by line breaks rather than spaces, so
+# differing line segmentation counts as error
+EVAL_EXTRA_KEEP_LINE_BREAKS = 'keep_line_breaks'
+# several extras are separated by comma
+EVAL_EXTRA_SEPARATOR = ','
+# texts are read with lines separated by line break
+# and regions by two of them, replaced by separators
+# of Evaluator not until line based data was kept
+REGION_BREAK = '\n\n'
 # mark unset values as 'not available'
 NOT_SET = 'n.a.'
+# kinds of groundtruth aggregated separately, labels
+# from file names or annotations match by first chars
+GT_TYPES = ['article', 'announcement', 'advertisement', 'table', 'heading', 'letter', 'list', 'poem']
+# length of label prefix to match groundtruth type
+GT_TYPE_PREFIX = 3
+# name of groundtruth type annotation in ALTO
+# OtherTag or PAGE MetadataItem / UserAttribute
+GT_TYPE_ANNOTATION = 'ulb_groundtruth_type'
+# diagnostic of word accuracy with aligned tokens
+DIAGNOSTIC_ALIGNMENT = 'alignment'
+# outcome of metric calculation, not part of it's settings
+METRIC_OUTCOME_FIELDS = ['value', 'diff', 'n_ref', 'n_candidate', 'operations', 'input_reference',
+                         'input_candidate', 'data_reference', 'data_candidate', 'gaps_applied',
+                         'diagnostics']
+# hex digits of configuration fingerprint
+FINGERPRINT_LENGTH = 16
+# settings added after fingerprints were introduced by
+# their default, hashed only if set otherwise, so that
+# fingerprints of unchanged configurations stay the same
+FINGERPRINT_OPTIONAL = {'min_word_confidence': None}
+# prefixes of parse warnings by file they concern
+PARSE_WARNING_REFERENCE = 'reference'
+PARSE_WARNING_CANDIDATE = 'candidate'
+# handling of ALTO String without CONTENT: skip it, keep
+# it as word without text, read SUBS_CONTENT instead
+# (skipped without either) or fail the file
+MISSING_CONTENT_SKIP = 'skip'
+MISSING_CONTENT_EMPTY = 'empty'
+MISSING_CONTENT_SUBS = 'subs'
+MISSING_CONTENT_ERROR = 'error'
+MISSING_CONTENT_MODES = [MISSING_CONTENT_SKIP, MISSING_CONTENT_EMPTY, MISSING_CONTENT_SUBS,
+                         MISSING_CONTENT_ERROR]
+# kinds of groundtruth layout boxes kept with entries
+BOX_REGION = 'region'
+BOX_LINE = 'line'
+
+# aggregates either pool all reference units of
+# a set (micro) or average per item values (macro)
+AVERAGE_MICRO = 'micro'
+AVERAGE_MACRO = 'macro'
+AVERAGE_BOTH = 'both'
+AVERAGES = [AVERAGE_MICRO, AVERAGE_MACRO, AVERAGE_BOTH]
 
 
 def strip_outliers_from(data_tuples):
@@ -72,6 +206,42 @@ def strip_outliers_from(data_tuples):
     return (regulars, Q1, Q3)
 
 
+def get_micro_mean(data_tuples):
+    """Mean of values weighted by their number of
+    reference units (chars, tokens), which equals
+    pooling edit operations of all items for edit
+    distance based metrics, so large items count
+    more than small ones"""
+
+    _n_refs = sum([e[2] for e in data_tuples])
+    if not _n_refs:
+        return np.mean([e[1] for e in data_tuples])
+    return sum([e[1] * e[2] for e in data_tuples]) / _n_refs
+
+
+def get_operations(data_tuples):
+    """Edit operations pooled over all items, None
+    unless known for each of them"""
+
+    _operations = [e[3] for e in data_tuples]
+    if not _operations or None in _operations:
+        return None
+    return {_op: sum(_o[_op] for _o in _operations) for _op in OPERATIONS}
+
+
+def get_document_statistics(data_tuples):
+    """Reference statistics pooled over all items,
+    None unless known for each of them"""
+
+    _statistics = [e[4] for e in data_tuples]
+    if not _statistics or None in _statistics:
+        return None
+    _pooled = DocumentStatistics(units=_statistics[0].units)
+    for _s in _statistics:
+        _pooled.add(_s)
+    return _pooled
+
+
 def get_statistics(data_points):
     """Get common statistics like mean, median and std for data_points"""
 
@@ -85,7 +255,7 @@ def gather_candidates(start_path) -> List:
     candidates = []
     if os.path.isdir(start_path):
         for curr_dir, _, files in os.walk(start_path):
-            xml_files = [f for f in files if str(f).endswith('.xml')]
+            xml_files = [f for f in files if str(f).endswith(tuple(reader_suffixes()))]
             if xml_files:
                 for xml_file in xml_files:
                     rel_path = os.path.join(curr_dir, xml_file)
@@ -98,6 +268,103 @@ def gather_candidates(start_path) -> List:
     return candidates
 
 
+def parse_shard(shard_label) -> Tuple[int, int]:
+    """Parse shard label like '3/8' into (index, number of shards)
+    with 1-based index"""
+
+    _match = re.match(r'^\s*(\d+)\s*/\s*(\d+)\s*$', str(shard_label))
+    if not _match:
+        raise ValueError(f"invalid shard '{shard_label}', expected 'i/n'")
+    (_index, _total) = (int(_match[1]), int(_match[2]))
+    if _total < 1 or _index < 1 or _index > _total:
+        raise ValueError(f"invalid shard '{shard_label}', expected 1 <= i <= n")
+    return (_index, _total)
+
+
+def filter_shard(candidates, root_candidates, shard_index, n_shards) -> List:
+    """Pick candidates belonging to shard 'shard_index' of 'n_shards'
+
+    Partition by hash of the candidate's path relative to root_candidates,
+    therefore each job gets the same partition, regardless of where the
+    data is mounted or in which order it was gathered.
+    """
+
+    _root = root_candidates if os.path.isdir(root_candidates) else os.path.dirname(root_candidates)
+    _shard = []
+    for _entry in candidates:
+        _rel_path = os.path.relpath(str(_entry.path_c), _root).replace(os.sep, '/')
+        _digest = hashlib.sha1(_rel_path.encode('utf-8')).hexdigest()
+        if int(_digest, 16) % n_shards == shard_index - 1:
+            _shard.append(_entry)
+    return _shard
+
+
+def parse_timeout(label) -> float:
+    """Parse duration like '60s', '2m' or '90' (seconds)
+    into positive seconds"""
+
+    _match = re.match(r'^\s*(\d+(?:\.\d+)?)\s*(s|m|h)?\s*$', str(label))
+    if not _match or float(_match[1]) <= 0:
+        raise ValueError(f"invalid timeout '{label}', expected positive duration like '60s' or '2m'")
+    return float(_match[1]) * {None: 1, 's': 1, 'm': 60, 'h': 3600}[_match[2]]
+
+
+def timeout_supported() -> bool:
+    """Whether calls can be aborted by timer signal,
+    i.e. not on Windows or off main thread"""
+
+    return hasattr(signal, 'setitimer') and threading.current_thread() is threading.main_thread()
+
+
+def run_limited(seconds, func, *args):
+    """Call func with args, raise EvalTimeoutError if it
+    takes longer than seconds (if set and supported)
+
+    Long running native code gets aborted only after it
+    returns control, like edit distance of a whole page"""
+
+    if not seconds or not timeout_supported():
+        return func(*args)
+
+    def _abort(_signum, _frame):
+        raise EvalTimeoutError(f"exceeded {seconds}s")
+
+    _previous = signal.signal(signal.SIGALRM, _abort)
+    signal.setitimer(signal.ITIMER_REAL, seconds)
+    try:
+        return func(*args)
+    finally:
+        signal.setitimer(signal.ITIMER_REAL, 0)
+        signal.signal(signal.SIGALRM, _previous)
+
+
+def resource_usage(started) -> dict:
+    """Wall time since started (perf_counter), CPU time of
+    run including worker processes and peak resident memory
+    (MiB) of main process and largest worker, where known
+
+    Workers count only after they're finished, memory is
+    unknown on platforms without resource module (Windows)"""
+
+    _usage = {'wall_seconds': round(time.perf_counter() - started, 3),
+              'cpu_seconds': round(time.process_time(), 3),
+              'peak_rss_mib': None,
+              'peak_rss_worker_mib': None}
+    try:
+        import resource
+    except ImportError:
+        return _usage
+    (_self, _workers) = (resource.getrusage(resource.RUSAGE_SELF),
+                         resource.getrusage(resource.RUSAGE_CHILDREN))
+    # max RSS comes in bytes on macOS, KiB otherwise
+    _per_mib = 1024 * 1024 if sys.platform == 'darwin' else 1024
+    _usage['cpu_seconds'] = round(_self.ru_utime + _self.ru_stime + _workers.ru_utime + _workers.ru_stime, 3)
+    _usage['peak_rss_mib'] = round(_self.ru_maxrss / _per_mib, 1)
+    if _workers.ru_maxrss:
+        _usage['peak_rss_worker_mib'] = round(_workers.ru_maxrss / _per_mib, 1)
+    return _usage
+
+
 def find_groundtruth(path_candidate, root_candidates, root_groundtruth):
     file_name = os.path.basename(path_candidate)
     file_dir = os.path.dirname(path_candidate)
@@ -152,9 +419,9 @@ def match_candidates(path_candidates, path_gt_file):
     '''Find candidates that match groundtruth'''
 
     if not os.path.isdir(path_candidates):
-        raise IOError('invalid ocr result path "{}"'.format(path_candidates))
+        raise EvalIOError('invalid ocr result path "{}"'.format(path_candidates))
     if not os.path.exists(path_gt_file):
-        raise IOError(
+        raise EvalIOError(
             'invalid groundtruth data path "{}"'.format(path_gt_file))
 
     gt_filename = os.path.basename(path_gt_file)
@@ -238,7 +505,7 @@ def get_bbox_data(file_path):
     '''Get Bounding Box Data from given resource, if any exists'''
 
     if not os.path.exists(file_path):
-        raise IOError('{} not existing!'.format(file_path))
+        raise EvalIOError('{} not existing!'.format(file_path))
 
     # 1: inspect filename
     file_name = os.path.basename(file_path)
@@ -255,37 +522,49 @@ def get_bbox_data(file_path):
         # rather brute force approach
         # to recognize OCR formats inside
         start_token = _handle.read(128)
-        
-        # switch by estimated ocr format
-        if 'alto' in start_token:
-            # legacy: read from custom ALTO meta data
-            root_element = ET.parse(file_path).getroot()
-            element = root_element.find(
-                './/alto:Tags/alto:OtherTag[@ID="ulb_groundtruth_points"]', XML_NS)
-            if element is not None:
-                points = element.attrib['VALUE'].split(' ')
-                _p1 = points[0].split(',')
-                p1 = (int(_p1[0]), int(_p1[1]))
-                _p2 = points[2].split(',')
-                p2 = (int(_p2[0]), int(_p2[1]))
-                return (p1, p2)
-
-            # read from given alto coordinates
-            raw_elements = root_element.findall('.//alto:String', XML_NS)
-            non_empty = [s for s in raw_elements if s.attrib['CONTENT'].strip(
-            ) and re.match(r'[^\d]', s.attrib['CONTENT'])]
-            return extract_from_geometric_data(non_empty, _map_alto)
-
-        elif 'PcGts' in start_token:
-            # read from given page coordinates
-            doc_root = xml.dom.minidom.parse(file_path).documentElement
-            name_space = doc_root.namespaceURI
-            root_element = ET.parse(file_path).getroot()
-            _xpr_coords = f'.//{{{name_space}}}TextLine/{{{name_space}}}Coords'
-            raw_elements = root_element.findall(_xpr_coords)
-            if not raw_elements:
-                raise RuntimeError(f"{file_path} missing {_xpr_coords} !")
-            return extract_from_geometric_data(raw_elements, _map_page2013)
+    try:
+        return _get_geometric_bbox_data(file_path, start_token)
+    except ET.ParseError as exc:
+        raise EvalParseError(f"{file_path}: {exc}", 'xml', exc.position) from exc
+    except xml.parsers.expat.ExpatError as exc:
+        raise EvalParseError(f"{file_path}: {exc}", 'xml', (exc.lineno, exc.offset)) from exc
+
+
+def _get_geometric_bbox_data(file_path, start_token):
+    """Bounding box from geometric data of ALTO or PAGE"""
+
+    # switch by estimated ocr format
+    if 'alto' in start_token:
+        # legacy: read from custom ALTO meta data
+        root_element = ET.parse(file_path).getroot()
+        # respect ALTO version, like V4 written by model
+        _ns = {'alto': root_element.tag[1:].split('}')[0]} if root_element.tag.startswith('{') else XML_NS
+        element = root_element.find(
+            './/alto:Tags/alto:OtherTag[@ID="ulb_groundtruth_points"]', _ns)
+        if element is not None:
+            points = element.attrib['VALUE'].split(' ')
+            _p1 = points[0].split(',')
+            p1 = (int(_p1[0]), int(_p1[1]))
+            _p2 = points[2].split(',')
+            p2 = (int(_p2[0]), int(_p2[1]))
+            return (p1, p2)
+
+        # read from given alto coordinates
+        raw_elements = root_element.findall('.//alto:String', _ns)
+        non_empty = [s for s in raw_elements if s.attrib['CONTENT'].strip(
+        ) and re.match(r'[^\d]', s.attrib['CONTENT'])]
+        return extract_from_geometric_data(non_empty, _map_alto)
+
+    elif 'PcGts' in start_token:
+        # read from given page coordinates
+        doc_root = xml.dom.minidom.parse(file_path).documentElement
+        name_space = doc_root.namespaceURI
+        root_element = ET.parse(file_path).getroot()
+        _xpr_coords = f'.//{{{name_space}}}TextLine/{{{name_space}}}Coords'
+        raw_elements = root_element.findall(_xpr_coords)
+        if not raw_elements:
+            raise EvalParseError(f"{file_path} missing {_xpr_coords} !", 'page')
+        return extract_from_geometric_data(raw_elements, _map_page2013)
 
     return None
 
@@ -319,43 +598,50 @@ def extract_from_geometric_data(elements: List[ET.Element], map_func) -> Tuple[i
 class OCRData:
     ''''Represents Groundtruth Data Item'''
 
-    def __init__(self, path_in):
+    def __init__(self, path_in, missing_content=MISSING_CONTENT_SKIP):
         self.blocks = []
         self.path_in = path_in
+        self.missing_content = missing_content
         self.page_dimensions = None
         self.type_data = None
         self.type_groundtruth = NOT_SET
         self._get_groundtruth_from_filename()
         self.log_level = 0
+        # non-fatal problems of data, like elements
+        # skipped, collected rather than swallowed
+        self.warnings = []
         self._read_data()
 
     def set_log_level(self, log_level):
         self.log_level = log_level
 
+    def _warn(self, message):
+        self.warnings.append(message)
+        if self.log_level > 1:
+            print(f'[TRACE]({self.path_in}) {message}')
+
     def _get_groundtruth_from_filename(self):
-        file_name = os.path.basename(self.path_in)
-        result = re.match(r'.*gt.(\w{3,}).xml$', file_name)
-        if result:
-            self.type_groundtruth = result[1]
-        else:
-            alternative = re.match(r'.*\.(\w{3,})\.gt\.xml$', file_name)
-            if alternative:
-                self.type_groundtruth = alternative[1]
+        self.type_groundtruth = gt_type_from_filename(self.path_in) or self.type_groundtruth
 
     def _read_data(self):
         doc_root = xml.dom.minidom.parse(self.path_in).documentElement
         if doc_root is None:
-            raise RuntimeError('invalid document root')
+            raise EvalParseError('invalid document root', 'xml')
         name_space = doc_root.getAttribute('xmlns')
-        if doc_root.localName == 'alto':
-            self._extract_alto_data(doc_root)
-        elif name_space == PAGE_2013:
-            self._extract_page_data(doc_root)
-        elif doc_root.localName == 'PcGts':
-            self._extract_page_data(doc_root, ns='pc:')
-        else:
-            raise RuntimeError(
-                'Unknown Data-Format "{}" in "{}"'.format(doc_root.localName, self.path_in))
+        _format = 'alto' if doc_root.localName == 'alto' else 'page'
+        try:
+            if doc_root.localName == 'alto':
+                return self._extract_alto_data(doc_root)
+            elif name_space == PAGE_2013:
+                return self._extract_page_data(doc_root)
+            elif doc_root.localName == 'PcGts':
+                return self._extract_page_data(doc_root, ns='pc:')
+        except EvalParseError:
+            raise
+        except RuntimeError as exc:
+            raise EvalParseError(str(exc), _format) from exc
+        raise EvalUnsupportedError(
+            'Unknown Data-Format "{}" in "{}"'.format(doc_root.localName, self.path_in))
 
     def _extract_alto_data(self, doc_root):
         # handle groundtruth type
@@ -366,7 +652,7 @@ class OCRData:
             if label:
                 self.type_groundtruth = label
             # new alto way
-            elif self.get_type_groundtruth is None:
+            else:
                 gt_els = [e for e in gt_type_el if e.getAttribute(
                     'ID') == "ulb_groundtruth_type"]
                 if len(gt_els) == 1:
@@ -391,31 +677,63 @@ class OCRData:
                 text_strings = text_line.getElementsByTagName('String')
                 for text_string in text_strings:
                     word_id = text_string.getAttribute('ID')
+                    if not text_string.hasAttribute('CONTENT'):
+                        ocr_word = self._alto_missing_content(word_id, text_string)
+                        if ocr_word is not None:
+                            ocr_line.add_word(ocr_word)
+                        continue
                     word_content = text_string.getAttribute('CONTENT')
                     if not word_content.strip():
-                        if self.log_level > 1:
-                            print('[TRACE]({}) ignore empty word "{}"'.format(
-                                self.path_in, word_id))
+                        self._warn(f'String@ID={word_id} with empty CONTENT, skipped')
                         continue
                     ocr_word = OCRWord(word_id, text_string)
+                    if ocr_word.confidence_invalid is not None:
+                        self._warn(f'String@ID={word_id} with invalid WC, confidence ignored')
                     ocr_line.add_word(ocr_word)
                 if len(ocr_line.words) > 0:
                     ocr_block.add_line(ocr_line)
                 else:
-                    if self.log_level > 1:
-                        print('[TRACE]({}) ignore empty line "{}"'.format(
-                            self.path_in, line_id))
+                    self._warn(f'TextLine@ID={line_id} without words, skipped')
             self.blocks.append(ocr_block)
 
+    def _alto_missing_content(self, word_id, text_string) -> OCRWord:
+        """Word of String without CONTENT by handling
+        missing_content, None if it's skipped"""
+
+        if self.missing_content == MISSING_CONTENT_ERROR:
+            raise EvalParseError(f'{self.path_in}: String@ID={word_id} without CONTENT', 'alto')
+        if self.missing_content == MISSING_CONTENT_EMPTY:
+            self._warn(f'String@ID={word_id} without CONTENT, read as empty')
+            ocr_word = OCRWord(word_id, text_string)
+            ocr_word.characters = ''
+            return ocr_word
+        _subs = text_string.getAttribute('SUBS_CONTENT').strip()
+        if self.missing_content == MISSING_CONTENT_SUBS and _subs:
+            self._warn(f'String@ID={word_id} without CONTENT, read SUBS_CONTENT')
+            ocr_word = OCRWord(word_id, text_string)
+            ocr_word.characters = _subs
+            return ocr_word
+        self._warn(f'String@ID={word_id} without CONTENT, skipped')
+        return None
+
     def _extract_page_data(self, doc_root, ns=''):
         page_one = doc_root.getElementsByTagName(ns+'Page')[0]
         self.page_dimensions = (int(page_one.getAttribute('imageWidth')), int(
             page_one.getAttribute('imageHeight')))
+        # handle groundtruth type annotated in metadata
+        for _metadata in doc_root.getElementsByTagName(ns+'Metadata'):
+            _items = (_metadata.getElementsByTagName(ns+'MetadataItem')
+                      + _metadata.getElementsByTagName(ns+'UserAttribute'))
+            for _item in _items:
+                if _item.getAttribute('name') == GT_TYPE_ANNOTATION and _item.getAttribute('value'):
+                    self.type_groundtruth = _item.getAttribute('value')
         blocks = doc_root.getElementsByTagName(ns+'TextRegion')
         blocks.extend (doc_root.getElementsByTagName(ns+'TableRegion'))
         for block in blocks:
             block_id = block.getAttribute('id')
             ocr_block = OCRRegion(block_id, block)
+            if ocr_block.p1 is None:
+                self._warn(f'{block.localName}@id={block_id} without coordinates')
             cured_lines = block.getElementsByTagName(ns+'TextLine')
             for text_line in cured_lines:
                 line_id = text_line.getAttribute('id')
@@ -426,15 +744,22 @@ class OCRData:
                     for word_token in word_tokens:
                         word_id = word_token.getAttribute('id')
                         ocr_word = OCRWord(word_id, word_token)
+                        if not ocr_word.get_characters():
+                            self._warn(f'Word@id={word_id} without text, skipped')
+                            continue
+                        if ocr_word.p1 is None:
+                            self._warn(f'Word@id={word_id} without coordinates')
+                        if ocr_word.confidence_invalid is not None:
+                            self._warn(f'Word@id={word_id} with invalid conf, confidence ignored')
                         ocr_line.add_word(ocr_word)
                 # 2. inspect PAGE on line level
                 else:
                     ocr_line = OCRWordLine(line_id, text_line)
+                    if ocr_line.p1 is None:
+                        self._warn(f'TextLine@id={line_id} without coordinates')
                 # final inspection
                 if not ocr_line or not ocr_line.contains_text():
-                    if self.log_level > 1:
-                        print('[TRACE]({}) ignore empty line "{}"'.format(
-                            self.path_in, line_id))
+                    self._warn(f'TextLine@id={line_id} without text, skipped')
                     continue
                 ocr_block.add_line(ocr_line)
             self.blocks.append(ocr_block)
@@ -447,29 +772,7 @@ class OCRData:
         return self.type_groundtruth
 
     def filter_all(self, coords_start, coords_end):
-        all_lines = self.get_lines()
-        filter_box = BoundingBox(coords_start, coords_end)
-
-        def centroid(bbox):
-            x = bbox.p1[0] + int((bbox.p2[0] - bbox.p1[0]) / 2)
-            y = bbox.p1[1] + int((bbox.p2[1] - bbox.p1[1]) / 2)
-            return (x, y)
-
-        filter_lines = []
-        for line in all_lines:
-            new_line = OCRWordLine(line.id)
-            if not isinstance(line.words, str):
-                for _word in line.words:
-                    c = centroid(_word)
-                    if filter_box.contains(BoundingBox(c, c)):
-                        new_line.add_word(_word)
-                if new_line.words:
-                    filter_lines.append(new_line)
-            elif isinstance(line.words, str):
-                c = centroid(line)
-                if filter_box.contains(BoundingBox(c, c)):
-                    filter_lines.append(line)
-        return filter_lines
+        return filter_lines(self.get_lines(), coords_start, coords_end)
 
     def get_lines_text(self) -> List[str]:
         the_lines = self.get_lines()
@@ -479,41 +782,366 @@ class OCRData:
         return self.page_dimensions
 
 
-def ocr_to_text(file_path, coords=None, oneliner=False) -> Tuple:
-    """Create representation which contains
-    * groundtruth type (if annotated)
-    * groundtruth text (as string or list of lines)
-    * number of text lines
+def filter_lines(all_lines, coords_start, coords_end) -> List[OCRWordLine]:
+    """Lines (or parts of them) with centroid inside frame"""
+
+    filter_box = BoundingBox(coords_start, coords_end)
+
+    def centroid(bbox):
+        x = bbox.p1[0] + int((bbox.p2[0] - bbox.p1[0]) / 2)
+        y = bbox.p1[1] + int((bbox.p2[1] - bbox.p1[1]) / 2)
+        return (x, y)
+
+    _filtered = []
+    for line in all_lines:
+        new_line = OCRWordLine(line.id)
+        new_line.region = line.region
+        if not isinstance(line.words, str):
+            for _word in line.words:
+                c = centroid(_word)
+                if filter_box.contains(BoundingBox(c, c)):
+                    new_line.add_word(_word)
+            if new_line.words:
+                _filtered.append(new_line)
+        elif isinstance(line.words, str):
+            c = centroid(line)
+            if filter_box.contains(BoundingBox(c, c)):
+                _filtered.append(line)
+    return _filtered
+
+
+class OCRDataReader(FormatReader):
+    """Read ALTO or PAGE with OCRData
+
+    missing_content: handling of ALTO String
+                     without CONTENT, like 'subs'
     """
 
-    gt_type = NOT_SET
-    try:
-        ocr_data = OCRData(file_path)
+    suffixes = ['.xml']
 
-        # optional groundtruth type
-        _type = ocr_data.get_type_groundtruth()
-        if _type:
-            gt_type = _type
+    def __init__(self, missing_content=MISSING_CONTENT_SKIP):
+        self.missing_content = missing_content
 
-        # optional filter frame
+    def read(self, path_in, coords=None, warnings=None) -> Tuple[str, List]:
+        try:
+            ocr_data = OCRData(path_in, self.missing_content)
+        except xml.parsers.expat.ExpatError as exc:
+            raise EvalParseError(str(exc), 'xml', (exc.lineno, exc.offset)) from exc
+        if warnings is not None:
+            warnings.extend(ocr_data.warnings)
+        gt_type = ocr_data.get_type_groundtruth() or NOT_SET
         if coords:
             (coords_start, coords_end) = coords
-            lines = ocr_data.filter_all(coords_start, coords_end)
-        else:
-            lines = ocr_data.get_lines()
+            return (gt_type, ocr_data.filter_all(coords_start, coords_end))
+        return (gt_type, ocr_data.get_lines())
 
-        if oneliner:
-            return (gt_type, ' '.join([c.get_text() for c in lines]), len(lines))
+    def line_text(self, line) -> str:
+        return line.get_text()
+
+    def line_region(self, line):
+        return line.region
+
+
+class AltoReader(OCRDataReader):
+
+    name = 'alto'
+
+    def sniff(self, path_in, head) -> bool:
+        return '<alto' in head
+
+
+class PageReader(OCRDataReader):
+
+    name = 'page'
+
+    def sniff(self, path_in, head) -> bool:
+        return 'PcGts' in head
+
+
+class HocrReader(FormatReader):
+    """Read words of hOCR lines"""
+
+    name = 'hocr'
+    suffixes = ['.hocr']
+
+    def sniff(self, path_in, head) -> bool:
+        return str(path_in).endswith('.hocr') or 'ocr_page' in head or 'ocr-system' in head
+
+    def read(self, path_in, coords=None, warnings=None) -> Tuple[str, List]:
+        try:
+            doc_root = xml.dom.minidom.parse(path_in).documentElement
+        except xml.parsers.expat.ExpatError as exc:
+            raise EvalParseError(str(exc), 'hocr', (exc.lineno, exc.offset)) from exc
+        warnings = [] if warnings is None else warnings
+        lines = []
+        for _element in doc_root.getElementsByTagName('span') + doc_root.getElementsByTagName('div'):
+            if not set(_element.getAttribute('class').split()) & set(HOCR_CLASSES_LINE):
+                continue
+            ocr_line = OCRWordLine(_element.getAttribute('id'))
+            for _word in _element.getElementsByTagName('span'):
+                if HOCR_CLASS_WORD in _word.getAttribute('class').split():
+                    ocr_word = OCRWord(_word.getAttribute('id'), _word)
+                    if ocr_word.get_characters():
+                        ocr_line.add_word(ocr_word)
+                    else:
+                        warnings.append(f"span@id={_word.getAttribute('id')} without text, skipped")
+            if ocr_line.contains_text():
+                lines.append(ocr_line)
+        if coords:
+            (coords_start, coords_end) = coords
+            lines = filter_lines(lines, coords_start, coords_end)
+        return (NOT_SET, lines)
+
+    def line_text(self, line) -> str:
+        return line.get_text()
+
+
+class TextReader(FormatReader):
+    """Read plain text, anything not looking like markup"""
+
+    name = 'text'
+
+    def sniff(self, path_in, head) -> bool:
+        return not head.lstrip('\ufeff \t\r\n').startswith('<')
+
+    def read(self, path_in, coords=None, warnings=None) -> Tuple[str, List]:
+        with open(path_in, mode='r', encoding='utf-8') as fhandle:
+            return (NOT_SET, fhandle.readlines())
+
+
+for _reader in [TextReader(), HocrReader(), PageReader(), AltoReader()]:
+    register_reader(_reader)
+
+
+class DocumentStatistics:
+    """Size of text read from single file, i.e. chars
+    and words of it's lines with text, without line
+    breaks, and regions containing them, if any.
+    Chars are counted in units, i.e. codepoints or
+    graphemes, like character based metrics do"""
+
+    def __init__(self, n_chars=0, n_lines=0, n_words=0, n_regions=0, units=UNITS_CODEPOINTS):
+        self.n_chars = n_chars
+        self.n_lines = n_lines
+        self.n_words = n_words
+        self.n_regions = n_regions
+        self.units = units
+
+    def count(self, reader, lines):
+        """Add lines as read by reader"""
+
+        _regions = []
+        for _line in lines:
+            _text = reader.line_text(_line)
+            if not _text.strip():
+                continue
+            self.n_chars += count_units(_text, self.units)
+            self.n_words += len(_text.split())
+            self.n_lines += 1
+            _region = reader.line_region(_line)
+            if _region is not None and not any(_region is _r for _r in _regions):
+                _regions.append(_region)
+        self.n_regions += len(_regions)
+
+    def add(self, other):
+        """Pool counts of other statistics"""
+
+        self.n_chars += other.n_chars
+        self.n_lines += other.n_lines
+        self.n_words += other.n_words
+        self.n_regions += other.n_regions
+
+    def __eq__(self, other) -> bool:
+        return isinstance(other, DocumentStatistics) and vars(self) == vars(other)
+
+    def __str__(self) -> str:
+        return f'{self.n_chars} chars, {self.n_words} words, {self.n_lines} lines, {self.n_regions} regions'
+
+
+def ocr_to_text(file_path, coords=None, oneliner=False, data_format=None, line_separator=' ',
+                separators: Separators = None, warnings: List[str] = None,
+                statistics: DocumentStatistics = None, min_confidence=None, words: List = None,
+                read_lines: List = None) -> Tuple:
+    """Create representation which contains
+    * groundtruth type (if annotated)
+    * groundtruth text (as string or list of lines)
+    * number of text lines
+
+    Format gets detected unless forced by data_format,
+    oneliner joins lines by line_separator, or words,
+    lines and regions by separators, if given.
+    Non-fatal problems reading data get appended
+    to warnings, and size of text read gets counted
+    by statistics, if given. Words with confidence
+    below min_confidence, if given, are dropped. Words
+    kept get appended to words as (text, confidence)
+    in order read, if given, and lines with text kept
+    to read_lines as (text, region, bounding box), if
+    given, words joined by separators.word.
+    """
+
+    reader = get_reader(data_format) if data_format else detect_reader(file_path)
+    try:
+        if warnings is None:
+            (gt_type, lines) = reader.read(file_path, coords)
         else:
-            return (gt_type, lines, len(lines))
-    except xml.parsers.expat.ExpatError as _:
-        with open(file_path, mode='r', encoding='utf-8') as fhandle:
-            text_lines = fhandle.readlines()
-            if oneliner:
-                text_lines = ' '.join([l.strip() for l in text_lines])
-            return (gt_type, text_lines, len(text_lines))
+            (gt_type, lines) = reader.read(file_path, coords, warnings)
+    except EvalError as exc:
+        raise _with_path(exc, file_path) from exc
     except RuntimeError as exc:
         raise RuntimeError(f"{file_path}: {exc}") from exc
+    if min_confidence is not None:
+        lines = drop_uncertain_words(lines, min_confidence)
+    if words is not None:
+        words += [(_w.get_characters(), _w.confidence) for _line in lines
+                  if isinstance(_line, OCRWordLine) and not isinstance(_line.words, str)
+                  for _w in _line.words if isinstance(_w, OCRWord) and _w.get_characters()]
+    if statistics is not None:
+        statistics.count(reader, lines)
+    if read_lines is not None:
+        _word = separators.word if separators else ' '
+        read_lines += [(_word.join(reader.line_text(_line).split()), reader.line_region(_line), _box_of(_line))
+                       for _line in lines if reader.line_text(_line).strip()]
+    # formats without annotations, like plain text
+    if not gt_type or gt_type == NOT_SET:
+        gt_type = gt_type_from_filename(file_path) or NOT_SET
+    if oneliner:
+        _separators = separators or Separators(line=line_separator, region=line_separator)
+        return (gt_type, join_lines(reader, lines, _separators), len(lines))
+    return (gt_type, lines, len(lines))
+
+
+def _box_of(token) -> BoundingBox:
+    """Bounding box of line or region read,
+    None if it lacks coordinates"""
+
+    (_p1, _p2) = (getattr(token, 'p1', None), getattr(token, 'p2', None))
+    if not _p1 or not _p2:
+        return None
+    return BoundingBox((min(_p1[0], _p2[0]), min(_p1[1], _p2[1])), (max(_p1[0], _p2[0]), max(_p1[1], _p2[1])))
+
+
+def read_regions(read_lines) -> List[Tuple[str, BoundingBox, str]]:
+    """ID, bounding box (None without coordinates) and
+    text (lines separated by newline) of regions of
+    lines as read, each run of lines of same region
+    making one, empty if lines have no regions"""
+
+    _regions = []
+    _previous = None
+    for (_text, _region, _) in read_lines:
+        if _region is None:
+            return []
+        if _region is _previous:
+            _regions[-1][2].append(_text)
+        else:
+            _regions.append((_region.id, _box_of(_region), [_text]))
+        _previous = _region
+    return [(_id, _box, '\n'.join(_texts)) for (_id, _box, _texts) in _regions]
+
+
+def read_region_text(read_lines, region_ids, separators: Separators = None) -> Tuple:
+    """Text of regions with given IDs like get_region_text,
+    but from lines as read (text, region, bounding box),
+    so file doesn't get parsed again"""
+
+    _ids = set(region_ids)
+    (_regions, _boxes) = ([], [])
+    for (_text, _region, _box) in read_lines:
+        if _region is None or _region.id not in _ids:
+            continue
+        if _regions and _regions[-1][0] is _region:
+            _regions[-1][1].append(_text)
+        else:
+            _regions.append((_region, [_text]))
+            _boxes.append(_box_of(_region))
+        _boxes.append(_box)
+    if not _regions:
+        return (None, None)
+    # frame covers regions' boxes as read and their lines
+    _boxes = [_b for _b in _boxes if _b is not None]
+    _frame = None
+    if _boxes:
+        _frame = ((min(_b.p1[0] for _b in _boxes), min(_b.p1[1] for _b in _boxes)),
+                  (max(_b.p2[0] for _b in _boxes), max(_b.p2[1] for _b in _boxes)))
+    _separators = separators or Separators()
+    return (_separators.region.join(_separators.line.join(_texts) for (_, _texts) in _regions), _frame)
+
+
+def drop_uncertain_words(lines, min_confidence) -> List:
+    """Lines without words of confidence below
+    min_confidence, lines left without any word
+    dropped, too, lines of plain text kept"""
+
+    _kept = []
+    for _line in lines:
+        if isinstance(_line, OCRWordLine):
+            _line.drop_words_below(min_confidence)
+            if not _line.contains_text():
+                continue
+        _kept.append(_line)
+    return _kept
+
+
+def join_lines(reader, lines, separators: Separators) -> str:
+    """Text of lines read by reader, words joined by
+    separators.word, lines by separators.line and lines
+    of different regions by separators.region"""
+
+    _texts = []
+    _previous = None
+    for _line in lines:
+        _text = reader.line_text(_line)
+        if separators.word != ' ':
+            _text = separators.word.join(_text.split())
+        _region = reader.line_region(_line)
+        if _texts:
+            _texts.append(separators.line if _region is _previous else separators.region)
+        _texts.append(_text)
+        _previous = _region
+    return ''.join(_texts)
+
+
+def parse_warning_counts(warnings) -> Counter:
+    """Number of parse warnings by kind, i.e. their
+    message without IDs of elements concerned"""
+
+    return Counter(re.sub(r'@\w+=\S*', '', _w) for _w in warnings)
+
+
+def _with_path(exc: EvalError, file_path) -> EvalError:
+    """Same error with path of affected file prepended"""
+
+    _error = copy.copy(exc)
+    _error.args = (f"{file_path}: {exc}",) + exc.args[1:]
+    return _error
+
+
+def get_region_text(file_path, region_ids, separators: Separators = None) -> Tuple:
+    """Text of regions with given IDs in document order
+    (joined by separators, if given, otherwise by spaces)
+    together with frame ((x0, y0), (x1, y1)) covering all
+    of them, (None, None) if document contains none of them
+    """
+
+    _ids = set(region_ids)
+    _regions = []
+    _stack = [to_pieces(file_path)]
+    while _stack:
+        _current = _stack.pop()
+        if _current.type == PieceType.REGION and _current.id in _ids:
+            _regions.append(_current)
+            continue
+        _stack += reversed(_current.pieces)
+    if not _regions:
+        return (None, None)
+    _points = [_p for _r in _regions for _p in _r.dimensions]
+    _frame = None
+    if _points:
+        (_x0, _y0, _x1, _y1) = _bounding_box(_points)
+        _frame = ((_x0, _y0), (_x1, _y1))
+    _separators = separators or Separators()
+    return (_separators.region.join(_r.text(_separators) for _r in _regions), _frame)
 
 
 def review2(file_path, frame=None, oneliner=True) -> Tuple:
@@ -545,12 +1173,16 @@ def review2(file_path, frame=None, oneliner=True) -> Tuple:
             return (gt_type, top_piece.transcription, len(the_lines))
         else:
             raise RuntimeError("not implemented")
-    except xml.parsers.expat.ExpatError as _:
+    except xml.parsers.expat.ExpatError as exc:
+        if str(file_path).endswith('.xml'):
+            raise EvalParseError(f"{file_path}: {exc}", 'xml', (exc.lineno, exc.offset)) from exc
         with open(file_path, mode='r', encoding='utf-8') as fhandle:
             text_lines = fhandle.readlines()
             if oneliner:
                 text_lines = ' '.join([l.strip() for l in text_lines])
             return (gt_type, text_lines, len(text_lines))
+    except EvalError as exc:
+        raise _with_path(exc, file_path) from exc
     except RuntimeError as exc:
         raise RuntimeError(f"{file_path}: {exc}") from exc
 
@@ -581,13 +1213,29 @@ def _uplete(curr):
         _uplete(_pa)
 
 
+def gt_type_from_filename(path_in):
+    """Groundtruth type label from file name, like
+    'page.gt.art1.xml' or 'page.art.gt.txt', None if
+    file name doesn't tell"""
+
+    file_name = os.path.basename(str(path_in))
+    result = re.match(r'.*gt.(\w{3,})\.\w+$', file_name)
+    if result:
+        return result[1]
+    alternative = re.match(r'.*\.(\w{3,})\.gt\.\w+$', file_name)
+    if alternative:
+        return alternative[1]
+    return None
+
+
 def _normalize_gt_type(label) -> str:
-    if label.startswith('art'):
-        return 'article'
-    elif label.startswith('ann'):
-        return 'announcement'
-    else:
+    _label = label.strip().lower()
+    if len(_label) < GT_TYPE_PREFIX:
         return NOT_SET
+    for _type in GT_TYPES:
+        if _label.startswith(_type[:GT_TYPE_PREFIX]):
+            return _type
+    return NOT_SET
 
 
 class EvaluationResult:
@@ -609,12 +1257,28 @@ class EvaluationResult:
         self.n_chars = n_chars
         self.n_lines = n_lines
         self.mean = 0.0
+        # pooled over all reference units (micro average)
+        # rather than averaged per item (macro average)
+        self.micro_mean = 0.0
         self.std = 0.0
         self.median = 0.0
         # set special descendant from same type
         # to hold optional metrics regarding
         # removed outliers
         self.cleared_result = None
+        # candidates removed as outliers
+        self.outliers = []
+        # edit operations pooled over all items,
+        # if known for each of them
+        self.operations = None
+        # reference statistics pooled over all
+        # items, if known for each of them
+        self.statistics = None
+
+    def get_mean(self, average=AVERAGE_MACRO) -> float:
+        """Mean by averaging, macro if both requested"""
+
+        return self.micro_mean if average == AVERAGE_MICRO else self.mean
 
     def get_defaults(self):
         '''Provide default data (eval_key, number of elements, mean) that must be available'''
@@ -622,6 +1286,36 @@ class EvaluationResult:
         return (self.eval_key, self.n_total, self.mean, self.median, self.n_chars)
 
 
+def metric_settings(metric) -> dict:
+    """Settings of metric, i.e. anything but outcome
+    of latest calculation"""
+
+    return _set_only({_k: _v for (_k, _v) in vars(metric).items() if _k not in METRIC_OUTCOME_FIELDS})
+
+
+def _set_only(settings) -> dict:
+    """Settings without optional ones by their default"""
+
+    return {_k: _v for (_k, _v) in settings.items()
+            if _k not in FINGERPRINT_OPTIONAL or _v != FINGERPRINT_OPTIONAL[_k]}
+
+
+def _jsonable(value):
+    if isinstance(value, (set, frozenset)):
+        return sorted(value)
+    if hasattr(value, '__dict__'):
+        return vars(value)
+    return str(value)
+
+
+def config_fingerprint(config) -> str:
+    """Short hash of configuration, independent
+    of order of dict keys"""
+
+    _serialized = json.dumps(config, sort_keys=True, ensure_ascii=False, default=_jsonable)
+    return hashlib.sha256(_serialized.encode('utf-8')).hexdigest()[:FINGERPRINT_LENGTH]
+
+
 class EvalEntry:
     """Container to transform evaluation results into
     string representation"""
@@ -631,25 +1325,66 @@ class EvalEntry:
         self.path_g = None
         self.gt_type = NOT_SET
         self.metrics = []
+        # raw texts, kept for further analysis
+        self.txt_reference = None
+        self.txt_candidate = None
+        # token offsets (start, end) of candidate
+        # window evaluated against snippet groundtruth
+        self.window = None
+        # seconds spent per phase ('read', 'metrics')
+        self.timings = {}
+        # label of publication era, if known
+        self.era = None
+        # whether evaluation exceeded time budget per
+        # file, then it lacks any metrics
+        self.timed_out = False
+        # problems noticed while evaluating, which
+        # may explain poor results
+        self.warnings = []
+        # non-fatal problems reading either file, like
+        # elements skipped, prefixed by it's role
+        self.parse_warnings = []
+        # size of texts read from both files
+        self.statistics_reference = None
+        self.statistics_candidate = None
+        # optional metrics per groundtruth region
+        self.regions = None
+        # optional bounding boxes of groundtruth
+        # regions and lines with their scores
+        self.boxes = None
+        # region IDs of reference and candidate in order
+        # serialized, unless each kept it's own order
+        self.region_order = None
+        # hash of configuration evaluated with
+        self.fingerprint = None
+
+    @property
+    def word_alignment(self):
+        """Aligned tokens of word accuracy, i.e. (operation,
+        reference token, candidate token) in reading order,
+        None if word accuracy wasn't calculated"""
+
+        for _metric in self.metrics:
+            if DIAGNOSTIC_ALIGNMENT in _metric.diagnostics:
+                return _metric.diagnostics[DIAGNOSTIC_ALIGNMENT]
+        return None
 
     def __str__(self) -> str:
         """Dependency between metrics 
-        * 0=CA => 1=LA 
-        * 2=WA => 3=BOT
+        * CA => LA 
+        * WA => BOT
         """
-        _pres = [0, 2]
-        _accs = [1, 3]
+        _pres = {'CLA': 'CCA', 'WBoW': 'WWA'}
+        _vals = {}
         _raws = []
-        _pre_v = None
-        for i, m in enumerate(self.metrics):
+        for m in self.metrics:
             _val = m.value
-            _raw = f'{m.label}:{_val:5.2f}({m.n_ref})'
-            if i in _pres:
-                _pre_v = _val
-            if i in _accs and _pre_v is not None:
+            _raw = f'{m.label}:{_val:5.2f}({int(m.n_ref)})'
+            _pre_v = _vals.get(_pres.get(m.label))
+            if _pre_v is not None:
                 diff = round(_val, 3) - round(_pre_v, 3)
                 _raw += f'(+{diff:5.2f})' if diff > 0 else f'(-{diff:5.2f})'
-                _pre_v = None
+            _vals[m.label] = _val
             _raws.append(_raw)
         return ', '.join(_raws)
 
@@ -657,6 +1392,50 @@ class EvalEntry:
         return '{} {}'.format(self.gt_type, self.path_c)
 
 
+class RegionEntry:
+    """Metrics of single groundtruth region versus
+    candidate region matched to it, if any"""
+
+    def __init__(self, region_id, candidate_id=None, iou=0.0):
+        self.id = region_id
+        self.candidate_id = candidate_id
+        self.iou = iou
+        self.metrics = []
+
+    def __repr__(self) -> str:
+        return f'{self.id}:{self.candidate_id}'
+
+    def __str__(self) -> str:
+        return ', '.join(f'{_m.label}:{_m.value:5.2f}({int(_m.n_ref)})' for _m in self.metrics)
+
+
+class LayoutBox:
+    """Bounding box of single groundtruth region or line
+    with it's scores by metric label, if evaluated by unit,
+    like region metrics or line accuracies of 'LCA'"""
+
+    def __init__(self, kind, box_id, bbox: BoundingBox, parent=None):
+        self.kind = kind
+        self.id = box_id
+        self.bbox = bbox
+        # ID of region enclosing line
+        self.parent = parent
+        self.scores = {}
+
+    def __repr__(self) -> str:
+        return f'{self.kind}:{self.id}'
+
+
+def _same_metric(metrics, index, others):
+    """Metric of others like metrics[index], i.e. with
+    same label and occurrence, None if there's none"""
+
+    _label = metrics[index].label
+    _nth = [_m.label for _m in metrics[:index]].count(_label)
+    _same = [_m for _m in others if _m.label == _label]
+    return _same[_nth] if _nth < len(_same) else None
+
+
 class Evaluator:
     '''Evaluate candidate versus existing groundtruth data
     aggregates results on each directory, starting from root_groundtruth
@@ -669,20 +1448,114 @@ class Evaluator:
         self.evaluation_data = {}
         self.evaluation_results = []
         self.evaluation_map = {}
-        self.text_mode = extras == EVAL_EXTRA_IGNORE_GEOMETRY
+        _extras = [_e.strip() for _e in str(extras).split(EVAL_EXTRA_SEPARATOR)] if extras else []
+        self.text_mode = EVAL_EXTRA_IGNORE_GEOMETRY in _extras
+        self.best_window = EVAL_EXTRA_BEST_WINDOW in _extras
+        self.line_separator = '\n' if EVAL_EXTRA_KEEP_LINE_BREAKS in _extras else ' '
+        # strings joining words, lines and regions
+        self.separators = Separators(line=self.line_separator, region=self.line_separator)
         self.metrics = [MetricCA(), MetricLA(), MetricWA(), MetricBoW(),
                         MetricPre(), MetricRec(), MetricFM()]
+        # optional composite score, appended to each entry's metrics
+        self.score = None
+        # optional IDs of groundtruth regions to evaluate exclusively
+        self.region_ids = None
+        # optional format of candidates, detected if not set
+        self.data_format = None
+        # optional normalizations of both texts
+        self.normalizations = []
+        # optional abbreviations of both texts to expand
+        self.abbreviations = {}
+        # optional variants of chars or tokens, like '„',
+        # by canonical forms, applied to both texts
+        self.equivalences = {}
+        # optional publication eras, each with further
+        # normalizations, assigned by date from METS
+        self.eras = []
+        # data of whole corpus required by metrics,
+        # passed to each entry's context
+        self.corpus_options = {}
+        # optional max seconds to evaluate single entry,
+        # entries taking longer are kept aside as timed out
+        self.timeout_per_file = None
+        self.timed_out_entries = []
+        # whether to evaluate each groundtruth region, too
+        self.by_region = False
+        # receivers of each entry as soon as it's done,
+        # like sinks.ResultSink
+        self.sinks = []
+        # whether any parse warning fails the entry,
+        # like for format checks of deliveries
+        self.strict_parse = False
+        # units to count reference and candidate chars by
+        self.count_units = UNITS_CODEPOINTS
+        # whether to keep bounding boxes of groundtruth
+        # regions and lines, i.e. to draw scores over images
+        self.layout_boxes = False
+        # whose reading order serializes both texts
+        self.order_authority = ORDER_EACH
+        # whether to re-assemble candidate lines in
+        # regions of reference matched by geometry
+        self.project_candidate = False
+        # optional min confidence of candidate words,
+        # others are dropped before evaluation
+        self.min_word_confidence = None
+
+    def fingerprint(self) -> str:
+        """Hash of effective configuration, i.e. anything
+        affecting metric values, like metrics with their
+        settings, preprocessing and reading of texts"""
+
+        return config_fingerprint(_set_only({
+            'metrics': [metric_settings(_m) for _m in self.metrics],
+            'score': metric_settings(self.score) if self.score else None,
+            'text_mode': self.text_mode,
+            'best_window': self.best_window,
+            'separators': vars(self.separators),
+            'region_ids': self.region_ids,
+            'data_format': self.data_format,
+            'normalizations': self.normalizations or [],
+            'abbreviations': self.abbreviations or {},
+            'equivalences': self.equivalences or {},
+            'eras': [vars(_e) for _e in self.eras or []],
+            'count_units': self.count_units,
+            'order_authority': self.order_authority,
+            'project_candidate': self.project_candidate,
+            'min_word_confidence': self.min_word_confidence,
+        }))
+
+    def __getstate__(self):
+        # sinks stay with the main process, which
+        # reports entries, workers don't need them
+        _state = dict(self.__dict__)
+        _state['sinks'] = []
+        return _state
 
     def eval_all(self, entries: List[EvalEntry], sequential=False) -> None:
-        """remove all paths where no groundtruth exists"""
+        """remove all paths where no groundtruth exists,
+        unless there are reference-free metrics for them
+
+        At verbosity 1 each entry is reported as soon as it's
+        done, in order of paths even if evaluated in parallel
+        """
 
+        _reference_free = bool(self._reference_free_metrics())
+        entries = sorted([e for e in entries if e.path_g or _reference_free], key=lambda e: e.path_c)
+        n_entries = len(entries)
+        self.corpus_options = self._corpus_options(entries)
+        if self.timeout_per_file and not timeout_supported():
+            print("[WARN ] timeout per file not supported on this platform, ignore it")
         if sequential:
-            for e in entries:
-                if e.path_g:
-                    try:
-                        self.eval_entry(e)
-                    except Exception as exc:
-                        print(f"[WARN ] '{exc}'")
+            _entries = []
+            for (i, e) in enumerate(entries, 1):
+                try:
+                    _entry = self._eval_entry_limited(e)
+                    if _entry is not None:
+                        _entries.append(_entry)
+                        self._report_entry(_entry, i, n_entries)
+                except Exception as exc:
+                    print(f"[WARN ] '{exc}'")
+            self.evaluation_entries = self._put_aside_timed_out(_entries)
         else:
             cpus = cpu_count()
             n_executors = cpus - 1 if cpus > 3 else 1
@@ -691,49 +1564,159 @@ class Evaluator:
             _entries = []
             with ProcessPoolExecutor(max_workers=n_executors) as executor:
                 try:
-                    _entries = list(executor.map(self._wrap_eval_entry, entries, timeout=30))
+                    # results arrive in order of submission, so
+                    # report each as soon as all before are done
+                    # per file budget bounds whole run already
+                    _timeout = None if self.timeout_per_file else 30
+                    for (i, _entry) in enumerate(executor.map(self._wrap_eval_entry, entries,
+                                                              timeout=_timeout), 1):
+                        _entries.append(_entry)
+                        if _entry is not None:
+                            self._report_entry(_entry, i, n_entries)
                 except Exception as err:
                     print(f"[WARN ] '{err}' creating evaluation data")
             if _entries:
                 _not_nones = [e for e in _entries if e is not None]
                 if self.verbosity == 1:
                     print(f"[DEBUG] processed {len(_entries)}, omitted {len(_entries) - len(_not_nones)} empty results")
-                self.evaluation_entries = _not_nones
-        
-        # report
+                self.evaluation_entries = self._put_aside_timed_out(_not_nones)
         self.evaluation_entries = sorted(self.evaluation_entries, key=lambda e: e.path_c)
-        if self.verbosity >= 1:
-            for _eval_entry in self.evaluation_entries:
-                try:
-                    image_name = os.path.basename(_eval_entry.path_c)
-                    _type = _eval_entry.gt_type
-                    if '+' in image_name and '_' in image_name:
-                        _tkns = image_name.split('_')
-                        image_name = _tkns[0].replace('+',':') + '_' + _tkns[1]
-                    if '.xml' in image_name:
-                        image_name = image_name.replace('.xml', '')
-                    gt_label = f"({_type[:3]})" if _type and _type != NOT_SET else ''
-                    print(f'[DEBUG] [{image_name}]{gt_label} [{_eval_entry}]')
-                except Exception as exc:
-                    print(f'[WARN ] {exc}')
-        
+
+    def _put_aside_timed_out(self, entries) -> List[EvalEntry]:
+        """Keep timed out entries aside, since they lack
+        metrics to aggregate, and return all others"""
+
+        self.timed_out_entries = [e for e in entries if e.timed_out]
+        for _entry in self.timed_out_entries:
+            print(f"[WARN ] {_entry.path_c}: timed out after {self.timeout_per_file}s, skipped")
+        return [e for e in entries if not e.timed_out]
+
+    def _eval_entry_limited(self, entry: EvalEntry) -> EvalEntry:
+        """Evaluate entry within time budget (if any),
+        otherwise mark it as timed out"""
+
+        try:
+            return run_limited(self.timeout_per_file, self.eval_entry, entry)
+        except EvalTimeoutError:
+            entry.timed_out = True
+            entry.metrics = []
+            return entry
+
+    def _report_entry(self, entry: EvalEntry, i, n_entries):
+        for _sink in self.sinks:
+            try:
+                _sink.entry(entry, i, n_entries)
+            except Exception as exc:
+                print(f'[WARN ] {type(_sink).__name__}: {exc}')
+        if self.verbosity < 1 or entry.timed_out:
+            return
+        try:
+            image_name = os.path.basename(entry.path_c)
+            _type = entry.gt_type
+            if '+' in image_name and '_' in image_name:
+                _tkns = image_name.split('_')
+                image_name = _tkns[0].replace('+',':') + '_' + _tkns[1]
+            if '.xml' in image_name:
+                image_name = image_name.replace('.xml', '')
+            gt_label = f"({_type[:3]})" if _type and _type != NOT_SET else ''
+            _window = f' window {entry.window[0]}-{entry.window[1]}' if entry.window else ''
+            _counts = ', '.join(f'{_k}: {_n}' for (_k, _n) in parse_warning_counts(entry.parse_warnings).items())
+            _parse = f' {len(entry.parse_warnings)} parse warnings ({_counts})' if entry.parse_warnings else ''
+            print(f'[DEBUG] ({i}/{n_entries}) [{image_name}]{gt_label} [{entry}]{_window}{_parse}', flush=True)
+            for _region in entry.regions or []:
+                print(f'[DEBUG] ({i}/{n_entries}) [{image_name}] [{_region.id}] [{_region}]', flush=True)
+        except Exception as exc:
+            print(f'[WARN ] {exc}')
 
     def _wrap_eval_entry(self, entry: EvalEntry):
         """Wrapper for creation of evaluation data
         to be used in common process-pooling"""
         
-        if entry.path_g:
+        if entry.path_g or self._reference_free_metrics():
             try:
-                return self.eval_entry(entry)
+                return self._eval_entry_limited(entry)
             except Exception as exc:
                 print(f"[WARN ] _wrap' {exc}'")
 
+    def _normalizations_of(self, path_c) -> Tuple:
+        """Year and era of candidate's publication (if eras
+        defined) and all normalizations to apply"""
+
+        _normalizations = list(self.normalizations)
+        (_year, _era) = (None, None)
+        if self.eras:
+            _year = page_year(path_c)
+            _era = era_of(_year, self.eras)
+            if _era:
+                _normalizations += [_n for _n in _era.normalizations if _n not in _normalizations]
+        return (_year, _era, _normalizations)
+
+    def _breaks(self) -> Separators:
+        """Separators of texts as read, i.e. lines by
+        line break and regions by REGION_BREAK, unless
+        regions are separated like lines anyway"""
+
+        _region = '\n' if self.separators.region == self.separators.line else REGION_BREAK
+        return Separators(self.separators.word, '\n', _region)
+
+    def _separated(self, text) -> str:
+        """Text as read with line and region breaks
+        replaced by separators"""
+
+        if self.separators.region == self.separators.line:
+            return text.replace('\n', self.separators.line)
+        # at once, since separators may contain breaks themselves
+        return re.sub(f'{REGION_BREAK}|\n', lambda _m: self.separators.region if _m.group() == REGION_BREAK
+                      else self.separators.line, text)
+
+    def _reference_text(self, entry: EvalEntry):
+        """Groundtruth text as evaluated, i.e. restricted to
+        regions and normalized, None if there is none"""
+
+        (_, txt_gt, _) = ocr_to_text(entry.path_g, oneliner=True, separators=self.separators)
+        if self.region_ids:
+            txt_gt = get_region_text(entry.path_g, self.region_ids, self.separators)[0]
+        if not txt_gt:
+            return None
+        if self.abbreviations:
+            txt_gt = expand_abbreviations(txt_gt, self.abbreviations)
+        txt_gt = normalize_text(txt_gt, self._normalizations_of(entry.path_c)[2])
+        return apply_equivalences(txt_gt, self.equivalences)
+
+    def _corpus_options(self, entries: List[EvalEntry]) -> dict:
+        """Context options with corpus data required by
+        metrics, like document frequencies of terms"""
+
+        _metrics = [_m for _m in self.metrics if _m.corpus_based]
+        if not _metrics:
+            return {}
+        _texts = []
+        for _entry in [_e for _e in entries if _e.path_g]:
+            try:
+                _texts.append(self._reference_text(_entry))
+            except Exception as exc:
+                print(f"[WARN ] {_entry.path_g}: no corpus data: {exc}")
+        _texts = [_t for _t in _texts if _t]
+        _frequencies = {}
+        for _metric in _metrics:
+            _key = _metric.terms_key()
+            if _key not in _frequencies:
+                _frequencies[_key] = document_frequencies(_metric.terms(_t) for _t in _texts)
+        if self.verbosity >= 1:
+            print(f"[DEBUG] document frequencies of {len(_texts)} groundtruth texts for "
+                  f"{[_m.label for _m in _metrics]}")
+        return {OPTION_DOC_FREQUENCIES: _frequencies}
+
     def eval_entry(self, entry: EvalEntry) -> EvalEntry:
         """Create evaluation entry for matching pair of 
-        groundtruth and candidate data"""
+        groundtruth and candidate data, None if restricted
+        to regions the groundtruth doesn't contain"""
 
+        if not entry.path_g:
+            return self._eval_candidate(entry)
         path_g = entry.path_g
         path_c = entry.path_c
+        _started = time.perf_counter()
 
         # read coordinate information (if any provided)
         # to create frame for candidate data
@@ -742,44 +1725,325 @@ class Evaluator:
             print(f"[TRACE] token coordinates {coords[0]}, {coords[1]}")
 
         # load ground-thruth text
-        (gt_type, txt_gt, _) = ocr_to_text(path_g, oneliner=True)
+        _warnings_g = []
+        _lines_g = []
+        entry.statistics_reference = DocumentStatistics(units=self.count_units)
+        (gt_type, txt_gt, _) = ocr_to_text(path_g, oneliner=True, separators=self._breaks(),
+                                           warnings=_warnings_g, statistics=entry.statistics_reference,
+                                           read_lines=_lines_g)
         if not txt_gt:
-            raise RuntimeError(f"missing gt text from {path_g}!")
+            raise EvalMetricError(f"missing gt text from {path_g}!")
+
+        # optional: restrict to specific groundtruth regions
+        # and candidate to frame covering them
+        if self.region_ids:
+            (txt_gt, _frame) = read_region_text(_lines_g, self.region_ids, self._breaks())
+            if txt_gt is None:
+                if self.verbosity >= 1:
+                    print(f'[DEBUG] {os.path.basename(path_g)}: none of regions {self.region_ids}, skip')
+                return None
+            coords = _frame
         
         # if text mode is enforced
         # forget groundtruth coodinates
         coords = None if self.text_mode else coords
 
         # read candidate data as text
-        (_, txt_c, _) = ocr_to_text(path_c, coords, oneliner=True)
+        _warnings_c = []
+        _words_c = []
+        _lines_c = []
+        entry.statistics_candidate = DocumentStatistics(units=self.count_units)
+        (_, txt_c, _) = ocr_to_text(path_c, coords, oneliner=True, data_format=self.data_format,
+                                    separators=self._breaks(), warnings=_warnings_c,
+                                    statistics=entry.statistics_candidate,
+                                    min_confidence=self.min_word_confidence, words=_words_c,
+                                    read_lines=_lines_c)
+        # optional: serialize regions of both texts
+        # in reading order of reference or candidate
+        # or candidate projected into reference regions
+        if (self.order_authority != ORDER_EACH or self.project_candidate) and not self.region_ids:
+            (txt_gt, txt_c) = self._ordered_texts(entry, _lines_g, _lines_c, txt_gt, txt_c)
+        entry.parse_warnings = [f'{PARSE_WARNING_REFERENCE}: {_w}' for _w in _warnings_g] \
+            + [f'{PARSE_WARNING_CANDIDATE}: {_w}' for _w in _warnings_c]
+        if coords is not None and not txt_c.strip():
+            (_, _txt_all, _) = ocr_to_text(path_c, oneliner=True, data_format=self.data_format)
+            if _txt_all.strip():
+                entry.warnings.append('candidate has no text within frame of groundtruth')
         if self.verbosity >= 2:
             _label_ref = os.path.basename(path_g)
             _label_can = os.path.basename(path_c)
             print(f'[TRACE][{_label_ref}] RAW GROUNDTRUTH :: "{txt_gt}"')
             print(f'[TRACE][{_label_can}] RAW CANDIDATE   :: "{txt_c}"')
-
-        # fill metrics with life
-        for _m in self.metrics:
-             _m.input_reference = txt_gt
-             _m.input_candidate = txt_c
-             _m.calc()
-             if self.verbosity >= 2:
+            for _warning in entry.parse_warnings:
+                print(f'[TRACE][{_label_can}] PARSE WARNING :: {_warning}')
+        if self.strict_parse and entry.parse_warnings:
+            raise EvalParseError(f"{os.path.basename(path_c)}: {len(entry.parse_warnings)} parse warnings, "
+                                 f"first '{entry.parse_warnings[0]}'")
+
+        # optional: expand abbreviations
+        if self.abbreviations:
+            txt_gt = expand_abbreviations(txt_gt, self.abbreviations)
+            txt_c = expand_abbreviations(txt_c, self.abbreviations)
+
+        # optional: normalize texts, adding those
+        # for publication era of page if known
+        (_year, _era, _normalizations) = self._normalizations_of(path_c)
+        if self.eras:
+            entry.era = _era.label if _era else None
+            if self.verbosity >= 2:
+                print(f'[TRACE][{os.path.basename(path_c)}] year {_year}, era {entry.era}')
+        if _normalizations:
+            txt_gt = normalize_text(txt_gt, _normalizations)
+            txt_c = normalize_text(txt_c, _normalizations)
+
+        # optional: replace variants by canonical
+        # forms of user's equivalence table
+        if self.equivalences:
+            txt_gt = apply_equivalences(txt_gt, self.equivalences)
+            txt_c = apply_equivalences(txt_c, self.equivalences)
+
+        # keep lines for line based metrics, words read for
+        # confidence metrics and paths for reference-free
+        # and layout metrics, but join lines and regions
+        # by separators
+        _options = {OPTION_LINES: (txt_gt, txt_c), OPTION_PATH_CANDIDATE: path_c,
+                    OPTION_PATH_REFERENCE: path_g, OPTION_WORDS_CANDIDATE: _words_c}
+        txt_gt = self._separated(txt_gt)
+        txt_c = self._separated(txt_c)
+
+        # optional: restrict candidate to window
+        # matching snippet groundtruth best
+        if self.best_window:
+            _tokens = txt_c.split()
+            (_start, _end, _) = best_window(txt_gt.split(), _tokens)
+            entry.window = (_start, _end)
+            txt_c = ' '.join(_tokens[_start:_end])
+            if self.verbosity >= 2:
+                print(f'[TRACE][{os.path.basename(path_c)}] best window tokens {_start}-{_end} '
+                      f'of {len(_tokens)} :: "{txt_c}"')
+
+        # fill metrics with life, sharing
+        # preprocessed data between them
+        _read = time.perf_counter()
+        entry.timings['read'] = _read - _started
+        _ctx = EvalContext(txt_gt, txt_c, options=dict(self.corpus_options, **_options))
+        _entry_metrics = []
+        for _m in self._counted_metrics(self.metrics):
+            try:
+                _em = _m.with_outcome(_m.calculate(_ctx))
+            except EvalError:
+                raise
+            except Exception as exc:
+                raise EvalMetricError(f"{os.path.basename(path_c)}: {_m.label}: {exc}") from exc
+            _em.input_reference = txt_gt
+            _em.input_candidate = txt_c
+            if isinstance(_em, MetricWA):
+                _em.diagnostics[DIAGNOSTIC_ALIGNMENT] = token_alignment(_em.data_reference, _em.data_candidate)
+            _entry_metrics.append(_em)
+            if not _em.n_ref:
+                entry.warnings.append(f'no reference units for {_em.label}')
+                if self.verbosity >= 1:
+                    print(f'[WARN ] {os.path.basename(path_g)}: no reference units for {_em.label}, '
+                          f'counts nothing for micro average')
+            if _em.gaps_applied and self.verbosity >= 1:
+                print(f'[DEBUG][{os.path.basename(path_c)}][{_em.label}] partial groundtruth, '
+                      f'free end gaps applied ({_em.n_ref} reference items covered)')
+            if self.verbosity >= 2:
                 _label_ref = os.path.basename(path_g)
                 _label_can = os.path.basename(path_c)
-                print(f'[TRACE][{_label_ref}][{_m.label}] REFERENCE :: "{_m.data_reference}"')
-                print(f'[TRACE][{_label_can}][{_m.label}] CANDIDATE :: "{_m.data_candidate}"')
+                print(f'[TRACE][{_label_ref}][{_em.label}] REFERENCE :: "{_em.data_reference}"')
+                print(f'[TRACE][{_label_can}][{_em.label}] CANDIDATE :: "{_em.data_candidate}"')
+
+        entry.timings['metrics'] = time.perf_counter() - _read
 
         # enrich entry with metrics and
         # normalized data type (i.e., art or ann or ...)
         _normed_gt_type = _normalize_gt_type(str(gt_type))
         entry.gt_type = _normed_gt_type
-        entry.metrics = self.metrics
+        entry.metrics = _entry_metrics
+        if self.score:
+            _score = copy.copy(self.score)
+            _score.combine(entry.metrics)
+            entry.metrics.append(_score)
+        entry.txt_reference = txt_gt
+        entry.txt_candidate = txt_c
+        entry.fingerprint = self.fingerprint()
+        if self.by_region:
+            entry.regions = self._eval_regions(path_g, path_c, _normalizations)
+        if self.layout_boxes:
+            entry.boxes = self._layout_boxes(path_g, entry)
         self.evaluation_entries.append(entry)
 
         # necessary to return values
         # due actual process pool mapping
         return entry
 
+    def _counted_metrics(self, metrics) -> List[Metric]:
+        """Metrics with chars counted by count_units,
+        unless character based metric picks units itself"""
+
+        if self.count_units == UNITS_CODEPOINTS:
+            return metrics
+        _metrics = []
+        for _m in metrics:
+            if _m.label in METRICS_CHAR_BASED and _m.units == UNITS_CODEPOINTS:
+                _m = copy.copy(_m)
+                _m.units = self.count_units
+            _metrics.append(_m)
+        return _metrics
+
+    def _reference_free_metrics(self) -> List[Metric]:
+        return [_m for _m in self.metrics if not _m.requires_reference]
+
+    def _eval_candidate(self, entry: EvalEntry) -> EvalEntry:
+        """Create evaluation entry for candidate without
+        groundtruth by reference-free metrics only"""
+
+        path_c = entry.path_c
+        _metrics = self._reference_free_metrics()
+        if not _metrics:
+            raise EvalMetricError(f"{os.path.basename(path_c)}: no groundtruth and no reference-free metric")
+        _started = time.perf_counter()
+        _warnings_c = []
+        entry.statistics_candidate = DocumentStatistics(units=self.count_units)
+        (_, txt_c, _) = ocr_to_text(path_c, oneliner=True, data_format=self.data_format,
+                                    separators=self._breaks(), warnings=_warnings_c,
+                                    statistics=entry.statistics_candidate,
+                                    min_confidence=self.min_word_confidence)
+        entry.parse_warnings = [f'{PARSE_WARNING_CANDIDATE}: {_w}' for _w in _warnings_c]
+        if self.strict_parse and entry.parse_warnings:
+            raise EvalParseError(f"{os.path.basename(path_c)}: {len(entry.parse_warnings)} parse warnings, "
+                                 f"first '{entry.parse_warnings[0]}'")
+        if self.abbreviations:
+            txt_c = expand_abbreviations(txt_c, self.abbreviations)
+        (_, _era, _normalizations) = self._normalizations_of(path_c)
+        if self.eras:
+            entry.era = _era.label if _era else None
+        if _normalizations:
+            txt_c = normalize_text(txt_c, _normalizations)
+        if self.equivalences:
+            txt_c = apply_equivalences(txt_c, self.equivalences)
+        _options = {OPTION_LINES: ('', txt_c), OPTION_PATH_CANDIDATE: path_c}
+        txt_c = self._separated(txt_c)
+        _read = time.perf_counter()
+        entry.timings['read'] = _read - _started
+        _ctx = EvalContext('', txt_c, options=dict(self.corpus_options, **_options))
+        _entry_metrics = []
+        for _m in _metrics:
+            try:
+                _em = _m.with_outcome(_m.calculate(_ctx))
+            except EvalError:
+                raise
+            except Exception as exc:
+                raise EvalMetricError(f"{os.path.basename(path_c)}: {_m.label}: {exc}") from exc
+            _em.input_candidate = txt_c
+            _entry_metrics.append(_em)
+            if self.verbosity >= 2:
+                print(f'[TRACE][{os.path.basename(path_c)}][{_em.label}] CANDIDATE :: "{_em.data_candidate}"')
+        entry.timings['metrics'] = time.perf_counter() - _read
+
+        # composite score requires all metrics,
+        # so there's none without groundtruth
+        entry.metrics = _entry_metrics
+        entry.txt_candidate = txt_c
+        entry.fingerprint = self.fingerprint()
+        self.evaluation_entries.append(entry)
+        return entry
+
+    def _eval_regions(self, path_g, path_c, normalizations) -> List['RegionEntry']:
+        """Evaluate each groundtruth region against candidate
+        region matched by IoU, if any, otherwise against empty
+        text, None if either file lacks regions"""
+
+        try:
+            (_regions_g, _regions_c) = [region_texts(to_pieces(str(_p))) for _p in (path_g, path_c)]
+        except EvalError:
+            raise
+        except Exception as exc:
+            print(f'[WARN ] {os.path.basename(path_c)}: no metrics by region, requires ALTO or PAGE: {exc}')
+            return None
+        if self.region_ids:
+            _regions_g = [_r for _r in _regions_g if _r[0] in self.region_ids]
+        _matches = {_i: (_j, _iou) for (_i, _j, _iou) in
+                    match_regions([_r[1] for _r in _regions_g], [_r[1] for _r in _regions_c])}
+        _metrics = self._counted_metrics([_m for _m in self.metrics if not _m.requires_resources])
+        _entries = []
+        for (_i, (_id, _, _txt_gt)) in enumerate(_regions_g):
+            (_j, _iou) = _matches.get(_i, (None, 0.0))
+            _region = RegionEntry(_id, _regions_c[_j][0] if _j is not None else None, _iou)
+            _txt_c = _regions_c[_j][2] if _j is not None else ''
+            if self.abbreviations:
+                (_txt_gt, _txt_c) = [expand_abbreviations(_t, self.abbreviations) for _t in (_txt_gt, _txt_c)]
+            if normalizations:
+                (_txt_gt, _txt_c) = [normalize_text(_t, normalizations) for _t in (_txt_gt, _txt_c)]
+            if self.equivalences:
+                (_txt_gt, _txt_c) = [apply_equivalences(_t, self.equivalences) for _t in (_txt_gt, _txt_c)]
+            _ctx = EvalContext(self._separated(_txt_gt), self._separated(_txt_c),
+                               options=dict(self.corpus_options, **{OPTION_LINES: (_txt_gt, _txt_c)}))
+            for _m in _metrics:
+                try:
+                    _region.metrics.append(_m.with_outcome(_m.calculate(_ctx)))
+                except EvalError:
+                    raise
+                except Exception as exc:
+                    raise EvalMetricError(f"{os.path.basename(path_c)}: {_id}: {_m.label}: {exc}") from exc
+            _entries.append(_region)
+        return _entries
+
+    def _ordered_texts(self, entry: EvalEntry, lines_g, lines_c, txt_gt, txt_c) -> Tuple[str, str]:
+        """Texts of lines as read (text, region, bounding box)
+        with regions serialized in reading order of order
+        authority or candidate lines projected into reference
+        regions, texts as read unless both contain regions"""
+
+        (_regions_g, _regions_c) = (read_regions(lines_g), read_regions(lines_c))
+        # projection takes candidate lines, like of hOCR, without regions
+        if not _regions_g or not (_regions_c or self.project_candidate):
+            print(f'[WARN ] {os.path.basename(entry.path_c)}: own reading order kept, '
+                  f'requires regions of ALTO or PAGE')
+            return (txt_gt, txt_c)
+        if self.project_candidate:
+            _regions_c = project_lines(_regions_g, [(None, _box, _text, None) for (_text, _, _box) in lines_c])
+        else:
+            (_regions_g, _regions_c) = order_regions(_regions_g, _regions_c, self.order_authority)
+        entry.region_order = tuple([_r[0] for _r in _regions] for _regions in (_regions_g, _regions_c))
+        _break = self._breaks().region
+        return tuple(_break.join(_r[2] for _r in _regions if _r[2].strip()) for _regions in (_regions_g, _regions_c))
+
+    def _layout_boxes(self, path_g, entry: EvalEntry) -> List[LayoutBox]:
+        """Bounding boxes of groundtruth regions with their
+        metrics, if evaluated by region, and lines with their
+        accuracy of 'LCA', if calculated, None if groundtruth
+        lacks coordinates"""
+
+        try:
+            _piece = to_pieces(str(path_g))
+        except EvalError:
+            raise
+        except Exception as exc:
+            print(f'[WARN ] {os.path.basename(path_g)}: no layout boxes, requires ALTO or PAGE: {exc}')
+            return None
+        _regions = {_r.id: _r for _r in entry.regions or []}
+        _boxes = []
+        for (_id, _bbox, _) in region_texts(_piece):
+            _box = LayoutBox(BOX_REGION, _id, _bbox)
+            if _id in _regions:
+                _box.scores = {_m.label: _m.value for _m in _regions[_id].metrics}
+            _boxes.append(_box)
+        _lines = [_l for _l in line_boxes(_piece) if _l[2] and _l[2].strip()]
+        _lca = next((_m for _m in entry.metrics if _m.label == 'LCA'), None)
+        _accuracies = _lca.diagnostics.get('accuracies', []) if _lca else []
+        # line accuracies refer to lines of text actually
+        # evaluated, which differ if restricted to regions
+        if len(_accuracies) != len(_lines):
+            _accuracies = [None] * len(_lines)
+        for ((_id, _bbox, _, _region_id), _accuracy) in zip(_lines, _accuracies):
+            _box = LayoutBox(BOX_LINE, _id, _bbox, _region_id)
+            if _accuracy is not None:
+                _box.scores = {_lca.label: _accuracy}
+            _boxes.append(_box)
+        return _boxes or None
+
     def _add(self, evaluation_result: EvaluationResult):
         self.evaluation_results.append(evaluation_result)
 
@@ -793,6 +2057,11 @@ class Evaluator:
             evaluation_result = EvaluationResult(k, n_total, n_chars=n_chars)
             evaluation_result.mean = data_points[0]
             evaluation_result.median = data_points[0]
+            evaluation_result.micro_mean = get_micro_mean(data_tuples)
+            evaluation_result.operations = get_operations(data_tuples)
+            evaluation_result.statistics = get_document_statistics(data_tuples)
+            if evaluation_result.statistics:
+                evaluation_result.n_lines = evaluation_result.statistics.n_lines
 
             # if more than one single evaluation item
             # calculate additional statistics to reflect
@@ -812,9 +2081,15 @@ class Evaluator:
                     clear_result.mean = mean2
                     clear_result.std = std2
                     clear_result.median = med2
+                    clear_result.micro_mean = get_micro_mean(regulars)
+                    clear_result.operations = get_operations(regulars)
+                    clear_result.statistics = get_document_statistics(regulars)
+                    if clear_result.statistics:
+                        clear_result.n_lines = clear_result.statistics.n_lines
                     clear_result.n_chars = sum([e[2] for e in regulars])
                     # set as child component
                     evaluation_result.cleared_result = clear_result
+                    evaluation_result.outliers = [e[0] for e in data_tuples if e not in regulars]
             self._add(evaluation_result)
             # re-order
             self.evaluation_results = sorted(self.evaluation_results, key=lambda e: e.eval_key)
@@ -826,16 +2101,26 @@ class Evaluator:
 
         root_base = Path(self.root_groundtruth).parts[-1]
 
+        # entries without groundtruth hold reference-free
+        # metrics only, so indices refer to those of entry
+        # with most metrics and pick same metric by label
+        _full = max(self.evaluation_entries, key=lambda e: len(e.metrics))
+
         # aggregate on each directory
         for _metrics_index in by_metrics:
             # if we do not have all these different metrics set, 
             # do of course not aggregate by non-existing index!
-            if _metrics_index > len(self.evaluation_entries[0].metrics):
+            if _metrics_index >= len(_full.metrics):
                 continue
             for ee in self.evaluation_entries:
-                path_key = f"{ee.metrics[_metrics_index].label}@{root_base}"
-                metric_value = ee.metrics[_metrics_index].value
-                metric_gt_refs = ee.metrics[_metrics_index].n_ref
+                _metric = _same_metric(_full.metrics, _metrics_index, ee.metrics)
+                if _metric is None:
+                    continue
+                path_key = f"{_metric.label}@{root_base}"
+                metric_value = _metric.value
+                metric_gt_refs = _metric.n_ref
+                _item = (ee.path_c, metric_value, metric_gt_refs, _metric.operations,
+                         ee.statistics_reference)
                 dir_o = os.path.dirname(ee.path_c)
                 ocr_parts = Path(dir_o).parts
                 if root_base in ocr_parts:
@@ -844,13 +2129,17 @@ class Evaluator:
                         # store at top-level
                         if path_key not in self.evaluation_map:
                             self.evaluation_map[path_key] = []
-                        self.evaluation_map[path_key].append((ee.path_c, metric_value, metric_gt_refs))
+                        self.evaluation_map[path_key].append(_item)
                         # if by_type, aggregate type at top level
                         if by_type and ee.gt_type and ee.gt_type != NOT_SET:
                             type_key = path_key + '@' + ee.gt_type
                             if type_key not in self.evaluation_map:
                                 self.evaluation_map[type_key] = []
-                            self.evaluation_map[type_key].append((ee.path_c, metric_value, metric_gt_refs))
+                            self.evaluation_map[type_key].append(_item)
+                        # aggregate publication era at top level
+                        if ee.era:
+                            era_key = f'{path_key}@{CONFIG_SECTION_ERA_PREFIX}{ee.era}'
+                            self.evaluation_map.setdefault(era_key, []).append(_item)
                         tokens.pop(0)
                         # store at any sub-level
                         curr = path_key
@@ -859,13 +2148,244 @@ class Evaluator:
                             curr = curr + os.sep + token
                             if curr not in self.evaluation_map:
                                 self.evaluation_map[curr] = []
-                            self.evaluation_map[curr].append((ee.path_c, metric_value, metric_gt_refs))
+                            self.evaluation_map[curr].append(_item)
 
     def _check_aggregate_preconditions(self):
         if not self.evaluation_entries:
-            raise RuntimeError("missing evaluation data")
-        if not Path(self.root_groundtruth).is_dir():
+            raise EvalMatchingError("missing evaluation data")
+        # aggregation only depends on the root's name, which
+        # must not exist locally when merging stored results
+        if not self.root_groundtruth:
             raise RuntimeError("no root dir to aggregate data from")
 
     def get_results(self):
         return self.evaluation_results
+
+
+class EvalConfig:
+    """Settings for evaluation of single pair
+
+    metrics: metrics to calculate (default: all)
+    score: optional composite score of metrics
+    extras: like Evaluator's extras, i.e. 'ignore_geometry'
+    region_ids: optional IDs of groundtruth regions to evaluate
+    data_format: optional format of candidate, like 'alto'
+    normalizations: optional normalizations of both texts
+    eras: optional publication eras with their normalizations
+    abbreviations: optional expansions of abbreviations in both texts
+    timeout: optional max seconds to evaluate pair
+    by_region: whether to evaluate each groundtruth region, too
+    separators: optional spec of strings joining words, lines
+                and regions, like 'line=newline:region=none'
+    strict_parse: whether any parse warning fails the pair
+    count_units: units to count chars of document statistics by
+    order_authority: whose reading order serializes both texts,
+                     like 'reference'
+    equivalences: optional canonical forms of variants in both texts
+    project_candidate: whether to re-assemble candidate lines
+                       in regions of reference
+    min_word_confidence: optional min confidence of candidate
+                         words, others are dropped
+    """
+
+    def __init__(self, metrics=None, score=None, extras=None, region_ids=None, verbosity=0,
+                 data_format=None, normalizations=None, eras=None, abbreviations=None, timeout=None,
+                 by_region=False, separators=None, strict_parse=False, count_units=UNITS_CODEPOINTS,
+                 order_authority=ORDER_EACH, equivalences=None, project_candidate=False,
+                 min_word_confidence=None):
+        self.metrics = metrics
+        self.score = score
+        self.extras = extras
+        self.region_ids = region_ids
+        self.verbosity = verbosity
+        self.data_format = data_format
+        self.normalizations = normalizations or []
+        self.eras = eras or []
+        self.abbreviations = abbreviations or {}
+        self.timeout = timeout
+        self.by_region = by_region
+        self.separators = separators
+        self.strict_parse = strict_parse
+        self.count_units = count_units
+        self.order_authority = order_authority
+        self.equivalences = equivalences or {}
+        self.project_candidate = project_candidate
+        self.min_word_confidence = min_word_confidence
+
+
+class PairResult:
+    """Outcome of evaluating single candidate
+    versus it's reference
+
+    metrics: calculated metrics in order of config,
+             followed by composite score (if any)
+    timings: seconds spent per phase ('read',
+             'metrics' and overall 'total')
+    regions: metrics per groundtruth region, if requested
+    parse_warnings: non-fatal problems reading either file
+    statistics_reference: size of reference text read
+    statistics_candidate: size of candidate text read
+    region_order: region IDs of reference and candidate
+                  in order serialized, if reordered
+    fingerprint: hash of configuration evaluated with
+    """
+
+    def __init__(self, path_candidate, path_reference):
+        self.path_candidate = path_candidate
+        self.path_reference = path_reference
+        self.gt_type = NOT_SET
+        self.metrics = []
+        self.window = None
+        self.era = None
+        self.txt_reference = None
+        self.txt_candidate = None
+        self.timings = {}
+        self.regions = None
+        self.parse_warnings = []
+        self.statistics_reference = None
+        self.statistics_candidate = None
+        self.region_order = None
+        self.fingerprint = None
+
+    @property
+    def values(self):
+        """Metric values by label"""
+
+        return {_m.label: _m.value for _m in self.metrics}
+
+    @property
+    def diagnostics(self):
+        """Details of metric calculations by label,
+        like number of reference units"""
+
+        return {_m.label: dict(_m.diagnostics, n_ref=_m.n_ref) for _m in self.metrics}
+
+    @property
+    def word_alignment(self):
+        """Aligned tokens of word accuracy like
+        EvalEntry.word_alignment, None if word
+        accuracy wasn't calculated"""
+
+        for _metric in self.metrics:
+            if DIAGNOSTIC_ALIGNMENT in _metric.diagnostics:
+                return _metric.diagnostics[DIAGNOSTIC_ALIGNMENT]
+        return None
+
+    @property
+    def operations(self):
+        """Edit operations (substitutions, insertions,
+        deletions) by label of edit distance metrics"""
+
+        return {_m.label: _m.operations for _m in self.metrics if _m.operations is not None}
+
+    def __str__(self) -> str:
+        return ', '.join(f'{_m.label}:{_m.value:5.2f}({int(_m.n_ref)})' for _m in self.metrics)
+
+
+def evaluate_pair(path_candidate, path_reference, config: EvalConfig = None) -> PairResult:
+    """Evaluate single candidate against it's reference
+
+    Stable entry point for programmatic use, raises
+    EvalError subtypes only: EvalIOError for missing files,
+    EvalParseError for inconsistent data or, if config's
+    strict_parse is set, any parse warning, EvalMetricError if
+    reference lacks text, EvalMatchingError if it lacks
+    regions of config's region_ids and EvalTimeoutError if
+    it takes longer than config's timeout. Without reference
+    only config's reference-free metrics are calculated.
+    """
+
+    for _path in [path_candidate, path_reference]:
+        if _path is not None and not os.path.isfile(str(_path)):
+            raise EvalIOError(f"{_path} not existing!")
+    _config = config or EvalConfig()
+    _started = time.perf_counter()
+    _evaluator = Evaluator(os.path.dirname(str(path_reference or path_candidate)), _config.verbosity,
+                           _config.extras)
+    if _config.metrics:
+        _evaluator.metrics = _config.metrics
+    _evaluator.score = _config.score
+    _evaluator.region_ids = _config.region_ids
+    _evaluator.data_format = _config.data_format
+    _evaluator.normalizations = _config.normalizations
+    _evaluator.eras = _config.eras
+    _evaluator.abbreviations = _config.abbreviations
+    _evaluator.by_region = _config.by_region
+    _evaluator.strict_parse = _config.strict_parse
+    _evaluator.count_units = _config.count_units
+    _evaluator.order_authority = _config.order_authority
+    _evaluator.equivalences = _config.equivalences
+    _evaluator.project_candidate = _config.project_candidate
+    _evaluator.min_word_confidence = _config.min_word_confidence
+    if _config.separators:
+        _evaluator.separators = separators_from_spec(_config.separators, _evaluator.separators)
+    _entry = EvalEntry(str(path_candidate))
+    _entry.path_g = str(path_reference) if path_reference else None
+    if run_limited(_config.timeout, _evaluator.eval_entry, _entry) is None:
+        raise EvalMatchingError(f"{path_reference} contains none of regions {_config.region_ids}")
+    _result = PairResult(str(path_candidate), str(path_reference) if path_reference else None)
+    _result.gt_type = _entry.gt_type
+    _result.metrics = _entry.metrics
+    _result.window = _entry.window
+    _result.era = _entry.era
+    _result.txt_reference = _entry.txt_reference
+    _result.txt_candidate = _entry.txt_candidate
+    _result.regions = _entry.regions
+    _result.parse_warnings = _entry.parse_warnings
+    _result.statistics_reference = _entry.statistics_reference
+    _result.statistics_candidate = _entry.statistics_candidate
+    _result.region_order = _entry.region_order
+    _result.fingerprint = _entry.fingerprint
+    _result.timings = dict(_entry.timings, total=time.perf_counter() - _started)
+    return _result
+
+
+# metrics requiring more than groundtruth's text,
+# like word confidences, regions or keyword terms
+SELF_CHECK_SKIP = [_l for (_l, _m) in METRICS.items() if _m.requires_resources]
+# max deviation of metric value from perfect value
+SELF_CHECK_TOLERANCE = 1e-6
+
+
+def perfect_value(metric) -> float:
+    """Value of metric for candidate identical to it's
+    reference, i.e. 1 for IR metrics, 0 for error rates
+    and 100 for all other metrics"""
+
+    if metric.label in METRICS_UNIT_RANGE:
+        return 1.0
+    if metric.label in ERROR_RATES:
+        return 0.0
+    return 100.0
+
+
+def self_check(path_groundtruth, config: EvalConfig = None) -> Tuple[int, List[Tuple[str, str]]]:
+    """Evaluate each groundtruth file below path_groundtruth
+    against itself, with metrics of config (default: all but
+    SELF_CHECK_SKIP), and return number of files checked and
+    deviations (path, reason) from perfect values
+
+    Any deviation reveals problems of parsing, normalization
+    or metrics, which falsify real evaluations, too"""
+
+    _config = copy.copy(config) if config else EvalConfig()
+    _config.metrics = [_m for _m in (_config.metrics or [METRICS[_l]() for _l in METRICS])
+                       if not _m.requires_resources]
+    _deviations = []
+    _paths = [path_groundtruth]
+    if os.path.isdir(path_groundtruth):
+        # plain text groundtruth, too
+        _suffixes = tuple(reader_suffixes() + ['.txt'])
+        _paths = sorted(os.path.join(_dir, _f) for (_dir, _, _files) in os.walk(path_groundtruth)
+                        for _f in _files if _f.endswith(_suffixes))
+    for _path in _paths:
+        try:
+            _result = evaluate_pair(_path, _path, _config)
+        except EvalError as _err:
+            _deviations.append((_path, f"can't evaluate: {_err}"))
+            continue
+        for _metric in _result.metrics:
+            _expected = perfect_value(_metric)
+            if _metric.value is None or abs(_metric.value - _expected) > SELF_CHECK_TOLERANCE:
+                _deviations.append((_path, f"{_metric.label} {_metric.value} instead of {_expected}"))
+    return (len(_paths), _deviations)
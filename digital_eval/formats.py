@@ -0,0 +1,114 @@
+# -*- coding: utf-8 -*-
+"""Format Readers Module
+
+Registry of readers for OCR data formats. Each reader
+sniffs the leading part of a file to decide whether it
+can read it, so detection doesn't depend on a fixed
+list of formats. New formats plug in with
+
+    register_reader(MyReader())
+
+Readers registered later get asked first, therefore
+they may take over files of builtin formats.
+"""
+
+from typing import (
+    List,
+    Tuple,
+)
+
+from .errors import (
+    EvalUnsupportedError,
+)
+
+# detect format of each file by sniffing
+FORMAT_AUTO = 'auto'
+# number of leading chars passed to sniffers
+SNIFF_LENGTH = 2048
+# plain text may start like markup, i.e. '<Titel>'
+# in transcriptions, so these files fall back to text
+# format if no other reader claims them
+FORMAT_TEXT = 'text'
+FORMAT_TEXT_SUFFIXES = ['.txt']
+
+
+class FormatReader:
+    """Read (optionally framed) text lines of OCR data
+
+    name: label of format, like 'alto', used by --format
+    suffixes: file suffixes of candidates in this format
+    """
+
+    name = None
+    suffixes = []
+
+    def sniff(self, path_in, head) -> bool:
+        """Whether file starting with head is in this format"""
+
+        raise NotImplementedError
+
+    def read(self, path_in, coords=None, warnings=None) -> Tuple[str, List]:
+        """Groundtruth type (if annotated) and lines,
+        restricted to frame coords ((x0, y0), (x1, y1))
+        if format provides geometric data. Non-fatal
+        problems, like elements skipped, get appended to
+        list warnings, which is only passed if requested"""
+
+        raise NotImplementedError
+
+    def line_text(self, line) -> str:
+        """Text of single line as returned by read()"""
+
+        return line.strip()
+
+    def line_region(self, line):
+        """Region containing single line as returned by
+        read(), None if format has no regions"""
+
+        return None
+
+
+_READERS = []
+
+
+def register_reader(reader: FormatReader):
+    """Register reader, replacing any previous
+    reader of same format"""
+
+    _READERS[:] = [_r for _r in _READERS if _r.name != reader.name]
+    _READERS.insert(0, reader)
+
+
+def reader_names() -> List[str]:
+    """Names of registered formats, builtins last"""
+
+    return [_r.name for _r in _READERS]
+
+
+def reader_suffixes() -> List[str]:
+    """File suffixes of all registered formats"""
+
+    return sorted({_s for _r in _READERS for _s in _r.suffixes})
+
+
+def get_reader(name) -> FormatReader:
+    """Reader of format by name"""
+
+    for _reader in _READERS:
+        if _reader.name == name:
+            return _reader
+    raise ValueError(f"unknown format '{name}', expected one of {reader_names()}")
+
+
+def detect_reader(path_in) -> FormatReader:
+    """First reader claiming to read file, plain text
+    reader for text files no reader claims"""
+
+    with open(path_in, mode='r', encoding='utf-8', errors='replace') as _handle:
+        _head = _handle.read(SNIFF_LENGTH)
+    for _reader in _READERS:
+        if _reader.sniff(path_in, _head):
+            return _reader
+    if str(path_in).endswith(tuple(FORMAT_TEXT_SUFFIXES)) and FORMAT_TEXT in reader_names():
+        return get_reader(FORMAT_TEXT)
+    raise EvalUnsupportedError(f"{path_in}: unknown format, expected one of {reader_names()}")
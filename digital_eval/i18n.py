@@ -0,0 +1,123 @@
+# -*- coding: utf-8 -*-
+"""Report Labels Module
+
+Translations of labels used by reports, since they're
+frequently passed to curators rather than developers.
+Log prefixes and metric labels (CCA, WWA, ...) stay
+untranslated, they're meant to be parsed.
+"""
+
+REPORT_LANG_EN = 'en'
+REPORT_LANG_DE = 'de'
+REPORT_LANG_DEFAULT = REPORT_LANG_EN
+
+LABELS = {
+    REPORT_LANG_EN: {
+        'summary': 'Evaluation Summary for "{candidates}" vs. "{reference}" ({date})',
+        'items': 'items',
+        'std': 'std',
+        'median': 'median',
+        'micro': 'micro',
+        'regression': 'regression',
+        'improvement': 'improvement',
+        'unchanged': 'unchanged',
+        'baseline_summary': 'compared {n} results with baseline "{path}": {n_regressions} regressions, '
+                            '{n_improvements} improvements, {n_unchanged} unchanged (threshold {threshold:.2f})',
+        'fails_under': 'fails under',
+        'passes': 'passes',
+        'general_results': 'General results',
+        'document': 'Document',
+        'candidate': 'Candidate',
+        'reference': 'Reference',
+        'window': 'Window',
+        'tokens': 'tokens',
+        'difference_spotting': 'Difference spotting',
+        'resources': 'Resource usage',
+        'regions': 'Regions',
+        'outliers': 'Outliers',
+        'metrics': 'Metrics',
+        'hints': 'Hints',
+        'word_errors': 'Word errors',
+        'operation': 'Operation',
+        'substitutions': 'substitutions',
+        'insertions': 'insertions',
+        'deletions': 'deletions',
+        'edit_operations': 'Edit operations',
+        'reference_size': 'Reference size',
+        'candidate_size': 'Candidate size',
+        'chars': 'chars',
+        'lines': 'lines',
+        'regions_count': 'regions',
+        'wall_time': 'Wall time',
+        'cpu_time': 'CPU time',
+        'peak_rss': 'Peak memory (RSS)',
+        'peak_rss_worker': 'Peak memory (RSS) per worker',
+        'CCA': 'Character Accuracy',
+        'CLA': 'Letter Accuracy',
+        'WWA': 'Word Token Accuracy',
+        'WBoW': 'Bag of Words',
+        'IRPre': 'IR Precision',
+        'IRRec': 'IR Recall',
+        'IRFM': 'IR F-Measure',
+    },
+    REPORT_LANG_DE: {
+        'summary': 'Auswertung von "{candidates}" gegen "{reference}" ({date})',
+        'items': 'Dokumente',
+        'std': 'Standardabweichung',
+        'median': 'Median',
+        'micro': 'gewichtet',
+        'regression': 'Verschlechterung',
+        'improvement': 'Verbesserung',
+        'unchanged': 'unverändert',
+        'baseline_summary': '{n} Ergebnisse mit Referenzlauf "{path}" verglichen: {n_regressions} '
+                            'Verschlechterungen, {n_improvements} Verbesserungen, {n_unchanged} unverändert '
+                            '(Schwelle {threshold:.2f})',
+        'fails_under': 'unterschreitet',
+        'passes': 'erreicht',
+        'general_results': 'Gesamtergebnis',
+        'document': 'Dokument',
+        'candidate': 'Kandidat',
+        'reference': 'Referenz',
+        'window': 'Ausschnitt',
+        'tokens': 'Wörter',
+        'difference_spotting': 'Unterschiede',
+        'resources': 'Ressourcenverbrauch',
+        'regions': 'Regionen',
+        'outliers': 'Ausreißer',
+        'metrics': 'Metriken',
+        'hints': 'Hinweise',
+        'word_errors': 'Wortfehler',
+        'operation': 'Operation',
+        'substitutions': 'Ersetzungen',
+        'insertions': 'Einfügungen',
+        'deletions': 'Löschungen',
+        'edit_operations': 'Editieroperationen',
+        'reference_size': 'Umfang Referenz',
+        'candidate_size': 'Umfang Kandidat',
+        'chars': 'Zeichen',
+        'lines': 'Zeilen',
+        'regions_count': 'Regionen',
+        'wall_time': 'Laufzeit',
+        'cpu_time': 'CPU-Zeit',
+        'peak_rss': 'Speicherspitze (RSS)',
+        'peak_rss_worker': 'Speicherspitze (RSS) je Worker',
+        'CCA': 'Zeichengenauigkeit',
+        'CLA': 'Buchstabengenauigkeit',
+        'WWA': 'Wortgenauigkeit',
+        'WBoW': 'Wortschatz (Bag of Words)',
+        'IRPre': 'IR Präzision',
+        'IRRec': 'IR Trefferquote',
+        'IRFM': 'IR F-Maß',
+    },
+}
+REPORT_LANGS = list(LABELS)
+
+
+def label(key, lang=REPORT_LANG_DEFAULT, **kwargs) -> str:
+    """Translated label, falls back to english and
+    finally to key itself if there's no translation"""
+
+    if lang not in LABELS:
+        raise ValueError(f"unknown report language '{lang}', expected one of {REPORT_LANGS}")
+    _text = LABELS[lang].get(key, LABELS[REPORT_LANG_DEFAULT].get(key, key))
+    return _text.format(**kwargs) if kwargs else _text
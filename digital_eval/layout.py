@@ -0,0 +1,479 @@
+# -*- coding: utf-8 -*-
+"""Layout Module
+
+Parameters for layout evaluation following PRImA's
+LayoutEval, i.e. weights for kinds of segmentation
+errors and types of regions affected by them, and
+matching of candidate regions to groundtruth regions
+by intersection over union of their bounding boxes
+
+Tables of PAGE groundtruth get compared by their cell
+grid, similar to TEDS (tree edit distance based
+similarity), but with rows aligned as sequences rather
+than general tree edit distance
+"""
+
+import xml.dom.minidom
+
+from typing import (
+    List,
+    Tuple,
+)
+
+from rapidfuzz.string_metric import (
+    levenshtein
+)
+
+from .model import (
+    BoundingBox,
+    Piece,
+    PieceType,
+)
+
+# segmentation error kinds as PRImA LayoutEval distinguishes
+LAYOUT_ERROR_MERGE = 'merge'
+LAYOUT_ERROR_SPLIT = 'split'
+LAYOUT_ERROR_MISS = 'miss'
+LAYOUT_ERROR_PARTIAL_MISS = 'partial-miss'
+LAYOUT_ERROR_MISCLASSIFICATION = 'misclassification'
+LAYOUT_ERROR_FALSE_DETECTION = 'false-detection'
+LAYOUT_ERRORS = [LAYOUT_ERROR_MERGE, LAYOUT_ERROR_SPLIT,
+                 LAYOUT_ERROR_MISS, LAYOUT_ERROR_PARTIAL_MISS,
+                 LAYOUT_ERROR_MISCLASSIFICATION, LAYOUT_ERROR_FALSE_DETECTION]
+
+# weight of anything not mentioned by profile
+LAYOUT_DEFAULT_WEIGHT = 1.0
+# separates region type from it's subtype, like 'TextRegion:heading'
+LAYOUT_SUBTYPE_SEPARATOR = ':'
+# min intersection over union of regions to match
+LAYOUT_IOU_THRESHOLD = 0.5
+# compare table cells by structure and text (default)
+# or by structure only, like TEDS-Struct
+TABLE_CONTENT_TEXT = 'text'
+TABLE_CONTENT_IGNORE = 'ignore'
+TABLE_CONTENT_MODES = [TABLE_CONTENT_TEXT, TABLE_CONTENT_IGNORE]
+# whose reading order serializes regions of both texts,
+# either reference or candidate order, the other side's
+# regions following their counterparts matched by IoU,
+# or each text's own order (default)
+ORDER_REFERENCE = 'reference'
+ORDER_CANDIDATE = 'candidate'
+ORDER_EACH = 'each'
+ORDER_AUTHORITIES = [ORDER_REFERENCE, ORDER_CANDIDATE, ORDER_EACH]
+
+
+class LayoutProfile:
+    """Weights for region types and error kinds
+
+    Region types use PAGE element names, optional with
+    subtype ('TextRegion:heading'). Subtypes without own
+    weight fall back to their region type's weight.
+    """
+
+    def __init__(self, name='default'):
+        self.name = name
+        self.error_weights = {e: LAYOUT_DEFAULT_WEIGHT for e in LAYOUT_ERRORS}
+        self.region_weights = {}
+
+    def error_weight(self, error_kind) -> float:
+        if error_kind not in self.error_weights:
+            raise ValueError(f"unknown layout error '{error_kind}', expected one of {LAYOUT_ERRORS}")
+        return self.error_weights[error_kind]
+
+    def region_weight(self, region_type, subtype=None) -> float:
+        if subtype:
+            _key = f'{region_type}{LAYOUT_SUBTYPE_SEPARATOR}{subtype}'
+            if _key in self.region_weights:
+                return self.region_weights[_key]
+        return self.region_weights.get(region_type, LAYOUT_DEFAULT_WEIGHT)
+
+    def weight(self, error_kind, region_type, subtype=None) -> float:
+        """Weight of single error affecting region"""
+
+        return self.error_weight(error_kind) * self.region_weight(region_type, subtype)
+
+
+def _read_weight(element, path_in) -> float:
+    _raw = element.getAttribute('value') or element.getAttribute('weight')
+    try:
+        _weight = float(_raw)
+    except ValueError as _err:
+        raise RuntimeError(f"{path_in}: invalid weight '{_raw}' for "
+                           f"'{element.getAttribute('type')}'") from _err
+    if _weight < 0:
+        raise RuntimeError(f"{path_in}: negative weight {_weight} for '{element.getAttribute('type')}'")
+    return _weight
+
+
+def load_layout_profile(path_in) -> LayoutProfile:
+    """Read layout evaluation profile (XML) with weights
+    like PRImA's LayoutEval uses them
+
+    This is a plain layout of weights only, grouped by
+    'ErrorTypeWeights' and 'RegionTypeWeights', each with
+    'Weight' elements carrying 'type', optional 'subtype'
+    and 'value'. Namespaces are ignored, other elements
+    are skipped.
+    """
+
+    doc_root = xml.dom.minidom.parse(str(path_in)).documentElement
+    if doc_root is None:
+        raise RuntimeError(f"{path_in}: invalid document root")
+    profile = LayoutProfile(doc_root.getAttribute('name') or 'default')
+    for _group in doc_root.getElementsByTagNameNS('*', 'ErrorTypeWeights'):
+        for _element in _group.getElementsByTagNameNS('*', 'Weight'):
+            _kind = _element.getAttribute('type')
+            if _kind not in LAYOUT_ERRORS:
+                raise RuntimeError(f"{path_in}: unknown layout error '{_kind}', expected one of {LAYOUT_ERRORS}")
+            profile.error_weights[_kind] = _read_weight(_element, path_in)
+    for _group in doc_root.getElementsByTagNameNS('*', 'RegionTypeWeights'):
+        for _element in _group.getElementsByTagNameNS('*', 'Weight'):
+            _type = _element.getAttribute('type')
+            if not _type:
+                raise RuntimeError(f"{path_in}: region weight without type")
+            _subtype = _element.getAttribute('subtype')
+            if _subtype:
+                _type = f'{_type}{LAYOUT_SUBTYPE_SEPARATOR}{_subtype}'
+            profile.region_weights[_type] = _read_weight(_element, path_in)
+    return profile
+
+
+def region_boxes(piece: Piece) -> List[BoundingBox]:
+    """Bounding boxes of all regions below piece,
+    in document order, skipping those without
+    coordinates"""
+
+    _boxes = []
+    for _child in piece.pieces:
+        if _child.type == PieceType.REGION and _child.dimensions:
+            _xs = [_p[0] for _p in _child.dimensions]
+            _ys = [_p[1] for _p in _child.dimensions]
+            _boxes.append(BoundingBox((min(_xs), min(_ys)), (max(_xs), max(_ys))))
+        _boxes.extend(region_boxes(_child))
+    return _boxes
+
+
+def region_weights(piece: Piece, profile: LayoutProfile, error_kind) -> List[float]:
+    """Weights of error_kind affecting each region below
+    piece by it's type, in order of region_boxes"""
+
+    _weights = []
+    for _child in piece.pieces:
+        if _child.type == PieceType.REGION and _child.dimensions:
+            _weights.append(profile.weight(error_kind, _child.region_type, _child.region_subtype))
+        _weights.extend(region_weights(_child, profile, error_kind))
+    return _weights
+
+
+def region_texts(piece: Piece) -> List[Tuple[str, BoundingBox, str]]:
+    """ID, bounding box and text (lines separated by
+    newline) of all regions below piece, in document
+    order, skipping those without coordinates"""
+
+    _regions = []
+    for _child in piece.pieces:
+        if _child.type == PieceType.REGION and _child.dimensions:
+            _xs = [_p[0] for _p in _child.dimensions]
+            _ys = [_p[1] for _p in _child.dimensions]
+            _lines = [_l.transcription for _l in _child.pieces if _l.type == PieceType.LINE]
+            _regions.append((_child.id, BoundingBox((min(_xs), min(_ys)), (max(_xs), max(_ys))),
+                             '\n'.join(_lines)))
+        _regions.extend(region_texts(_child))
+    return _regions
+
+
+def line_boxes(piece: Piece, region_id=None) -> List[Tuple[str, BoundingBox, str, str]]:
+    """ID, bounding box, text and ID of enclosing region
+    (if any) of all lines below piece, in document order,
+    skipping those without coordinates"""
+
+    _lines = []
+    for _child in piece.pieces:
+        if _child.type == PieceType.LINE and _child.dimensions:
+            _xs = [_p[0] for _p in _child.dimensions]
+            _ys = [_p[1] for _p in _child.dimensions]
+            _lines.append((_child.id, BoundingBox((min(_xs), min(_ys)), (max(_xs), max(_ys))),
+                           _child.transcription, region_id))
+        _parent = _child.id if _child.type == PieceType.REGION else region_id
+        _lines.extend(line_boxes(_child, _parent))
+    return _lines
+
+
+def _area(box: BoundingBox) -> float:
+    return (box.p2[0] - box.p1[0]) * (box.p2[1] - box.p1[1])
+
+
+def iou(box_a: BoundingBox, box_b: BoundingBox) -> float:
+    """Intersection over union of both boxes,
+    0 if any lacks coordinates"""
+
+    if box_a is None or box_b is None:
+        return 0.0
+    _intersection = box_a.intersection(box_b)
+    _union = _area(box_a) + _area(box_b) - _intersection
+    return _intersection / _union if _union > 0 else 0.0
+
+
+def match_regions(reference_boxes, candidate_boxes,
+                  threshold=LAYOUT_IOU_THRESHOLD) -> List[Tuple[int, int, float]]:
+    """Match each reference box with at most one candidate
+    box and vice versa, pairs with greatest overlap first,
+    as long as it reaches threshold
+
+    Returns (reference index, candidate index, IoU)"""
+
+    _pairs = sorted(((iou(_r, _c), _i, _j) for (_i, _r) in enumerate(reference_boxes)
+                     for (_j, _c) in enumerate(candidate_boxes)), reverse=True)
+    (_matches, _used_reference, _used_candidate) = ([], set(), set())
+    for (_iou, _i, _j) in _pairs:
+        if _iou < threshold or _iou <= 0:
+            break
+        if _i in _used_reference or _j in _used_candidate:
+            continue
+        _used_reference.add(_i)
+        _used_candidate.add(_j)
+        _matches.append((_i, _j, _iou))
+    return sorted(_matches)
+
+
+def _follow_order(matches, n_regions) -> List[int]:
+    """Indices of regions sorted by position of their
+    counterparts, unmatched ones right after the region
+    preceding them in their own order"""
+
+    _keys = []
+    _anchor = -1
+    for _j in range(n_regions):
+        if _j in matches:
+            _anchor = matches[_j]
+            _keys.append((_anchor, 0, _j))
+        else:
+            _keys.append((_anchor, 1, _j))
+    return [_k[2] for _k in sorted(_keys)]
+
+
+def order_regions(regions_reference, regions_candidate, authority) -> Tuple[List, List]:
+    """Regions of both sides (ID, bounding box and text,
+    like region_texts) in reading order of authority"""
+
+    if authority == ORDER_EACH or not regions_reference or not regions_candidate:
+        return (regions_reference, regions_candidate)
+    _matches = match_regions([_r[1] for _r in regions_reference], [_r[1] for _r in regions_candidate])
+    if authority == ORDER_REFERENCE:
+        _order = _follow_order({_j: _i for (_i, _j, _) in _matches}, len(regions_candidate))
+        return (regions_reference, [regions_candidate[_j] for _j in _order])
+    _order = _follow_order({_i: _j for (_i, _j, _) in _matches}, len(regions_reference))
+    return ([regions_reference[_i] for _i in _order], regions_candidate)
+
+
+def project_lines(regions_reference, lines_candidate) -> List[Tuple[str, BoundingBox, str]]:
+    """Candidate lines (like line_boxes) re-assembled in
+    regions of reference (like region_texts), each line
+    going to the region which covers most of it, in their
+    own order. Lines outside any region, or without
+    coordinates, make regions of their own behind the
+    region preceding them"""
+
+    _assigned = [[] for _ in regions_reference]
+    _outside = {}
+    _previous = -1
+    for (_, _box, _text, _) in lines_candidate:
+        if not _text or not _text.strip():
+            continue
+        _overlaps = [_box.intersection(_r[1]) if _box is not None and _r[1] is not None else 0
+                     for _r in regions_reference]
+        _best = max(range(len(_overlaps)), key=lambda _i: _overlaps[_i], default=None)
+        if _best is not None and _overlaps[_best] > 0:
+            _assigned[_best].append(_text)
+            _previous = _best
+        else:
+            _outside.setdefault(_previous, []).append(_text)
+    _projected = []
+    if -1 in _outside:
+        _projected.append((None, None, '\n'.join(_outside[-1])))
+    for (_i, (_id, _box, _)) in enumerate(regions_reference):
+        _projected.append((_id, _box, '\n'.join(_assigned[_i])))
+        if _i in _outside:
+            _projected.append((None, None, '\n'.join(_outside[_i])))
+    return _projected
+
+
+class TableCell:
+    """Cell of table grid with it's text"""
+
+    def __init__(self, row, col, row_span=1, col_span=1, text=''):
+        self.row = row
+        self.col = col
+        self.row_span = row_span
+        self.col_span = col_span
+        self.text = text
+
+    def __repr__(self) -> str:
+        return f"({self.row},{self.col}):{self.text}"
+
+
+class Table:
+    """Table region with bounding box and cells"""
+
+    def __init__(self, table_id, box: BoundingBox, cells: List[TableCell]):
+        self.id = table_id
+        self.box = box
+        self.cells = cells
+
+    @property
+    def rows(self) -> List[List[TableCell]]:
+        """Cells grouped by row, each row ordered by column"""
+
+        _rows = {}
+        for _cell in self.cells:
+            _rows.setdefault(_cell.row, []).append(_cell)
+        return [sorted(_rows[_r], key=lambda c: c.col) for _r in sorted(_rows)]
+
+    @property
+    def n_nodes(self) -> int:
+        """Nodes of table's tree, i.e. table, rows and cells"""
+
+        return 1 + len(self.rows) + len(self.cells)
+
+
+def _box_of(element) -> BoundingBox:
+    _coords = [_n for _n in element.childNodes if _n.localName == 'Coords']
+    if not _coords or not _coords[0].getAttribute('points'):
+        return None
+    _points = [[int(float(_v)) for _v in _p.split(',')] for _p in _coords[0].getAttribute('points').split()]
+    _xs = [_p[0] for _p in _points]
+    _ys = [_p[1] for _p in _points]
+    return BoundingBox((min(_xs), min(_ys)), (max(_xs), max(_ys)))
+
+
+def _unicode_of(element) -> str:
+    _equivs = [_n for _n in element.childNodes if _n.localName == 'TextEquiv']
+    if not _equivs:
+        return None
+    _unicodes = _equivs[0].getElementsByTagNameNS('*', 'Unicode')
+    if not _unicodes or not _unicodes[0].firstChild:
+        return ''
+    return _unicodes[0].firstChild.nodeValue.strip()
+
+
+def _cell_text(element) -> str:
+    """Text of cell's lines, if any, otherwise it's own"""
+
+    _lines = [_unicode_of(_l) for _l in element.getElementsByTagNameNS('*', 'TextLine')]
+    _lines = [_l for _l in _lines if _l]
+    if _lines:
+        return ' '.join(_lines)
+    return _unicode_of(element) or ''
+
+
+def _int_attribute(element, names, default) -> int:
+    for _name in names:
+        if element.getAttribute(_name):
+            return int(element.getAttribute(_name))
+    return default
+
+
+def _read_cells(table_element) -> List[TableCell]:
+    """Cells either as TableCell elements (Transkribus) or as
+    TextRegion children with TableCellRole (PAGE 2019)"""
+
+    _cells = []
+    for _child in table_element.childNodes:
+        if _child.localName == 'TableCell':
+            _attributes = _child
+        elif _child.localName == 'TextRegion':
+            _roles = _child.getElementsByTagNameNS('*', 'TableCellRole')
+            if not _roles:
+                continue
+            _attributes = _roles[0]
+        else:
+            continue
+        _cells.append(TableCell(_int_attribute(_attributes, ['row', 'rowIndex'], 0),
+                                _int_attribute(_attributes, ['col', 'columnIndex'], 0),
+                                _int_attribute(_attributes, ['rowSpan'], 1),
+                                _int_attribute(_attributes, ['colSpan'], 1),
+                                _cell_text(_child)))
+    return _cells
+
+
+def read_tables(path_in) -> List[Table]:
+    """Read all table regions of PAGE file, in document
+    order, other formats don't carry table structure"""
+
+    doc_root = xml.dom.minidom.parse(str(path_in)).documentElement
+    if doc_root is None or doc_root.localName != 'PcGts':
+        return []
+    _tables = []
+    for _element in doc_root.getElementsByTagNameNS('*', 'TableRegion'):
+        _box = _box_of(_element)
+        if _box is not None:
+            _tables.append(Table(_element.getAttribute('id'), _box, _read_cells(_element)))
+    return _tables
+
+
+def _cell_cost(cell_a: TableCell, cell_b: TableCell, content) -> float:
+    """Cells spanning differently don't match at all, otherwise
+    their texts differ by normalized edit distance"""
+
+    if (cell_a.row_span, cell_a.col_span) != (cell_b.row_span, cell_b.col_span):
+        return 1.0
+    if content == TABLE_CONTENT_IGNORE:
+        return 0.0
+    _length = max(len(cell_a.text), len(cell_b.text))
+    return levenshtein(cell_a.text, cell_b.text) / _length if _length else 0.0
+
+
+def _sequence_distance(items_a, items_b, cost_insert, cost_substitute) -> float:
+    """Weighted edit distance of both sequences"""
+
+    _previous = [0.0]
+    for _b in items_b:
+        _previous.append(_previous[-1] + cost_insert(_b))
+    for _a in items_a:
+        _current = [_previous[0] + cost_insert(_a)]
+        for (_j, _b) in enumerate(items_b, 1):
+            _current.append(min(_previous[_j] + cost_insert(_a),
+                                _current[_j - 1] + cost_insert(_b),
+                                _previous[_j - 1] + cost_substitute(_a, _b)))
+        _previous = _current
+    return _previous[-1]
+
+
+def table_distance(reference: Table, candidate: Table, content=TABLE_CONTENT_TEXT) -> float:
+    """Edit distance of both table trees, which inserts or
+    deletes rows with all their cells and cells within rows
+    (1 per node), or substitutes cells by their cost"""
+
+    def _row_distance(_row_a, _row_b):
+        return _sequence_distance(_row_a, _row_b, lambda _c: 1.0,
+                                  lambda _a, _b: _cell_cost(_a, _b, content))
+
+    return _sequence_distance(reference.rows, candidate.rows, lambda _r: 1.0 + len(_r), _row_distance)
+
+
+def table_similarity(reference_tables, candidate_tables, threshold=LAYOUT_IOU_THRESHOLD,
+                     content=TABLE_CONTENT_TEXT) -> Tuple[float, float, int]:
+    """Match tables by IoU and compare matched ones by
+    tree edit distance, tables without counterpart count
+    as completely missed
+
+    Returns (similarity 0 - 1, total distance, number of
+    matched tables), similarity is 0 without any tables"""
+
+    _matches = match_regions([_t.box for _t in reference_tables],
+                             [_t.box for _t in candidate_tables], threshold)
+    (_distance, _nodes) = (0.0, 0)
+    for (_i, _j, _) in _matches:
+        (_reference, _candidate) = (reference_tables[_i], candidate_tables[_j])
+        _distance += table_distance(_reference, _candidate, content)
+        _nodes += max(_reference.n_nodes, _candidate.n_nodes)
+    _matched_reference = {_i for (_i, _, _) in _matches}
+    _matched_candidate = {_j for (_, _j, _) in _matches}
+    _unmatched = ([_t for (_i, _t) in enumerate(reference_tables) if _i not in _matched_reference]
+                  + [_t for (_j, _t) in enumerate(candidate_tables) if _j not in _matched_candidate])
+    for _table in _unmatched:
+        _distance += _table.n_nodes
+        _nodes += _table.n_nodes
+    _similarity = max(0.0, 1 - _distance / _nodes) if _nodes else 0.0
+    return (_similarity, _distance, len(_matches))
+
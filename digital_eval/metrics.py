@@ -1,9 +1,17 @@
 # -*- coding: utf-8 -*-
 """Metrics Module"""
 
+import copy
+import math
+import os
+import statistics
+
 from collections import (
     Counter
 )
+from functools import (
+    lru_cache,
+)
 
 import string
 
@@ -29,9 +37,60 @@ from nltk.metrics import (
 from rapidfuzz.string_metric import (
     levenshtein
 )
+from rapidfuzz.distance import (
+    Levenshtein
+)
 
-# unicode normalization
-UC_NORMALIZATION = 'NFKD'
+from uniseg.graphemecluster import (
+    grapheme_clusters as _grapheme_clusters
+)
+
+from .alignment import (
+    ALIGN_DELETION,
+    ALIGN_MERGE,
+    ALIGN_SPLIT,
+    align_words,
+    confidence_errors,
+    word_groups,
+)
+
+from .errors import (
+    EvalError,
+)
+
+from .estimation import (
+    CONF_LOW,
+    LT_LANGUAGE,
+    dictionary_hits,
+    extract_features,
+    languagetool_rate,
+    predict_cer,
+    read_dictionary,
+)
+
+from .layout import (
+    LAYOUT_DEFAULT_WEIGHT,
+    LAYOUT_ERROR_FALSE_DETECTION,
+    LAYOUT_ERROR_MISS,
+    LAYOUT_ERROR_PARTIAL_MISS,
+    LAYOUT_IOU_THRESHOLD,
+    TABLE_CONTENT_MODES,
+    TABLE_CONTENT_TEXT,
+    load_layout_profile,
+    match_regions,
+    read_tables,
+    region_boxes,
+    region_weights,
+    table_similarity,
+)
+
+from .model import (
+    to_pieces,
+)
+
+from .normalization import (
+    UC_NORMALIZATION,
+)
 
 # punctuations to take into account
 # includes
@@ -43,24 +102,301 @@ PUNCTUATIONS = string.punctuation + '\u2012' + '\u2013' + '\u2014' + '\u2015' +
 PUNCTUATIONS = PUNCTUATIONS + '\u2E17'  # DOUBLE OBLIQUE HYPHEN
 # no spaces
 PUNCTUATIONS = PUNCTUATIONS + '\u0020' + '\u00a0' + '\u2000' + '\u2001' + '\u2002' + '\u2003' + '\u2004' + '\u2005' + '\u2006' + '\u2007' + '\u2008' + '\u2009' + '\u200a' + '\u2028' + '\u205f' + '\u3000'
+# no line breaks
+PUNCTUATIONS = PUNCTUATIONS + '\n'
 # arabib indic digits
 DIGITS = string.digits + '\u0660' + '\u0661' + '\u0662' + '\u0663' + '\u0664' + '\u0665' + '\u0666' + '\u0667' + '\u0668' + '\u0669' 
 # persian indic digits
 DIGITS = DIGITS + '\u06f0' + '\u06f1' + '\u06f2' + '\u06f3' + '\u06f4' + '\u06f5' + '\u06f6' + '\u06f7' + '\u06f8' + '\u06f9'
 
+# token classes which word based metrics can drop
+TOKEN_CLASS_PUNCT = 'punct'
+TOKEN_CLASS_DIGITS = 'digits'
+TOKEN_CLASS_SPACE = 'space'
+# chars ending sentences, maybe followed by
+# closing quotes or brackets
+SENTENCE_ENDS = '.!?'
+SENTENCE_CLOSINGS = '"\'“”‘’»«)]'
+# digits of roman numerals, which end with a dot
+# like ordinals ('Heinrich II.') without ending sentences
+ROMAN_DIGITS = 'IVXLCDM'
+
+# separate metric label from options and options
+# from each other, like "WWA:drop=punct,digits"
+METRIC_SPEC_SEPARATOR = ':'
+METRIC_OPTION_DROP = 'drop'
+METRIC_OPTION_GAPS = 'gaps'
+METRIC_OPTION_UNITS = 'units'
+METRIC_OPTION_TOKENIZE = 'tokenize'
+METRIC_OPTION_CASE = 'case'
+METRIC_OPTION_MARKS = 'marks'
+METRIC_OPTION_FUZZY = 'fuzzy'
+METRIC_OPTION_WEIGHTING = 'weighting'
+METRIC_OPTION_REPORT = 'report'
+METRIC_OPTION_LENGTH = 'length'
+# quality estimation model and optional
+# resources to extract it's features
+METRIC_OPTION_MODEL = 'model'
+METRIC_OPTION_DICTIONARY = 'dictionary'
+METRIC_OPTION_LM = 'lm'
+# max order of n-grams and weight of recall compared
+# to precision by char n-gram metric, order is
+# respected by word n-gram metric, too
+METRIC_OPTION_ORDER = 'n'
+METRIC_OPTION_BETA = 'beta'
+# LanguageTool server and language to check
+# candidate's words with, language of IR metrics
+# are ISO 639-2 codes of their stopwords
+METRIC_OPTION_API = 'api'
+METRIC_OPTION_LANGUAGE = 'language'
+# similarity of token counts by frequency bag of words
+METRIC_OPTION_SIMILARITY = 'similarity'
+# min intersection over union of matching regions
+METRIC_OPTION_IOU = 'iou'
+# PRImA layout evaluation profile weighting regions
+# by their type and errors by their kind
+METRIC_OPTION_PROFILE = 'profile'
+# whether table cells are compared by their text, too
+METRIC_OPTION_CONTENT = 'content'
+# file of important terms, like names or places,
+# one per line, to recall by keyword metric
+METRIC_OPTION_TERMS = 'terms'
+
+# char n-gram F-score like chrF, with n-grams of order
+# 1 up to CHRF_ORDER and recall weighted CHRF_BETA times
+# as much as precision, ignoring whitespace, so it's
+# rather robust to differing word segmentation
+CHRF_ORDER = 6
+CHRF_BETA = 2
+
+# word n-gram precision like BLEU, with n-grams of order
+# 1 up to BLEU_ORDER and brevity penalty for candidates
+# shorter than reference
+BLEU_ORDER = 4
+
+# frequency bag of words compares token counts either by
+# weighted Jaccard, i.e. sum of lesser over sum of greater
+# counts, or cosine of count vectors
+SIMILARITY_JACCARD = 'jaccard'
+SIMILARITY_COSINE = 'cosine'
+SIMILARITY_MODES = [SIMILARITY_JACCARD, SIMILARITY_COSINE]
+
+# values of edit distance and bag of words metrics either
+# as accuracy, i.e. percentage of reference units recognized,
+# or as error rate, i.e. edits per reference unit (percent),
+# which may exceed 100 like CER and WER of other tools
+REPORT_ACCURACY = 'accuracy'
+REPORT_ERROR_RATE = 'error-rate'
+REPORT_MODES = [REPORT_ACCURACY, REPORT_ERROR_RATE]
+# label and name of metrics reported as error rate
+ERROR_RATE_METRICS = {
+    'CCA': ('CER', 'Character Error Rate'),
+    'CLA': ('LER', 'Letter Error Rate'),
+    'WWA': ('WER', 'Word Error Rate'),
+    'WBoW': ('BoWER', 'Bag of Words Error Rate'),
+}
+
+# weighting of tokens by IR metrics, either all alike or
+# by tf-idf with document frequencies over the evaluated
+# corpus, so missing rare, discriminative words hurts
+# more than missing frequent ones
+WEIGHTING_NONE = 'none'
+WEIGHTING_TFIDF = 'tfidf'
+WEIGHTING_MODES = [WEIGHTING_NONE, WEIGHTING_TFIDF]
+# option of context with document frequencies of corpus
+OPTION_DOC_FREQUENCIES = 'doc_frequencies'
+# option of context with reference and candidate text,
+# each with lines separated by line breaks
+OPTION_LINES = 'lines'
+# number of worst lines kept per entry by line metrics
+LINES_WORST = 5
+# option of context with path of candidate file
+OPTION_PATH_CANDIDATE = 'path_candidate'
+# option of context with path of reference file
+OPTION_PATH_REFERENCE = 'path_reference'
+# option of context with candidate's words and their
+# confidences (or None), as read by evaluation
+OPTION_WORDS_CANDIDATE = 'words_candidate'
+
+# whether metrics distinguish upper and lower case
+# or lowercase both texts first, since capitalization
+# of historical prints is often inconsistent
+CASE_SENSITIVE = 'sensitive'
+CASE_IGNORE = 'ignore'
+CASE_MODES = [CASE_SENSITIVE, CASE_IGNORE]
+
+# whether metrics compare combining marks, like accents
+# or umlaut dots, or strip them from both decomposed
+# texts first, to exclude accent errors of engines
+MARKS_KEEP = 'keep'
+MARKS_IGNORE = 'ignore'
+MARKS_MODES = [MARKS_KEEP, MARKS_IGNORE]
+
+# tokenization of token based metrics, either split at
+# whitespace only, additionally split off each punctuation
+# char as token of it's own or strip punctuation at both
+# ends of tokens, so '(Sand Gate),' and '( Sand Gate ) ,'
+# yield same tokens
+TOKENIZE_WHITESPACE = 'whitespace'
+TOKENIZE_SPLIT_PUNCT = 'split-punct'
+TOKENIZE_STRIP_PUNCT = 'strip-punct'
+TOKENIZE_MODES = [TOKENIZE_WHITESPACE, TOKENIZE_SPLIT_PUNCT, TOKENIZE_STRIP_PUNCT]
+
+# units compared by character based metrics, either
+# single unicode codepoints or user-perceived chars,
+# where i.e. 'uͤ' or a char with combining diacritics
+# (decomposed by NFKD) counts as one unit only
+UNITS_CODEPOINTS = 'codepoints'
+UNITS_GRAPHEMES = 'graphemes'
+UNITS_MODES = [UNITS_CODEPOINTS, UNITS_GRAPHEMES]
+
+# alignment modes of edit distance metrics, either
+# count all edits (global), skip leading and trailing
+# part of longer text not covered by shorter one (free)
+# or decide by heuristics below per entry (auto)
+GAPS_GLOBAL = 'global'
+GAPS_FREE = 'free'
+GAPS_AUTO = 'auto'
+GAPS_MODES = [GAPS_GLOBAL, GAPS_FREE, GAPS_AUTO]
+# auto: shorter text must be at most this ratio of longer one
+GAPS_AUTO_MAX_LENGTH_RATIO = 0.8
+# auto: and match part of longer text with at most this error rate
+GAPS_AUTO_MAX_ERROR_RATE = 0.25
+
+# length edit distances get normalized by, either the
+# reference's, as usual in OCR evaluation, where errors
+# may exceed reference length (and accuracy gets clipped
+# at 0), or the longer text's, like common similarity
+# measures, which keeps values within 0 - 100
+LENGTH_REFERENCE = 'reference'
+LENGTH_MAX = 'max'
+LENGTH_MODES = [LENGTH_REFERENCE, LENGTH_MAX]
+
+# edit operations counted by edit distance metrics
+OP_SUBSTITUTIONS = 'substitutions'
+OP_INSERTIONS = 'insertions'
+OP_DELETIONS = 'deletions'
+OPERATIONS = [OP_SUBSTITUTIONS, OP_INSERTIONS, OP_DELETIONS]
+# operations counted by tag of levenshtein edit
+OPERATIONS_TAGS = {'replace': OP_SUBSTITUTIONS, 'insert': OP_INSERTIONS, 'delete': OP_DELETIONS}
+
+# composite score from weighted metrics, like "0.6*CCA + 0.4*WWA"
+SCORE_LABEL_DEFAULT = 'Score'
+SCORE_TERM_SEPARATOR = '+'
+SCORE_WEIGHT_SEPARATOR = '*'
+# labels of metrics which can't be picked by label, like
+# those of compatibility modes, but weighted by scores
+SCORE_LABELS_EXTRA = []
+
 # information retrival (nltk)
 STOPWORDS = ['german', 'russian', 'english', 'french', 'greek', 'arabic', 'turkish', 'italian']
 STOPWORDS_DEFAULT = ['german', 'english', 'arabic','russian']
+# stopword languages by ISO 639-2 code with their NLTK
+# name, languages requested by code take lists bundled
+# in STOPWORDS_DIR, if any, languages by name from NLTK
+STOPWORDS_ISO = {'deu': 'german', 'eng': 'english', 'fra': 'french', 'ell': 'greek',
+                 'lat': None, 'heb': None, 'rus': 'russian', 'ara': 'arabic',
+                 'tur': 'turkish', 'ita': 'italian'}
+STOPWORDS_DIR = os.path.join(os.path.dirname(os.path.abspath(__file__)), 'stopwords')
+STOPWORDS_SEPARATOR = ','
+
+
+class EvalContext:
+    """Input of all metrics for a single entry
+
+    Carries raw reference and candidate texts, optional
+    regions and options of entry and preprocessed data
+    shared by metrics: each preprocessing is identified by
+    a key, which must cover all of it's settings
+    (normalization, token filters, ...), and done only once
+    """
+
+    def __init__(self, reference, candidate, regions=None, options=None):
+        self.reference = reference
+        self.candidate = candidate
+        self.regions = regions
+        self.options = options or {}
+        self.n_hits = 0
+        self._data = {}
+
+    def get(self, key, transform) -> Tuple:
+        """Pair of transformed reference and candidate,
+        which must not be modified by callers"""
+
+        if key in self._data:
+            self.n_hits += 1
+        else:
+            self._data[key] = (transform(self.reference), transform(self.candidate))
+        return self._data[key]
+
+    def shared(self, key, compute):
+        """Data computed once from context, rather than
+        from each text, which must not be modified by
+        callers"""
+
+        if key in self._data:
+            self.n_hits += 1
+        else:
+            self._data[key] = compute(self)
+        return self._data[key]
+
+
+class MetricOutcome:
+    """Result of metric calculation for single entry
+
+    n_ref: number of reference units (chars, tokens)
+           the value refers to
+    diagnostics: further details, like whether free
+                 end gaps were applied by alignment
+    n_candidate: number of candidate units, by default
+                 those of data_candidate
+    operations: edit operations (substitutions, insertions,
+                deletions) summing up to diff, if known
+    """
+
+    def __init__(self, value, diff=None, n_ref=0,
+                 data_reference=None, data_candidate=None, diagnostics=None,
+                 n_candidate=None, operations=None):
+        self.value = value
+        self.diff = diff
+        self.n_ref = n_ref
+        self.data_reference = data_reference
+        self.data_candidate = data_candidate
+        self.diagnostics = diagnostics or {}
+        if n_candidate is None and data_candidate is not None:
+            n_candidate = len(data_candidate)
+        self.n_candidate = n_candidate
+        self.operations = operations
 
 
 class Metric:
-    """Basic definition of a Metric"""
+    """Basic definition of a Metric
+
+    Settings (label, token filters, alignment mode, ...)
+    are fixed after creation, calculate() doesn't modify
+    the metric, so it can be shared between entries
+    """
+
+    # whether groundtruth is required, reference-free
+    # metrics evaluate candidates without groundtruth, too
+    requires_reference = True
+    # whether more than both texts is required, like lines,
+    # words read, files or services, so metric can't be
+    # recalculated from texts alone
+    requires_context = False
+    # whether data beyond texts of reference is required,
+    # like word confidences, geometry, models, services or
+    # term lists, so metric can't evaluate single regions
+    # or groundtruth against itself
+    requires_resources = False
 
     def __init__(self, precision=2) -> None:
         self.precision = precision
         self.value = None
         self.diff = None
         self.n_ref = 0
+        self.n_candidate = None
+        self.operations = None
         self.label = None
         self.name = None
         self.input_reference = None
@@ -68,14 +404,126 @@ class Metric:
         self.data_reference = None
         self.data_candidate = None
         self.languages = None
-
-    def calc(self):
-        """Calculate metric value
+        # token classes to drop before calculation
+        # only respected by word based metrics
+        self.token_filters = []
+        # tokenization, only respected
+        # by word based metrics
+        self.tokenization = TOKENIZE_WHITESPACE
+        # alignment mode, only respected
+        # by edit distance based metrics
+        self.gaps = GAPS_GLOBAL
+        # whether free gaps were actually applied
+        self.gaps_applied = False
+        # compared units, only respected
+        # by character based metrics
+        self.units = UNITS_CODEPOINTS
+        # whether to lowercase both texts first
+        self.case = CASE_SENSITIVE
+        # whether to strip combining marks of both texts first
+        self.marks = MARKS_KEEP
+        # max edits (int) or edits per char (float) of
+        # candidate token to still match reference token,
+        # only respected by bag of words and IR metrics
+        self.fuzzy = None
+        # token weighting, only respected by IR metrics
+        self.weighting = WEIGHTING_NONE
+        # accuracy or error rate, only respected
+        # by edit distance and bag of words metrics
+        self.report = REPORT_ACCURACY
+        # length to normalize edit distance by, only
+        # respected by edit distance metrics
+        self.length = LENGTH_REFERENCE
+        # further details of latest outcome
+        self.diagnostics = {}
+
+    def as_error_rate(self):
+        """Report error rate rather than accuracy,
+        with label and name changed accordingly"""
+
+        if self.label not in ERROR_RATE_METRICS:
+            raise ValueError(f"{self.label}: no error rate, expected one of {list(ERROR_RATE_METRICS)}")
+        (self.label, self.name) = ERROR_RATE_METRICS[self.label]
+        self.report = REPORT_ERROR_RATE
+        return self
+
+    def _normalization(self) -> Tuple:
+        """Key and function of UTF-8 level normalization"""
+
+        (_key, _norm_func) = (UC_NORMALIZATION, _normalize)
+        if self.case == CASE_IGNORE:
+            (_key, _norm_func) = ((UC_NORMALIZATION, CASE_IGNORE), _normalize_caseless)
+        if self.marks == MARKS_IGNORE:
+            return ((_key, MARKS_IGNORE), lambda t: strip_marks(_norm_func(t)))
+        return (_key, _norm_func)
+
+    def _chars(self, ctx: EvalContext, key, transform) -> Tuple:
+        if self.units == UNITS_GRAPHEMES:
+            return ctx.get((key, UNITS_GRAPHEMES), lambda t: grapheme_clusters(transform(t)))
+        return ctx.get(key, transform)
+
+    def _tokenize(self, ctx: EvalContext) -> Tuple[List[str], List[str]]:
+        (_filters, _mode) = (self.token_filters, self.tokenization)
+        (_key, _norm_func) = self._normalization()
+        return ctx.get((_key, 'tokens', tuple(_filters), _mode),
+                       lambda t: filter_tokens(tokenize(_norm_func(t), _mode), _filters))
+
+    @property
+    def corpus_based(self) -> bool:
+        """Whether metric requires data of whole corpus"""
+
+        return self.weighting == WEIGHTING_TFIDF
+
+    def _ir_tokens(self, ctx: EvalContext) -> Tuple[set, set]:
+        (_languages, _filters) = (self.languages or STOPWORDS_DEFAULT, self.token_filters)
+        _mode = self.tokenization
+        (_key, _norm_func) = self._normalization()
+        return ctx.get((_key, 'ir', tuple(_languages), tuple(_filters), _mode),
+                       lambda t: _ir_tokens(_norm_func(t), _languages, _filters, _mode))
+
+    def _edit_accuracy(self, reference, candidate) -> MetricOutcome:
+        """Accuracy by edit distance respecting alignment mode"""
+
+        (distance, _n_ref, _applied) = edit_distance(reference, candidate, self.gaps)
+        # candidate's uncovered ends don't count with free gaps
+        _n = max(_n_ref, len(candidate)) if self.length == LENGTH_MAX and not _applied else _n_ref
+        _value = error_rate(_n, distance) if self.report == REPORT_ERROR_RATE else _norm(_n, distance)
+        # operations of global alignment only
+        _operations = None if _applied else edit_operations(reference, candidate)
+        return MetricOutcome(_value, distance, round(_n_ref, self.precision),
+                             reference, candidate, {'gaps_applied': _applied}, operations=_operations)
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        """Calculate metric for entry's context
         First, normalize text on UTF-8 level
         """
 
-        self.data_reference = unicodedata.normalize(UC_NORMALIZATION, self.input_reference)
-        self.data_candidate = unicodedata.normalize(UC_NORMALIZATION, self.input_candidate)
+        (_reference, _candidate) = ctx.get(*self._normalization())
+        return MetricOutcome(None, data_reference=_reference, data_candidate=_candidate)
+
+    def with_outcome(self, outcome: MetricOutcome):
+        """Copy of metric holding outcome"""
+
+        _metric = copy.copy(self)
+        _metric._apply(outcome)
+        return _metric
+
+    def _apply(self, outcome: MetricOutcome):
+        self.value = outcome.value
+        self.diff = outcome.diff
+        self.n_ref = outcome.n_ref
+        self.n_candidate = outcome.n_candidate
+        self.operations = outcome.operations
+        self.data_reference = outcome.data_reference
+        self.data_candidate = outcome.data_candidate
+        self.gaps_applied = outcome.diagnostics.get('gaps_applied', False)
+        self.diagnostics = dict(outcome.diagnostics)
+
+    def calc(self):
+        """Calculate metric value for inputs
+        and keep outcome with metric itself"""
+
+        self._apply(self.calculate(EvalContext(self.input_reference, self.input_candidate)))
 
 
 class MetricCA(Metric):
@@ -85,10 +533,9 @@ class MetricCA(Metric):
         self.label = 'CCA'
         self.name = 'Character Accuracy'
 
-    def calc(self):
-        super().calc()
-        self.value, self.diff, _n_ref = character_accuracy(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._chars(ctx, *self._normalization())
+        return self._edit_accuracy(_reference, _candidate)
 
 
 class MetricLA(Metric):
@@ -98,12 +545,11 @@ class MetricLA(Metric):
         self.label = 'CLA'
         self.name = 'Letter Accuracy'
 
-    def calc(self):
-        super().calc()
-        self.data_reference = transform_string(self.data_reference)
-        self.data_candidate = transform_string(self.data_candidate)
-        self.value, self.diff, _n_ref = calculate_lar(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_key, _norm_func) = self._normalization()
+        (_reference, _candidate) = self._chars(ctx, (_key, 'letters'),
+                                               lambda t: transform_string(_norm_func(t)))
+        return self._edit_accuracy(_reference, _candidate)
 
 
 class MetricWA(Metric):
@@ -113,12 +559,274 @@ class MetricWA(Metric):
         self.label = 'WWA'
         self.name = 'Word Token Accuracy'
     
-    def calc(self):
-        super().calc()
-        self.data_reference = self.data_reference.split()
-        self.data_candidate = self.data_candidate.split()
-        self.value, self.diff, _n_ref = token_based(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._tokenize(ctx)
+        return self._edit_accuracy(_reference, _candidate)
+
+
+class MetricWAs(Metric):
+    """Word accuracy of content words, i.e. with
+    stopwords of languages dropped from both texts
+    regardless of case"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'WWAs'
+        self.name = 'Word Token Accuracy without Stopwords'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_languages, _filters) = (tuple(self.languages or STOPWORDS_DEFAULT), self.token_filters)
+        _mode = self.tokenization
+        (_key, _norm_func) = self._normalization()
+        _stopwords = _normalized_stopwords(_languages, self.marks)
+        (_reference, _candidate) = ctx.get(
+            (_key, 'tokens-stopwords', _languages, tuple(_filters), _mode),
+            lambda t: [_t for _t in filter_tokens(tokenize(_norm_func(t), _mode), _filters)
+                       if _t.lower() not in _stopwords])
+        return self._edit_accuracy(_reference, _candidate)
+
+
+class MetricLineCA(Metric):
+    """Mean character accuracy of reference lines, each
+    compared with candidate line aligned to it, so errors
+    concentrated in a few lines become visible"""
+
+    requires_context = True
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'LCA'
+        self.name = 'Line Character Accuracy'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        _norm_func = self._normalization()[1]
+        _texts = ctx.options.get(OPTION_LINES, (ctx.reference, ctx.candidate))
+        (_reference, _candidate) = [[_l.strip() for _l in _norm_func(_t).split('\n') if _l.strip()]
+                                    for _t in _texts]
+        _aligned = align_lines(_reference, _candidate)
+        _lines = [(_norm(len(_ref), _dist), _ref, _cand) for (_ref, _cand, _dist) in _aligned]
+        _accuracies = [_a for (_a, _, _) in _lines]
+        _value = statistics.mean(_accuracies) if _accuracies else _norm(0, 0)
+        _diagnostics = {
+            'accuracies': _accuracies,
+            'median': statistics.median(_accuracies) if _accuracies else None,
+            'worst': sorted(_lines, key=lambda l: l[0])[:LINES_WORST],
+            'n_inserted': len(_candidate) - len([_c for (_, _c, _) in _aligned if _c]),
+        }
+        return MetricOutcome(_value, sum(_d for (_, _, _d) in _aligned), len(_reference),
+                             _reference, _candidate, _diagnostics)
+
+
+class MetricQE(Metric):
+    """Reference-free character accuracy, estimated
+    by learned model from candidate's features, like
+    word confidences, dictionary rate or perplexity"""
+
+    requires_reference = False
+    requires_context = True
+    requires_resources = True
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'QCA'
+        self.name = 'Estimated Character Accuracy'
+        self.path_model = None
+        self.path_dictionary = None
+        self.path_lm = None
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        if not self.path_model:
+            raise ValueError(f"{self.label}: requires option '{METRIC_OPTION_MODEL}'")
+        _candidate = _normalize(ctx.candidate)
+        _features = extract_features(ctx.options.get(OPTION_PATH_CANDIDATE), _candidate,
+                                     self.path_dictionary, self.path_lm)
+        _cer = predict_cer(self.path_model, _features)
+        _value = min(max(100 - _cer, 0.0), 100.0)
+        return MetricOutcome(_value, n_ref=len(_candidate), data_candidate=_candidate,
+                             diagnostics={'predicted_cer': _cer, 'features': _features})
+
+
+class MetricDictionary(Metric):
+    """Reference-free ratio of candidate's words known
+    by LanguageTool server, i.e. without spelling error,
+    or found in local dictionaries"""
+
+    requires_reference = False
+    requires_context = True
+    requires_resources = True
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'DICT'
+        self.name = 'Dictionary Rate'
+        self.api_url = None
+        self.language = LT_LANGUAGE
+        self.path_dictionary = None
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        if not self.api_url and not self.path_dictionary:
+            raise ValueError(f"{self.label}: requires LanguageTool server, option '{METRIC_OPTION_API}', "
+                             f"or local dictionary, option '{METRIC_OPTION_DICTIONARY}'")
+        # dictionaries hold composed chars
+        _candidate = unicodedata.normalize('NFC', ctx.candidate)
+        if self.path_dictionary:
+            (_n_tokens, _n_hits) = dictionary_hits(_candidate.split(), read_dictionary(self.path_dictionary))
+            (_rate, _n_unknown) = (_n_hits / _n_tokens if _n_tokens else 0.0, _n_tokens - _n_hits)
+        else:
+            (_rate, _n_tokens, _n_unknown) = languagetool_rate(_candidate, self.api_url, self.language)
+        return MetricOutcome(100 * _rate, _n_unknown, _n_tokens, data_candidate=_candidate,
+                             diagnostics={'n_unknown': _n_unknown})
+
+
+class MetricCaseER(Metric):
+    """Rate of reference words erroneous only by case,
+    i.e. word errors which vanish after casefolding"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'CaseER'
+        self.name = 'Case Error Rate'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._tokenize(ctx)
+        (_filters, _mode) = (self.token_filters, self.tokenization)
+        (_folded_reference, _folded_candidate) = ctx.get(
+            (UC_NORMALIZATION, 'casefold', 'tokens', tuple(_filters), _mode),
+            lambda t: filter_tokens(tokenize(_normalize(t).casefold(), _mode), _filters))
+        (_, _distance, _n_ref) = token_based(_reference, _candidate)
+        (_, _folded_distance, _) = token_based(_folded_reference, _folded_candidate)
+        _n_case = max(_distance - _folded_distance, 0)
+        _value = 100 * _n_case / _n_ref if _n_ref else 0.0
+        return MetricOutcome(_value, _n_case, _n_ref, _reference, _candidate,
+                             {'n_case_errors': _n_case})
+
+
+class MetricDiacriticsER(Metric):
+    """Rate of reference characters erroneous only by
+    diacritics, i.e. char errors which vanish after
+    stripping combining marks"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'DiaER'
+        self.name = 'Diacritics Error Rate'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_key, _norm_func) = self._normalization()
+        (_reference, _candidate) = self._chars(ctx, _key, _norm_func)
+        (_stripped_reference, _stripped_candidate) = self._chars(
+            ctx, (_key, 'no-marks'), lambda t: strip_marks(_norm_func(t)))
+        (_distance, _n_ref, _) = edit_distance(_reference, _candidate)
+        (_stripped_distance, _, _) = edit_distance(_stripped_reference, _stripped_candidate)
+        _n_marks = max(_distance - _stripped_distance, 0)
+        _value = 100 * _n_marks / _n_ref if _n_ref else 0.0
+        return MetricOutcome(_value, _n_marks, _n_ref, _reference, _candidate,
+                             {'n_diacritics_errors': _n_marks})
+
+
+class MetricSegmentationER(Metric):
+    """Rate of word edits due to token boundaries only,
+    i.e. words split or merged, which are identical
+    after removing whitespace"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'SegER'
+        self.name = 'Segmentation Error Rate'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._tokenize(ctx)
+        _groups = [(_error, _pairs) for (_error, _pairs) in word_groups(_reference, _candidate)
+                   if _error in [ALIGN_SPLIT, ALIGN_MERGE]]
+        # each pair of group is a word edit
+        _n_edits = sum(len(_pairs) for (_, _pairs) in _groups)
+        _diagnostics = {
+            'n_split': sum(1 for (_error, _) in _groups if _error == ALIGN_SPLIT),
+            'n_merge': sum(1 for (_error, _) in _groups if _error == ALIGN_MERGE),
+        }
+        return MetricOutcome(error_rate(len(_reference), _n_edits), _n_edits, len(_reference),
+                             _reference, _candidate, _diagnostics)
+
+
+class MetricConfidence(Metric):
+    """Word accuracy weighted by candidate's word confidences,
+    so errors the engine is sure about count most, together
+    with how low confidences correlate with actual errors.
+    Reference words missed by candidate count as errors
+    of full confidence."""
+
+    requires_context = True
+    requires_resources = True
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'CWA'
+        self.name = 'Confidence Weighted Word Accuracy'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        _words = ctx.options.get(OPTION_WORDS_CANDIDATE)
+        if _words is None:
+            raise ValueError(f"{self.label}: requires candidate words read with their confidences")
+        _norm_func = self._normalization()[1]
+        (_reference, _candidate) = [_norm_func(_t).split() for _t in (ctx.reference, ctx.candidate)]
+        _confidences = confidence_errors(_reference, _candidate, _words)
+        if not _confidences:
+            raise ValueError(f"{self.label}: no word confidences in candidate, like ALTO 'WC'")
+        _n_missed = sum(1 for (_, _, _error) in align_words(_reference, _candidate) if _error == ALIGN_DELETION)
+        _total = sum(_c for (_c, _) in _confidences) + _n_missed
+        _correct = sum(_c for (_c, _error) in _confidences if not _error)
+        _value = 100 * _correct / _total if _total else 0.0
+        (_low, _high) = ([_e for (_c, _e) in _confidences if _c < CONF_LOW],
+                         [_e for (_c, _e) in _confidences if _c >= CONF_LOW])
+        _diagnostics = {
+            'confidences': _confidences,
+            'correlation': correlation([_c for (_c, _) in _confidences],
+                                       [float(_e) for (_, _e) in _confidences]),
+            'error_rate_low': sum(_low) / len(_low) if _low else None,
+            'error_rate_high': sum(_high) / len(_high) if _high else None,
+            'n_missed': _n_missed,
+        }
+        return MetricOutcome(_value, n_ref=len(_confidences) + _n_missed, data_reference=_reference,
+                             data_candidate=_candidate, diagnostics=_diagnostics)
+
+
+class MetricChrF(Metric):
+    """F-score of char n-grams of reference and candidate,
+    averaged over all orders up to n"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'ChrF'
+        self.name = 'Character n-gram F-Score'
+        self.order = CHRF_ORDER
+        self.beta = CHRF_BETA
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_key, _norm_func) = self._normalization()
+        (_reference, _candidate) = self._chars(ctx, (_key, 'no-space'),
+                                               lambda t: ''.join(_norm_func(t).split()))
+        (_value, _precision, _recall) = char_ngram_fscore(_reference, _candidate, self.order, self.beta)
+        return MetricOutcome(100 * _value, n_ref=len(_reference),
+                             data_reference=_reference, data_candidate=_candidate,
+                             diagnostics={'precision': _precision, 'recall': _recall})
+
+
+class MetricBLEU(Metric):
+    """Geometric mean of clipped precisions of word
+    n-grams up to order n times brevity penalty"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'BLEU'
+        self.name = 'Word n-gram Precision'
+        self.order = BLEU_ORDER
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._tokenize(ctx)
+        (_value, _precisions, _penalty) = word_ngram_bleu(_reference, _candidate, self.order)
+        return MetricOutcome(100 * _value, n_ref=len(_reference),
+                             data_reference=_reference, data_candidate=_candidate,
+                             diagnostics={'precisions': _precisions, 'brevity_penalty': _penalty})
 
 
 class MetricBoW(Metric):
@@ -128,63 +836,354 @@ class MetricBoW(Metric):
         self.label = 'WBoW'
         self.name = 'Bag of Words'
 
-    def calc(self):
-        super().calc()
-        self.data_reference = self.data_reference.split()
-        self.data_candidate = self.data_candidate.split()
-        self.value, self.diff, _n_ref = bag_of_tokens(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._tokenize(ctx)
+        if self.fuzzy is not None:
+            _candidate = fuzzy_tokens(_reference, _candidate, self.fuzzy)
+        _value, _diff, _n_ref = bag_of_tokens(_reference, _candidate)
+        if self.report == REPORT_ERROR_RATE:
+            # errors are the larger number of either
+            # missing or spurious tokens
+            _diff = max(_diff, sum((Counter(_candidate) - Counter(_reference)).values()))
+            _value = error_rate(_n_ref, _diff)
+        return MetricOutcome(_value, _diff, round(_n_ref, self.precision), _reference, _candidate)
+
+
+class MetricBoWF(Metric):
+    """Bag of words respecting token frequencies of
+    both texts, so spurious repetitions count, too"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'BoWf'
+        self.name = 'Frequency Bag of Words'
+        self.similarity = SIMILARITY_JACCARD
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._tokenize(ctx)
+        if self.fuzzy is not None:
+            _candidate = fuzzy_tokens(_reference, _candidate, self.fuzzy)
+        (_counts_reference, _counts_candidate) = (Counter(_reference), Counter(_candidate))
+        # missing and spurious tokens
+        _diff = sum(((_counts_reference - _counts_candidate) + (_counts_candidate - _counts_reference)).values())
+        _value = 100 * bag_similarity(_counts_reference, _counts_candidate, self.similarity)
+        return MetricOutcome(_value, _diff, len(_reference), _reference, _candidate)
+
+
+class MetricRegions(Metric):
+    """Common base of layout metrics, which match regions
+    of structured candidate and reference files by
+    intersection over union (IoU) of their bounding
+    boxes, at least iou_threshold. With a layout profile
+    regions are weighted by their type and the kind of
+    error affecting them, otherwise all count alike"""
+
+    requires_context = True
+    requires_resources = True
+
+    def __init__(self):
+        super().__init__()
+        self.iou_threshold = LAYOUT_IOU_THRESHOLD
+        self.path_profile = None
+
+    def _region_matches(self, ctx: EvalContext) -> Tuple:
+        """Reference and candidate page and pairs of
+        their regions matched (reference index,
+        candidate index, IoU)"""
+
+        (_path_reference, _path_candidate) = (ctx.options.get(OPTION_PATH_REFERENCE),
+                                              ctx.options.get(OPTION_PATH_CANDIDATE))
+        if not _path_reference or not _path_candidate:
+            raise ValueError(f"{self.label}: requires reference and candidate file with regions")
+
+        def _match(_ctx):
+            try:
+                (_reference, _candidate) = [to_pieces(str(_p)) for _p in (_path_reference, _path_candidate)]
+            except EvalError:
+                raise
+            except Exception as _exc:
+                raise ValueError(f"{self.label}: requires ALTO or PAGE with regions: {_exc}") from _exc
+            _matches = match_regions(region_boxes(_reference), region_boxes(_candidate), self.iou_threshold)
+            return (_reference, _candidate, _matches)
+
+        return ctx.shared(('regions', self.iou_threshold), _match)
+
+    def _weights(self, piece, error_kind) -> List[float]:
+        if not self.path_profile:
+            return [LAYOUT_DEFAULT_WEIGHT] * len(region_boxes(piece))
+        return region_weights(piece, read_layout_profile(self.path_profile), error_kind)
+
+    def _outcome(self, weights, matched, n_reference, n_candidate) -> MetricOutcome:
+        """Weighted percentage of regions matched"""
+
+        _total = sum(weights)
+        _value = 100 * sum(weights[_i] for _i in matched) / _total if _total else 0.0
+        return MetricOutcome(_value, len(weights) - len(matched), len(weights),
+                             diagnostics={'n_reference': n_reference, 'n_candidate': n_candidate,
+                                          'n_matched': len(matched)})
+
+
+class MetricRegionPre(MetricRegions):
+    """Percentage of candidate regions matching
+    a reference region"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'RegPre'
+        self.name = 'Region Precision'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate, _matches) = self._region_matches(ctx)
+        _weights = self._weights(_candidate, LAYOUT_ERROR_FALSE_DETECTION)
+        return self._outcome(_weights, [_j for (_, _j, _) in _matches],
+                             len(region_boxes(_reference)), len(_weights))
+
+
+class MetricRegionRec(MetricRegions):
+    """Percentage of reference regions matched
+    by a candidate region"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'RegRec'
+        self.name = 'Region Recall'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate, _matches) = self._region_matches(ctx)
+        _weights = self._weights(_reference, LAYOUT_ERROR_MISS)
+        return self._outcome(_weights, [_i for (_i, _, _) in _matches],
+                             len(_weights), len(region_boxes(_candidate)))
+
+
+class MetricRegionIoU(MetricRegions):
+    """Mean IoU (percent) of matched regions, i.e.
+    how exact matched regions are located, weighted
+    by reference region like partial misses"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'RegIoU'
+        self.name = 'Region Mean IoU'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate, _matches) = self._region_matches(ctx)
+        _weights = self._weights(_reference, LAYOUT_ERROR_PARTIAL_MISS)
+        _total = sum(_weights[_i] for (_i, _, _) in _matches)
+        _value = 100 * sum(_weights[_i] * _iou for (_i, _, _iou) in _matches) / _total if _total else 0.0
+        return MetricOutcome(_value, 0, len(_matches),
+                             diagnostics={'n_reference': len(_weights),
+                                          'n_candidate': len(region_boxes(_candidate)),
+                                          'n_matched': len(_matches)})
+
+
+class MetricTables(MetricRegions):
+    """Similarity (percent) of table structure like TEDS,
+    i.e. tables of PAGE files matched by IoU and compared
+    by edit distance of their trees of rows and cells,
+    the latter by span and text, if content is set"""
 
+    def __init__(self):
+        super().__init__()
+        self.label = 'TblSim'
+        self.name = 'Table Structure Similarity'
+        self.content = TABLE_CONTENT_TEXT
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        (_path_reference, _path_candidate) = (ctx.options.get(OPTION_PATH_REFERENCE),
+                                              ctx.options.get(OPTION_PATH_CANDIDATE))
+        if not _path_reference or not _path_candidate:
+            raise ValueError(f"{self.label}: requires reference and candidate file with tables")
+        try:
+            (_reference, _candidate) = [read_tables(_p) for _p in (_path_reference, _path_candidate)]
+        except EvalError:
+            raise
+        except Exception as _exc:
+            raise ValueError(f"{self.label}: requires PAGE with tables: {_exc}") from _exc
+        (_similarity, _distance, _n_matched) = table_similarity(_reference, _candidate,
+                                                                self.iou_threshold, self.content)
+        return MetricOutcome(100 * _similarity, round(_distance), sum(len(_t.cells) for _t in _reference),
+                             diagnostics={'n_reference': len(_reference), 'n_candidate': len(_candidate),
+                                          'n_matched': _n_matched})
+
+
+class MetricKeywordRecall(Metric):
+    """Percentage of important terms, like names or
+    places, found in candidate out of those occuring
+    in reference, each term counted once, terms of
+    several words must occur in sequence"""
+
+    requires_resources = True
 
-class MetricPre(Metric):
+    def __init__(self):
+        super().__init__()
+        self.label = 'KWRec'
+        self.name = 'Keyword Recall'
+        self.tokenization = TOKENIZE_STRIP_PUNCT
+        self.path_terms = None
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        if not self.path_terms:
+            raise ValueError(f"{self.label}: requires option '{METRIC_OPTION_TERMS}'")
+        _norm_func = self._normalization()[1]
+        _terms = {tuple(tokenize(_norm_func(_t), self.tokenization)) for _t in read_terms(self.path_terms)}
+        _terms.discard(())
+        (_reference, _candidate) = self._tokenize(ctx)
+        _present = sorted(_t for _t in _terms if _contains(_reference, _t))
+        _missed = [' '.join(_t) for _t in _present if not _contains(_candidate, _t)]
+        _value = _norm(len(_present), len(_missed))
+        return MetricOutcome(_value, len(_missed), len(_present), _reference, _candidate,
+                             {'missed': _missed})
+
+
+class MetricCapitalizedRecall(Metric):
+    """Percentage of capitalized reference words, except
+    those starting sentences, found in candidate, which
+    approximates recall of named entities, each word
+    matching one candidate word regardless of position"""
+
+    def __init__(self):
+        super().__init__()
+        self.label = 'CapRec'
+        self.name = 'Capitalized Word Recall'
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        # capitalization is detected before any
+        # case folding, which applies to matching
+        _norm_func = self._normalization()[1]
+        _reference = Counter(_norm_func(_t) for _t in capitalized_tokens(_normalize(ctx.reference)))
+        _candidate = Counter(_norm_func(_t) for _t in tokenize(_normalize(ctx.candidate), TOKENIZE_STRIP_PUNCT))
+        _missed = _reference - _candidate
+        (_n_ref, _n_missed) = (sum(_reference.values()), sum(_missed.values()))
+        return MetricOutcome(_norm(_n_ref, _n_missed), _n_missed, _n_ref, sorted(_reference.elements()),
+                             sorted(_candidate.elements()), {'missed': sorted(_missed)})
+
+
+class MetricIR(Metric):
+    """Common base of information retrival metrics,
+    which compare distinct tokens without stopwords"""
+
+    def __init__(self):
+        super().__init__()
+        self.languages = None
+
+    def _ir_calc(self, reference, candidate) -> Tuple:
+        raise NotImplementedError
+
+    def _ir_pick(self, precision_value, recall_value) -> float:
+        raise NotImplementedError
+
+    def terms_key(self) -> Tuple:
+        """Settings which terms depend on"""
+
+        return (self.case, self.marks, tuple(self.languages or STOPWORDS_DEFAULT),
+                tuple(self.token_filters), self.tokenization)
+
+    def terms(self, text) -> set:
+        """Distinct terms of text as compared by metric"""
+
+        _norm_func = self._normalization()[1]
+        return _ir_tokens(_norm_func(text), self.languages or STOPWORDS_DEFAULT,
+                          self.token_filters, self.tokenization)
+
+    def _ir_counts(self, ctx: EvalContext) -> Tuple[Counter, Counter]:
+        (_languages, _filters) = (self.languages or STOPWORDS_DEFAULT, self.token_filters)
+        _mode = self.tokenization
+        (_key, _norm_func) = self._normalization()
+        return ctx.get((_key, 'ir-counts', tuple(_languages), tuple(_filters), _mode),
+                       lambda t: _ir_token_counts(_norm_func(t), _languages, _filters, _mode))
+
+    def _calculate_weighted(self, ctx: EvalContext) -> MetricOutcome:
+        (_reference, _candidate) = self._ir_counts(ctx)
+        if self.fuzzy is not None:
+            _candidate = Counter(fuzzy_tokens(sorted(_reference.elements()),
+                                              sorted(_candidate.elements()), self.fuzzy))
+        _frequencies = ctx.options.get(OPTION_DOC_FREQUENCIES, {}).get(self.terms_key())
+        (_precision, _recall) = ir_weighted(_reference, _candidate, _frequencies)
+        return MetricOutcome(self._ir_pick(_precision, _recall), n_ref=round(len(_reference), self.precision),
+                             data_reference=sorted(_reference), data_candidate=sorted(_candidate))
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        if self.weighting == WEIGHTING_TFIDF:
+            return self._calculate_weighted(ctx)
+        (_reference, _candidate) = self._ir_tokens(ctx)
+        if self.fuzzy is not None:
+            _candidate = set(fuzzy_tokens(sorted(_reference), sorted(_candidate), self.fuzzy))
+        _value, _n_ref = self._ir_calc(_reference, _candidate)
+        return MetricOutcome(_value, n_ref=round(_n_ref, self.precision),
+                             data_reference=sorted(_reference), data_candidate=sorted(_candidate))
+
+
+class MetricPre(MetricIR):
 
     def __init__(self):
         super().__init__()
         self.label = 'IRPre'
         self.name = 'IR Precision'
-        self.languages = None
 
-    def calc(self):
-        super().calc()
-        self.data_reference, self.data_candidate = _ir_preprocess(self.data_reference, self.data_candidate, self.languages)
-        self.value, _n_ref = ir_precision(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
-        self.data_reference = sorted(self.data_reference)
-        self.data_candidate = sorted(self.data_candidate)
+    def _ir_calc(self, reference, candidate) -> Tuple:
+        return ir_precision(reference, candidate)
 
+    def _ir_pick(self, precision_value, recall_value) -> float:
+        return precision_value
 
-class MetricRec(Metric):
+
+class MetricRec(MetricIR):
 
     def __init__(self):
         super().__init__()
         self.label = 'IRRec'
         self.name = 'IR Recall'
-        self.languages = None
 
-    def calc(self):
-        super().calc()
-        self.data_reference, self.data_candidate = _ir_preprocess(self.data_reference, self.data_candidate, self.languages)
-        self.value, _n_ref = ir_recall(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
-        self.data_reference = sorted(self.data_reference)
-        self.data_candidate = sorted(self.data_candidate)
+    def _ir_calc(self, reference, candidate) -> Tuple:
+        return ir_recall(reference, candidate)
+
+    def _ir_pick(self, precision_value, recall_value) -> float:
+        return recall_value
 
 
-class MetricFM(Metric):
+class MetricFM(MetricIR):
 
     def __init__(self):
         super().__init__()
         self.label = 'IRFM'
         self.name = 'IR F-Measure'
-        self.languages = None
 
-    def calc(self):
-        super().calc()
-        self.data_reference, self.data_candidate = _ir_preprocess(self.data_reference, self.data_candidate, self.languages)
-        self.value, _n_ref = ir_fmeasure(self.data_reference, self.data_candidate)
-        self.n_ref = round(_n_ref, self.precision)
-        self.data_reference = sorted(self.data_reference)
-        self.data_candidate = sorted(self.data_candidate)
+    def _ir_calc(self, reference, candidate) -> Tuple:
+        return ir_fmeasure(reference, candidate)
+
+    def _ir_pick(self, precision_value, recall_value) -> float:
+        if precision_value + recall_value == 0:
+            return 0.0
+        return 2 * precision_value * recall_value / (precision_value + recall_value)
+
+
+def _normalize(text) -> str:
+    return unicodedata.normalize(UC_NORMALIZATION, text)
+
+
+def _normalize_caseless(text) -> str:
+    return _normalize(text).lower()
+
+
+def strip_marks(text) -> str:
+    """Drop combining marks of decomposed text"""
+
+    return ''.join(_c for _c in text if not unicodedata.combining(_c))
+
+
+def grapheme_clusters(text) -> List[str]:
+    """Split text into user-perceived characters"""
+
+    return list(_grapheme_clusters(text))
+
+
+def count_units(text, units=UNITS_CODEPOINTS) -> int:
+    """Length of text in units, i.e. unicode
+    codepoints or user-perceived characters"""
+
+    if units == UNITS_GRAPHEMES:
+        return len(grapheme_clusters(text))
+    return len(text)
 
 
 def transform_string(the_content):
@@ -197,6 +1196,126 @@ def transform_string(the_content):
     return the_content
 
 
+def _is_punct_char(char) -> bool:
+    return char in PUNCTUATIONS or unicodedata.category(char).startswith('P')
+
+
+def _is_punct_token(token) -> bool:
+    return all(_is_punct_char(c) for c in token)
+
+
+def tokenize(text, mode=TOKENIZE_WHITESPACE) -> List[str]:
+    """Split text into tokens by tokenization mode"""
+
+    if mode not in TOKENIZE_MODES:
+        raise ValueError(f"unknown tokenization '{mode}', expected one of {TOKENIZE_MODES}")
+    _tokens = text.split()
+    if mode == TOKENIZE_STRIP_PUNCT:
+        _stripped = [_strip_punct(_t) for _t in _tokens]
+        return [_t for _t in _stripped if _t]
+    if mode == TOKENIZE_SPLIT_PUNCT:
+        _split = []
+        for _token in _tokens:
+            _word = ''
+            for _char in _token:
+                if _is_punct_char(_char):
+                    _split += [_word, _char] if _word else [_char]
+                    _word = ''
+                else:
+                    _word += _char
+            if _word:
+                _split.append(_word)
+        return _split
+    return _tokens
+
+
+def _strip_punct(token) -> str:
+    (_start, _end) = (0, len(token))
+    while _start < _end and _is_punct_char(token[_start]):
+        _start += 1
+    while _end > _start and _is_punct_char(token[_end - 1]):
+        _end -= 1
+    return token[_start:_end]
+
+
+def _abbreviated(token) -> bool:
+    """Whether token ending with dot is ordinal number,
+    roman numeral or initial, like '3.', 'II.' or 'M.'"""
+
+    _stem = token[:-1]
+    if not token.endswith('.') or not _stem:
+        return False
+    return _stem.isdigit() or (len(_stem) == 1 and _stem.isalpha()) or all(_c in ROMAN_DIGITS for _c in _stem)
+
+
+def capitalized_tokens(text) -> List[str]:
+    """Tokens starting with uppercase letter, punctuation
+    stripped, except first token and tokens following
+    one which ends a sentence, unless it's ordinal number,
+    roman numeral or initial. Other abbreviations, like
+    'Röm.', end sentences, unless expanded before. In
+    German all nouns are capitalized, so these are
+    rather nouns than named entities"""
+
+    _capitalized = []
+    _sentence_start = True
+    for _raw in text.split():
+        _token = _strip_punct(_raw)
+        if _token and not _sentence_start and _token[0].isupper():
+            _capitalized.append(_token)
+        # closing quotes alone keep sentence start
+        _rest = _raw.rstrip(SENTENCE_CLOSINGS)
+        if _rest:
+            _sentence_start = _rest[-1] in SENTENCE_ENDS and not _abbreviated(_rest)
+    return _capitalized
+
+
+@lru_cache(maxsize=None)
+def read_layout_profile(path_in):
+    """Layout profile, read once per file"""
+
+    return load_layout_profile(path_in)
+
+
+@lru_cache(maxsize=None)
+def read_terms(path_in) -> Tuple[str]:
+    """Terms of keyword file, one per line, ignoring
+    empty lines and lines starting with '#'"""
+
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        return tuple(_l.strip() for _l in _handle if _l.strip() and not _l.strip().startswith('#'))
+
+
+def _contains(tokens, term) -> bool:
+    """Whether term's tokens occur in sequence"""
+
+    _n = len(term)
+    return any(tuple(tokens[_i:_i + _n]) == term for _i in range(len(tokens) - _n + 1))
+
+
+def _is_digit_token(token) -> bool:
+    return all(c in DIGITS or c.isdigit() for c in token)
+
+
+# shared token filters, each detecting tokens
+# consisting only of chars of specific class
+TOKEN_FILTERS = {
+    TOKEN_CLASS_PUNCT: _is_punct_token,
+    TOKEN_CLASS_DIGITS: _is_digit_token,
+    TOKEN_CLASS_SPACE: str.isspace,
+}
+
+
+def filter_tokens(tokens, token_classes) -> List[str]:
+    """Drop tokens which consist only of chars
+    from any of the given token classes"""
+
+    if not token_classes:
+        return tokens
+    _filters = [TOKEN_FILTERS[c] for c in token_classes]
+    return [t for t in tokens if not any(f(t) for f in _filters)]
+
+
 def character_accuracy(gt_str, test_str) -> Tuple[float, int, int]:
     """Calculate common levenshtein-distance"""
 
@@ -229,6 +1348,159 @@ def token_based(reference_tokens: List[str], candidate_tokens: List[str]) -> Tup
     return (_norm(_len_ref, distance), distance, _len_ref)
 
 
+def align_lines(reference_lines: List[str], candidate_lines: List[str]) -> List[Tuple[str, str, int]]:
+    """Align candidate lines with reference lines in order,
+    minimizing total edits, where each reference line
+    matches at most one candidate line and lines without
+    match count all their chars
+
+    Returns each reference line with aligned candidate
+    line ('' if none) and their edit distance
+    """
+
+    (_n, _m) = (len(reference_lines), len(candidate_lines))
+    _dist = [[levenshtein(_r, _c) for _c in candidate_lines] for _r in reference_lines]
+    _cost = [[0] * (_m + 1) for _ in range(_n + 1)]
+    for _i in range(1, _n + 1):
+        _cost[_i][0] = _cost[_i - 1][0] + len(reference_lines[_i - 1])
+    for _j in range(1, _m + 1):
+        _cost[0][_j] = _cost[0][_j - 1] + len(candidate_lines[_j - 1])
+    for _i in range(1, _n + 1):
+        for _j in range(1, _m + 1):
+            _cost[_i][_j] = min(_cost[_i - 1][_j - 1] + _dist[_i - 1][_j - 1],
+                                _cost[_i - 1][_j] + len(reference_lines[_i - 1]),
+                                _cost[_i][_j - 1] + len(candidate_lines[_j - 1]))
+    _aligned = []
+    (_i, _j) = (_n, _m)
+    while _i > 0:
+        if _j > 0 and _cost[_i][_j] == _cost[_i - 1][_j - 1] + _dist[_i - 1][_j - 1]:
+            _aligned.append((reference_lines[_i - 1], candidate_lines[_j - 1], _dist[_i - 1][_j - 1]))
+            (_i, _j) = (_i - 1, _j - 1)
+        elif _cost[_i][_j] == _cost[_i - 1][_j] + len(reference_lines[_i - 1]):
+            _aligned.append((reference_lines[_i - 1], '', len(reference_lines[_i - 1])))
+            _i -= 1
+        else:
+            _j -= 1
+    return list(reversed(_aligned))
+
+
+def _window_scores(items, sequence, anchored=False) -> List[int]:
+    """Distance of all items against best window of
+    sequence ending at each position (0 up to length),
+    if anchored against window starting at first one
+
+    Bit-parallel (Myers), each bit of vertical deltas
+    stands for one item, so it takes one pass over
+    sequence with a few operations of integers as long
+    as items are, rather than a matrix of both
+    """
+
+    _mask = (1 << len(items)) - 1
+    _high = 1 << (len(items) - 1)
+    _matches = {}
+    for (_i, _item) in enumerate(items):
+        _matches[_item] = _matches.get(_item, 0) | (1 << _i)
+    (_pv, _mv, _score) = (_mask, 0, len(items))
+    _scores = [_score]
+    for _other in sequence:
+        _eq = _matches.get(_other, 0)
+        _xv = _eq | _mv
+        _xh = (((_eq & _pv) + _pv) ^ _pv) | _eq
+        _ph = _mv | (~(_xh | _pv) & _mask)
+        _mh = _pv & _xh
+        if _ph & _high:
+            _score += 1
+        elif _mh & _high:
+            _score -= 1
+        # unless anchored windows start anywhere, so no carry into first row
+        (_ph, _mh) = (((_ph << 1) | anchored) & _mask, (_mh << 1) & _mask)
+        _pv = _mh | (~(_xv | _ph) & _mask)
+        _mv = _ph & _xv
+        _scores.append(_score)
+    return _scores
+
+
+def _window_alignment(items, sequence) -> Tuple[int, int, int]:
+    """Align all items against best matching window
+    of sequence, which's remainder doesn't count
+
+    Returns distance, start and end of window
+    """
+
+    if not items:
+        return (0, 0, 0)
+    _scores = _window_scores(items, sequence)
+    (_end, distance) = min(enumerate(_scores), key=lambda e: e[1])
+    # start is end of best window aligned backwards
+    _scores_back = _window_scores(items[::-1], sequence[:_end][::-1], anchored=True)
+    _length = max(_k for (_k, _d) in enumerate(_scores_back) if _d == distance)
+    return (distance, _end - _length, _end)
+
+
+def semi_global_distance(reference, candidate) -> Tuple[int, int]:
+    """Levenshtein with free end gaps, i.e. leading and
+    trailing items of the longer sequence not covered
+    by the shorter one don't count as edits
+
+    Returns distance and number of reference items
+    covered by the alignment
+    """
+
+    if len(reference) > len(candidate):
+        (distance, _start, _end) = _window_alignment(candidate, reference)
+        return (distance, _end - _start)
+    (distance, _, _) = _window_alignment(reference, candidate)
+    return (distance, len(reference))
+
+
+def best_window(reference_tokens: List[str], candidate_tokens: List[str]) -> Tuple[int, int, int]:
+    """Slide reference snippet over candidate tokens
+
+    Returns start and end of best matching window of
+    candidate tokens together with it's distance
+    """
+
+    (distance, _start, _end) = _window_alignment(reference_tokens, candidate_tokens)
+    return (_start, _end, distance)
+
+
+def edit_distance(reference, candidate, gaps=GAPS_GLOBAL) -> Tuple[int, int, bool]:
+    """Levenshtein of strings or token lists by alignment mode,
+    the latter with edits of whole tokens (WER)
+
+    Returns distance, number of reference items it refers
+    to and whether free end gaps were applied. Mode 'auto'
+    applies them only if shorter text is considerably shorter
+    but matches part of the longer one well, which indicates
+    partial transcription rather than bad recognition.
+    """
+
+    if gaps not in GAPS_MODES:
+        raise ValueError(f"unknown alignment mode '{gaps}', expected one of {GAPS_MODES}")
+    _len_short = min(len(reference), len(candidate))
+    if gaps == GAPS_GLOBAL or _len_short == 0:
+        return (levenshtein(reference, candidate), len(reference), False)
+    if gaps == GAPS_AUTO and _len_short > GAPS_AUTO_MAX_LENGTH_RATIO * max(len(reference), len(candidate)):
+        return (levenshtein(reference, candidate), len(reference), False)
+    (distance, _n_ref) = semi_global_distance(reference, candidate)
+    if gaps == GAPS_AUTO and distance > GAPS_AUTO_MAX_ERROR_RATE * _len_short:
+        return (levenshtein(reference, candidate), len(reference), False)
+    return (distance, _n_ref, True)
+
+
+def edit_operations(reference, candidate) -> dict:
+    """Substitutions, insertions and deletions of global
+    levenshtein alignment of strings or token lists, the
+    latter with edits of whole tokens, which sum up to
+    edit distance
+    """
+
+    operations = dict.fromkeys(OPERATIONS, 0)
+    for _op in Levenshtein.editops(reference, candidate):
+        operations[OPERATIONS_TAGS[_op.tag]] += 1
+    return operations
+
+
 def bag_of_tokens(reference_tokens: List[str], candidate_tokens: List[str]) -> Tuple[float, int, int]:
     """Calculate intersection/difference
     between GT and Candidate Text"""
@@ -236,11 +1508,141 @@ def bag_of_tokens(reference_tokens: List[str], candidate_tokens: List[str]) -> T
     n_tokens_gt = len(reference_tokens)
     diff_tokens =_diff(reference_tokens, candidate_tokens)
     n_tokens_missed = len(diff_tokens)
-    hit_rate = 100 * (n_tokens_gt - len(diff_tokens)) / n_tokens_gt
+    hit_rate = _norm(n_tokens_gt, n_tokens_missed)
     _len_ref = len(reference_tokens)
     return (hit_rate, n_tokens_missed, _len_ref)
 
 
+def bag_similarity(reference_counts: Counter, candidate_counts: Counter,
+                   similarity=SIMILARITY_JACCARD) -> float:
+    """Similarity (0 - 1) of token counts, two empty
+    bags are considered equal"""
+
+    if similarity not in SIMILARITY_MODES:
+        raise ValueError(f"unknown similarity '{similarity}', expected one of {SIMILARITY_MODES}")
+    if not reference_counts and not candidate_counts:
+        return 1.0
+    _tokens = set(reference_counts) | set(candidate_counts)
+    if similarity == SIMILARITY_COSINE:
+        _dot = sum(reference_counts[_t] * candidate_counts[_t] for _t in _tokens)
+        _norms = math.sqrt(sum(_c * _c for _c in reference_counts.values())) * \
+            math.sqrt(sum(_c * _c for _c in candidate_counts.values()))
+        return _dot / _norms if _norms else 0.0
+    _lesser = sum(min(reference_counts[_t], candidate_counts[_t]) for _t in _tokens)
+    _greater = sum(max(reference_counts[_t], candidate_counts[_t]) for _t in _tokens)
+    return _lesser / _greater
+
+
+def _max_edits(token, fuzzy) -> int:
+    if isinstance(fuzzy, float):
+        return int(fuzzy * len(token))
+    return fuzzy
+
+
+def fuzzy_tokens(reference_tokens: List[str], candidate_tokens: List[str], fuzzy) -> List[str]:
+    """Candidate tokens with each one close to an otherwise
+    unmatched reference token replaced by it, like fuzzy
+    search finds slightly misrecognized words
+
+    Exact matches go first, each reference token gets
+    matched by at most one candidate token, the one with
+    least edits within max edits by fuzzy
+    """
+
+    _unmatched = list((Counter(reference_tokens) - Counter(candidate_tokens)).elements())
+    _pool = Counter(candidate_tokens) - Counter(reference_tokens)
+    _replaced = {}
+    for _ref in _unmatched:
+        _max = _max_edits(_ref, fuzzy)
+        if _max < 1:
+            continue
+        _best = None
+        for _cand in sorted(_t for _t in _pool if _pool[_t] > 0):
+            if abs(len(_cand) - len(_ref)) > _max:
+                continue
+            _dist = levenshtein(_ref, _cand)
+            if _dist <= _max and (_best is None or _dist < _best[0]):
+                _best = (_dist, _cand)
+        if _best:
+            _pool[_best[1]] -= 1
+            _replaced.setdefault(_best[1], []).append(_ref)
+    _tokens = []
+    for _cand in candidate_tokens:
+        _refs = _replaced.get(_cand)
+        _tokens.append(_refs.pop(0) if _refs else _cand)
+    return _tokens
+
+
+def correlation(xs, ys):
+    """Pearson correlation coefficient of two samples,
+    None if either doesn't vary at all"""
+
+    if len(xs) < 2:
+        return None
+    (_mean_x, _mean_y) = (statistics.mean(xs), statistics.mean(ys))
+    _cov = sum((_x - _mean_x) * (_y - _mean_y) for (_x, _y) in zip(xs, ys))
+    _var_x = sum((_x - _mean_x) ** 2 for _x in xs)
+    _var_y = sum((_y - _mean_y) ** 2 for _y in ys)
+    if _var_x == 0 or _var_y == 0:
+        return None
+    return _cov / math.sqrt(_var_x * _var_y)
+
+
+def char_ngrams(chars, order) -> Counter:
+    """Count n-grams of chars (or grapheme clusters,
+    tokens)"""
+
+    return Counter(tuple(chars[_i:_i + order]) for _i in range(len(chars) - order + 1))
+
+
+def char_ngram_fscore(reference, candidate, order=CHRF_ORDER, beta=CHRF_BETA) -> Tuple[float, float, float]:
+    """F-score, precision and recall (0 - 1) of char n-grams,
+    latter both averaged over orders 1 up to order which
+    occur in reference or candidate"""
+
+    _precisions = []
+    _recalls = []
+    for _n in range(1, order + 1):
+        (_ref_grams, _cand_grams) = (char_ngrams(reference, _n), char_ngrams(candidate, _n))
+        (_n_ref, _n_cand) = (sum(_ref_grams.values()), sum(_cand_grams.values()))
+        if _n_ref == 0 and _n_cand == 0:
+            break
+        _matches = sum((_ref_grams & _cand_grams).values())
+        _precisions.append(_matches / _n_cand if _n_cand else 0.0)
+        _recalls.append(_matches / _n_ref if _n_ref else 0.0)
+    # nothing to miss at all
+    if not _precisions:
+        return (1.0, 1.0, 1.0)
+    _precision = statistics.mean(_precisions)
+    _recall = statistics.mean(_recalls)
+    if _precision + _recall == 0:
+        return (0.0, _precision, _recall)
+    _fscore = (1 + beta ** 2) * _precision * _recall / (beta ** 2 * _precision + _recall)
+    return (_fscore, _precision, _recall)
+
+
+def word_ngram_bleu(reference, candidate, order=BLEU_ORDER) -> Tuple[float, List[float], float]:
+    """BLEU (0 - 1), precisions per order and brevity
+    penalty of candidate tokens, where candidate n-grams
+    count only as often as they occur in reference
+
+    Orders exceeding candidate's length are skipped,
+    so a short candidate isn't zero by this alone"""
+
+    if not candidate:
+        return (1.0 if not reference else 0.0, [], 1.0 if not reference else 0.0)
+    _precisions = []
+    for _n in range(1, min(order, len(candidate)) + 1):
+        (_ref_grams, _cand_grams) = (char_ngrams(reference, _n), char_ngrams(candidate, _n))
+        _matches = sum((_ref_grams & _cand_grams).values())
+        _precisions.append(_matches / sum(_cand_grams.values()))
+    _penalty = 1.0 if len(candidate) >= len(reference) else math.exp(1 - len(reference) / len(candidate))
+    if min(_precisions) == 0:
+        return (0.0, _precisions, _penalty)
+    _mean = math.exp(sum(math.log(_p) for _p in _precisions) / len(_precisions))
+    return (_penalty * _mean, _precisions, _penalty)
+
+
 def _diff(gt_tokens, cd_tokens) -> List[str]:
     return list((Counter(gt_tokens) - Counter(cd_tokens)).elements())
 
@@ -255,23 +1657,114 @@ def _setup_stopwords(word_mappings=STOPWORDS):
         download('stopwords')
 
 
-def _ir_preprocess(gt_data, test_data, languages):
-    """Common Preprocessing for Information Retrival Metrics"""
-    _setup_stopwords()
+def stopword_language(language) -> str:
+    """ISO 639-2 code of language, given either
+    by code or by NLTK name, like 'deu' or 'german'"""
+
+    _language = str(language).strip().lower()
+    if _language in STOPWORDS_ISO:
+        return _language
+    for (_code, _name) in STOPWORDS_ISO.items():
+        if _name == _language:
+            return _code
+    raise ValueError(f"unknown stopword language '{language}', expected one of {list(STOPWORDS_ISO)}")
+
+
+def parse_languages(label) -> List[str]:
+    """ISO 639-2 codes from comma-separated label"""
+
+    return [stopword_language(_l) for _l in str(label).split(STOPWORDS_SEPARATOR) if _l.strip()]
+
+
+def _read_stopwords(path_in) -> List[str]:
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        return [_l.strip() for _l in _handle if _l.strip() and not _l.startswith('#')]
+
+
+@lru_cache(maxsize=None)
+def _stopwords_of(languages) -> frozenset:
+    """Stopwords of languages, bundled lists for
+    ISO 639-2 codes, NLTK lists for NLTK names"""
+
+    _words = set()
+    for _language in languages:
+        _code = stopword_language(_language)
+        _path = os.path.join(STOPWORDS_DIR, f'{_code}.txt')
+        if _language == _code and os.path.isfile(_path):
+            _words.update(_read_stopwords(_path))
+        else:
+            _setup_stopwords([STOPWORDS_ISO[_code]])
+            _words.update(stopwords.words(STOPWORDS_ISO[_code]))
+    return frozenset(_words)
+
+
+@lru_cache(maxsize=None)
+def _normalized_stopwords(languages, marks=MARKS_KEEP) -> frozenset:
+    """Stopwords of languages like normalized texts"""
+
+    _words = [_normalize(_w).lower() for _w in _stopwords_of(languages)]
+    if marks == MARKS_IGNORE:
+        _words = [strip_marks(_w) for _w in _words]
+    return frozenset(_words)
+
+
+def _ir_tokens(data, languages, token_filters=None, tokenization=TOKENIZE_WHITESPACE) -> set:
+    """Distinct tokens without stopwords of languages"""
+
+    return set(_ir_token_counts(data, languages, token_filters, tokenization))
+
+
+def _ir_token_counts(data, languages, token_filters=None, tokenization=TOKENIZE_WHITESPACE) -> Counter:
+    """Tokens without stopwords of languages by their number"""
+
     if languages == None:
         languages = STOPWORDS_DEFAULT
-    _stopwords = set([_all_words
-                      for _lang in languages
-                      for _all_words in stopwords.words(_lang)]
-                    )  
     # propably feed with list strings
-    if isinstance(gt_data, list):
-        gt_data = ' '.join(gt_data)
-    if isinstance(test_data, list):
-        test_data = ' '.join(test_data)
-    gt_tokens = set(gt_data.split()) - _stopwords
-    test_tokens = set(test_data.split()) - _stopwords
-    return (gt_tokens, test_tokens)
+    if isinstance(data, list):
+        data = ' '.join(data)
+    _stopwords = _stopwords_of(tuple(languages))
+    return Counter(_t for _t in filter_tokens(tokenize(data, tokenization), token_filters)
+                   if _t not in _stopwords)
+
+
+def document_frequencies(documents) -> Tuple[int, Counter]:
+    """Number of documents and number of documents
+    containing each term, from terms of each document"""
+
+    _frequencies = Counter()
+    _n_docs = 0
+    for _terms in documents:
+        _frequencies.update(set(_terms))
+        _n_docs += 1
+    return (_n_docs, _frequencies)
+
+
+def idf(term, frequencies=None) -> float:
+    """Smoothed inverse document frequency of term,
+    which is 1 for all terms without corpus data"""
+
+    (_n_docs, _frequencies) = frequencies or (0, {})
+    return math.log((1 + _n_docs) / (1 + _frequencies.get(term, 0))) + 1
+
+
+def ir_weighted(reference: Counter, candidate: Counter, frequencies=None) -> Tuple[float, float]:
+    """Precision and recall with each distinct token
+    weighted by it's tf-idf in reference or candidate"""
+
+    _w_ref = {_t: _n * idf(_t, frequencies) for (_t, _n) in reference.items()}
+    _w_cand = {_t: _n * idf(_t, frequencies) for (_t, _n) in candidate.items()}
+    _total_ref = sum(_w_ref.values())
+    _total_cand = sum(_w_cand.values())
+    _precision = sum(_w for (_t, _w) in _w_cand.items() if _t in reference) / _total_cand if _total_cand else 0.0
+    _recall = sum(_w for (_t, _w) in _w_ref.items() if _t in candidate) / _total_ref if _total_ref else 0.0
+    return (_precision, _recall)
+
+
+def _ir_preprocess(gt_data, test_data, languages, token_filters=None):
+    """Common Preprocessing for Information Retrival Metrics"""
+
+    return (_ir_tokens(gt_data, languages, token_filters),
+            _ir_tokens(test_data, languages, token_filters))
 
 
 def ir_precision(refrence_data, candidate_data) -> Tuple:
@@ -289,6 +1782,9 @@ def ir_recall(refrence_data, candidate_data) -> Tuple:
     
     # here nltk reports 0.0 if nothing recalled
     _rec = recall(refrence_data, candidate_data)
+    # but None if there's nothing to recall at all
+    if _rec == None:
+        _rec = 0.0
     return (_rec, len(refrence_data))
 
 
@@ -306,4 +1802,344 @@ def _norm(reference, errs, scale_by=100) -> float:
     '''Normalize outcome based on specific reference into range 0 - 100'''
     if (reference - errs) < 0:
         return 0
+    # nothing to miss at all
+    if reference == 0:
+        return scale_by
     return scale_by * ((reference - errs) / reference)
+
+
+def error_rate(reference, errs, scale_by=100) -> float:
+    '''Errors per reference unit, which may exceed scale_by,
+    but any error without reference at all counts as
+    scale_by, like _norm yields 0 accuracy then'''
+    if reference == 0:
+        return 0.0 if errs == 0 else scale_by
+    return scale_by * errs / reference
+
+
+# metrics by label
+METRICS = {
+    'CCA': MetricCA,
+    'CLA': MetricLA,
+    'LCA': MetricLineCA,
+    'QCA': MetricQE,
+    'DICT': MetricDictionary,
+    'ChrF': MetricChrF,
+    'BLEU': MetricBLEU,
+    'CWA': MetricConfidence,
+    'CaseER': MetricCaseER,
+    'DiaER': MetricDiacriticsER,
+    'SegER': MetricSegmentationER,
+    'WWA': MetricWA,
+    'WWAs': MetricWAs,
+    'WBoW': MetricBoW,
+    'BoWf': MetricBoWF,
+    'IRPre': MetricPre,
+    'IRRec': MetricRec,
+    'IRFM': MetricFM,
+    'KWRec': MetricKeywordRecall,
+    'CapRec': MetricCapitalizedRecall,
+    'RegPre': MetricRegionPre,
+    'RegRec': MetricRegionRec,
+    'RegIoU': MetricRegionIoU,
+    'TblSim': MetricTables,
+}
+# metrics calculated unless picked otherwise
+METRICS_DEFAULT = ['CCA', 'CLA', 'WWA', 'WBoW', 'IRPre', 'IRRec', 'IRFM']
+# metrics which operate on tokens
+METRICS_TOKEN_BASED = ['WWA', 'WWAs', 'WBoW', 'BoWf', 'IRPre', 'IRRec', 'IRFM', 'CaseER', 'SegER', 'BLEU', 'KWRec']
+# metrics which compare bags or sets of tokens
+METRICS_BAG_BASED = ['WBoW', 'BoWf', 'IRPre', 'IRRec', 'IRFM']
+# information retrival metrics
+METRICS_IR = ['IRPre', 'IRRec', 'IRFM']
+# metrics which drop stopwords of their languages
+METRICS_STOPWORDS = METRICS_IR + ['WWAs']
+# metrics which rely on edit distance
+METRICS_EDIT_DISTANCE = ['CCA', 'CLA', 'WWA', 'WWAs']
+# metrics which compare characters
+METRICS_CHAR_BASED = ['CCA', 'CLA', 'ChrF', 'DiaER']
+# metrics which compare regions rather than text
+METRICS_LAYOUT = ['RegPre', 'RegRec', 'RegIoU', 'TblSim']
+# layout metrics weighting regions by profile
+METRICS_REGIONS = ['RegPre', 'RegRec', 'RegIoU']
+# metrics ranging 0 - 1 rather than 0 - 100
+METRICS_UNIT_RANGE = ['IRPre', 'IRRec', 'IRFM']
+
+
+def register_metric(label, factory):
+    """Register factory creating metric by label, like a
+    Metric subclass or closure without arguments, replacing
+    any previous metric of same label, so it can be picked
+    by label like builtin metrics"""
+
+    if not label or METRIC_SPEC_SEPARATOR in label or '=' in label:
+        raise ValueError(f"invalid metric label '{label}'")
+    if not callable(factory):
+        raise ValueError(f"{label}: factory must be callable, got {factory!r}")
+    METRICS[label] = factory
+
+
+def _parse_fuzzy(label, value):
+    """Max edits as int or edits per char as float"""
+
+    try:
+        _fuzzy = float(value) if '.' in value else int(value)
+    except ValueError:
+        _fuzzy = -1
+    if _fuzzy < 0 or (isinstance(_fuzzy, float) and _fuzzy >= 1):
+        raise ValueError(f"{label}: invalid fuzzy threshold '{value}', expected max edits "
+                         f"like '1' or edits per char like '0.2'")
+    return _fuzzy
+
+
+def _parse_unit(label, key, value):
+    try:
+        _number = float(value)
+    except ValueError:
+        _number = 0
+    if not 0 < _number <= 1:
+        raise ValueError(f"{label}: invalid option '{key}={value}', expected number within (0, 1]")
+    return _number
+
+
+def _parse_positive(label, key, value, convert):
+    try:
+        _number = convert(value)
+    except ValueError:
+        _number = 0
+    if _number <= 0:
+        raise ValueError(f"{label}: invalid option '{key}={value}', expected positive number")
+    return _number
+
+
+def metric_from_spec(spec) -> Metric:
+    """Create metric from label with optional settings, i.e.
+    * "CCA"
+    * "WWA:drop=punct"
+    * "WBoW:drop=punct,digits"
+    * "CCA:gaps=auto"
+    * "CCA:units=graphemes"
+    * "WWA:tokenize=split-punct"
+    * "CCA:case=ignore"
+    * "CCA:marks=ignore"
+    * "WBoW:fuzzy=1" or "IRRec:fuzzy=0.2"
+    * "IRRec:weighting=tfidf"
+    * "QCA:model=qe.onnx:dictionary=words.txt"
+    * "ChrF:n=3:beta=1"
+    * "BLEU:n=2"
+    * "CCA:report=error-rate"
+    * "CCA:length=max"
+    * "DICT:api=http://localhost:8081/v2:language=de-DE"
+    * "IRRec:language=deu,lat"
+    * "WWAs:language=deu"
+    * "DICT:dictionary=de_DE.dic,names.txt"
+    * "BoWf:similarity=cosine"
+    * "RegRec:iou=0.7"
+    * "RegRec:profile=newspaper.xml"
+    * "TblSim:content=ignore"
+    * "KWRec:terms=names.txt"
+    """
+
+    _tokens = []
+    for _token in str(spec).split(METRIC_SPEC_SEPARATOR):
+        # values may contain separator, like URLs
+        if len(_tokens) > 1 and '=' not in _token:
+            _tokens[-1] += METRIC_SPEC_SEPARATOR + _token
+        else:
+            _tokens.append(_token)
+    _tokens = [t.strip() for t in _tokens]
+    _label = _tokens[0]
+    if _label not in METRICS:
+        raise ValueError(f"unknown metric '{_label}', expected one of {list(METRICS)}")
+    _metric = METRICS[_label]()
+    _report = REPORT_ACCURACY
+    for _option in _tokens[1:]:
+        if '=' not in _option:
+            raise ValueError(f"{_label}: invalid option '{_option}', expected 'key=value'")
+        (_key, _value) = [o.strip() for o in _option.split('=', 1)]
+        if _key == METRIC_OPTION_DROP:
+            if _label not in METRICS_TOKEN_BASED:
+                raise ValueError(f"{_label}: option '{_key}' requires token based metric")
+            _classes = [c.strip() for c in _value.split(',') if c.strip()]
+            _unknown = [c for c in _classes if c not in TOKEN_FILTERS]
+            if _unknown:
+                raise ValueError(f"{_label}: unknown token class {_unknown}, expected {list(TOKEN_FILTERS)}")
+            _metric.token_filters = _classes
+        elif _key == METRIC_OPTION_GAPS:
+            if _label not in METRICS_EDIT_DISTANCE:
+                raise ValueError(f"{_label}: option '{_key}' requires edit distance metric {METRICS_EDIT_DISTANCE}")
+            if _value not in GAPS_MODES:
+                raise ValueError(f"{_label}: unknown alignment mode '{_value}', expected one of {GAPS_MODES}")
+            _metric.gaps = _value
+        elif _key == METRIC_OPTION_LENGTH:
+            if _label not in METRICS_EDIT_DISTANCE:
+                raise ValueError(f"{_label}: option '{_key}' requires edit distance metric {METRICS_EDIT_DISTANCE}")
+            if _value not in LENGTH_MODES:
+                raise ValueError(f"{_label}: unknown length '{_value}', expected one of {LENGTH_MODES}")
+            _metric.length = _value
+        elif _key == METRIC_OPTION_UNITS:
+            if _label not in METRICS_CHAR_BASED:
+                raise ValueError(f"{_label}: option '{_key}' requires character based metric {METRICS_CHAR_BASED}")
+            if _value not in UNITS_MODES:
+                raise ValueError(f"{_label}: unknown units '{_value}', expected one of {UNITS_MODES}")
+            _metric.units = _value
+        elif _key == METRIC_OPTION_TOKENIZE:
+            if _label not in METRICS_TOKEN_BASED:
+                raise ValueError(f"{_label}: option '{_key}' requires token based metric")
+            if _value not in TOKENIZE_MODES:
+                raise ValueError(f"{_label}: unknown tokenization '{_value}', expected one of {TOKENIZE_MODES}")
+            _metric.tokenization = _value
+        elif _key == METRIC_OPTION_CASE:
+            if _value not in CASE_MODES:
+                raise ValueError(f"{_label}: unknown case mode '{_value}', expected one of {CASE_MODES}")
+            _metric.case = _value
+        elif _key == METRIC_OPTION_MARKS:
+            if _value not in MARKS_MODES:
+                raise ValueError(f"{_label}: unknown marks mode '{_value}', expected one of {MARKS_MODES}")
+            _metric.marks = _value
+        elif _key == METRIC_OPTION_FUZZY:
+            if _label not in METRICS_BAG_BASED:
+                raise ValueError(f"{_label}: option '{_key}' requires bag of words metric {METRICS_BAG_BASED}")
+            _metric.fuzzy = _parse_fuzzy(_label, _value)
+        elif _key == METRIC_OPTION_WEIGHTING:
+            if _label not in METRICS_IR:
+                raise ValueError(f"{_label}: option '{_key}' requires IR metric {METRICS_IR}")
+            if _value not in WEIGHTING_MODES:
+                raise ValueError(f"{_label}: unknown weighting '{_value}', expected one of {WEIGHTING_MODES}")
+            _metric.weighting = _value
+        elif _key == METRIC_OPTION_REPORT:
+            if _label not in ERROR_RATE_METRICS:
+                raise ValueError(f"{_label}: option '{_key}' requires one of {list(ERROR_RATE_METRICS)}")
+            if _value not in REPORT_MODES:
+                raise ValueError(f"{_label}: unknown report mode '{_value}', expected one of {REPORT_MODES}")
+            _report = _value
+        elif _key == METRIC_OPTION_DICTIONARY and _label == 'DICT':
+            _metric.path_dictionary = _value
+        elif _key in [METRIC_OPTION_MODEL, METRIC_OPTION_DICTIONARY, METRIC_OPTION_LM]:
+            if _label != 'QCA':
+                raise ValueError(f"{_label}: option '{_key}' requires quality estimation metric 'QCA'")
+            setattr(_metric, f'path_{_key}', _value)
+        elif _key == METRIC_OPTION_SIMILARITY:
+            if _label != 'BoWf':
+                raise ValueError(f"{_label}: option '{_key}' requires frequency bag of words metric 'BoWf'")
+            if _value not in SIMILARITY_MODES:
+                raise ValueError(f"{_label}: unknown similarity '{_value}', expected one of {SIMILARITY_MODES}")
+            _metric.similarity = _value
+        elif _key == METRIC_OPTION_LANGUAGE and _label in METRICS_STOPWORDS:
+            _metric.languages = parse_languages(_value)
+        elif _key in [METRIC_OPTION_API, METRIC_OPTION_LANGUAGE]:
+            if _label != 'DICT' and _key == METRIC_OPTION_LANGUAGE:
+                raise ValueError(f"{_label}: option '{_key}' requires dictionary metric 'DICT' or "
+                                 f"stopword metric {METRICS_STOPWORDS}")
+            if _label != 'DICT':
+                raise ValueError(f"{_label}: option '{_key}' requires dictionary metric 'DICT'")
+            if _key == METRIC_OPTION_API:
+                _metric.api_url = _value
+            else:
+                _metric.language = _value
+        elif _key == METRIC_OPTION_IOU:
+            if _label not in METRICS_LAYOUT:
+                raise ValueError(f"{_label}: option '{_key}' requires layout metric {METRICS_LAYOUT}")
+            _metric.iou_threshold = _parse_unit(_label, _key, _value)
+        elif _key == METRIC_OPTION_PROFILE:
+            if _label not in METRICS_REGIONS:
+                raise ValueError(f"{_label}: option '{_key}' requires region metric {METRICS_REGIONS}")
+            _metric.path_profile = _value
+        elif _key == METRIC_OPTION_CONTENT:
+            if _label != 'TblSim':
+                raise ValueError(f"{_label}: option '{_key}' requires table metric 'TblSim'")
+            if _value not in TABLE_CONTENT_MODES:
+                raise ValueError(f"{_label}: unknown content '{_value}', expected one of {TABLE_CONTENT_MODES}")
+            _metric.content = _value
+        elif _key == METRIC_OPTION_TERMS:
+            if _label != 'KWRec':
+                raise ValueError(f"{_label}: option '{_key}' requires keyword metric 'KWRec'")
+            _metric.path_terms = _value
+        elif _key == METRIC_OPTION_ORDER and _label == 'BLEU':
+            _metric.order = _parse_positive(_label, _key, _value, int)
+        elif _key in [METRIC_OPTION_ORDER, METRIC_OPTION_BETA]:
+            if _label != 'ChrF':
+                raise ValueError(f"{_label}: option '{_key}' requires char n-gram metric 'ChrF'")
+            if _key == METRIC_OPTION_ORDER:
+                _metric.order = _parse_positive(_label, _key, _value, int)
+            else:
+                _metric.beta = _parse_positive(_label, _key, _value, float)
+        else:
+            raise ValueError(f"{_label}: unknown option '{_key}'")
+    if _report == REPORT_ERROR_RATE:
+        _metric.as_error_rate()
+    if _label == 'DICT' and _metric.api_url and _metric.path_dictionary:
+        raise ValueError(f"{_label}: use either option '{METRIC_OPTION_API}' or '{METRIC_OPTION_DICTIONARY}'")
+    if _label == 'QCA' and not _metric.path_model:
+        raise ValueError(f"{_label}: requires option '{METRIC_OPTION_MODEL}', like 'QCA:model=qe.onnx'")
+    if _label == 'KWRec' and not _metric.path_terms:
+        raise ValueError(f"{_label}: requires option '{METRIC_OPTION_TERMS}', like 'KWRec:terms=names.txt'")
+    return _metric
+
+
+class MetricScore(Metric):
+    """Composite score as weighted sum of other
+    metric's values, calculated per entry after
+    all weighted metrics are done. Values ranging
+    0 - 1 get scaled to 0 - 100 like all others"""
+
+    def __init__(self, weights, label=SCORE_LABEL_DEFAULT):
+        super().__init__()
+        self.label = label
+        self.name = 'Composite Score'
+        # weight by metric label
+        self.weights = weights
+
+    def validate(self, labels):
+        """Ensure all weighted metrics get calculated"""
+
+        _missing = [_l for _l in self.weights if _l not in labels]
+        if _missing:
+            raise ValueError(f"{self.label}: weighted metrics {_missing} not calculated, only {list(labels)}")
+
+    def combine(self, metrics):
+        _by_label = {m.label: m for m in metrics}
+        self.validate(_by_label)
+        # all on same scale 0 - 100
+        self.value = sum(_w * _by_label[_l].value * (100 if _l in METRICS_UNIT_RANGE else 1)
+                         for (_l, _w) in self.weights.items())
+        self.n_ref = max(_by_label[_l].n_ref for _l in self.weights)
+
+    def calculate(self, ctx: EvalContext) -> MetricOutcome:
+        raise RuntimeError(f"{self.label}: composite score requires other metrics, use combine()")
+
+    def __str__(self) -> str:
+        return f' {SCORE_TERM_SEPARATOR} '.join(f'{_w}{SCORE_WEIGHT_SEPARATOR}{_l}'
+                                               for (_l, _w) in self.weights.items())
+
+
+def score_labels() -> List[str]:
+    """Labels of metrics a composite score may weight,
+    i.e. picked by label, as error rate or by
+    compatibility mode"""
+
+    _labels = list(METRICS) + [_rate[0] for _rate in ERROR_RATE_METRICS.values()]
+    return _labels + [_l for _l in SCORE_LABELS_EXTRA if _l not in _labels]
+
+
+def score_from_spec(spec, label=SCORE_LABEL_DEFAULT) -> MetricScore:
+    """Create composite score from weighted sum
+    of metric labels, like "0.6*CCA + 0.4*WWA"
+    """
+
+    _weights = {}
+    for _term in str(spec).split(SCORE_TERM_SEPARATOR):
+        _tokens = [t.strip() for t in _term.split(SCORE_WEIGHT_SEPARATOR)]
+        if len(_tokens) != 2:
+            raise ValueError(f"invalid score term '{_term.strip()}', expected 'weight*metric'")
+        (_weight, _label) = _tokens
+        try:
+            _weight = float(_weight)
+        except ValueError as _err:
+            raise ValueError(f"invalid score weight '{_weight}' for '{_label}'") from _err
+        if _label not in score_labels():
+            raise ValueError(f"unknown metric '{_label}', expected one of {score_labels()}")
+        if _label in _weights:
+            raise ValueError(f"metric '{_label}' weighted twice")
+        _weights[_label] = _weight
+    return MetricScore(_weights, label)
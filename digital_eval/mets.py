@@ -0,0 +1,83 @@
+# -*- coding: utf-8 -*-
+"""METS Module
+
+Read publication dates from METS/MODS files next to
+digitized pages, as produced by mass digitization
+workflows, like
+
+    <work>/<record>.xml              (METS)
+    <work>/FULLTEXT/<page-01>.xml    (candidate)
+"""
+
+import functools
+import os
+import re
+import xml.etree.ElementTree as ET
+
+XML_NS_MODS = 'http://www.loc.gov/mods/v3'
+# METS file may reside this many levels above page
+METS_MAX_LEVELS = 2
+# number of leading chars to recognize METS by
+METS_SNIFF_LENGTH = 512
+
+_YEAR = re.compile(r'\b(\d{4})\b')
+
+
+def is_mets(path_in) -> bool:
+    """Whether file looks like METS"""
+
+    if not str(path_in).endswith('.xml') or not os.path.isfile(path_in):
+        return False
+    with open(path_in, mode='r', encoding='utf-8', errors='replace') as _handle:
+        _head = _handle.read(METS_SNIFF_LENGTH)
+    return '<mets:mets' in _head or 'www.loc.gov/METS/' in _head
+
+
+@functools.lru_cache(maxsize=None)
+def _mets_in(dir_path):
+    _names = sorted(os.listdir(dir_path)) if os.path.isdir(dir_path) else []
+    for _name in _names:
+        if is_mets(os.path.join(dir_path, _name)):
+            return os.path.join(dir_path, _name)
+    return None
+
+
+def find_mets(path_page, max_levels=METS_MAX_LEVELS):
+    """Path of METS file in page's directory or up to
+    max_levels above, None if there is none"""
+
+    _dir = os.path.dirname(os.path.abspath(str(path_page)))
+    for _ in range(max_levels + 1):
+        _mets = _mets_in(_dir)
+        if _mets:
+            return _mets
+        _dir = os.path.dirname(_dir)
+    return None
+
+
+@functools.lru_cache(maxsize=None)
+def read_mets_year(path_mets):
+    """Year of publication from MODS originInfo, prefers
+    dateIssued over dateCreated and key dates over others,
+    None if METS lacks any date or can't be parsed"""
+
+    try:
+        _root = ET.parse(path_mets).getroot()
+    except (ET.ParseError, OSError) as _exc:
+        print(f"[WARN ] {os.path.basename(path_mets)}: no year of publication, invalid METS: {_exc}")
+        return None
+    for _tag in ['dateIssued', 'dateCreated']:
+        _dates = _root.findall(f'.//{{{XML_NS_MODS}}}originInfo/{{{XML_NS_MODS}}}{_tag}')
+        _dates.sort(key=lambda d: d.attrib.get('keyDate') != 'yes')
+        for _date in _dates:
+            _match = _YEAR.search(_date.text or '')
+            if _match:
+                return int(_match.group(1))
+    return None
+
+
+def page_year(path_page, max_levels=METS_MAX_LEVELS):
+    """Year of publication of page's METS, if any"""
+
+    _mets = find_mets(path_page, max_levels)
+    return read_mets_year(_mets) if _mets else None
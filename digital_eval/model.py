@@ -1,27 +1,113 @@
 # -*- coding: utf-8 -*-
 """Model Module"""
 
+from datetime import (
+    datetime
+)
 from enum import (
     Enum
 )
 from typing import (
     List, 
+    Optional,
 )
 
+import re
 import xml.dom.minidom
 
 from shapely.geometry import (
     Polygon
 )
 
+from .errors import (
+    EvalParseError,
+    EvalUnsupportedError,
+)
+
 
 PAGE_2013 = 'http://schema.primaresearch.org/PAGE/gts/pagecontent/2013-07-15'
 XML_NS = {'alto': 'http://www.loc.gov/standards/alto/ns-v3#',
           'pg2013': PAGE_2013}
+XML_NS_XSI = 'http://www.w3.org/2001/XMLSchema-instance'
+PAGE_2019 = 'http://schema.primaresearch.org/PAGE/gts/pagecontent/2019-07-15'
+PAGE_2019_SCHEMA = f'{PAGE_2019}/pagecontent.xsd'
+ALTO_V4 = 'http://www.loc.gov/standards/alto/ns-v4#'
+ALTO_V4_SCHEMA = 'http://www.loc.gov/standards/alto/v4/alto-4-2.xsd'
+# software mentioned in processing steps
+PROCESSING_SOFTWARE = 'digital-eval'
+# ALTO marks groundtruth type with this tag
+ALTO_TAG_GROUNDTRUTH = 'ulb_groundtruth_type'
+# valid XML ID (NCName), otherwise ID gets generated
+_XML_ID = re.compile(r'^[A-Za-z_][\w.-]*$')
+# hOCR classes of words and lines, geometry as title property
+HOCR_CLASS_WORD = 'ocrx_word'
+HOCR_CLASSES_LINE = ['ocr_line', 'ocr_header', 'ocr_caption', 'ocr_textfloat']
+_HOCR_BBOX = re.compile(r'\bbbox\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)')
+_HOCR_WCONF = re.compile(r'\bx_wconf\s+(\d+(?:\.\d+)?)')
+
+# strings joining words to lines, lines to regions
+# and regions to pages, by name or taken literally
+SEPARATOR_NAMES = {'space': ' ', 'newline': '\n', 'tab': '\t', 'none': ''}
+SEPARATOR_LEVELS = ['word', 'line', 'region']
 
 
 UNSET = 'n.a.'
 
+
+class Separators:
+    """Strings joining words to lines (word), lines
+    to regions (line) and regions to pages (region)"""
+
+    def __init__(self, word=' ', line=' ', region=' '):
+        self.word = word
+        self.line = line
+        self.region = region
+
+    def __eq__(self, other) -> bool:
+        return isinstance(other, Separators) and vars(self) == vars(other)
+
+    def __repr__(self) -> str:
+        return ':'.join(f'{_level}={getattr(self, _level)!r}' for _level in SEPARATOR_LEVELS)
+
+    def of(self, piece_type) -> str:
+        """Separator between pieces of piece_type"""
+
+        if piece_type == PieceType.REGION:
+            return self.region
+        return self.line if piece_type == PieceType.LINE else self.word
+
+
+def separators_from_spec(spec, defaults: Separators = None) -> Separators:
+    """Separators from spec like 'line=newline:region=\\n\\n',
+    each value one of SEPARATOR_NAMES or a custom string
+    with escapes like '\\n', levels not mentioned are
+    taken from defaults"""
+
+    _defaults = defaults or Separators()
+    _values = {_level: getattr(_defaults, _level) for _level in SEPARATOR_LEVELS}
+    for _option in [_o for _o in str(spec).split(':') if _o.strip()]:
+        (_level, _eq, _value) = _option.partition('=')
+        _level = _level.strip()
+        if _level not in SEPARATOR_LEVELS or not _eq:
+            raise ValueError(f"invalid separator '{_option}', expected 'level=value' "
+                             f"with level one of {SEPARATOR_LEVELS}")
+        if _value.strip() in SEPARATOR_NAMES:
+            _values[_level] = SEPARATOR_NAMES[_value.strip()]
+        else:
+            _values[_level] = _value.replace('\\n', '\n').replace('\\t', '\t')
+    return Separators(**_values)
+
+
+def parse_confidence(value) -> Optional[float]:
+    """Confidence of attribute value like WC or conf,
+    None if it's no number, like '0,95' with comma"""
+
+    try:
+        return float(value)
+    except ValueError:
+        return None
+
+
 class PieceType(Enum):
     # more hierarchically
     UNKNOWN = 0
@@ -29,7 +115,7 @@ class PieceType(Enum):
     WORD = 2
     LINE = 3
     REGION = 4
-    PAGE = 4
+    PAGE = 5
 
 class PieceSubject(Enum):
     # more layout
@@ -44,12 +130,16 @@ class PieceSubject(Enum):
     ANNOUNCEMENT = 7
     ADVERTISEMENT = 8
 
+_SEPARATORS_SPACE = Separators()
+
+
 class PieceTranscription:
 
     def __init__(self):
         self.language = UNSET
         self.text = ''
-        self.confidence = 0.0
+        # unknown, if not provided
+        self.confidence = None
 
 class PieceData:
 
@@ -57,6 +147,59 @@ class PieceData:
         self.mime_type = UNSET
         self.data = None
 
+class PieceMetadata:
+    """Metadata section of source data (ALTO Description,
+    PAGE Metadata), kept to carry it over when written"""
+
+    def __init__(self, source_format, element):
+        # 'ALTO' or 'PAGE'
+        self.source_format = source_format
+        self.element = element
+
+    def _texts(self, element, local_name) -> List[str]:
+        return [_e.firstChild.nodeValue.strip()
+                for _e in element.getElementsByTagNameNS('*', local_name)
+                if _e.firstChild and _e.firstChild.nodeValue and _e.firstChild.nodeValue.strip()]
+
+    @property
+    def processing_steps(self) -> List:
+        """Processing steps recorded so far as list of
+        (date time, description, software), each unset if unknown"""
+
+        _steps = []
+        if self.source_format == 'ALTO':
+            # default description by kind of step
+            _names = {'preProcessingStep': 'preprocessing', 'ocrProcessingStep': 'ocr',
+                      'postProcessingStep': 'postprocessing', 'Processing': UNSET}
+            _elements = [(_e, _d) for (_n, _d) in _names.items()
+                         for _e in self.element.getElementsByTagNameNS('*', _n)]
+            for (_element, _default) in _elements:
+                _date = (self._texts(_element, 'processingDateTime') or [UNSET])[0]
+                _description = (self._texts(_element, 'processingStepDescription') or [_default])[0]
+                _software = (self._texts(_element, 'softwareName') or [UNSET])[0]
+                _steps.append((_date, _description, _software))
+        elif self.source_format == 'PAGE':
+            _creators = self._texts(self.element, 'Creator')
+            if _creators:
+                _created = (self._texts(self.element, 'Created') or [UNSET])[0]
+                _steps.append((_created, 'created', _creators[0]))
+            for _item in self.element.getElementsByTagNameNS('*', 'MetadataItem'):
+                if _item.getAttribute('type') == 'processingStep':
+                    _steps.append((UNSET, _item.getAttribute('name') or UNSET,
+                                   _item.getAttribute('value') or UNSET))
+        return _steps
+
+    @property
+    def image_name(self):
+        """Name of source image, if recorded"""
+
+        if self.source_format == 'ALTO':
+            _names = self._texts(self.element, 'fileName')
+            if _names:
+                return _names[0]
+        return None
+
+
 class Piece:
     """Piece base composition for analytical purposes"""
 
@@ -70,6 +213,11 @@ class Piece:
         self.custom = {}
         self.dimensions = []
         self.pieces = []
+        # source metadata, only for pages
+        self.metadata = None
+        # PAGE element name and subtype, only for regions
+        self.region_type = None
+        self.region_subtype = None
 
     def __repr__(self) -> str:
         return f"{self.id}:{self.transcription}"
@@ -86,11 +234,22 @@ class Piece:
         Text will come without sanitized linebreaks, but
         includes a whitespace between single lines and words.
         """
+        return self.text()
+
+    def text(self, separators: Separators = None) -> str:
+        """Textual content like transcription, but joined
+        by separators of each level, words by separators.word,
+        lines by separators.line and regions by separators.region
+        """
         if self._transcriptions:
             return self._transcriptions[0].text
         elif not self._transcriptions and self._is_superstruct():
-            return ' '.join([_p.transcription
-                for _p in self.pieces])
+            _separators = separators or _SEPARATORS_SPACE
+            _texts = [_p.text(separators) for _p in self.pieces]
+            _joined = _texts[0] if _texts else ''
+            for (_previous, _piece, _text) in zip(self.pieces, self.pieces[1:], _texts[1:]):
+                _joined += _separators.of(max(_previous.type, _piece.type, key=lambda t: t.value)) + _text
+            return _joined
         raise RuntimeError(f"ID={self.id}: Can't get text_content for type {self.type}!")
 
     @transcription.setter
@@ -100,6 +259,19 @@ class Piece:
         _transcription.text = transcription
         self._transcriptions.append(_transcription)
 
+    @property
+    def confidence(self):
+        """Confidence of own transcription, if any"""
+        if self._transcriptions:
+            return self._transcriptions[0].confidence
+        return None
+
+    @confidence.setter
+    def confidence(self, confidence):
+        if not self._transcriptions:
+            raise RuntimeError(f"ID={self.id}: no transcription to set confidence for!")
+        self._transcriptions[0].confidence = confidence
+
     def __contains__(self, other_piece) -> bool:
         """Test for topological membership of an other_piece"""
         if not self.dimensions:
@@ -121,17 +293,22 @@ def to_pieces(path_in):
 def _read_data(path_in):
     doc_root = xml.dom.minidom.parse(path_in).documentElement
     if doc_root is None:
-        raise RuntimeError('invalid document root')
+        raise EvalParseError('invalid document root', 'xml')
     name_space = doc_root.getAttribute('xmlns')
-    if doc_root.localName == 'alto':
-        return _extract_alto_data(doc_root)
-    elif name_space == PAGE_2013:
-       return  _extract_page_data(doc_root)
-    elif doc_root.localName == 'PcGts':
-        return _extract_page_data(doc_root, ns='pc:')
-    else:
-        raise RuntimeError(
-            'Unknown Data-Format "{}" in "{}"'.format(doc_root.localName, path_in))
+    _format = 'alto' if doc_root.localName == 'alto' else 'page'
+    try:
+        if doc_root.localName == 'alto':
+            return _extract_alto_data(doc_root)
+        elif name_space == PAGE_2013:
+            return _extract_page_data(doc_root)
+        elif doc_root.localName == 'PcGts':
+            return _extract_page_data(doc_root, ns='pc:')
+    except EvalParseError:
+        raise
+    except RuntimeError as exc:
+        raise EvalParseError(str(exc), _format) from exc
+    raise EvalUnsupportedError(
+        'Unknown Data-Format "{}" in "{}"'.format(doc_root.localName, path_in))
 
 
 def _extract_alto_data(doc_root):
@@ -143,6 +320,9 @@ def _extract_alto_data(doc_root):
     top_piece.dimensions = _dimensions
     top_piece.type = PieceType.PAGE
     top_piece.subject = __get_piece_subject_alto(doc_root)
+    _descriptions = [n for n in doc_root.childNodes if n.localName == 'Description']
+    if _descriptions:
+        top_piece.metadata = PieceMetadata('ALTO', _descriptions[0])
     # composed level
     _block_pieces = []
     comp_blocks = doc_root.getElementsByTagName('ComposedBlock')
@@ -173,6 +353,8 @@ def _read_alto_blocks(block_elements, parent):
     for _block in block_elements:
         _block_piece = Piece(_block.getAttribute('ID'))
         _block_piece.type = PieceType.REGION
+        # PAGE equivalent
+        _block_piece.region_type = 'TextRegion'
         _lines = _block.getElementsByTagName('TextLine')
         if len(_lines) == 0:
             raise RuntimeError(f"TextBlock@ID={_block_piece.id} contains no lines!")
@@ -192,7 +374,7 @@ def __get_piece_subject_alto(doc_root):
         if label:
             _subject = label
         # new alto way
-        elif _subject is None:
+        else:
             gt_els = [e for e in gt_type_el if e.getAttribute(
                 'ID') == "ulb_groundtruth_type"]
             if len(gt_els) == 1:
@@ -228,6 +410,8 @@ def _read_words_alto(text_strings, parent):
         if not _content.strip():
             continue
         word_piece.transcription = _content
+        if _text_string.hasAttribute('WC'):
+            word_piece.confidence = parse_confidence(_text_string.getAttribute('WC'))
         word_piece.dimensions = __extract_alto_dimensions(_text_string)
         word_piece.parent = parent
         _words.append(word_piece)
@@ -255,6 +439,9 @@ def _extract_page_data(doc_root, ns=''):
     page_height = int(page_one.getAttribute('imageHeight'))
     top_piece = Piece(page_one.getAttribute('imageFilename'))
     top_piece.type = PieceType.PAGE
+    _metadatas = [n for n in doc_root.childNodes if n.localName == 'Metadata']
+    if _metadatas:
+        top_piece.metadata = PieceMetadata('PAGE', _metadatas[0])
     top_piece.dimensions = [[0,0], [page_width,0], 
         [page_width, page_height], [0, page_height]]
     regions = doc_root.getElementsByTagName(ns+'TextRegion')
@@ -304,6 +491,9 @@ def __from_page_text_element(element, parent, ns) -> Piece:
     _piece = Piece(_id)
     _piece.type = _type
     _piece.parent = parent
+    if _type == PieceType.REGION:
+        _piece.region_type = _local
+        _piece.region_subtype = element.getAttribute('type') or None
     # inspect geometry
     _coords = [n for n in element.childNodes if n.localName == 'Coords']
     if len(_coords) < 1 or 'points' not in _coords[0].attributes:
@@ -323,6 +513,8 @@ def __from_page_text_element(element, parent, ns) -> Piece:
     # only add content when not top-level piece
     if _type == PieceType.WORD:
         _piece.transcription = _content
+        if _txt_eqs[0].hasAttribute('conf'):
+            _piece.confidence = parse_confidence(_txt_eqs[0].getAttribute('conf'))
     return _piece
 
 
@@ -339,6 +531,529 @@ def ___map_piece_type(element):
 
 
 
+def _bounding_box(dimensions):
+    _xs = [_p[0] for _p in dimensions]
+    _ys = [_p[1] for _p in dimensions]
+    return (min(_xs), min(_ys), max(_xs), max(_ys))
+
+
+def _is_rectangle(dimensions) -> bool:
+    if len(dimensions) != 4:
+        return False
+    (_x1, _y1, _x2, _y2) = _bounding_box(dimensions)
+    return all(_p[0] in (_x1, _x2) and _p[1] in (_y1, _y2) for _p in dimensions)
+
+
+def _xml_id(piece, fallback) -> str:
+    _id = piece.id
+    if _id and _id != UNSET and _XML_ID.match(_id):
+        return _id
+    return fallback
+
+
+def _unique_ids(element, id_attribute, used=None):
+    """Rename IDs which occour more than once
+    in source data, keeping the first one"""
+
+    used = set() if used is None else used
+    if element.hasAttribute(id_attribute):
+        _id = element.getAttribute(id_attribute)
+        _unique = _id
+        _n = 1
+        while _unique in used:
+            _n += 1
+            _unique = f'{_id}_{_n}'
+        element.setAttribute(id_attribute, _unique)
+        used.add(_unique)
+    for _child in element.childNodes:
+        if _child.nodeType == _child.ELEMENT_NODE:
+            _unique_ids(_child, id_attribute, used)
+    return used
+
+
+def _copy_element(document, source, prefix=''):
+    """Re-create source element and it's content in document
+    with own namespace prefix, since source may use another
+    ALTO or PAGE version or prefix"""
+
+    _element = document.createElement(prefix + source.localName)
+    for (_key, _value) in source.attributes.items():
+        if _key != 'xmlns' and not _key.startswith('xmlns:'):
+            _element.setAttribute(_key, _value)
+    for _child in source.childNodes:
+        if _child.nodeType == _child.ELEMENT_NODE:
+            _element.appendChild(_copy_element(document, _child, prefix))
+        elif _child.nodeType == _child.TEXT_NODE and _child.nodeValue.strip():
+            _element.appendChild(document.createTextNode(_child.nodeValue.strip()))
+    return _element
+
+
+def _append_text(document, parent, name, text):
+    _element = _append(document, parent, name)
+    _element.appendChild(document.createTextNode(text))
+    return _element
+
+
+def _append(document, parent, name, attributes=None):
+    _element = document.createElement(name)
+    for (_key, _value) in (attributes or {}).items():
+        _element.setAttribute(_key, str(_value))
+    parent.appendChild(_element)
+    return _element
+
+
+def _indent(document, element, level=0):
+    """Indent child elements, but keep text content
+    untouched (toprettyxml alters it before Python 3.8)"""
+
+    _children = [_c for _c in element.childNodes if _c.nodeType == _c.ELEMENT_NODE]
+    if not _children:
+        return
+    for _child in _children:
+        element.insertBefore(document.createTextNode('\n' + '  ' * (level + 1)), _child)
+        _indent(document, _child, level + 1)
+    element.appendChild(document.createTextNode('\n' + '  ' * level))
+
+
+def _write_xml(document, path_out):
+    _indent(document, document.documentElement)
+    with open(path_out, mode='wb') as _handle:
+        _handle.write(document.toxml(encoding='UTF-8').replace(b'?>', b'?>\n', 1))
+        _handle.write(b'\n')
+
+
+def _append_alto_geometry(document, element, piece, with_shape=True):
+    """Set mandatory box and, if it isn't
+    a plain rectangle, polygon shape"""
+
+    if not piece.dimensions:
+        raise RuntimeError(f"ID={piece.id}: no dimensions to write!")
+    (_x1, _y1, _x2, _y2) = _bounding_box(piece.dimensions)
+    element.setAttribute('HPOS', str(_x1))
+    element.setAttribute('VPOS', str(_y1))
+    element.setAttribute('WIDTH', str(_x2 - _x1))
+    element.setAttribute('HEIGHT', str(_y2 - _y1))
+    if with_shape and not _is_rectangle(piece.dimensions):
+        _shape = _append(document, element, 'Shape')
+        _points = ' '.join(f'{_p[0]},{_p[1]}' for _p in piece.dimensions)
+        _append(document, _shape, 'Polygon', {'POINTS': _points})
+
+
+def _append_alto_string(document, parent, piece, text, fallback_id):
+    _string = _append(document, parent, 'String', {'ID': _xml_id(piece, fallback_id)})
+    _append_alto_geometry(document, _string, piece)
+    _string.setAttribute('CONTENT', text)
+    if piece.confidence is not None:
+        _string.setAttribute('WC', f'{piece.confidence:.3f}')
+    return _string
+
+
+def _append_alto_line(document, parent, line, fallback_id):
+    _words = [_p for _p in line.pieces if _p.type == PieceType.WORD]
+    _line = _append(document, parent, 'TextLine', {'ID': _xml_id(line, fallback_id)})
+    _append_alto_geometry(document, _line, line)
+    if _words:
+        for (_i, _word) in enumerate(_words, 1):
+            if _i > 1:
+                _append(document, _line, 'SP')
+            _append_alto_string(document, _line, _word, _word.transcription, f'{fallback_id}_w{_i}')
+    elif line._transcriptions:
+        # line level only data, like some PAGE
+        _append_alto_string(document, _line, line, line.transcription, f'{fallback_id}_w1')
+
+
+def _append_alto_region(document, parent, region, fallback_id):
+    _subs = [_p for _p in region.pieces if _p.type == PieceType.REGION]
+    if _subs:
+        _block = _append(document, parent, 'ComposedBlock', {'ID': _xml_id(region, fallback_id)})
+        _append_alto_geometry(document, _block, region)
+        for (_i, _sub) in enumerate(_subs, 1):
+            _append_alto_region(document, _block, _sub, f'{fallback_id}_{_i}')
+        return
+    _block = _append(document, parent, 'TextBlock', {'ID': _xml_id(region, fallback_id)})
+    _append_alto_geometry(document, _block, region)
+    _lines = [_p for _p in region.pieces if _p.type == PieceType.LINE]
+    for (_i, _line) in enumerate(_lines, 1):
+        # ALTO requires at least one String per line
+        if _line.pieces or _line._transcriptions:
+            _append_alto_line(document, _block, _line, f'{fallback_id}_l{_i}')
+
+
+def _append_alto_processing(document, parent, step_id, date_time, description, software):
+    _processing = _append(document, parent, 'Processing', {'ID': step_id})
+    if date_time != UNSET:
+        _append_text(document, _processing, 'processingDateTime', date_time)
+    if description != UNSET:
+        _append_text(document, _processing, 'processingStepDescription', description)
+    if software != UNSET:
+        _software = _append(document, _processing, 'processingSoftware')
+        _append_text(document, _software, 'softwareName', software)
+
+
+def _append_alto_description(document, root, piece, image_name, step_description):
+    """Carry over source metadata, either as it is from
+    ALTO or it's processing steps from PAGE, and append
+    step for this transformation"""
+
+    _description = _append(document, root, 'Description')
+    _append_text(document, _description, 'MeasurementUnit', 'pixel')
+    _source = piece.metadata
+    _infos = []
+    if _source and _source.source_format == 'ALTO':
+        _infos = [n for n in _source.element.childNodes if n.localName == 'sourceImageInformation']
+    if _infos and not image_name:
+        _description.appendChild(_copy_element(document, _infos[0]))
+    else:
+        _image_name = image_name or (piece.id if piece.id != UNSET else None)
+        if _image_name:
+            _info = _append(document, _description, 'sourceImageInformation')
+            _append_text(document, _info, 'fileName', _image_name)
+    _n_steps = 0
+    if _source and _source.source_format == 'ALTO':
+        for _child in _source.element.childNodes:
+            if _child.localName in ['OCRProcessing', 'Processing']:
+                _description.appendChild(_copy_element(document, _child))
+                _n_steps += 1
+    elif _source:
+        for (_date, _step, _software) in _source.processing_steps:
+            _n_steps += 1
+            _append_alto_processing(document, _description, f'proc_{_n_steps}', _date, _step, _software)
+    _now = datetime.now().replace(microsecond=0).isoformat()
+    _append_alto_processing(document, _description, f'proc_{_n_steps + 1}', _now,
+                            step_description, PROCESSING_SOFTWARE)
+
+
+def to_alto(piece, image_name=None, step_description='write ALTO V4') -> xml.dom.minidom.Document:
+    """Serialize page piece with regions, lines and words
+    into ALTO V4 document, including coordinates and
+    word confidences. Source metadata gets carried over
+    and extended by this transformation's processing step
+
+    Page dimensions span from origin to most outer point
+    of all regions, since the actual image size isn't
+    kept in the piece model
+    """
+
+    if piece.type != PieceType.PAGE:
+        raise RuntimeError(f"ID={piece.id}: can only write pages, not {piece.type}!")
+    document = xml.dom.minidom.getDOMImplementation().createDocument(ALTO_V4, 'alto', None)
+    _root = document.documentElement
+    _root.setAttribute('xmlns', ALTO_V4)
+    _root.setAttribute('xmlns:xsi', XML_NS_XSI)
+    _root.setAttribute('xsi:schemaLocation', f'{ALTO_V4} {ALTO_V4_SCHEMA}')
+    _append_alto_description(document, _root, piece, image_name, step_description)
+    if isinstance(piece.subject, str) and piece.subject != UNSET:
+        _tags = _append(document, _root, 'Tags')
+        _append(document, _tags, 'OtherTag', {'ID': ALTO_TAG_GROUNDTRUTH,
+                                              'LABEL': piece.subject, 'VALUE': piece.subject})
+    _layout = _append(document, _root, 'Layout')
+    (_, _, _width, _height) = _bounding_box(piece.dimensions)
+    _page = _append(document, _layout, 'Page', {'ID': _xml_id(piece, 'p1'), 'WIDTH': _width,
+                                                'HEIGHT': _height, 'PHYSICAL_IMG_NR': 1})
+    _print_space = _append(document, _page, 'PrintSpace')
+    # page dimensions are joined from all regions
+    _append_alto_geometry(document, _print_space, piece, with_shape=False)
+    _regions = [_p for _p in piece.pieces if _p.type == PieceType.REGION]
+    for (_i, _region) in enumerate(_regions, 1):
+        _append_alto_region(document, _print_space, _region, f'r{_i}')
+    _unique_ids(_root, 'ID')
+    return document
+
+
+def write_alto(piece, path_out, image_name=None, validate=False, step_description='write ALTO V4'):
+    """Write page piece as ALTO V4 file, optional
+    check structure before (see validate_alto)"""
+
+    _document = to_alto(piece, image_name, step_description)
+    if validate:
+        _ensure_valid(_document, validate_alto, path_out)
+    _write_xml(_document, path_out)
+
+
+def _append_page_text(document, parent, text, confidence=None):
+    _equiv = _append(document, parent, 'pc:TextEquiv')
+    if confidence is not None:
+        _equiv.setAttribute('conf', f'{confidence:.3f}')
+    _unicode = _append(document, _equiv, 'pc:Unicode')
+    _unicode.appendChild(document.createTextNode(text))
+
+
+def _append_page_coords(document, parent, piece):
+    if not piece.dimensions:
+        raise RuntimeError(f"ID={piece.id}: no dimensions to write!")
+    _points = ' '.join(f'{_p[0]},{_p[1]}' for _p in piece.dimensions)
+    _append(document, parent, 'pc:Coords', {'points': _points})
+
+
+def _append_page_line(document, parent, line, fallback_id):
+    _words = [_p for _p in line.pieces if _p.type == PieceType.WORD]
+    _line = _append(document, parent, 'pc:TextLine', {'id': _xml_id(line, fallback_id)})
+    _append_page_coords(document, _line, line)
+    for (_i, _word) in enumerate(_words, 1):
+        _word_el = _append(document, _line, 'pc:Word', {'id': _xml_id(_word, f'{fallback_id}_w{_i}')})
+        _append_page_coords(document, _word_el, _word)
+        _append_page_text(document, _word_el, _word.transcription, _word.confidence)
+    _append_page_text(document, _line, line.transcription, line.confidence)
+
+
+def _leaf_regions(piece) -> List:
+    """Flatten nested regions (like ALTO ComposedBlock)
+    in their order, since only text regions get written"""
+
+    _leafs = []
+    for _sub in piece.pieces:
+        if _sub.type != PieceType.REGION:
+            continue
+        if any(_p.type == PieceType.REGION for _p in _sub.pieces):
+            _leafs.extend(_leaf_regions(_sub))
+        else:
+            _leafs.append(_sub)
+    return _leafs
+
+
+def _append_page_step(document, parent, description, software, date_time=UNSET):
+    _item = _append(document, parent, 'pc:MetadataItem', {'type': 'processingStep',
+                                                           'name': description, 'value': software})
+    if date_time != UNSET:
+        _item.setAttribute('date', date_time)
+
+
+def _append_page_metadata(document, root, piece, step_description):
+    """Carry over source metadata, either as it is from
+    PAGE or processing steps from ALTO, and append step
+    for this transformation"""
+
+    _metadata = _append(document, root, 'pc:Metadata')
+    _now = datetime.now().replace(microsecond=0).isoformat()
+    _source = piece.metadata
+    if _source and _source.source_format == 'PAGE':
+        for _child in _source.element.childNodes:
+            if _child.nodeType != _child.ELEMENT_NODE:
+                continue
+            if _child.localName == 'LastChange':
+                _append_text(document, _metadata, 'pc:LastChange', _now)
+            # skip proprietary extensions, like TranskribusMetadata
+            elif _child.localName in PAGE_2019_CHILDREN['Metadata']:
+                _metadata.appendChild(_copy_element(document, _child, 'pc:'))
+    else:
+        for (_name, _value) in [('pc:Creator', PROCESSING_SOFTWARE), ('pc:Created', _now), ('pc:LastChange', _now)]:
+            _append_text(document, _metadata, _name, _value)
+        if _source:
+            for (_date, _step, _software) in _source.processing_steps:
+                _append_page_step(document, _metadata, _step, _software, _date)
+    _append_page_step(document, _metadata, step_description, PROCESSING_SOFTWARE)
+
+
+def to_page(piece, image_name=None, step_description='write PAGE 2019') -> xml.dom.minidom.Document:
+    """Serialize page piece with regions, lines and words
+    into PAGE 2019 document with reading order following
+    the piece's order and text equivalents on each level.
+    Source metadata gets carried over and extended by
+    this transformation's processing step
+
+    Page dimensions span from origin to most outer point
+    of all regions, since the actual image size isn't
+    kept in the piece model
+    """
+
+    if piece.type != PieceType.PAGE:
+        raise RuntimeError(f"ID={piece.id}: can only write pages, not {piece.type}!")
+    document = xml.dom.minidom.getDOMImplementation().createDocument(PAGE_2019, 'pc:PcGts', None)
+    _root = document.documentElement
+    _root.setAttribute('xmlns:pc', PAGE_2019)
+    _root.setAttribute('xmlns:xsi', XML_NS_XSI)
+    _root.setAttribute('xsi:schemaLocation', f'{PAGE_2019} {PAGE_2019_SCHEMA}')
+    _append_page_metadata(document, _root, piece, step_description)
+    (_, _, _width, _height) = _bounding_box(piece.dimensions)
+    _image_name = image_name or (piece.metadata and piece.metadata.image_name) \
+        or (piece.id if piece.id != UNSET else '')
+    _page = _append(document, _root, 'pc:Page', {'imageFilename': _image_name,
+                                                 'imageWidth': _width, 'imageHeight': _height})
+    _regions = _leaf_regions(piece)
+    _region_ids = []
+    for (_i, _region) in enumerate(_regions, 1):
+        _id = _xml_id(_region, f'r{_i}')
+        _region_ids.append(_id if _id not in _region_ids else f'{_id}_{_i}')
+    if _regions:
+        _order = _append(document, _page, 'pc:ReadingOrder')
+        _group = _append(document, _order, 'pc:OrderedGroup', {'id': 'ro_1'})
+        for (_i, _id) in enumerate(_region_ids):
+            _append(document, _group, 'pc:RegionRefIndexed', {'index': _i, 'regionRef': _id})
+    for (_region, _id) in zip(_regions, _region_ids):
+        _region_el = _append(document, _page, 'pc:TextRegion', {'id': _id})
+        _append_page_coords(document, _region_el, _region)
+        _lines = [_p for _p in _region.pieces if _p.type == PieceType.LINE]
+        for (_i, _line) in enumerate(_lines, 1):
+            _append_page_line(document, _region_el, _line, f'{_id}_l{_i}')
+        _append_page_text(document, _region_el, _region.transcription)
+    # region IDs come first, therefore references stay valid
+    _unique_ids(_root, 'id')
+    return document
+
+
+def write_page(piece, path_out, image_name=None, validate=False, step_description='write PAGE 2019'):
+    """Write page piece as PAGE 2019 file, optional
+    check structure before (see validate_page)"""
+
+    _document = to_page(piece, image_name, step_description)
+    if validate:
+        _ensure_valid(_document, validate_page, path_out)
+    _write_xml(_document, path_out)
+
+
+# structural rules of written ALTO V4: allowed children
+# and mandatory attributes of each (known) element
+ALTO_V4_CHILDREN = {
+    'alto': ['Description', 'Styles', 'Tags', 'Layout'],
+    'Description': ['MeasurementUnit', 'sourceImageInformation', 'OCRProcessing', 'Processing'],
+    'sourceImageInformation': ['fileName'],
+    'Tags': ['OtherTag'],
+    'Layout': ['Page'],
+    'Page': ['PrintSpace'],
+    'PrintSpace': ['ComposedBlock', 'TextBlock'],
+    'ComposedBlock': ['Shape', 'ComposedBlock', 'TextBlock'],
+    'TextBlock': ['Shape', 'TextLine'],
+    'TextLine': ['Shape', 'String', 'SP', 'HYP'],
+    'String': ['Shape'],
+    'Shape': ['Polygon'],
+}
+_ALTO_BOX = ['HPOS', 'VPOS', 'WIDTH', 'HEIGHT']
+ALTO_V4_ATTRIBUTES = {
+    'Page': ['ID', 'WIDTH', 'HEIGHT', 'PHYSICAL_IMG_NR'],
+    'PrintSpace': _ALTO_BOX,
+    'ComposedBlock': ['ID'] + _ALTO_BOX,
+    'TextBlock': ['ID'] + _ALTO_BOX,
+    'TextLine': ['ID'] + _ALTO_BOX,
+    'String': ['ID', 'CONTENT'] + _ALTO_BOX,
+    'Polygon': ['POINTS'],
+    'OtherTag': ['ID'],
+}
+# structural rules of written PAGE 2019
+PAGE_2019_CHILDREN = {
+    'PcGts': ['Metadata', 'Page'],
+    'Metadata': ['Creator', 'Created', 'LastChange', 'Comments', 'UserDefined', 'MetadataItem'],
+    'Page': ['ReadingOrder', 'TextRegion'],
+    'ReadingOrder': ['OrderedGroup'],
+    'OrderedGroup': ['RegionRefIndexed'],
+    'TextRegion': ['Coords', 'TextRegion', 'TextLine', 'TextEquiv'],
+    'TextLine': ['Coords', 'Word', 'TextEquiv'],
+    'Word': ['Coords', 'TextEquiv'],
+    'TextEquiv': ['Unicode'],
+}
+PAGE_2019_ATTRIBUTES = {
+    'Page': ['imageFilename', 'imageWidth', 'imageHeight'],
+    'OrderedGroup': ['id'],
+    'RegionRefIndexed': ['index', 'regionRef'],
+    'TextRegion': ['id'],
+    'TextLine': ['id'],
+    'Word': ['id'],
+    'Coords': ['points'],
+}
+_INTEGER_ATTRIBUTES = _ALTO_BOX + ['WIDTH', 'HEIGHT', 'PHYSICAL_IMG_NR',
+                                   'imageWidth', 'imageHeight', 'index']
+_POINTS = re.compile(r'^\d+,\d+( \d+,\d+)*$')
+
+
+def _validate_element(element, children, attributes, id_attribute, ids, errors):
+    _name = element.localName
+    _label = f"{_name}@{id_attribute}={element.getAttribute(id_attribute)}" \
+        if element.hasAttribute(id_attribute) else _name
+    for _attribute in attributes.get(_name, []):
+        if not element.hasAttribute(_attribute):
+            errors.append(f"{_label}: missing attribute {_attribute}")
+    for _attribute in _INTEGER_ATTRIBUTES:
+        if element.hasAttribute(_attribute) and not element.getAttribute(_attribute).isdigit():
+            errors.append(f"{_label}: {_attribute}='{element.getAttribute(_attribute)}' "
+                          "is no non-negative integer")
+    for _attribute in ['POINTS', 'points']:
+        if element.hasAttribute(_attribute) and not _POINTS.match(element.getAttribute(_attribute)):
+            errors.append(f"{_label}: invalid {_attribute}='{element.getAttribute(_attribute)}'")
+    for _attribute in ['WC', 'conf']:
+        if element.hasAttribute(_attribute):
+            try:
+                _valid = 0.0 <= float(element.getAttribute(_attribute)) <= 1.0
+            except ValueError:
+                _valid = False
+            if not _valid:
+                errors.append(f"{_label}: {_attribute}='{element.getAttribute(_attribute)}' not in [0,1]")
+    if element.hasAttribute(id_attribute):
+        _id = element.getAttribute(id_attribute)
+        if not _XML_ID.match(_id):
+            errors.append(f"{_label}: invalid ID")
+        elif _id in ids:
+            errors.append(f"{_label}: duplicate ID")
+        ids.add(_id)
+    _children = [_c for _c in element.childNodes if _c.nodeType == _c.ELEMENT_NODE]
+    if _name in children:
+        for _child in _children:
+            if _child.localName not in children[_name]:
+                errors.append(f"{_label}: unexpected child {_child.localName}")
+    for _child in _children:
+        _validate_element(_child, children, attributes, id_attribute, ids, errors)
+
+
+def validate_alto(document) -> List[str]:
+    """Check ALTO V4 document structurally, i.e. namespace,
+    element nesting, mandatory attributes and their values
+    and unique IDs. This covers what the writer produces,
+    but is no replacement for full XSD validation.
+    Returns list of errors, empty if valid"""
+
+    errors = []
+    _root = document.documentElement
+    if _root.localName != 'alto' or _root.namespaceURI != ALTO_V4:
+        return [f"root {_root.tagName} is no ALTO V4 ({ALTO_V4})"]
+    _validate_element(_root, ALTO_V4_CHILDREN, ALTO_V4_ATTRIBUTES, 'ID', set(), errors)
+    if not _root.getElementsByTagName('MeasurementUnit'):
+        errors.append("Description: missing MeasurementUnit")
+    if len(_root.getElementsByTagName('Page')) != 1:
+        errors.append("Layout: requires exactly one Page")
+    for _line in _root.getElementsByTagName('TextLine'):
+        if not _line.getElementsByTagName('String'):
+            errors.append(f"TextLine@ID={_line.getAttribute('ID')}: no String")
+    return errors
+
+
+def validate_page(document) -> List[str]:
+    """Check PAGE 2019 document structurally, i.e. namespace,
+    element nesting, mandatory attributes and their values,
+    unique IDs and reading order references. This covers
+    what the writer produces, but is no replacement for
+    full XSD validation.
+    Returns list of errors, empty if valid"""
+
+    errors = []
+    _root = document.documentElement
+    if _root.localName != 'PcGts' or _root.namespaceURI != PAGE_2019:
+        return [f"root {_root.tagName} is no PAGE 2019 ({PAGE_2019})"]
+    _ids = set()
+    _validate_element(_root, PAGE_2019_CHILDREN, PAGE_2019_ATTRIBUTES, 'id', _ids, errors)
+    _metadata = [_c for _c in _root.childNodes if _c.localName == 'Metadata']
+    if len(_metadata) != 1:
+        errors.append("PcGts: requires exactly one Metadata")
+    else:
+        for _mandatory in ['Creator', 'Created', 'LastChange']:
+            if not [_c for _c in _metadata[0].childNodes if _c.localName == _mandatory]:
+                errors.append(f"Metadata: missing {_mandatory}")
+    for _name in ['TextRegion', 'TextLine', 'Word']:
+        for _element in _root.getElementsByTagNameNS('*', _name):
+            _children = [_c.localName for _c in _element.childNodes if _c.nodeType == _c.ELEMENT_NODE]
+            if not _children or _children[0] != 'Coords':
+                errors.append(f"{_name}@id={_element.getAttribute('id')}: Coords must come first")
+            if 'TextEquiv' in _children and _children[-1] != 'TextEquiv':
+                errors.append(f"{_name}@id={_element.getAttribute('id')}: TextEquiv must come last")
+    for _ref in _root.getElementsByTagNameNS('*', 'RegionRefIndexed'):
+        if _ref.getAttribute('regionRef') not in _ids:
+            errors.append(f"ReadingOrder: unknown regionRef '{_ref.getAttribute('regionRef')}'")
+    return errors
+
+
+def _ensure_valid(document, validator, path_out):
+    _errors = validator(document)
+    if _errors:
+        raise RuntimeError(f"{path_out}: invalid output: {'; '.join(_errors)}")
+
+
 def filter_all(self, coords_start, coords_end):
     all_lines = self.get_lines()
     filter_box = BoundingBox(coords_start, coords_end)
@@ -423,8 +1138,16 @@ class OCRToken(BoundingBox):
     def is_page_without_namespace(element):
         return not ':' in element.nodeName
 
+    @staticmethod
+    def is_hocr(element):
+        return _HOCR_BBOX.search(element.getAttribute('title'))
+
     def calculate_points(self, element):
-        if OCRToken.is_alto(element):
+        if OCRToken.is_hocr(element):
+            (x0, y0, x1, y1) = [int(c) for c in OCRToken.is_hocr(element).groups()]
+            self.p1 = (x0, y0)
+            self.p2 = (x1, y1)
+        elif OCRToken.is_alto(element):
             hpos = int(element.getAttribute('HPOS'))
             vpos = int(element.getAttribute('VPOS'))
             self.p1 = (hpos, vpos)
@@ -459,20 +1182,49 @@ class OCRWord(OCRToken):
     def __init__(self, identifier, element):
         super().__init__(identifier)
         self.characters = None
+        # confidence within [0, 1], if provided
+        self.confidence = None
+        # attribute value of confidence, if it's no number
+        self.confidence_invalid = None
         if element.localName == 'String':
             self._read_alto_string(element)
         if element.localName == 'Word':
             self._read_page_word(element)
+        if HOCR_CLASS_WORD in element.getAttribute('class').split():
+            self._read_hocr_word(element)
         self.calculate_points(element)
 
+    def _read_hocr_word(self, element):
+        _stack = [element]
+        _texts = []
+        while _stack:
+            _node = _stack.pop()
+            if _node.nodeType == _node.TEXT_NODE:
+                _texts.append(_node.data)
+            else:
+                _stack += reversed(_node.childNodes)
+        self.characters = ''.join(_texts).strip()
+        _wconf = _HOCR_WCONF.search(element.getAttribute('title'))
+        if _wconf:
+            self.confidence = float(_wconf.group(1)) / 100
+
     def _read_alto_string(self, element):
         self.characters = element.getAttribute('CONTENT')
+        if element.getAttribute('WC'):
+            self._read_confidence(element.getAttribute('WC'))
+
+    def _read_confidence(self, value):
+        self.confidence = parse_confidence(value)
+        if self.confidence is None:
+            self.confidence_invalid = value
 
     def _read_page_word(self, element):
         text_equivs = [node 
                       for node in element.childNodes
                       if node.localName == 'TextEquiv']
         if len(text_equivs) == 1:
+            if text_equivs[0].getAttribute('conf'):
+                self._read_confidence(text_equivs[0].getAttribute('conf'))
             try:
                 txt_data = [coded.childNodes[0].data 
                             for coded in text_equivs[0].childNodes
@@ -495,6 +1247,8 @@ class OCRWordLine(OCRToken):
     def __init__(self, identifier, element=None):
         super().__init__(identifier)
         self.words = []
+        # region containing line, if any
+        self.region = None
         if element:
             self.calculate_points(element)
             self.has_text = True
@@ -561,10 +1315,22 @@ class OCRWordLine(OCRToken):
         self.p2 = new_box.p2
         self.words.append(ocr_word)
 
+    def drop_words_below(self, min_confidence) -> int:
+        """Drop words with confidence below min_confidence,
+        keep those without any, return number dropped"""
+
+        if isinstance(self.words, str):
+            return 0
+        _kept = [_w for _w in self.words if _w.confidence is None or _w.confidence >= min_confidence]
+        _n_dropped = len(self.words) - len(_kept)
+        self.words = _kept
+        return _n_dropped
+
     def get_text(self) -> List[str]:
+        # words kept without text don't count
         line = ' '.join([word.get_characters()
-                         for word in self.words if isinstance(word, OCRWord)])
-        if not line:
+                         for word in self.words if isinstance(word, OCRWord) and word.get_characters()])
+        if not line and isinstance(self.words, str):
             line = self.words
         return line
 
@@ -581,6 +1347,7 @@ class OCRRegion(OCRToken):
         return self.lines
 
     def add_line(self, ocr_line: OCRWordLine):
+        ocr_line.region = self
         self.lines.append(ocr_line)
 
     def __repr__(self) -> str:
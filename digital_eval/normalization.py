@@ -0,0 +1,285 @@
+# -*- coding: utf-8 -*-
+"""Normalization Module
+
+Optional preprocessing of reference and candidate texts
+before any metric gets calculated, to compensate
+differing transcription conventions. Applied to both
+texts alike, prior to the metrics' own UTF-8
+normalization.
+"""
+
+import json
+import re
+import unicodedata
+
+from typing import (
+    Dict,
+    List,
+)
+
+# unicode normalization of metrics and alignment
+UC_NORMALIZATION = 'NFKD'
+
+# fold long s 'ſ' into round 's', since groundtruth
+# often keeps it while OCR models don't (or vice versa)
+NORM_LONG_S = 'long-s'
+# superscript and subscript digits, like footnote
+# references '¹' or chemical formulas 'H₂O', either
+# mapped to baseline digits or dropped
+NORM_SUPERSCRIPTS = 'superscripts'
+NORM_DROP_SUPERSCRIPTS = 'drop-superscripts'
+# drop footnote markers, i.e. symbols like '*' or '†'
+# and superscript digits, each optionally with ')'
+NORM_DROP_FOOTNOTE_MARKERS = 'drop-footnote-markers'
+FOOTNOTE_SYMBOLS = '*⁎†‡'
+# roman numerals from unicode number forms, like 'Ⅻ',
+# mapped to ASCII letters 'XII', or standalone ASCII
+# numerals mapped to unicode ones where such exist
+NORM_ROMAN_ASCII = 'roman-ascii'
+NORM_ROMAN_UNICODE = 'roman-unicode'
+# collapse line breaks into single spaces, leaving
+# any other whitespace untouched, to separate errors
+# due to line segmentation from recognition errors
+NORM_LINE_BREAKS = 'line-breaks'
+# join words broken at line end by hyphen, if the next
+# line continues in lowercase, like 'Fu⸗\nchs' (lines
+# are only kept with extra 'keep_line_breaks')
+NORM_DEHYPHENATE = 'dehyphenate'
+# hyphens which mark words broken at line end
+HYPHENS = '-\u00ac\u00ad\u2010\u2e17\u2e40'
+# fold historical glyphs of diplomatic transcriptions
+# into their modern forms by one of GLYPH_TABLES, like
+# long s 'ſ', r rotunda 'ꝛ' or 'uͤ' with combining e,
+# since OCR models are often trained on modernized text
+NORM_GLYPHS = 'historical-glyphs'
+NORM_GLYPHS_MUFI = 'historical-glyphs-mufi'
+
+# several normalizations are separated by comma
+NORM_SEPARATOR = ','
+
+# user dictionary of abbreviations, one per line like
+# 'Dr̃<TAB>Doctor', empty lines and '#' comments skipped
+ABBREVIATION_SEPARATOR = '\t'
+ABBREVIATION_COMMENT = '#'
+
+# user table of equivalent chars or tokens, one per line
+# like '„<TAB>"', or JSON object like {"ß": "ss"}, each
+# variant replaced by it's canonical form in both texts
+EQUIVALENCE_SEPARATOR = '\t'
+
+
+def _fold_long_s(text) -> str:
+    return text.replace('ſ', 's')
+
+
+# historical glyphs by their modern forms, the basic
+# table for common Fraktur and Antiqua glyphs, the MUFI
+# table adds ligatures and special letters of the
+# Medieval Unicode Font Initiative found in early prints
+GLYPHS_BASIC = {
+    'ſ': 's', 'ꝛ': 'r', 'ʒ': 'z',
+    'a\u0364': 'ä', 'o\u0364': 'ö', 'u\u0364': 'ü',
+    'A\u0364': 'Ä', 'O\u0364': 'Ö', 'U\u0364': 'Ü',
+}
+GLYPHS_MUFI = {
+    **GLYPHS_BASIC,
+    'ﬀ': 'ff', 'ﬁ': 'fi', 'ﬂ': 'fl', 'ﬃ': 'ffi', 'ﬄ': 'ffl', 'ﬅ': 'st', 'ﬆ': 'st',
+    'ꜩ': 'tz', 'ẜ': 's', 'ẝ': 's', 'ꝝ': 'rum', 'ꝰ': 'us', 'ꝯ': 'con', 'ꝫ': 'et',
+    'e\u0364': 'ë', 'ı': 'i', 'ȷ': 'j',
+}
+GLYPH_TABLES = {
+    NORM_GLYPHS: GLYPHS_BASIC,
+    NORM_GLYPHS_MUFI: GLYPHS_MUFI,
+}
+
+
+def _glyph_folding(table):
+    _pattern = re.compile('|'.join(re.escape(_k) for _k in sorted(table, key=len, reverse=True)))
+    return lambda text: _pattern.sub(lambda m: table[m.group(0)], text)
+
+
+def _script_digits(kinds):
+    _digits = {}
+    for _code in range(0x00B0, 0x2090):
+        _decomposition = unicodedata.decomposition(chr(_code)).split()
+        if len(_decomposition) == 2 and _decomposition[0] in kinds:
+            _base = chr(int(_decomposition[1], 16))
+            if _base.isdigit():
+                _digits[chr(_code)] = _base
+    return _digits
+
+
+# superscript and subscript digits by their baseline digits
+SCRIPT_DIGITS = _script_digits(('<super>', '<sub>'))
+SUPERSCRIPT_DIGITS = _script_digits(('<super>',))
+_FOOTNOTE_MARKER = re.compile(f"[{re.escape(FOOTNOTE_SYMBOLS + ''.join(SUPERSCRIPT_DIGITS))}]+\\)?")
+
+
+def _map_script_digits(text) -> str:
+    return ''.join(SCRIPT_DIGITS.get(c, c) for c in text)
+
+
+def _drop_script_digits(text) -> str:
+    return ''.join(c for c in text if c not in SCRIPT_DIGITS)
+
+
+def _drop_footnote_markers(text) -> str:
+    return _FOOTNOTE_MARKER.sub('', text)
+
+
+def _roman_numerals():
+    _numerals = {}
+    for _code in range(0x2160, 0x2180):
+        _decomposition = unicodedata.decomposition(chr(_code)).split()
+        if _decomposition and _decomposition[0] == '<compat>':
+            _numerals[chr(_code)] = ''.join(chr(int(_c, 16)) for _c in _decomposition[1:])
+    return _numerals
+
+
+# unicode roman numerals by their ASCII letters
+ROMAN_NUMERALS = _roman_numerals()
+_ROMAN_UNICODE = {_ascii: _char for (_char, _ascii) in ROMAN_NUMERALS.items()}
+_ROMAN_ASCII = re.compile(r'\b(' + '|'.join(sorted(_ROMAN_UNICODE, key=len, reverse=True)) + r')\b')
+
+
+def _roman_to_ascii(text) -> str:
+    return ''.join(ROMAN_NUMERALS.get(c, c) for c in text)
+
+
+def _roman_to_unicode(text) -> str:
+    return _ROMAN_ASCII.sub(lambda m: _ROMAN_UNICODE[m.group(1)], text)
+
+
+_LINE_BREAKS = re.compile('(?:\r\n|[\n\r\x0b\x0c\x85\u2028\u2029])+')
+
+
+def _collapse_line_breaks(text) -> str:
+    return _LINE_BREAKS.sub(' ', text)
+
+
+_HYPHENATED = re.compile(r'(\w)[' + re.escape(HYPHENS) + r'][ \t]*\r?\n[ \t]*(\w)')
+
+
+def _dehyphenate(text) -> str:
+    return _HYPHENATED.sub(lambda m: m.group(1) + m.group(2) if m.group(2).islower() else m.group(0), text)
+
+
+NORMALIZATIONS = {
+    NORM_LONG_S: _fold_long_s,
+    NORM_SUPERSCRIPTS: _map_script_digits,
+    NORM_DROP_SUPERSCRIPTS: _drop_script_digits,
+    NORM_DROP_FOOTNOTE_MARKERS: _drop_footnote_markers,
+    NORM_ROMAN_ASCII: _roman_to_ascii,
+    NORM_ROMAN_UNICODE: _roman_to_unicode,
+    NORM_LINE_BREAKS: _collapse_line_breaks,
+    NORM_DEHYPHENATE: _dehyphenate,
+    NORM_GLYPHS: _glyph_folding(GLYPHS_BASIC),
+    NORM_GLYPHS_MUFI: _glyph_folding(GLYPHS_MUFI),
+}
+
+
+def parse_normalizations(label) -> List[str]:
+    """Parse comma-separated normalization names"""
+
+    _names = [_n.strip() for _n in str(label).split(NORM_SEPARATOR) if _n.strip()]
+    _unknown = [_n for _n in _names if _n not in NORMALIZATIONS]
+    if _unknown:
+        raise ValueError(f"unknown normalization {_unknown}, expected one of {list(NORMALIZATIONS)}")
+    return _names
+
+
+def normalize_text(text, normalizations) -> str:
+    """Apply normalizations in given order"""
+
+    if not text:
+        return text
+    for _name in normalizations:
+        text = NORMALIZATIONS[_name](text)
+    return text
+
+
+def read_abbreviations(path_in) -> Dict[str, str]:
+    """Read abbreviations and their expansions"""
+
+    _abbreviations = {}
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        for (_n, _line) in enumerate(_handle, 1):
+            _line = _line.rstrip('\r\n')
+            if not _line.strip() or _line.lstrip().startswith(ABBREVIATION_COMMENT):
+                continue
+            if ABBREVIATION_SEPARATOR not in _line:
+                raise ValueError(f"{path_in}:{_n}: expected 'abbreviation<TAB>expansion', got '{_line}'")
+            (_abbreviation, _expansion) = _line.split(ABBREVIATION_SEPARATOR, 1)
+            if not _abbreviation:
+                raise ValueError(f"{path_in}:{_n}: empty abbreviation")
+            _abbreviations[unicodedata.normalize('NFC', _abbreviation)] = _expansion
+    return _abbreviations
+
+
+def expand_abbreviations(text, abbreviations) -> str:
+    """Replace each abbreviation by it's expansion,
+    longer abbreviations first"""
+
+    if not text or not abbreviations:
+        return text
+    _keys = sorted(abbreviations, key=len, reverse=True)
+    _pattern = re.compile('|'.join(_abbreviation_pattern(_k) for _k in _keys))
+    return _pattern.sub(lambda m: abbreviations[m.group(0)], unicodedata.normalize('NFC', text))
+
+
+def _abbreviation_pattern(abbreviation) -> str:
+    # abbreviations starting or ending with letters or digits
+    # mustn't continue a word there, so 'u.' doesn't hit 'zu.'
+    _pattern = re.escape(abbreviation)
+    if re.match(r'\w', abbreviation):
+        _pattern = r'(?<!\w)' + _pattern
+    if re.search(r'\w$', abbreviation):
+        _pattern = _pattern + r'(?!\w)'
+    return _pattern
+
+
+def read_equivalences(path_in) -> Dict[str, str]:
+    """Read variants and their canonical forms, either
+    from JSON object or lines 'variant<TAB>canonical'"""
+
+    if str(path_in).lower().endswith('.json'):
+        with open(path_in, mode='r', encoding='utf-8') as _handle:
+            _data = json.load(_handle)
+        if not isinstance(_data, dict) or not all(isinstance(_v, str) for _v in _data.values()):
+            raise ValueError(f"{path_in}: expected JSON object of variants and their canonical forms")
+        _pairs = list(_data.items())
+    else:
+        _pairs = []
+        with open(path_in, mode='r', encoding='utf-8') as _handle:
+            for (_n, _line) in enumerate(_handle, 1):
+                _line = _line.rstrip('\r\n')
+                if not _line.strip() or _line.lstrip().startswith(ABBREVIATION_COMMENT):
+                    continue
+                if EQUIVALENCE_SEPARATOR not in _line:
+                    raise ValueError(f"{path_in}:{_n}: expected 'variant<TAB>canonical', got '{_line}'")
+                _pairs.append(tuple(_line.split(EQUIVALENCE_SEPARATOR, 1)))
+    _equivalences = {}
+    for (_variant, _canonical) in _pairs:
+        if not _variant:
+            raise ValueError(f"{path_in}: empty variant")
+        _equivalences[unicodedata.normalize('NFC', _variant)] = unicodedata.normalize('NFC', _canonical)
+    return _equivalences
+
+
+def _variant_pattern(variant) -> str:
+    # words of several letters or digits match whole
+    # tokens only, so 'und' doesn't hit 'Kunde'
+    if len(variant) > 1 and re.fullmatch(r'\w+', variant):
+        return r'(?<!\w)' + re.escape(variant) + r'(?!\w)'
+    return re.escape(variant)
+
+
+def apply_equivalences(text, equivalences) -> str:
+    """Replace each variant by it's canonical form,
+    longer variants first"""
+
+    if not text or not equivalences:
+        return text
+    _keys = sorted(equivalences, key=len, reverse=True)
+    _pattern = re.compile('|'.join(_variant_pattern(_k) for _k in _keys))
+    return _pattern.sub(lambda m: equivalences[m.group(0)], unicodedata.normalize('NFC', text))
@@ -0,0 +1,526 @@
+# -*- coding: utf-8 -*-
+"""Evaluation Results Persistence Module
+
+Store evaluated entries as (partial) result files
+and merge them later on into one single set, i.e.
+when evaluation was distributed across several
+machines or jobs.
+"""
+
+import difflib
+import html
+import json
+import math
+import os
+import statistics
+
+from typing import (
+    List,
+    Tuple,
+)
+
+from .alignment import (
+    ALIGN_MATCH,
+)
+
+from .compat import (
+    ERROR_RATES,
+)
+
+from .errors import (
+    EvalMatchingError,
+    EvalUnsupportedError,
+)
+
+from .evaluation import (
+    AVERAGE_MACRO,
+    DocumentStatistics,
+    EvalEntry,
+    Evaluator,
+    LayoutBox,
+    NOT_SET,
+    RegionEntry,
+    get_operations,
+)
+
+from .i18n import (
+    LABELS,
+    REPORT_LANG_DEFAULT,
+    label,
+)
+
+from .metrics import (
+    METRICS_UNIT_RANGE,
+    Metric,
+)
+
+from .model import (
+    BoundingBox,
+)
+
+# bump if layout of result files changes
+RESULTS_FORMAT_VERSION = 1
+
+# OCR-D evaluation (ocrd_eval.md) names error rates in
+# lower case and reports them as fractions, i.e. 0.02
+# rather than 2 percent, per page and document wide
+RESULTS_FORMAT_DIGITAL_EVAL = 'digital-eval'
+RESULTS_FORMAT_OCRD = 'ocrd'
+RESULTS_FORMATS = [RESULTS_FORMAT_DIGITAL_EVAL, RESULTS_FORMAT_OCRD]
+OCRD_EVAL_METRICS = {'CER': 'cer', 'WER': 'wer'}
+OCRD_EVAL_TOOL = 'digital-eval'
+
+# minimal change of mean (points of 0 - 100) to count
+# as regression or improvement against baseline,
+# scaled for metrics ranging 0 - 1
+BASELINE_THRESHOLD = 0.5
+BASELINE_REGRESSION = 'regression'
+BASELINE_IMPROVEMENT = 'improvement'
+BASELINE_UNCHANGED = 'unchanged'
+
+
+def json_number(value):
+    """JSON can't represent infinite values, like
+    error rates without any reference, store them
+    as null"""
+
+    if isinstance(value, float) and not math.isfinite(value):
+        return None
+    return value
+
+
+def metric_to_dict(metric) -> dict:
+    """Represent calculated metric by it's plain values"""
+
+    _data = {
+        'label': metric.label,
+        'name': metric.name,
+        'value': json_number(metric.value),
+        'diff': metric.diff,
+        'n_ref': metric.n_ref,
+    }
+    if metric.n_candidate is not None:
+        _data['n_candidate'] = metric.n_candidate
+    if metric.operations is not None:
+        _data['operations'] = dict(metric.operations)
+    return _data
+
+
+def metric_from_dict(data) -> Metric:
+    """Restore stored metric values.
+    Since only values are restored, this
+    doesn't care about the metric's actual type"""
+
+    _metric = Metric()
+    _metric.label = data['label']
+    _metric.name = data.get('name')
+    # null marks an infinite value
+    _metric.value = data['value'] if data['value'] is not None else math.inf
+    _metric.diff = data.get('diff')
+    _metric.n_ref = data.get('n_ref', 0)
+    _metric.n_candidate = data.get('n_candidate')
+    _metric.operations = data.get('operations')
+    return _metric
+
+
+def entry_to_dict(entry: EvalEntry) -> dict:
+    """Represent evaluated entry"""
+
+    _data = {
+        'path_c': str(entry.path_c),
+        'path_g': str(entry.path_g) if entry.path_g else None,
+        'gt_type': entry.gt_type,
+        'metrics': [metric_to_dict(m) for m in entry.metrics],
+        'window': list(entry.window) if entry.window else None,
+        'era': entry.era,
+    }
+    if entry.fingerprint:
+        _data['fingerprint'] = entry.fingerprint
+    if entry.timed_out:
+        _data['timed_out'] = True
+    if entry.warnings:
+        _data['warnings'] = list(entry.warnings)
+    if entry.parse_warnings:
+        _data['parse_warnings'] = list(entry.parse_warnings)
+    if entry.statistics_reference and entry.statistics_candidate:
+        _data['statistics'] = {'reference': vars(entry.statistics_reference),
+                               'candidate': vars(entry.statistics_candidate)}
+    if entry.regions is not None:
+        _data['regions'] = [{'id': _r.id, 'candidate_id': _r.candidate_id, 'iou': _r.iou,
+                             'metrics': [metric_to_dict(m) for m in _r.metrics]} for _r in entry.regions]
+    if entry.boxes is not None:
+        _data['boxes'] = [{'kind': _b.kind, 'id': _b.id, 'parent': _b.parent,
+                           'bbox': [*_b.bbox.p1, *_b.bbox.p2], 'scores': _b.scores} for _b in entry.boxes]
+    return _data
+
+
+def entry_from_dict(data) -> EvalEntry:
+    """Restore evaluated entry"""
+
+    entry = EvalEntry(data['path_c'])
+    entry.path_g = data.get('path_g')
+    entry.gt_type = data.get('gt_type', NOT_SET)
+    entry.metrics = [metric_from_dict(m) for m in data.get('metrics', [])]
+    entry.window = tuple(data['window']) if data.get('window') else None
+    entry.era = data.get('era')
+    entry.fingerprint = data.get('fingerprint')
+    entry.timed_out = data.get('timed_out', False)
+    entry.warnings = list(data.get('warnings', []))
+    entry.parse_warnings = list(data.get('parse_warnings', []))
+    if data.get('statistics'):
+        entry.statistics_reference = DocumentStatistics(**data['statistics']['reference'])
+        entry.statistics_candidate = DocumentStatistics(**data['statistics']['candidate'])
+    if data.get('regions') is not None:
+        entry.regions = []
+        for _data in data['regions']:
+            _region = RegionEntry(_data['id'], _data.get('candidate_id'), _data.get('iou', 0.0))
+            _region.metrics = [metric_from_dict(m) for m in _data.get('metrics', [])]
+            entry.regions.append(_region)
+    if data.get('boxes') is not None:
+        entry.boxes = []
+        for _data in data['boxes']:
+            (_x0, _y0, _x1, _y1) = _data['bbox']
+            _box = LayoutBox(_data['kind'], _data['id'], BoundingBox((_x0, _y0), (_x1, _y1)), _data.get('parent'))
+            _box.scores = dict(_data.get('scores', {}))
+            entry.boxes.append(_box)
+    return entry
+
+
+def write_results(path_out, entries: List[EvalEntry],
+                  root_candidates, root_groundtruth, shard=None, resources=None):
+    """Write evaluated entries as JSON result file
+
+    shard: optional label (like '3/8') to mark
+           partial result of distributed evaluation
+    resources: optional resource usage of run
+    """
+
+    _data = {
+        'version': RESULTS_FORMAT_VERSION,
+        'root_candidates': str(root_candidates),
+        'root_groundtruth': str(root_groundtruth),
+        'shard': shard,
+        'entries': [entry_to_dict(e) for e in entries],
+    }
+    if resources:
+        _data['resources'] = resources
+    with open(path_out, mode='w', encoding='utf-8') as _handle:
+        json.dump(_data, _handle, indent=2, ensure_ascii=False)
+
+
+def _ocrd_rates(entries: List[EvalEntry], label_metric) -> List[Tuple[float, int]]:
+    """Error rates (fraction) and reference units of
+    metric by entry, None if entry lacks it"""
+
+    _rates = []
+    for _entry in entries:
+        _metric = next((_m for _m in _entry.metrics if _m.label == label_metric), None)
+        _rates.append((_metric.value / 100, _metric.n_ref) if _metric is not None else None)
+    return _rates
+
+
+def write_ocrd_eval(path_out, entries: List[EvalEntry],
+                    root_candidates, root_groundtruth, resources=None):
+    """Write evaluated entries as evaluation report of
+    OCR-D (ocrd_eval.md), i.e. 'cer' and 'wer' of each
+    page and document wide pooled over all reference
+    units, with mean, median, standard deviation and
+    range of pages. Timed out entries are left out.
+    """
+
+    _entries = [_e for _e in entries if not _e.timed_out]
+    _pages = [{'page_id': os.path.relpath(_e.path_c, root_candidates)} for _e in _entries]
+    _document = {}
+    for (_label, _key) in OCRD_EVAL_METRICS.items():
+        _rates = _ocrd_rates(_entries, _label)
+        for (_page, _rate) in zip(_pages, _rates):
+            if _rate is not None:
+                _page[_key] = json_number(_rate[0])
+        # infinite rates, i.e. pages without reference, don't count
+        _finite = [_r for _r in _rates if _r is not None and math.isfinite(_r[0])]
+        if not _finite:
+            continue
+        _values = [_v for (_v, _) in _finite]
+        _n_ref = sum(_n for (_, _n) in _finite)
+        _document[_key] = sum(_v * _n for (_v, _n) in _finite) / _n_ref if _n_ref else 0.0
+        _document[f'{_key}_mean'] = statistics.mean(_values)
+        _document[f'{_key}_median'] = statistics.median(_values)
+        _document[f'{_key}_standard_deviation'] = statistics.pstdev(_values)
+        _document[f'{_key}_min_max'] = [min(_values), max(_values)]
+    if resources:
+        _document['wall_time'] = resources.get('wall_seconds')
+        _document['cpu_time'] = resources.get('cpu_seconds')
+    _data = [{
+        'label': f'{root_candidates} vs. {root_groundtruth}',
+        'metadata': {
+            'ocr_workflow': {'label': str(root_candidates)},
+            'gt_workspace': {'label': str(root_groundtruth)},
+            'eval_tool': OCRD_EVAL_TOOL,
+        },
+        'evaluation_results': {
+            'document_wide': _document,
+            'by_page': _pages,
+        },
+    }]
+    with open(path_out, mode='w', encoding='utf-8') as _handle:
+        json.dump(_data, _handle, indent=2, ensure_ascii=False)
+
+
+def read_results(path_in) -> dict:
+    """Read JSON result file, restore entries"""
+
+    with open(path_in, mode='r', encoding='utf-8') as _handle:
+        _data = json.load(_handle)
+    _version = _data.get('version')
+    if _version != RESULTS_FORMAT_VERSION:
+        raise EvalUnsupportedError(f"{path_in}: unsupported result format version {_version}")
+    _data['entries'] = [entry_from_dict(e) for e in _data.get('entries', [])]
+    return _data
+
+
+def check_fingerprints(entries: List[EvalEntry], fingerprint=None, label='results', force=False):
+    """Ensure entries got evaluated with same configuration,
+    i.e. same fingerprint as given one or as each other,
+    ignoring entries without, like those timed out. With
+    force differing configurations are just reported"""
+
+    _fingerprints = sorted({_e.fingerprint for _e in entries if _e.fingerprint} | ({fingerprint} - {None}))
+    if len(_fingerprints) < 2:
+        return
+    _message = f"{label}: evaluated with differing configurations {_fingerprints}"
+    if not force:
+        raise EvalMatchingError(_message)
+    print(f'[WARN ] {_message}, forced anyway')
+
+
+def merge_results(paths_in, force=False) -> Tuple[str, str, List[EvalEntry]]:
+    """Merge several (partial) result files into one set of entries
+
+    All partial results must originate from the same groundtruth root
+    (by name), since aggregation happens relative to it, and the same
+    configuration, by fingerprint, unless forced. Entries which
+    occour in more than one partial result are considered only once.
+    """
+
+    if not paths_in:
+        raise RuntimeError("no result files to merge")
+    root_candidates = None
+    root_groundtruth = None
+    merged = {}
+    for _path in paths_in:
+        _data = read_results(_path)
+        _root_gt = _data['root_groundtruth']
+        if root_groundtruth is None:
+            root_candidates = _data['root_candidates']
+            root_groundtruth = _root_gt
+        elif os.path.basename(_root_gt) != os.path.basename(root_groundtruth):
+            raise EvalMatchingError(f"{_path}: groundtruth root '{_root_gt}' "
+                                    f"doesn't match '{root_groundtruth}'")
+        for _entry in _data['entries']:
+            if _entry.path_c in merged:
+                print(f"[WARN ] {_path}: ignore duplicate entry '{_entry.path_c}'")
+                continue
+            merged[_entry.path_c] = _entry
+    entries = sorted(merged.values(), key=lambda e: e.path_c)
+    check_fingerprints(entries, label=', '.join(str(_p) for _p in paths_in), force=force)
+    return (root_candidates, root_groundtruth, entries)
+
+
+def aggregate_baseline(path_in, fingerprint=None, force=False) -> list:
+    """Aggregate stored results of baseline
+    for each metric they contain, evaluated
+    with configuration of fingerprint (if any)
+    unless forced"""
+
+    _data = read_results(path_in)
+    _entries = [e for e in _data['entries'] if not e.timed_out]
+    if not _entries:
+        raise RuntimeError(f"{path_in}: baseline without results")
+    check_fingerprints(_entries, fingerprint, path_in, force)
+    evaluator = Evaluator(_data['root_groundtruth'])
+    evaluator.evaluation_entries = _entries
+    _n_metrics = max(len(e.metrics) for e in _entries)
+    evaluator.aggregate(by_type=True, by_metrics=list(range(_n_metrics)))
+    evaluator.eval_map()
+    return evaluator.get_results()
+
+
+def compare_results(results, results_baseline, threshold=BASELINE_THRESHOLD,
+                    average=AVERAGE_MACRO) -> List[Tuple]:
+    """Compare aggregated means of each evaluation key
+    (metric and domain) present in both result sets,
+    averaged micro or macro
+
+    Returns (eval_key, mean baseline, mean, delta, status)
+    where status considers error rates, which improve
+    by decreasing. Threshold is given in points of 0 - 100
+    and scaled for metrics ranging 0 - 1
+    """
+
+    _baseline = {r.eval_key: r.get_mean(average) for r in results_baseline}
+    comparison = []
+    for _result in results:
+        if _result.eval_key not in _baseline:
+            continue
+        _mean_base = _baseline[_result.eval_key]
+        _mean = _result.get_mean(average)
+        _delta = _mean - _mean_base
+        _label = _result.eval_key.split('@')[0]
+        _gain = -_delta if _label in ERROR_RATES else _delta
+        _threshold = threshold / 100 if _label in METRICS_UNIT_RANGE else threshold
+        _status = BASELINE_UNCHANGED
+        if _gain <= -_threshold:
+            _status = BASELINE_REGRESSION
+        elif _gain >= _threshold:
+            _status = BASELINE_IMPROVEMENT
+        comparison.append((_result.eval_key, _mean_base, _mean, _delta, _status))
+    return comparison
+
+
+HTML_STYLE = """
+body { font-family: sans-serif; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #999; padding: 0.2em 0.5em; vertical-align: top; }
+td.text { font-family: monospace; white-space: pre-wrap; width: 50%; }
+span.diff { background-color: #f99; }
+"""
+
+
+def _html_difference(reference, candidate) -> Tuple[str, str]:
+    """Mark differing spans of both texts"""
+
+    _matcher = difflib.SequenceMatcher(None, reference, candidate, autojunk=False)
+    _ref = []
+    _can = []
+    for (_tag, i1, i2, j1, j2) in _matcher.get_opcodes():
+        _r = html.escape(reference[i1:i2])
+        _c = html.escape(candidate[j1:j2])
+        if _tag == 'equal':
+            _ref.append(_r)
+            _can.append(_c)
+        else:
+            _ref.append(f'<span class="diff">{_r}</span>' if _r else '')
+            _can.append(f'<span class="diff">{_c}</span>' if _c else '')
+    return (''.join(_ref), ''.join(_can))
+
+
+def _as_text(text) -> str:
+    if not text:
+        return ''
+    if isinstance(text, list):
+        text = ' '.join(text)
+    return ' '.join(text.split())
+
+
+def format_resource_usage(resources, lang=REPORT_LANG_DEFAULT) -> List[Tuple[str, str]]:
+    """Labels and values of resource usage, unknown
+    values marked as not available"""
+
+    def _value(_key, _unit):
+        return f'{resources[_key]:.2f} {_unit}' if resources.get(_key) is not None else NOT_SET
+
+    return [(label('wall_time', lang), _value('wall_seconds', 's')),
+            (label('cpu_time', lang), _value('cpu_seconds', 's')),
+            (label('peak_rss', lang), _value('peak_rss_mib', 'MiB')),
+            (label('peak_rss_worker', lang), _value('peak_rss_worker_mib', 'MiB'))]
+
+
+def write_html_report(path_out, entries: List[EvalEntry], title='Evaluation', lang=REPORT_LANG_DEFAULT,
+                      resources=None, outliers=None):
+    """Write per-document summary with general results
+    and spotted differences, like ocrevalUAtion does,
+    resource usage of run and outliers with hints at
+    their causes (see analysis.outliers), if given"""
+
+    _labels = []
+    for _entry in entries:
+        for _metric in _entry.metrics:
+            if _metric.label not in _labels:
+                _labels.append(_metric.label)
+    _out = ['<!DOCTYPE html>', '<html>', '<head>', '<meta charset="utf-8">',
+            f'<title>{html.escape(title)}</title>', f'<style>{HTML_STYLE}</style>',
+            '</head>', '<body>', f'<h1>{html.escape(title)}</h1>',
+            f'<h2>{label("general_results", lang)}</h2>', '<table>',
+            f'<tr><th>{label("document", lang)}</th>' + ''.join(f'<th>{html.escape(l)}</th>' for l in _labels) + '</tr>']
+    for _entry in entries:
+        _values = {m.label: m for m in _entry.metrics}
+        _cells = [f'<td>{_values[l].value:.2f}</td>' if l in _values else '<td></td>' for l in _labels]
+        _name = html.escape(os.path.basename(str(_entry.path_c)))
+        _out.append(f'<tr><td><a href="#{_name}">{_name}</a></td>' + ''.join(_cells) + '</tr>')
+    _out.append('</table>')
+    # operations pooled over all documents, like
+    # per set of summary, if known for each
+    _pooled = [(_l, get_operations([(None, None, None, _m.operations) for _e in entries
+                                    for _m in _e.metrics if _m.label == _l])) for _l in _labels]
+    _pooled = [(_l, _ops) for (_l, _ops) in _pooled if _ops]
+    if _pooled:
+        _out += [f'<h2>{label("edit_operations", lang)}</h2>', '<table>',
+                 f'<tr><th>{label("metrics", lang)}</th>'
+                 + ''.join(f'<th>{label(_op, lang)}</th>' for _op in _pooled[0][1]) + '</tr>']
+        _out += [f'<tr><td>{html.escape(_l)}</td>' + ''.join(f'<td>{_n}</td>' for _n in _ops.values()) + '</tr>'
+                 for (_l, _ops) in _pooled]
+        _out.append('</table>')
+    if resources:
+        _out += [f'<h2>{label("resources", lang)}</h2>', '<table>']
+        _out += [f'<tr><th>{html.escape(_l)}</th><td>{_v}</td></tr>'
+                 for (_l, _v) in format_resource_usage(resources, lang)]
+        _out.append('</table>')
+    if outliers:
+        _out += [f'<h2>{label("outliers", lang)}</h2>', '<table>',
+                 f'<tr><th>{label("document", lang)}</th><th>{label("metrics", lang)}</th>'
+                 f'<th>{label("hints", lang)}</th></tr>']
+        for (_entry, _labels, _hints) in outliers:
+            _name = html.escape(os.path.basename(str(_entry.path_c)))
+            _out.append(f'<tr><td><a href="#{_name}">{_name}</a></td><td>{html.escape(", ".join(_labels))}</td>'
+                        f'<td>{html.escape("; ".join(_hints))}</td></tr>')
+        _out.append('</table>')
+    for _entry in entries:
+        _name = html.escape(os.path.basename(str(_entry.path_c)))
+        _out.append(f'<h2 id="{_name}">{_name}</h2>')
+        _out.append('<table>')
+        _out.append(f'<tr><th>{label("candidate", lang)}</th><td>{html.escape(str(_entry.path_c))}</td></tr>')
+        _out.append(f'<tr><th>{label("reference", lang)}</th><td>{html.escape(str(_entry.path_g or NOT_SET))}</td></tr>')
+        if _entry.statistics_reference and _entry.statistics_candidate:
+            for (_key, _statistics) in [('reference_size', _entry.statistics_reference),
+                                        ('candidate_size', _entry.statistics_candidate)]:
+                _out.append(f'<tr><th>{label(_key, lang)}</th><td>{_statistics.n_chars} {label("chars", lang)}, '
+                            f'{_statistics.n_words} {label("tokens", lang)}, {_statistics.n_lines} '
+                            f'{label("lines", lang)}, {_statistics.n_regions} {label("regions_count", lang)}</td></tr>')
+        if _entry.window:
+            _out.append(f'<tr><th>{label("window", lang)}</th><td>{label("tokens", lang)} '
+                        f'{_entry.window[0]}-{_entry.window[1]}</td></tr>')
+        for _metric in _entry.metrics:
+            _label = html.escape(label(_metric.label, lang) if _metric.label in LABELS[lang]
+                                 else _metric.name or _metric.label)
+            _operations = ''.join(f', {label(_op, lang)} {_n}' for (_op, _n) in (_metric.operations or {}).items())
+            _out.append(f'<tr><th>{_label}</th><td>{_metric.value:.2f} ({_metric.n_ref}){_operations}</td></tr>')
+        _out.append('</table>')
+        _word_errors = [_a for _a in _entry.word_alignment or [] if _a[0] != ALIGN_MATCH]
+        if _word_errors:
+            _out.append(f'<h3>{label("word_errors", lang)}</h3>')
+            _out.append(f'<table><tr><th>{label("reference", lang)}</th><th>{label("candidate", lang)}</th>'
+                        f'<th>{label("operation", lang)}</th></tr>')
+            for (_operation, _ref, _can) in _word_errors:
+                _out.append(f'<tr><td>{html.escape(_ref or "")}</td><td>{html.escape(_can or "")}</td>'
+                            f'<td>{_operation}</td></tr>')
+            _out.append('</table>')
+        if _entry.regions:
+            _region_labels = [_m.label for _m in _entry.regions[0].metrics]
+            _out.append(f'<h3>{label("regions", lang)}</h3>')
+            _out.append(f'<table><tr><th>{label("reference", lang)}</th><th>{label("candidate", lang)}</th>'
+                        f'<th>IoU</th>' + ''.join(f'<th>{html.escape(l)}</th>' for l in _region_labels) + '</tr>')
+            for _region in _entry.regions:
+                _candidate = html.escape(_region.candidate_id) if _region.candidate_id else NOT_SET
+                _out.append(f'<tr><td>{html.escape(_region.id)}</td><td>{_candidate}</td>'
+                            f'<td>{_region.iou:.2f}</td>'
+                            + ''.join(f'<td>{_m.value:.2f} ({_m.n_ref})</td>' for _m in _region.metrics)
+                            + '</tr>')
+            _out.append('</table>')
+        if _entry.txt_reference is not None and _entry.txt_candidate is not None:
+            (_ref, _can) = _html_difference(_as_text(_entry.txt_reference), _as_text(_entry.txt_candidate))
+            _out.append(f'<h3>{label("difference_spotting", lang)}</h3>')
+            _out.append(f'<table><tr><th>{label("reference", lang)}</th><th>{label("candidate", lang)}</th></tr>')
+            _out.append(f'<tr><td class="text">{_ref}</td><td class="text">{_can}</td></tr></table>')
+    _out += ['</body>', '</html>']
+    with open(path_out, mode='w', encoding='utf-8') as _handle:
+        _handle.write('\n'.join(_out) + '\n')
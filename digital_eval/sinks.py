@@ -0,0 +1,256 @@
+# -*- coding: utf-8 -*-
+"""Result Sinks Module
+
+Report backends receiving each evaluated entry as soon
+as it's done and finally the aggregated results, so
+output streams even while evaluation runs in parallel.
+Sinks are picked by spec 'kind[:path]', like
+'csv:results.csv' or 'stdout'.
+"""
+
+import csv
+import json
+import os
+import sqlite3
+import threading
+
+from typing import (
+    List,
+)
+
+from .evaluation import (
+    EvalEntry,
+    EvaluationResult,
+    NOT_SET,
+)
+
+from .results import (
+    entry_to_dict,
+    json_number,
+)
+
+SINK_STDOUT = 'stdout'
+SINK_JSON = 'json'
+SINK_CSV = 'csv'
+SINK_SQLITE = 'sqlite'
+SINK_PROMETHEUS = 'prometheus'
+SINK_KINDS = [SINK_STDOUT, SINK_JSON, SINK_CSV, SINK_SQLITE, SINK_PROMETHEUS]
+# separates sink kind from it's path
+SINK_SEPARATOR = ':'
+
+# columns of csv sink, entries and aggregates alike
+CSV_COLUMNS = ['kind', 'key', 'gt_type', 'metric', 'value', 'micro_value', 'n_ref', 'n_items']
+# prefix of metric names in prometheus text format
+PROMETHEUS_PREFIX = 'digital_eval'
+
+
+class ResultSink:
+    """Receiver of evaluation events
+
+    Events may arrive from several threads, so they're
+    serialized by lock, subclasses implement _on_entry,
+    _on_aggregate and (optional) _on_close only
+    """
+
+    def __init__(self, path_out=None):
+        self.path_out = path_out
+        self._lock = threading.Lock()
+
+    def entry(self, entry: EvalEntry, i, n_entries):
+        """Entry i of n_entries is done"""
+
+        with self._lock:
+            self._on_entry(entry, i, n_entries)
+
+    def aggregate(self, results: List[EvaluationResult]):
+        """All entries are done and aggregated"""
+
+        with self._lock:
+            self._on_aggregate(results)
+
+    def close(self):
+        with self._lock:
+            self._on_close()
+
+    def _on_entry(self, entry: EvalEntry, i, n_entries):
+        raise NotImplementedError
+
+    def _on_aggregate(self, results: List[EvaluationResult]):
+        raise NotImplementedError
+
+    def _on_close(self):
+        pass
+
+
+def _split_key(result: EvaluationResult):
+    """Split eval key 'CCA@odem/ger' into metric and set"""
+
+    (_metric, _, _set) = result.eval_key.partition('@')
+    return (_metric, _set)
+
+
+class StdoutSink(ResultSink):
+    """Each entry's metrics and each aggregate's means
+    as single line, meant to be parsed"""
+
+    def _on_entry(self, entry: EvalEntry, i, n_entries):
+        _state = 'timed out' if entry.timed_out else str(entry)
+        print(f'[INFO ] ({i}/{n_entries}) [{entry.path_c}] [{_state}]', flush=True)
+
+    def _on_aggregate(self, results: List[EvaluationResult]):
+        for _result in results:
+            print(f'[INFO ] [{_result.eval_key}] mean {_result.mean:.2f}, micro mean {_result.micro_mean:.2f}, '
+                  f'{_result.n_total} items', flush=True)
+
+
+class JsonSink(ResultSink):
+    """JSON lines, one per entry like stored in results
+    file, final line with aggregates"""
+
+    def __init__(self, path_out):
+        super().__init__(path_out)
+        self._handle = open(path_out, mode='w', encoding='utf-8')
+
+    def _write(self, data):
+        self._handle.write(json.dumps(data, ensure_ascii=False) + '\n')
+        self._handle.flush()
+
+    def _on_entry(self, entry: EvalEntry, i, n_entries):
+        self._write(dict(entry_to_dict(entry), event='entry', index=i, n_entries=n_entries))
+
+    def _on_aggregate(self, results: List[EvaluationResult]):
+        self._write({'event': 'aggregate',
+                     'results': [{'eval_key': _r.eval_key, 'mean': json_number(_r.mean),
+                                  'micro_mean': json_number(_r.micro_mean),
+                                  'median': json_number(_r.median), 'std': json_number(_r.std),
+                                  'n_items': _r.n_total,
+                                  'n_ref': _r.n_chars, 'operations': _r.operations} for _r in results]})
+
+    def _on_close(self):
+        self._handle.close()
+
+
+class CsvSink(ResultSink):
+    """Row per metric of each entry and of each
+    aggregate, distinguished by column 'kind'"""
+
+    def __init__(self, path_out):
+        super().__init__(path_out)
+        self._handle = open(path_out, mode='w', encoding='utf-8', newline='')
+        self._writer = csv.writer(self._handle)
+        self._writer.writerow(CSV_COLUMNS)
+
+    def _on_entry(self, entry: EvalEntry, i, n_entries):
+        for _metric in entry.metrics:
+            self._writer.writerow(['entry', entry.path_c, entry.gt_type, _metric.label,
+                                   f'{_metric.value:.4f}', '', _metric.n_ref, 1])
+        if entry.timed_out:
+            self._writer.writerow(['timed_out', entry.path_c, entry.gt_type, '', '', '', '', 1])
+        self._handle.flush()
+
+    def _on_aggregate(self, results: List[EvaluationResult]):
+        for _result in results:
+            (_metric, _set) = _split_key(_result)
+            self._writer.writerow(['aggregate', _set, NOT_SET, _metric, f'{_result.mean:.4f}',
+                                   f'{_result.micro_mean:.4f}', int(_result.n_chars), _result.n_total])
+        self._handle.flush()
+
+    def _on_close(self):
+        self._handle.close()
+
+
+class SqliteSink(ResultSink):
+    """Tables 'entries' (row per metric of each entry)
+    and 'aggregates', committed as they arrive, so
+    running evaluations can be queried"""
+
+    def __init__(self, path_out):
+        super().__init__(path_out)
+        self._connection = sqlite3.connect(str(path_out), check_same_thread=False)
+        self._connection.execute('CREATE TABLE IF NOT EXISTS entries (path_c TEXT, path_g TEXT, gt_type TEXT, '
+                                 'metric TEXT, value REAL, n_ref INTEGER, timed_out INTEGER)')
+        self._connection.execute('CREATE TABLE IF NOT EXISTS aggregates (eval_key TEXT, metric TEXT, '
+                                 'eval_set TEXT, mean REAL, micro_mean REAL, median REAL, std REAL, '
+                                 'n_items INTEGER, n_ref INTEGER)')
+        self._connection.commit()
+
+    def _on_entry(self, entry: EvalEntry, i, n_entries):
+        _rows = [(str(entry.path_c), str(entry.path_g), entry.gt_type, _m.label, _m.value, _m.n_ref, 0)
+                 for _m in entry.metrics]
+        if entry.timed_out:
+            _rows.append((str(entry.path_c), str(entry.path_g), entry.gt_type, None, None, None, 1))
+        self._connection.executemany('INSERT INTO entries VALUES (?, ?, ?, ?, ?, ?, ?)', _rows)
+        self._connection.commit()
+
+    def _on_aggregate(self, results: List[EvaluationResult]):
+        _rows = [(_r.eval_key, *_split_key(_r), _r.mean, _r.micro_mean, _r.median, _r.std,
+                  _r.n_total, int(_r.n_chars)) for _r in results]
+        self._connection.executemany('INSERT INTO aggregates VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)', _rows)
+        self._connection.commit()
+
+    def _on_close(self):
+        self._connection.close()
+
+
+class PrometheusSink(ResultSink):
+    """Text exposition format, like for node exporter's
+    textfile collector, rewritten atomically with progress
+    after each entry and with means after aggregation"""
+
+    def __init__(self, path_out):
+        super().__init__(path_out)
+        self._progress = {'entries_done': 0, 'entries_total': 0, 'entries_timed_out': 0}
+        self._results = []
+
+    def _write(self):
+        _lines = []
+        for (_name, _value) in self._progress.items():
+            _lines += [f'# TYPE {PROMETHEUS_PREFIX}_{_name} gauge', f'{PROMETHEUS_PREFIX}_{_name} {_value}']
+        _aggregates = [('mean', 'mean'), ('micro_mean', 'micro_mean'), ('items', 'n_total')] if self._results else []
+        for (_name, _attribute) in _aggregates:
+            _lines.append(f'# TYPE {PROMETHEUS_PREFIX}_{_name} gauge')
+            for _result in self._results:
+                (_metric, _set) = [_v.replace('\\', '\\\\').replace('"', '\\"') for _v in _split_key(_result)]
+                _lines.append(f'{PROMETHEUS_PREFIX}_{_name}{{metric="{_metric}",set="{_set}"}} '
+                              f'{getattr(_result, _attribute)}')
+        _path_tmp = f'{self.path_out}.tmp'
+        with open(_path_tmp, mode='w', encoding='utf-8') as _handle:
+            _handle.write('\n'.join(_lines) + '\n')
+        os.replace(_path_tmp, self.path_out)
+
+    def _on_entry(self, entry: EvalEntry, i, n_entries):
+        self._progress['entries_done'] += 1
+        self._progress['entries_total'] = n_entries
+        if entry.timed_out:
+            self._progress['entries_timed_out'] += 1
+        self._write()
+
+    def _on_aggregate(self, results: List[EvaluationResult]):
+        self._results = list(results)
+        self._write()
+
+
+SINKS = {
+    SINK_STDOUT: StdoutSink,
+    SINK_JSON: JsonSink,
+    SINK_CSV: CsvSink,
+    SINK_SQLITE: SqliteSink,
+    SINK_PROMETHEUS: PrometheusSink,
+}
+
+
+def sink_from_spec(spec) -> ResultSink:
+    """Create sink from 'kind:path', like 'csv:out.csv',
+    or 'stdout' which doesn't take any path"""
+
+    (_kind, _, _path) = str(spec).partition(SINK_SEPARATOR)
+    _kind = _kind.strip()
+    if _kind not in SINKS:
+        raise ValueError(f"unknown sink '{_kind}', expected one of {SINK_KINDS}")
+    if _kind == SINK_STDOUT:
+        if _path:
+            raise ValueError(f"sink '{_kind}' doesn't take path '{_path}'")
+        return StdoutSink()
+    if not _path.strip():
+        raise ValueError(f"sink '{_kind}' requires path, like '{_kind}{SINK_SEPARATOR}<file>'")
+    return SINKS[_kind](_path.strip())
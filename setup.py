@@ -23,7 +23,11 @@ setup(
     version='1.1.0',
     packages=find_packages(exclude=["tests"]),
     include_package_data=True,
+    package_data={'digital_eval': ['stopwords/*.txt']},
     install_requires=install_requires,
+    extras_require={
+        'onnx': ['onnxruntime'],
+    },
     tests_require=tests_require,
     entry_points={
         'console_scripts': [
@@ -1,6 +1,8 @@
 # -*- coding: utf-8 -*-
 """Shared test functionalities"""
 
+import os
+
 from os.path import (
     join
 )
@@ -9,7 +11,37 @@ from pathlib import (
     Path
 )
 
+import pytest
+
 
 PROJECT_ROOT_DIR = Path(__file__).resolve().parents[1]
 PROJECT_RES_DIR = join(PROJECT_ROOT_DIR, 'resources')
 TEST_RES_DIR = join(PROJECT_ROOT_DIR, 'tests', 'resources')
+GOLDEN_DIR = join(TEST_RES_DIR, 'golden')
+
+
+def pytest_addoption(parser):
+    parser.addoption("--update-golden", action='store_true', default=False,
+                     help="rewrite golden files of report tests with actual output")
+
+
+@pytest.fixture
+def update_golden(request):
+    """Whether golden files get rewritten rather than compared"""
+
+    return request.config.getoption("--update-golden")
+
+
+def assert_golden(name, actual, update=False):
+    """Compare actual output with golden file of same name,
+    or rewrite golden file if update is requested"""
+
+    _path = join(GOLDEN_DIR, name)
+    if update:
+        os.makedirs(GOLDEN_DIR, exist_ok=True)
+        with open(_path, mode='w', encoding='utf-8') as _handle:
+            _handle.write(actual)
+        return
+    assert os.path.isfile(_path), f"missing golden file {_path}, run pytest with --update-golden"
+    with open(_path, mode='r', encoding='utf-8') as _handle:
+        assert actual == _handle.read(), f"output differs from {_path}, if intended run pytest with --update-golden"
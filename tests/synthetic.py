@@ -0,0 +1,217 @@
+# -*- coding: utf-8 -*-
+"""Synthetic Fixture Corpora
+
+Generate pairs of groundtruth and candidate pages (ALTO
+or PAGE) with errors under control, so metrics and their
+aggregation can be checked against known scores rather
+than scores observed once.
+
+Errors substitute chars of distinct words with chars
+missing in vocabulary, therefore each counts as exactly
+one char edit and one word edit. Further quirks permute
+reading order of regions or split punctuation from words.
+"""
+
+import random
+
+from typing import (
+    List,
+)
+
+from digital_eval.model import (
+    Piece,
+    PieceType,
+    write_alto,
+    write_page,
+)
+
+# words to pick text from, none contains a substitute
+# and none decomposes by unicode normalization
+SYNTH_VOCABULARY = ['der', 'die', 'das', 'und', 'faule', 'Fuchs', 'springt', 'auf', 'den',
+                    'Zaun', 'Hase', 'lief', 'Wiese', 'Stadt', 'Markt', 'heute', 'Abend',
+                    'Rathaus', 'wird', 'sein', 'Rat', 'Halle', 'Zeitung', 'Woche']
+# chars substituted for erroneous ones
+SYNTH_SUBSTITUTES = 'xqy'
+SYNTH_SEED = 4711
+SYNTH_FORMATS = ['alto', 'page']
+# geometry of generated lines and regions, words
+# share line's width, therefore reference and
+# candidate lines span the same, whatever their words
+SYNTH_LINE_WIDTH = 1200
+SYNTH_LINE_HEIGHT = 40
+SYNTH_REGION_GAP = 80
+
+
+class SyntheticPage:
+    """Page as regions of lines of words, in reading
+    order, with vertical slot of each region on page
+    and number of errors put into candidate"""
+
+    def __init__(self, regions: List[List[List[str]]], slots=None):
+        self.regions = regions
+        self.slots = slots or list(range(len(regions)))
+        self.n_errors = 0
+
+    @property
+    def words(self) -> List[str]:
+        return [_w for _region in self.regions for _line in _region for _w in _line]
+
+    @property
+    def text(self) -> str:
+        return ' '.join(self.words)
+
+    @property
+    def n_chars(self) -> int:
+        return len(self.text)
+
+    def copy(self):
+        _page = SyntheticPage([[list(_line) for _line in _region] for _region in self.regions],
+                              list(self.slots))
+        _page.n_errors = self.n_errors
+        return _page
+
+
+def synthetic_page(rng, n_regions=2, n_lines=3, n_words=5) -> SyntheticPage:
+    """Page of random words picked from vocabulary"""
+
+    return SyntheticPage([[[rng.choice(SYNTH_VOCABULARY) for _ in range(n_words)]
+                           for _ in range(n_lines)]
+                          for _ in range(n_regions)])
+
+
+def with_errors(page: SyntheticPage, n_errors, rng) -> SyntheticPage:
+    """Copy of page with one char substituted in each
+    of n_errors distinct words"""
+
+    _page = page.copy()
+    _positions = [(_r, _l, _w) for (_r, _region) in enumerate(_page.regions)
+                  for (_l, _line) in enumerate(_region) for _w in range(len(_line))]
+    if n_errors > len(_positions):
+        raise ValueError(f"can't put {n_errors} errors into {len(_positions)} words")
+    for (_r, _l, _w) in rng.sample(_positions, n_errors):
+        _word = _page.regions[_r][_l][_w]
+        _i = rng.randrange(len(_word))
+        _page.regions[_r][_l][_w] = _word[:_i] + rng.choice(SYNTH_SUBSTITUTES) + _word[_i + 1:]
+    _page.n_errors += n_errors
+    return _page
+
+
+def with_error_rate(page: SyntheticPage, char_error_rate, rng) -> SyntheticPage:
+    """Copy of page with errors of about char_error_rate,
+    exact rate is n_errors / n_chars"""
+
+    return with_errors(page, round(char_error_rate * page.n_chars), rng)
+
+
+def with_permuted_regions(page: SyntheticPage, rng) -> SyntheticPage:
+    """Copy of page with regions in different reading
+    order, but same place on page, unless page has only
+    one region"""
+
+    _page = page.copy()
+    if len(_page.regions) > 1:
+        _order = list(range(len(_page.regions)))
+        while _order == sorted(_order):
+            rng.shuffle(_order)
+        _page.regions = [_page.regions[_i] for _i in _order]
+        _page.slots = [_page.slots[_i] for _i in _order]
+    return _page
+
+
+def with_punctuation(page: SyntheticPage, split=False) -> SyntheticPage:
+    """Copy of page with comma after each line's last
+    word, either attached or split as token of it's own"""
+
+    _page = page.copy()
+    for _region in _page.regions:
+        for _line in _region:
+            if split:
+                _line.append(',')
+            else:
+                _line[-1] = _line[-1] + ','
+    return _page
+
+
+def _box(x, y, width, height) -> List:
+    return [(x, y), (x + width, y), (x + width, y + height), (x, y + height)]
+
+
+def to_piece(page: SyntheticPage, page_id='p1') -> Piece:
+    """Page piece with grid geometry, regions stacked top
+    to bottom by their slots"""
+
+    _page = Piece(page_id)
+    _page.type = PieceType.PAGE
+    _y = 0
+    _tops = {}
+    for _i in sorted(range(len(page.regions)), key=lambda _j: page.slots[_j]):
+        _tops[_i] = _y
+        _y += len(page.regions[_i]) * SYNTH_LINE_HEIGHT + SYNTH_REGION_GAP
+    for (_r, _region) in enumerate(page.regions, 1):
+        _region_piece = Piece(f'r{_r}')
+        _region_piece.type = PieceType.REGION
+        _region_piece.parent = _page
+        _top = _tops[_r - 1]
+        _region_piece.dimensions = _box(0, _top, SYNTH_LINE_WIDTH, len(_region) * SYNTH_LINE_HEIGHT)
+        for (_l, _line) in enumerate(_region, 1):
+            _line_piece = Piece(f'r{_r}_l{_l}')
+            _line_piece.type = PieceType.LINE
+            _line_piece.parent = _region_piece
+            _line_top = _top + (_l - 1) * SYNTH_LINE_HEIGHT
+            _line_piece.dimensions = _box(0, _line_top, SYNTH_LINE_WIDTH, SYNTH_LINE_HEIGHT)
+            _word_width = SYNTH_LINE_WIDTH // max(len(_line), 1)
+            for (_w, _word) in enumerate(_line, 1):
+                _word_piece = Piece(f'r{_r}_l{_l}_w{_w}')
+                _word_piece.type = PieceType.WORD
+                _word_piece.parent = _line_piece
+                _word_piece.transcription = _word
+                _word_piece.dimensions = _box((_w - 1) * _word_width, _line_top,
+                                              _word_width, SYNTH_LINE_HEIGHT)
+                _line_piece.pieces.append(_word_piece)
+            _region_piece.pieces.append(_line_piece)
+        _page.pieces.append(_region_piece)
+    _page.dimensions = _box(0, 0, SYNTH_LINE_WIDTH, _y)
+    return _page
+
+
+def write_synthetic(page: SyntheticPage, path_out, data_format='page'):
+    """Write page as ALTO or PAGE file"""
+
+    if data_format not in SYNTH_FORMATS:
+        raise ValueError(f"unknown format '{data_format}', expected one of {SYNTH_FORMATS}")
+    _write = write_alto if data_format == 'alto' else write_page
+    _write(to_piece(page), str(path_out))
+
+
+def write_pair(reference: SyntheticPage, candidate: SyntheticPage, dir_groundtruth, dir_candidate,
+               name, data_format='page'):
+    """Write groundtruth and candidate, named to match
+    each other, and return their paths"""
+
+    _path_gt = dir_groundtruth / f'{name}.gt.xml'
+    _path_c = dir_candidate / f'{name}.xml'
+    write_synthetic(reference, _path_gt, data_format)
+    write_synthetic(candidate, _path_c, data_format)
+    return (_path_c, _path_gt)
+
+
+def synthetic_corpus(path_root, error_counts, data_format='page', seed=SYNTH_SEED) -> List:
+    """Corpus below path_root, with groundtruth in
+    'groundtruth/odem/ger' and candidates in
+    'media/odem/ger', one page per error count
+
+    Returns (reference, candidate) pages in order of
+    their names, like evaluation orders them"""
+
+    _rng = random.Random(seed)
+    _dir_gt = path_root / 'groundtruth' / 'odem' / 'ger'
+    _dir_c = path_root / 'media' / 'odem' / 'ger'
+    _dir_gt.mkdir(parents=True, exist_ok=True)
+    _dir_c.mkdir(parents=True, exist_ok=True)
+    _pages = []
+    for (_i, _n_errors) in enumerate(error_counts, 1):
+        _reference = synthetic_page(_rng)
+        _candidate = with_errors(_reference, _n_errors, _rng)
+        write_pair(_reference, _candidate, _dir_gt, _dir_c, f'page{_i:04d}', data_format)
+        _pages.append((_reference, _candidate))
+    return _pages
@@ -0,0 +1,231 @@
+# -*- coding: utf-8 -*-
+"""Word Alignment Test Module"""
+
+from os.path import (
+    join,
+)
+
+import pytest
+
+from digital_eval import alignment as alignment_module
+
+from digital_eval.alignment import (
+    align,
+    anchors,
+    align_words,
+    classify,
+    format_token_alignment,
+    segments,
+    token_alignment,
+    word_annotations,
+    word_errors,
+    write_alignment,
+)
+
+from digital_eval.model import (
+    to_pieces,
+)
+
+from .conftest import (
+    TEST_RES_DIR,
+)
+
+
+def test_align_minimal_edits():
+    """Matches kept, gaps marked by None"""
+
+    pairs = align(list('der Fuchs'), list('die Fuchs!'))
+
+    assert pairs[0] == (0, 0)
+    assert pairs[-7:] == [(3, 3), (4, 4), (5, 5), (6, 6), (7, 7), (8, 8), (None, 9)]
+    assert [_i for (_i, _) in pairs if _i is not None] == list(range(9))
+    assert [_j for (_, _j) in pairs if _j is not None] == list(range(10))
+
+
+def test_token_alignment_surface_forms():
+    """Each operation with both sides' tokens,
+    missing side None"""
+
+    # act
+    result = token_alignment('der faule Fuchs springt'.split(), 'der fanle Fuchs springt hoch'.split())
+
+    # assert
+    assert result == [('match', 'der', 'der'), ('substitution', 'faule', 'fanle'),
+                      ('match', 'Fuchs', 'Fuchs'), ('match', 'springt', 'springt'),
+                      ('insertion', None, 'hoch')]
+    assert token_alignment(['Fuchs', 'Hase'], ['Hase'])[0] == ('deletion', 'Fuchs', None)
+
+
+def test_format_token_alignment_columns():
+    """Markers of errors only, candidate column
+    aligned behind longest reference token"""
+
+    # arrange
+    alignment = token_alignment('der faule Fuchs'.split(), 'der fanle Fuchs hoch'.split())
+
+    # act
+    lines = format_token_alignment(alignment)
+
+    # assert
+    assert lines == ['  der    der', 'S faule  fanle', '  Fuchs  Fuchs', 'I ∅      hoch']
+
+
+def test_anchors_rare_words_in_order():
+    """Only words unique in both sequences anchor,
+    crossing matches are left out"""
+
+    # arrange
+    reference = 'Anfang der Fuchs und der Hase und der Igel Ende'.split()
+    candidate = 'Igel Anfang der Fnchs und der Hase und der Ende'.split()
+
+    # act
+    result = anchors(reference, candidate)
+
+    # assert
+    assert result == [(0, 1), (5, 6), (9, 9)]
+    assert segments(reference, candidate) == [((0, 0), (0, 1)), ((1, 5), (2, 6)),
+                                              ((6, 9), (7, 9)), ((10, 10), (10, 10))]
+
+
+def test_align_anchored_long_text(monkeypatch):
+    """Anchored alignment of long sequences covers
+    all items in order like plain alignment"""
+
+    # arrange
+    monkeypatch.setattr(alignment_module, 'ANCHOR_MIN_LENGTH', 4)
+    reference = 'und der Fuchs und der Hase und der Igel und'.split()
+    candidate = 'und der Fnchs und der Hase und Igel und'.split()
+
+    # act
+    pairs = align(reference, candidate)
+
+    # assert
+    assert [p[0] for p in pairs if p[0] is not None] == list(range(len(reference)))
+    assert [p[1] for p in pairs if p[1] is not None] == list(range(len(candidate)))
+    assert (5, 5) in pairs
+    assert (8, 7) in pairs
+    assert sum(1 for (_i, _j) in pairs if _i is None or _j is None) == 1
+
+
+def test_align_parallel_segments_like_sequential(monkeypatch):
+    """Segments aligned by several workers
+    give the very same alignment"""
+
+    # arrange
+    monkeypatch.setattr(alignment_module, 'ANCHOR_MIN_LENGTH', 4)
+    reference = [f'w{_n % 7}' if _n % 5 else f'a{_n}' for _n in range(300)]
+    candidate = [_w.replace('w3', 'w8') for _w in reference if _w != 'w2']
+
+    # act
+    sequential = align(reference, candidate, workers=1)
+    parallel = align(reference, candidate, workers=2)
+
+    # assert
+    assert parallel == sequential
+    assert len(sequential) == len(reference)
+
+
+@pytest.mark.parametrize("reference,candidate,expected", [
+    ('Fuchs', 'Fuchs', 'match'),
+    ('Fuchs', 'fuchs', 'case'),
+    ('Fuchs,', 'Fuchs', 'punctuation'),
+    ('Fuchs', 'Fnchs', 'substitution'),
+    ('Fuchs', None, 'deletion'),
+    (None, 'Fuchs', 'insertion')])
+def test_classify(reference, candidate, expected):
+    assert classify(reference, candidate) == expected
+
+
+def test_align_words():
+    """Inserted and deleted words identified"""
+
+    # act
+    alignment = align_words('der faule Fuchs springt'.split(), 'der rote Fuchs'.split())
+    inserted = align_words('der Fuchs'.split(), 'der rote Fuchs'.split())
+
+    # assert
+    assert alignment == [('der', 0, 'match'), ('faule', 1, 'substitution'),
+                         ('Fuchs', 2, 'match'), ('springt', None, 'deletion')]
+    assert inserted == [('der', 0, 'match'), (None, 1, 'insertion'), ('Fuchs', 2, 'match')]
+
+
+@pytest.mark.parametrize("reference,candidate,expected", [
+    ('der faule Fuchs', 'der fau le Fuchs', [('faule', 1, 'split'), (None, 2, 'split')]),
+    ('der faule Fuchs', 'derfaule Fuchs', [('der', 0, 'merge'), ('faule', None, 'merge')]),
+    ('der faule Fuchs', 'der fau⸗ le Fuchs', [('faule', 1, 'hyphenation'), (None, 2, 'hyphenation')]),
+    ('der fau- le Fuchs', 'der faule Fuchs', [('fau-', 1, 'hyphenation'), ('le', None, 'hyphenation')])])
+def test_align_words_split_and_merged(reference, candidate, expected):
+    """Pairs of split or merged words share class"""
+
+    alignment = align_words(reference.split(), candidate.split())
+
+    assert [_a for _a in alignment if _a[2] != 'match'] == expected
+
+
+def test_align_words_large_block_as_aligned(monkeypatch):
+    """Blocks of unmatched words exceeding cell limit
+    keep pairs of plain alignment, split not grouped"""
+
+    # arrange
+    monkeypatch.setattr(alignment_module, 'SPLIT_MAX_CELLS', 1)
+
+    # act
+    alignment = align_words('der faule Fuchs'.split(), 'der fau le Fuchs'.split())
+
+    # assert
+    assert [_a for _a in alignment if _a[2] != 'match'] == [(None, 1, 'insertion'), ('faule', 2, 'substitution')]
+
+
+def test_word_errors_by_class():
+    """Split words count as single error"""
+
+    # arrange
+    reference = 'Der faule Fuchs springt über den Zaun,'.split()
+    candidate = 'der fau le Fnchs springt überden Zaun'.split()
+
+    # act
+    errors = word_errors(reference, candidate)
+
+    # assert
+    assert errors == {'case': 1, 'split': 1, 'substitution': 1, 'merge': 1, 'punctuation': 1}
+
+
+def test_word_annotations_deleted_words():
+    """Deleted reference words annotated at candidate
+    word following them, trailing ones at last word"""
+
+    # arrange
+    class _Word:
+        def __init__(self, text):
+            self.transcription = text
+
+    words = [_Word('faule'), _Word('Fuchs')]
+
+    # act
+    annotations = word_annotations('der faule Fuchs springt'.split(), words)
+
+    # assert
+    assert annotations[0] == {'reference': 'faule', 'error': 'match', 'deleted': ['der']}
+    assert annotations[1]['deleted_after'] == ['springt']
+
+
+@pytest.mark.parametrize("export_format,expected", [
+    ('page', 'custom="alignment {reference:und;error:match;}"'),
+    ('hocr', 'data-error="match" data-reference="und">und</span>')])
+def test_write_alignment(tmp_path, export_format, expected):
+    """Candidate words carry aligned reference word
+    and error class in both formats"""
+
+    # arrange
+    piece = to_pieces(join(TEST_RES_DIR, 'candidate/frk_page/urn+nbn+de+gbv+3+1-115907-p0042-0_ger.xml'))
+    reference = 'und erklaͤret die Schriſt nicht nur'.split()
+    path_out = tmp_path / f'alignment.{export_format}'
+
+    # act
+    write_alignment(piece, reference, path_out, export_format)
+
+    # assert
+    exported = path_out.read_text(encoding='utf-8')
+    assert expected in exported
+    assert 'Schriſt' in exported
+    assert 'substitution' in exported
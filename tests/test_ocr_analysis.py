@@ -0,0 +1,365 @@
+# -*- coding: utf-8 -*-
+"""OCR Analysis Test Module"""
+
+import os
+
+import pytest
+
+from pytest import (
+    approx
+)
+
+from digital_eval.analysis import (
+    UnicodeStatistics,
+    confidence_statistics,
+    deviating_codepoints,
+    duplicate_candidates,
+    hyphenation_statistics,
+    line_break_scores,
+    metric_correlations,
+    outlier_hints,
+    outliers,
+    page_zone,
+    parse_analyses,
+    position_statistics,
+    report_unicode_statistics,
+    suspicious_groundtruth,
+    unicode_category_group,
+    word_error_statistics,
+)
+
+from digital_eval.alignment import (
+    candidate_words,
+)
+
+from digital_eval.evaluation import (
+    EvalEntry,
+    EvaluationResult,
+    ocr_to_text,
+)
+
+from digital_eval.metrics import (
+    EvalContext,
+    MetricCA,
+    MetricLineCA,
+    MetricWA,
+    score_from_spec,
+)
+
+from digital_eval.model import (
+    to_pieces,
+)
+
+from .conftest import (
+    TEST_RES_DIR
+)
+
+
+def _entry(txt_reference, txt_candidate):
+    entry = EvalEntry('dummy_candidate')
+    entry.txt_reference = txt_reference
+    entry.txt_candidate = txt_candidate
+    return entry
+
+
+@pytest.mark.parametrize("char,group", [
+    ('a', 'letters'), ('ſ', 'letters'), ('7', 'digits'), ('⸗', 'punctuation'),
+    ('§', 'punctuation'), ('+', 'symbols'), ('ͤ', 'marks'), ('\n', 'separators')])
+def test_unicode_category_group(char, group):
+    assert group == unicode_category_group(char)
+
+
+def test_unicode_statistics_ratios():
+    """Ratios refer to normalized text like metrics
+    'ä' decomposes into letter and combining mark"""
+
+    stats = UnicodeStatistics()
+    stats.add('ä 1.')
+
+    assert stats.n_chars == 5
+    assert stats.ratio('letters') == approx(20.0)
+    assert stats.ratio('marks') == approx(20.0)
+    assert stats.ratio('digits') == approx(20.0)
+    assert stats.ratio('separators') == approx(20.0)
+    assert stats.ratio('punctuation') == approx(20.0)
+
+
+def test_deviating_codepoints_double_hyphen():
+    """Groundtruth with double oblique hyphens,
+    which candidate reads as regular hyphens
+
+    please note:
+        long s 'ſ' won't show up, since it is already
+        folded to 's' by unicode normalization
+    """
+
+    ref = UnicodeStatistics()
+    ref.add('Vor⸗ ſchlag und Ge⸗ ſetz')
+    can = UnicodeStatistics()
+    can.add('Vor- schlag und Ge- setz')
+
+    deviations = deviating_codepoints(ref, can, top=2)
+
+    assert sorted(deviations) == [('-', 0, 2), ('⸗', 2, 0)]
+
+
+def test_report_unicode_statistics():
+    entries = [_entry('Vor⸗ ſchlag', 'Vor- schlag'), _entry('Ge⸗ ſetz', 'Ge- setz')]
+
+    lines = report_unicode_statistics(entries)
+
+    assert lines[0].startswith('Unicode categories')
+    assert "  U+2E17 '⸗' DOUBLE OBLIQUE HYPHEN\t2\t0" in lines
+
+
+def test_parse_analyses_unknown():
+    with pytest.raises(ValueError) as err:
+        parse_analyses('unicode,foo')
+    assert "foo" in str(err.value)
+
+
+def test_suspicious_groundtruth():
+    """Flag each kind of suspicious groundtruth, but
+    leave regular groundtruth alone"""
+
+    # arrange
+    regular = _entry('der faule Fuchs springt', 'dcr faule Fucbs springt')
+    short = _entry('der Fuchs', 'der faule Fuchs springt in die Hecke')
+    no_letters = _entry('1899 - 12', '1899 - 12 .')
+    copied = _entry('der  schnelle\nHase', 'der schnelle Hase')
+    duplicate = _entry('der faule Fuchs springt', 'der faule Fuchs sprang')
+    for i, e in enumerate([regular, short, no_letters, copied, duplicate]):
+        e.path_g = f'/data/gt/page{i}.gt.xml'
+
+    # act
+    suspicious = dict(suspicious_groundtruth([regular, short, no_letters, copied, duplicate]))
+
+    # assert
+    assert suspicious[regular] == ['duplicate of page4.gt.xml']
+    assert suspicious[short] == ['short (9 vs. 36 chars)']
+    assert suspicious[no_letters] == ['no letters']
+    assert suspicious[copied] == ['identical to candidate']
+    assert suspicious[duplicate] == ['duplicate of page0.gt.xml']
+
+
+def test_duplicate_candidates():
+    """Cluster identical and near-identical candidates,
+    but leave different texts alone"""
+
+    # arrange
+    text = ('Ein jeder iſt von Natur ein Kind des Unglaubens, ſo, daß er nicht nur uͤberhaupt '
+            'ein gaͤntzliches vor GOtt fliehendes, ja gar wider GOtt ſich hinſetzendes Hertz hat')
+    identical = [_entry('', text), _entry('', f'  {text}\n')]
+    near = [_entry('', text.replace('Kind', 'Kinb')), _entry('', text.replace('Natur', 'Natnr'))]
+    other = _entry('', 'und erklaͤret die Schrift nicht nur alles unter dem Unglauben, Roͤm. II. v. 32.')
+    entries = identical + near + [other, _entry('', '')]
+    for i, e in enumerate(entries):
+        e.path_c = f'/data/media/page{i}.xml'
+
+    # act
+    clusters = duplicate_candidates(entries)
+
+    # assert
+    assert len(clusters) == 1
+    (members, kind, least) = clusters[0]
+    assert members == identical + near
+    assert kind == 'near-identical'
+    assert 0.9 <= least < 1.0
+    assert duplicate_candidates(identical + [other])[0][:2] == (identical, 'identical')
+    assert not duplicate_candidates(near, min_similarity=1.0)
+
+
+@pytest.mark.parametrize("point,expected", [
+    ((50, 100), ('top', 'margin-left')),
+    ((300, 500), ('middle', 'left')),
+    ((700, 999), ('bottom', 'right')),
+    ((950, 300), ('top', 'margin-right'))])
+def test_page_zone(point, expected):
+    assert page_zone(point, (0, 0, 1000, 1000)) == expected
+
+
+def test_position_statistics_counts_all_reference_words(tmp_path):
+    """Each reference word counts for exactly one zone,
+    errors only where candidate deviates"""
+
+    # arrange
+    _name = 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger'
+    entry = EvalEntry(os.path.join(TEST_RES_DIR, 'candidate', 'frk_page', f'{_name}.xml'))
+    entry.path_g = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', f'{_name}.gt.xml')
+    entry.txt_reference = ocr_to_text(entry.path_g, oneliner=True)[1]
+    identical = EvalEntry(entry.path_c)
+    identical.path_g = str(tmp_path / f'{_name}.gt.txt')
+    identical.txt_reference = ' '.join(_w.transcription for _w in candidate_words(to_pieces(entry.path_c)))
+    (tmp_path / f'{_name}.gt.txt').write_text(identical.txt_reference, encoding='utf-8')
+
+    # act
+    stats = position_statistics([entry])
+    stats_identical = position_statistics([identical])
+
+    # assert
+    assert stats.n_pages == 1
+    assert not stats.skipped
+    assert sum(stats.n_words.values()) >= len(entry.txt_reference.split())
+    assert sum(stats.n_errors.values()) > 0
+    assert stats_identical.n_pages == 1
+    assert sum(stats_identical.n_errors.values()) == 0
+
+
+def test_position_statistics_skips_text_candidates():
+    entry = _entry('der faule Fuchs', 'der faule Fnchs')
+    entry.path_c = os.path.join(TEST_RES_DIR, 'candidate', 'txt', 'OCR-Fraktur_1246734.txt')
+    entry.path_g = os.path.join(TEST_RES_DIR, 'groundtruth', 'txt', '1246734.gt.txt')
+
+    stats = position_statistics([entry])
+
+    assert stats.n_pages == 0
+    assert len(stats.skipped) == 1
+
+
+def test_line_break_scores():
+    """Line segmentation costs characters only,
+    since tokens get split at any whitespace. Line
+    metric requires lines kept by evaluation, so
+    it's skipped."""
+
+    # arrange
+    entry = _entry('der faule\nFuchs springt', 'der\nfaule Fuchs springt')
+    entry.metrics = [_m.with_outcome(_m.calculate(EvalContext(entry.txt_reference, entry.txt_candidate)))
+                     for _m in [MetricCA(), MetricWA(), MetricLineCA()]]
+
+    # act
+    (n_breaks, scores) = line_break_scores([entry])
+
+    # assert
+    assert n_breaks == 1
+    assert [(_l, round(_b, 2), round(_a, 2)) for (_l, _b, _a) in scores] == [
+        ('CCA', 91.3, 100.0), ('WWA', 100.0, 100.0)]
+
+
+def test_word_error_statistics_over_entries():
+    """Errors of all entries summed up by class"""
+
+    # arrange
+    entries = [_entry(['der faule', 'Fuchs'], 'der fau⸗ le Fuchs'),
+               _entry('springt über den Zaun', 'Springt überden Zaun')]
+
+    # act
+    (n_words, errors) = word_error_statistics(entries)
+
+    # assert
+    assert n_words == 7
+    assert errors == {'hyphenation': 1, 'case': 1, 'merge': 1}
+
+
+def test_hyphenation_statistics_line_end_only():
+    """Words broken by hyphen count only at line end,
+    gain by dehyphenation shows in metrics"""
+
+    # arrange
+    entry = _entry('der Fuchs springt über den Zaun', 'der Fu⸗\nchs springt über⸗ den Zaun')
+    entry.metrics = [_m.with_outcome(_m.calculate(EvalContext(entry.txt_reference, entry.txt_candidate)))
+                     for _m in [MetricWA()]]
+
+    # act
+    (n_breaks, n_errors, n_hyphenation, scores) = hyphenation_statistics([entry])
+
+    # assert
+    assert (n_breaks, n_errors, n_hyphenation) == (1, 2, 1)
+    assert [(_l, round(_b, 2), round(_a, 2)) for (_l, _b, _a) in scores] == [('WWA', 50.0, 83.33)]
+
+
+def _entry_of_confidences(confidences):
+    entry = _entry('', '')
+    metric = MetricWA()
+    metric.label = 'CWA'
+    metric.diagnostics = {'confidences': confidences}
+    entry.metrics = [metric]
+    return entry
+
+
+def test_confidence_statistics_by_range():
+    """Error rates by confidence range from outcomes
+    of confidence metric over all entries"""
+
+    # arrange
+    entries = [_entry_of_confidences([(0.3, True), (0.95, False)]),
+               _entry_of_confidences([(0.4, False), (0.8, False), (0.99, False)])]
+
+    # act
+    (ranges, coefficient) = confidence_statistics(entries)
+
+    # assert
+    assert ranges == [(0.5, 2, 0.5), (0.7, 0, None), (0.9, 1, 0.0), (1.0, 2, 0.0)]
+    assert coefficient < 0
+
+
+def _entry_of_values(**values):
+    entry = _entry('', '')
+    for (_label, _value) in values.items():
+        _metric = MetricCA()
+        _metric.label = _label
+        _metric.value = _value
+        entry.metrics.append(_metric)
+    return entry
+
+
+def test_metric_correlations_strongest_first():
+    """Pairs ordered by strength regardless of sign,
+    entries missing a metric don't count for it's pairs,
+    composite scores get skipped"""
+
+    # arrange
+    entries = [_entry_of_values(CCA=90.0, WWA=70.0, CER=10.0),
+               _entry_of_values(CCA=95.0, WWA=85.0, CER=5.0),
+               _entry_of_values(CCA=99.0, WWA=80.0, CER=1.0),
+               _entry_of_values(CCA=80.0, CER=20.0)]
+    score = score_from_spec('0.5*CCA + 0.5*WWA')
+    score.value = 50.0
+    entries[0].metrics.append(score)
+
+    # act
+    pairs = metric_correlations(entries)
+
+    # assert
+    assert [(_a, _b, _n) for (_a, _b, _n, _) in pairs] == [('CCA', 'CER', 4), ('CCA', 'WWA', 3), ('WWA', 'CER', 3)]
+    assert pairs[0][3] == approx(-1.0)
+    assert pairs[1][3] == approx(-pairs[2][3])
+
+
+@pytest.mark.parametrize(['txt_reference', 'txt_candidate', 'expected'],
+                         [('der faule Fuchs springt über den Zaun und der Hund bellt laut', '',
+                           ['empty candidate']),
+                          ('der Zaun', 'der Zaun', ['very short groundtruth (8 vs. 8 chars)']),
+                          ('der faule Fuchs springt über den Zaun und der Hund bellt laut',
+                           'und der Hund bellt laut der faule Fuchs springt über den Zaun',
+                           ['suspected reading order issue (bag of words 100.00 vs. words 16.67)']),
+                          ('der faule Fuchs springt über den Zaun und der Hund bellt laut',
+                           'der faule Fuchs springt über den Zaun und der Hund bellt laut', [])])
+def test_outlier_hints(txt_reference, txt_candidate, expected):
+    """Hints derived from texts of entry"""
+
+    assert outlier_hints(_entry(txt_reference, txt_candidate)) == expected
+
+
+def test_outliers_with_metrics_and_warnings():
+    """Outliers of any aggregate once per entry with
+    labels of metrics, warnings complete hints"""
+
+    # arrange
+    entries = [_entry('der faule Fuchs springt über den Zaun und der Hund bellt laut', _t)
+               for _t in ['der faule Fuchs', 'der faule Fuchs springt über den Zaun und der Hund bellt laut']]
+    (entries[0].path_c, entries[1].path_c) = ('page01.xml', 'page02.xml')
+    entries[0].warnings = ['no reference units for IRRec']
+    results = [EvaluationResult('CCA@odem'), EvaluationResult('CCA@odem/ger'), EvaluationResult('WWA@odem')]
+    for _result in results:
+        _result.outliers = ['page01.xml']
+
+    # act
+    found = outliers(results, entries)
+
+    # assert
+    assert len(found) == 1
+    (entry, labels, hints) = found[0]
+    assert entry is entries[0]
+    assert labels == ['CCA', 'WWA']
+    assert hints[-1] == 'no reference units for IRRec'
@@ -0,0 +1,186 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation CLI Test Module"""
+
+import io
+import json
+import sys
+
+import pytest
+
+from digital_eval.cli import (
+    ANSI_GREEN,
+    ANSI_RED,
+    ANSI_YELLOW,
+    _main_merge,
+    _quality_color,
+    main,
+    match_reference,
+    read_region_ids,
+    use_color,
+)
+from digital_eval.evaluation import (
+    EvalEntry,
+)
+from digital_eval.metrics import (
+    metric_from_spec,
+)
+from digital_eval.results import (
+    write_results,
+)
+
+from .synthetic import (
+    SyntheticPage,
+    write_pair,
+)
+
+
+@pytest.mark.parametrize("eval_key,value,expected", [
+    ('CCA@odem', 97.5, ANSI_GREEN),
+    ('CCA@odem/ger', 85.0, ANSI_YELLOW),
+    ('WWA@odem', 42.0, ANSI_RED),
+    ('IRFM@odem', 0.96, ANSI_GREEN),
+    ('CER@odem', 2.5, ANSI_GREEN),
+    ('CER@odem', 25.0, ANSI_RED)])
+def test_quality_color_respects_metric_range(eval_key, value, expected):
+    assert _quality_color(eval_key, value) == expected
+
+
+def test_use_color_only_on_terminals(monkeypatch):
+    """Piped output isn't colorized unless forced"""
+
+    # arrange
+    monkeypatch.delenv('NO_COLOR', raising=False)
+    piped = io.StringIO()
+
+    # assert
+    assert not use_color('auto', piped)
+    assert use_color('always', piped)
+    assert not use_color('never', piped)
+
+
+def test_read_region_ids_from_list_and_file(tmp_path):
+    """IDs of both sources get joined in order without duplicates"""
+
+    # arrange
+    path_ids = tmp_path / 'regions.txt'
+    path_ids.write_text('# headings\nr2\n\nr3\n', encoding='utf-8')
+
+    # act
+    region_ids = read_region_ids('r1, r2', str(path_ids))
+
+    # assert
+    assert region_ids == ['r1', 'r2', 'r3']
+
+
+def test_read_region_ids_requires_any():
+    with pytest.raises(ValueError):
+        read_region_ids(' , ')
+
+
+def test_match_reference_reports_further_matches(tmp_path):
+    """Groundtruth named like candidate is picked,
+    others starting with candidate's name listed"""
+
+    # arrange
+    (dir_c, dir_gt) = (tmp_path / 'c', tmp_path / 'gt')
+    dir_c.mkdir()
+    dir_gt.mkdir()
+    (dir_c / 'p1.xml').write_text('<xml/>', encoding='utf-8')
+    for _name in ['p1.xml', 'p1.gt.txt', 'p2.xml']:
+        (dir_gt / _name).write_text('der Fuchs', encoding='utf-8')
+
+    # act
+    (path_gt, how, others) = match_reference(str(dir_c / 'p1.xml'), str(dir_gt))
+
+    # assert
+    assert path_gt == str(dir_gt / 'p1.xml')
+    assert how == "by name 'p1.xml'"
+    assert others == [str(dir_gt / 'p1.gt.txt')]
+
+
+def test_main_merge_aggregates_all_metrics(tmp_path, capsys):
+    """Merged summary covers metrics beyond
+    the first four, too"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = EvalEntry(str(path_dir_c / 'ger' / 'page01.xml'))
+    entry.path_g = '/data/ocr/groundtruth/odem/ger/page01.gt.xml'
+    entry.metrics = []
+    for _label in ['CCA', 'CLA', 'WWA', 'WBoW', 'ChrF']:
+        _metric = metric_from_spec(_label)
+        _metric.value = 90.0
+        _metric.n_ref = 100
+        entry.metrics.append(_metric)
+    path_results = tmp_path / 'part1.json'
+    write_results(path_results, [entry], path_dir_c, '/data/ocr/groundtruth/odem')
+
+    # act
+    _main_merge([path_results], 0)
+
+    # assert
+    assert 'ChrF@odem' in capsys.readouterr().out
+
+
+@pytest.mark.parametrize("average,expected,unexpected", [
+    ('micro', 'micro ∅: 86.00(1000)', '∅: 70.00'),
+    ('macro', '∅: 70.00(1000)', '86.00'),
+    ('both', 'micro ∅: 86.00(1000)', None)])
+def test_main_merge_reports_average_picked(tmp_path, capsys, average, expected, unexpected):
+    """Micro mean pools reference units of all pages,
+    so large page outweighs small one, macro mean
+    counts each page alike"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entries = []
+    for (_name, _value, _n_ref) in [('small', 50.0, 100), ('large', 90.0, 900)]:
+        _entry = EvalEntry(str(path_dir_c / 'ger' / f'{_name}.xml'))
+        _entry.path_g = f'/data/ocr/groundtruth/odem/ger/{_name}.gt.xml'
+        _metric = metric_from_spec('CCA')
+        _metric.value = _value
+        _metric.n_ref = _n_ref
+        _entry.metrics = [_metric]
+        entries.append(_entry)
+    path_results = tmp_path / 'part1.json'
+    write_results(path_results, entries, path_dir_c, '/data/ocr/groundtruth/odem')
+
+    # act
+    _main_merge([path_results], 0, average=average)
+
+    # assert
+    _lines = [_l for _l in capsys.readouterr().out.splitlines() if '"CCA@odem"' in _l]
+    assert expected in _lines[0]
+    if unexpected:
+        assert unexpected not in _lines[0]
+
+
+def test_main_preset_ocrd(tmp_path, monkeypatch, capsys):
+    """Preset calculates CER and WER like OCR-D and writes
+    them as OCR-D evaluation report, rates as fractions"""
+
+    # arrange
+    (dir_c, dir_gt) = (tmp_path / 'media' / 'odem', tmp_path / 'groundtruth' / 'odem')
+    dir_c.mkdir(parents=True)
+    dir_gt.mkdir(parents=True)
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs', 'springt']]])
+    write_pair(reference, SyntheticPage([[['der', 'faule', 'Fnchs', 'springt']]]), dir_gt, dir_c, 'page0001')
+    write_pair(reference, reference, dir_gt, dir_c, 'page0002')
+    path_report = tmp_path / 'ocrd-eval.json'
+    monkeypatch.setattr(sys, 'argv', ['digital-eval', str(dir_c), '-ref', str(dir_gt),
+                                      '--preset', 'ocrd', '--results-file', str(path_report)])
+
+    # act
+    main()
+
+    # assert
+    summary = capsys.readouterr().out
+    assert 'CER@odem' in summary and 'WER@odem' in summary
+    (report,) = json.loads(path_report.read_text(encoding='utf-8'))
+    document = report['evaluation_results']['document_wide']
+    assert document['cer'] == pytest.approx(1 / 46)
+    assert document['wer'] == pytest.approx(1 / 8)
+    assert document['cer_min_max'] == [0.0, pytest.approx(1 / 23)]
+    assert document['wer_median'] == pytest.approx(0.125)
+    assert [(_p['page_id'], _p['wer']) for _p in report['evaluation_results']['by_page']] == [
+        ('page0001.xml', 0.25), ('page0002.xml', 0.0)]
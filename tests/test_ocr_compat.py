@@ -0,0 +1,90 @@
+# -*- coding: utf-8 -*-
+"""OCR Compatibility Test Module
+
+Expected values are taken from dinglehopper's own test suite
+"""
+
+import json
+import os
+
+import pytest
+
+from pytest import (
+    approx
+)
+
+from digital_eval.compat import (
+    MetricDinglehopperCER,
+    MetricDinglehopperWER,
+    compat_metrics,
+    dinglehopper_words,
+)
+
+from .conftest import (
+    TEST_RES_DIR
+)
+
+
+def _load_fixtures(kind):
+    with open(os.path.join(TEST_RES_DIR, 'compat', 'dinglehopper.json'), encoding='utf-8') as _handle:
+        return json.load(_handle)[kind]
+
+
+def _calc(metric, reference, candidate):
+    metric.input_reference = reference
+    metric.input_candidate = candidate
+    metric.calc()
+    return metric.value
+
+
+@pytest.mark.parametrize("fixture", _load_fixtures('cer'))
+def test_dinglehopper_cer(fixture):
+    expected = 100 * float(fixture['expected'])
+    actual = _calc(MetricDinglehopperCER(), fixture['reference'], fixture['candidate'])
+    assert actual == approx(expected)
+
+
+@pytest.mark.parametrize("fixture", _load_fixtures('wer'))
+def test_dinglehopper_wer(fixture):
+    expected = 100 * float(fixture['expected'])
+    actual = _calc(MetricDinglehopperWER(), fixture['reference'], fixture['candidate'])
+    assert actual == approx(expected)
+
+
+@pytest.mark.parametrize("fixture", _load_fixtures('words'))
+def test_dinglehopper_words(fixture):
+    assert dinglehopper_words(fixture['text']) == fixture['expected']
+
+
+def test_dinglehopper_cer_counts_graphemes():
+    """'m̃' counts as one single reference character"""
+
+    metric = MetricDinglehopperCER()
+    _calc(metric, 'Schlym̃', 'Schlyñ')
+
+    assert metric.n_ref == 6
+    assert metric.diff == 1
+
+
+def test_compat_metrics_unknown_mode():
+    with pytest.raises(ValueError) as err:
+        compat_metrics('ocropus')
+    assert "unknown compatibility mode" in str(err.value)
+
+
+@pytest.mark.parametrize("reference,candidate,cer,wer,wer_oi", [
+    ('Dies ist ein Beispiel.', 'Dies ist ein Beispiel.', 0.0, 0.0, 0.0),
+    ('Dies ist ein Beispiel.', 'Dies  ist ein Beispiel', 100 / 22, 0.0, 0.0),
+    ('Dies ist ein Beispiel.', 'ein Beispiel ist Dies.', 1500 / 22, 100.0, 0.0),
+    ('Dies ist ein Beispiel.', 'Dies ist cin Beispiel.', 100 / 22, 25.0, 25.0),
+    ('Dies ist ein Beispiel.', '', 100.0, 100.0, 100.0)])
+def test_ocrevaluation_metrics(reference, candidate, cer, wer, wer_oi):
+    """Whitespace collapsed, punctuation doesn't form words
+    and order independent WER ignores word positions"""
+
+    metrics = compat_metrics('ocrevaluation')
+
+    values = [_calc(m, reference, candidate) for m in metrics]
+
+    assert [m.label for m in metrics] == ['CER', 'WER', 'WERoi']
+    assert values == approx([cer, wer, wer_oi])
@@ -0,0 +1,73 @@
+# -*- coding: utf-8 -*-
+"""Configuration Test Module"""
+
+import pytest
+
+from digital_eval.config import (
+    era_of,
+    eras_from_config,
+    read_config,
+    score_from_config,
+)
+
+
+def test_score_from_config(tmp_path):
+    """Read composite score with custom label"""
+
+    # arrange
+    path_config = tmp_path / 'eval.ini'
+    path_config.write_text('[score]\nlabel = Quality\nweights = 0.6*CCA + 0.4*WWA\n')
+
+    # act
+    score = score_from_config(read_config(str(path_config)))
+
+    # assert
+    assert score.label == 'Quality'
+    assert score.weights == {'CCA': 0.6, 'WWA': 0.4}
+
+
+def test_score_from_config_without_score(tmp_path):
+    """No section, no score"""
+
+    path_config = tmp_path / 'eval.ini'
+    path_config.write_text('[other]\nkey = value\n')
+
+    assert score_from_config(read_config(str(path_config))) is None
+
+
+def test_score_from_config_without_weights(tmp_path):
+    path_config = tmp_path / 'eval.ini'
+    path_config.write_text('[score]\nlabel = Quality\n')
+
+    with pytest.raises(ValueError) as err:
+        score_from_config(read_config(str(path_config)))
+    assert "misses 'weights'" in str(err.value)
+
+
+def test_eras_from_config(tmp_path):
+    """Eras keep order of sections, open ranges allowed"""
+
+    # arrange
+    path_config = tmp_path / 'eval.ini'
+    path_config.write_text('[score]\nweights = 1.0*CCA\n'
+                           '[era:pre-1900]\nuntil = 1899\nnormalize = long-s\n'
+                           '[era:since-1900]\nfrom = 1900\n')
+
+    # act
+    eras = eras_from_config(read_config(str(path_config)))
+
+    # assert
+    assert [e.label for e in eras] == ['pre-1900', 'since-1900']
+    assert eras[0].normalizations == ['long-s']
+    assert era_of(1789, eras) is eras[0]
+    assert era_of(1900, eras) is eras[1]
+    assert era_of(None, eras) is None
+
+
+def test_eras_from_config_requires_years(tmp_path):
+    path_config = tmp_path / 'eval.ini'
+    path_config.write_text('[era:any]\nnormalize = long-s\n')
+
+    with pytest.raises(ValueError) as err:
+        eras_from_config(read_config(str(path_config)))
+    assert "requires 'from' or 'until'" in str(err.value)
@@ -0,0 +1,185 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation Corruption Test Module"""
+
+import os
+import random
+import shutil
+
+import pytest
+
+from digital_eval.corruption import (
+    CORRUPT_DIACRITICS,
+    CORRUPT_MERGE,
+    CORRUPT_SPLIT,
+    CORRUPT_SUBSTITUTE,
+    corrupt_all,
+    corrupt_file,
+    corrupt_text,
+    parse_corruptions,
+    read_confusions,
+)
+from digital_eval.errors import (
+    EvalUnsupportedError,
+)
+from digital_eval.evaluation import (
+    EvalConfig,
+    evaluate_pair,
+)
+from digital_eval.metrics import (
+    metric_from_spec,
+)
+from digital_eval.model import (
+    to_pieces,
+)
+
+from .conftest import (
+    TEST_RES_DIR,
+)
+from .synthetic import (
+    SyntheticPage,
+    write_pair,
+)
+
+PAGE_GT = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', '1667522809_J_0001_0002.art.gt.xml')
+TXT_GT = os.path.join(TEST_RES_DIR, 'groundtruth', 'txt', '217745.gt.txt')
+
+
+@pytest.mark.parametrize('corruption', [CORRUPT_SUBSTITUTE, CORRUPT_SPLIT, CORRUPT_MERGE])
+def test_corrupt_text_rate(corruption):
+    """Each error costs one edit, as long as it's
+    single char confusions only"""
+
+    # arrange
+    _text = 'der faule Fuchs springt über den Zaun ' * 10
+    _confusions = {'e': ['c'], 'u': ['n']}
+
+    # act
+    (_corrupted, _counts) = corrupt_text(_text, 0.05, [corruption], random.Random(1), _confusions)
+
+    # assert
+    assert _counts == {corruption: round(0.05 * len(_text))}
+    assert _corrupted != _text
+
+
+def test_corrupt_text_drop_diacritics():
+    """Only marks get dropped, if there are fewer
+    than requested, all get dropped"""
+
+    # act
+    (_corrupted, _counts) = corrupt_text('Über schöne Häuser', 0.5, [CORRUPT_DIACRITICS])
+
+    # assert
+    assert _corrupted == 'Uber schone Hauser'
+    assert _counts == {CORRUPT_DIACRITICS: 3}
+
+
+def test_corrupt_text_reproducible():
+    """Same seed, same errors"""
+
+    # arrange
+    _text = 'der faule Fuchs springt über den Zaun'
+
+    # act
+    _first = corrupt_text(_text, 0.2, rng=random.Random(7))
+    _second = corrupt_text(_text, 0.2, rng=random.Random(7))
+
+    # assert
+    assert _first == _second
+
+
+def test_read_confusions(tmp_path):
+    """Repeated originals collect replacements,
+    comments and blank lines get skipped"""
+
+    # arrange
+    _path = tmp_path / 'confusions.tsv'
+    _path.write_text('# original\treplacement\ne\tc\ne\to\n\nrn\tm\n', encoding='utf-8')
+
+    # act
+    _confusions = read_confusions(str(_path))
+
+    # assert
+    assert _confusions == {'e': ['c', 'o'], 'rn': ['m']}
+
+
+def test_read_confusions_invalid(tmp_path):
+    # arrange
+    _path = tmp_path / 'confusions.tsv'
+    _path.write_text('e c\n', encoding='utf-8')
+
+    # act
+    with pytest.raises(ValueError) as _err:
+        read_confusions(str(_path))
+
+    # assert
+    assert 'confusions.tsv:1' in _err.value.args[0]
+
+
+def test_parse_corruptions_invalid():
+    with pytest.raises(ValueError) as _err:
+        parse_corruptions('substitute,typo')
+    assert "['typo']" in _err.value.args[0]
+
+
+def test_corrupt_all_matches_groundtruth(tmp_path):
+    """Candidates lose '.gt', keep format and sub
+    directories and get about the requested rate"""
+
+    # arrange
+    _dir_gt = tmp_path / 'groundtruth'
+    (_dir_gt / 'page').mkdir(parents=True)
+    (_dir_gt / 'txt').mkdir()
+    shutil.copy(PAGE_GT, _dir_gt / 'page')
+    shutil.copy(TXT_GT, _dir_gt / 'txt')
+    _dir_out = tmp_path / 'candidates'
+
+    # act
+    (_n_written, _counts) = corrupt_all(str(_dir_gt), str(_dir_out), rate=0.05,
+                                        corruptions=[CORRUPT_SUBSTITUTE])
+
+    # assert
+    assert _n_written == 2
+    assert _counts[CORRUPT_SUBSTITUTE] > 0
+    _path_c = _dir_out / 'page' / '1667522809_J_0001_0002.art.xml'
+    assert os.path.isfile(_path_c)
+    assert os.path.isfile(_dir_out / 'txt' / '217745.txt')
+    assert to_pieces(str(_path_c)).transcription != to_pieces(PAGE_GT).transcription
+    _result = evaluate_pair(str(_path_c), PAGE_GT, EvalConfig(metrics=[metric_from_spec('CCA')]))
+    assert 90.0 < _result.values['CCA'] < 98.0
+
+
+def test_corrupt_file_rate_of_short_lines(tmp_path):
+    """Rate applies to whole file, so lines too short
+    for a single error still get errors together"""
+
+    # arrange
+    _page = SyntheticPage([[['der', 'Zaun']] * 30])
+    (_, _path_gt) = write_pair(_page, _page, tmp_path, tmp_path, 'page0001')
+
+    # act
+    _counts = corrupt_file(str(_path_gt), str(tmp_path / 'corrupted.xml'), rate=0.05,
+                           corruptions=[CORRUPT_SUBSTITUTE])
+
+    # assert
+    assert _counts == {CORRUPT_SUBSTITUTE: round(0.05 * 8 * 30)}
+
+
+def test_corrupt_file_detects_format_by_reader(tmp_path):
+    """Format gets detected like for evaluation, so PAGE
+    mentioning ALTO stays PAGE and hOCR is rejected"""
+
+    # arrange
+    with open(PAGE_GT, mode='r', encoding='utf-8') as _handle:
+        _xml = _handle.read()
+    _path_page = tmp_path / 'page.gt.xml'
+    _path_page.write_text(_xml.replace('?>', '?><!-- converted from alto -->', 1), encoding='utf-8')
+    _path_hocr = tmp_path / 'page.gt.hocr'
+    _path_hocr.write_text('<html><body><div class="ocr_page"></div></body></html>', encoding='utf-8')
+
+    # act
+    corrupt_file(str(_path_page), str(tmp_path / 'page.xml'), rate=0.05)
+
+    # assert
+    assert 'PcGts' in (tmp_path / 'page.xml').read_text(encoding='utf-8')
+    with pytest.raises(EvalUnsupportedError):
+        corrupt_file(str(_path_hocr), str(tmp_path / 'page.hocr'))
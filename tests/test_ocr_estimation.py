@@ -0,0 +1,323 @@
+# -*- coding: utf-8 -*-
+"""OCR Quality Estimation Test Module"""
+
+import json
+import os
+
+import pytest
+
+from pytest import (
+    approx
+)
+
+from digital_eval import estimation
+from digital_eval.estimation import (
+    QE_FEATURES,
+    CharLanguageModel,
+    dictionary_rate,
+    extract_features,
+    read_hunspell,
+    word_confidences,
+)
+from digital_eval.evaluation import (
+    EvalConfig,
+    EvalEntry,
+    Evaluator,
+    evaluate_pair,
+    ocr_to_text,
+)
+from digital_eval.metrics import (
+    EvalContext,
+    metric_from_spec,
+)
+
+from .conftest import (
+    TEST_RES_DIR
+)
+
+PATH_ALTO = os.path.join(TEST_RES_DIR, 'candidate', 'frk_alto', '1667522809_J_0073_0001_part.xml')
+
+
+class _FakeInput:
+    name = 'features'
+
+
+class _FakeSession:
+    """Stands in for ONNX session, predicts
+    CER from ratio of low confidence words"""
+
+    def __init__(self):
+        self.inputs = []
+
+    def get_inputs(self):
+        return [_FakeInput()]
+
+    def run(self, output_names, feeds):
+        self.inputs.append(feeds['features'])
+        _features = dict(zip(QE_FEATURES, feeds['features'][0]))
+        return [[[100 * _features['conf_low_ratio'] + 2.5]]]
+
+
+def test_word_confidences_alto():
+    confidences = word_confidences(PATH_ALTO)
+
+    assert len(confidences) == 30
+    assert all(0 <= _c <= 1 for _c in confidences)
+
+
+def test_word_confidences_plain_text():
+    path_txt = os.path.join(TEST_RES_DIR, 'groundtruth', 'txt', '1246734.gt.txt')
+
+    assert word_confidences(path_txt) == []
+
+
+def test_dictionary_rate_ignores_case_and_punctuation():
+    assert dictionary_rate(['Der', 'faule', 'Fnchs', '—'], {'der', 'faule', 'fuchs'}) == approx(2 / 3)
+
+
+def test_char_language_model_perplexity():
+    """Text like training data is less perplexing"""
+
+    lm = CharLanguageModel().train('der faule Fuchs springt über den faulen Zaun')
+
+    assert lm.perplexity('der faule Fuchs') < lm.perplexity('dcr fanle Fnchs')
+
+
+def test_extract_features_missing_resources(tmp_path):
+    """Features without resources stay missing"""
+
+    # arrange
+    path_words = tmp_path / 'words.txt'
+    path_words.write_text('der\nfaule\n', encoding='utf-8')
+
+    # act
+    features = extract_features(PATH_ALTO, 'der faule Fnchs', str(path_words))
+
+    # assert
+    assert list(features) == QE_FEATURES
+    assert features['conf_mean'] > 0
+    assert features['dict_rate'] == approx(2 / 3)
+    assert features['lm_perplexity'] == 0.0
+
+
+def test_metric_qe_predicts_accuracy_reference_free(monkeypatch):
+    """Estimated accuracy doesn't depend on reference"""
+
+    # arrange
+    session = _FakeSession()
+    monkeypatch.setattr(estimation, 'load_model', lambda path: session)
+    metric = metric_from_spec('QCA:model=qe.onnx')
+    ctx = EvalContext('completely different', 'der faule Fuchs', options={'path_candidate': PATH_ALTO})
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert len(session.inputs[0][0]) == len(QE_FEATURES)
+    low_ratio = outcome.diagnostics['features']['conf_low_ratio']
+    assert outcome.value == approx(100 - 100 * low_ratio - 2.5)
+    assert outcome.diagnostics['predicted_cer'] == approx(100 * low_ratio + 2.5)
+
+
+def test_metric_confidence_weighted_word_accuracy():
+    """Candidate covers first words of page only,
+    the low confidence word is the erroneous one"""
+
+    # arrange
+    metric = metric_from_spec('CWA')
+    words = []
+    ocr_to_text(PATH_ALTO, words=words)
+    ctx = EvalContext('großen Wahlrechtsdemonstration jede,', 'großen Wahlrehisdemonſtratio jede,',
+                      options={'words_candidate': words})
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert outcome.n_ref == 3
+    assert outcome.value == approx(100 * (0.96 + 0.6) / (0.96 + 0.35 + 0.6))
+    assert outcome.diagnostics['error_rate_low'] == approx(1.0)
+    assert outcome.diagnostics['error_rate_high'] == approx(0.0)
+    assert outcome.diagnostics['correlation'] < -0.8
+
+
+def test_metric_confidence_counts_missed_words():
+    """Reference words missed by candidate lower
+    accuracy like errors of full confidence"""
+
+    # arrange
+    words = [('der', 0.9), ('Fuchs', 0.8)]
+    ctx = EvalContext('der faule Fuchs', 'der Fuchs', options={'words_candidate': words})
+
+    # act
+    outcome = metric_from_spec('CWA').calculate(ctx)
+
+    # assert
+    assert outcome.n_ref == 3
+    assert outcome.diagnostics['n_missed'] == 1
+    assert outcome.value == approx(100 * (0.9 + 0.8) / (0.9 + 0.8 + 1))
+
+
+def test_metric_confidence_requires_confidences():
+    ctx = EvalContext('der faule Fuchs', 'der faule Fuchs',
+                      options={'words_candidate': [('der', None), ('faule', None), ('Fuchs', None)]})
+
+    with pytest.raises(ValueError) as err:
+        metric_from_spec('CWA').calculate(ctx)
+    assert 'no word confidences' in str(err.value)
+
+    with pytest.raises(ValueError) as err:
+        metric_from_spec('CWA').calculate(EvalContext('der faule Fuchs', 'der faule Fuchs'))
+    assert 'requires candidate words' in str(err.value)
+
+
+AFF = """SET UTF-8
+PFX U Y 1
+PFX U 0 un .
+SFX S Y 2
+SFX S 0 e [^e]
+SFX S 0 n e
+"""
+
+DIC = """3
+Fuchs/S
+faul/SU
+Hase/S
+"""
+
+
+def test_read_hunspell_expands_affixes(tmp_path):
+    """Suffixes and prefixes apply if their condition
+    matches, cross products combine both"""
+
+    # arrange
+    (tmp_path / 'de_DE.aff').write_text(AFF, encoding='utf-8')
+    (tmp_path / 'de_DE.dic').write_text(DIC, encoding='utf-8')
+
+    # act
+    words = read_hunspell(str(tmp_path / 'de_DE.dic'))
+
+    # assert
+    assert words == {'Fuchs', 'Fuchse', 'faul', 'faule', 'unfaul', 'unfaule', 'Hase', 'Hasen'}
+
+
+def test_metric_dictionary_offline(tmp_path):
+    """Words count as known if found in any of
+    hunspell dictionary or word list"""
+
+    # arrange
+    (tmp_path / 'de_DE.aff').write_text(AFF, encoding='utf-8')
+    (tmp_path / 'de_DE.dic').write_text(DIC, encoding='utf-8')
+    (tmp_path / 'words.txt').write_text('der\n', encoding='utf-8')
+    spec = f"DICT:dictionary={tmp_path / 'de_DE.dic'},{tmp_path / 'words.txt'}"
+    ctx = EvalContext('completely different', 'Der unfaule Fuchs, Hasc')
+
+    # act
+    outcome = metric_from_spec(spec).calculate(ctx)
+
+    # assert
+    assert outcome.value == approx(75.0)
+    assert outcome.n_ref == 4
+    assert outcome.diagnostics['n_unknown'] == 1
+
+
+class _FakeResponse:
+    """LanguageTool response with matches of
+    spelling and grammar rules"""
+
+    def __init__(self, matches):
+        self.body = json.dumps({'matches': matches}).encode('utf-8')
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *args):
+        return False
+
+    def read(self):
+        return self.body
+
+
+def test_metric_dictionary_languagetool(monkeypatch):
+    """Only words with spelling errors count as unknown,
+    punctuation at word ends doesn't matter"""
+
+    # arrange
+    requests = []
+    matches = [{'offset': 4, 'length': 5, 'rule': {'issueType': 'misspelling'}},
+               {'offset': 0, 'length': 3, 'rule': {'issueType': 'grammar'}}]
+
+    def _urlopen(url, data, timeout):
+        requests.append((url, data))
+        return _FakeResponse(matches)
+
+    monkeypatch.setattr(estimation.urllib.request, 'urlopen', _urlopen)
+    metric = metric_from_spec('DICT:api=http://localhost:8081/v2/:language=de-DE')
+    ctx = EvalContext('completely different', 'der fauie Fuchs, springt')
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert requests[0][0] == 'http://localhost:8081/v2/check'
+    assert b'language=de-DE' in requests[0][1]
+    assert outcome.value == approx(75.0)
+    assert outcome.n_ref == 4
+    assert outcome.diagnostics['n_unknown'] == 1
+
+
+@pytest.mark.parametrize("spec,message", [
+    ('QCA', "requires option 'model'"),
+    ('CCA:api=http://localhost:8081/v2', "requires dictionary metric"),
+    ('DICT:api=http://localhost:8081/v2:dictionary=de_DE.dic', "either option 'api' or 'dictionary'"),
+    ('CCA:model=qe.onnx', "requires quality estimation metric")])
+def test_metric_qe_spec_invalid(spec, message):
+    with pytest.raises(ValueError) as err:
+        metric_from_spec(spec)
+    assert message in str(err.value)
+
+
+def test_evaluate_pair_reference_free_without_reference(tmp_path):
+    """Candidate without reference gets evaluated
+    by reference-free metrics only"""
+
+    # arrange
+    (tmp_path / 'words.txt').write_text('der\nfaule\nFuchs\n', encoding='utf-8')
+    path_cd = tmp_path / 'page01.txt'
+    path_cd.write_text('der faule Fnchs', encoding='utf-8')
+    metrics = [metric_from_spec('WWA'), metric_from_spec(f"DICT:dictionary={tmp_path / 'words.txt'}")]
+
+    # act
+    result = evaluate_pair(str(path_cd), None, EvalConfig(metrics))
+
+    # assert
+    assert [_m.label for _m in result.metrics] == ['DICT']
+    assert result.metrics[0].value == approx(200 / 3)
+    assert result.path_reference is None
+
+
+def test_eval_all_aggregates_candidates_without_groundtruth(tmp_path):
+    """Candidates without groundtruth count for
+    reference-free metrics, but not for others"""
+
+    # arrange
+    (tmp_path / 'words.txt').write_text('der\nfaule\nFuchs\n', encoding='utf-8')
+    path_root = tmp_path / 'odem'
+    path_root.mkdir()
+    (path_root / 'page01.gt.txt').write_text('der faule Fuchs', encoding='utf-8')
+    entries = []
+    for (_name, _text) in [('page01', 'der faule Fuchs'), ('page02', 'der faule Fnchs')]:
+        (path_root / f'{_name}.txt').write_text(_text, encoding='utf-8')
+        entries.append(EvalEntry(str(path_root / f'{_name}.txt')))
+    entries[0].path_g = str(path_root / 'page01.gt.txt')
+    evaluator = Evaluator(str(path_root))
+    evaluator.metrics = [metric_from_spec('WWA'), metric_from_spec(f"DICT:dictionary={tmp_path / 'words.txt'}")]
+
+    # act
+    evaluator.eval_all(entries, sequential=True)
+    evaluator.aggregate(by_metrics=[0, 1])
+
+    # assert
+    assert [len(_e.metrics) for _e in evaluator.evaluation_entries] == [2, 1]
+    assert len(evaluator.evaluation_map['WWA@odem']) == 1
+    assert [_i[1] for _i in evaluator.evaluation_map['DICT@odem']] == approx([100.0, 200 / 3])
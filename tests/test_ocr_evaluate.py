@@ -2,6 +2,7 @@
 """OCR Evaluation Test Module"""
 
 import os
+import time
 
 from xml.dom.minidom import (
     parseString,
@@ -13,24 +14,67 @@ from pytest import (
     approx
 )
 
+from digital_eval import evaluation as evaluation_module
+
 from digital_eval.evaluation import (
+    MISSING_CONTENT_EMPTY,
+    MISSING_CONTENT_ERROR,
+    MISSING_CONTENT_SUBS,
+    AltoReader,
+    DocumentStatistics,
+    EvalConfig,
     EvalEntry,
     MetricCA,
     Evaluator,
     OCRData,
+    evaluate_pair,
+    filter_shard,
+    get_region_text,
+    gt_type_from_filename,
     match_candidates,
     ocr_to_text,
+    parse_shard,
+    parse_timeout,
+    parse_warning_counts,
+    perfect_value,
+    resource_usage,
+    self_check,
+    _normalize_gt_type,
+)
+
+from digital_eval.config import (
+    Era,
+)
+
+from digital_eval.errors import (
+    EvalError,
+    EvalIOError,
+    EvalMetricError,
+    EvalParseError,
+    EvalTimeoutError,
+)
+
+from digital_eval.metrics import (
+    MetricOutcome,
+    MetricWA,
+    metric_from_spec,
+    score_from_spec,
 )
 
 from digital_eval.model import (
     BoundingBox,
     OCRWord,
     OCRWordLine,
+    separators_from_spec,
 )
 
 from .conftest import (
     TEST_RES_DIR
 )
+from .synthetic import (
+    SyntheticPage,
+    write_pair,
+)
 
 @pytest.mark.skip("due re-structuring")
 def test_match_candidates_alto_candidate_with_coords():
@@ -238,6 +282,37 @@ def test_evaluate_set_with_5_entries(tmp_path):
     assert results[0].cleared_result.std == approx(1.06, abs=1e-2)
 
 
+def test_evaluate_micro_mean_weights_by_reference_units(tmp_path):
+    """Micro average pools all reference units, so a large
+    page counts more than a small one, macro doesn't care"""
+
+    # arrange
+    path_dir_gt = tmp_path / 'odem'
+    path_dir_gt.mkdir()
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entries = []
+    for (_name, _value, _n_ref) in [('small', 50.0, 100), ('large', 90.0, 900)]:
+        _metric = MetricCA()
+        _metric.value = _value
+        _metric.n_ref = _n_ref
+        _entry = EvalEntry(path_dir_c / 'ger' / f'{_name}.xml')
+        _entry.path_g = str(path_dir_gt / 'ger' / f'{_name}.gt.xml')
+        _entry.metrics = [_metric]
+        entries.append(_entry)
+    evaluator = Evaluator(path_dir_gt)
+    evaluator.evaluation_entries = entries
+
+    # act
+    evaluator.aggregate(by_metrics=[0])
+    evaluator.eval_map()
+    result = evaluator.get_results()[0]
+
+    # assert
+    assert result.mean == approx(70.0)
+    assert result.micro_mean == approx(86.0)
+    assert result.n_chars == 1000
+
+
 @pytest.mark.parametrize("b1,b2,expected", [
     (BoundingBox((100, 100), (200, 200)), BoundingBox((0, 0), (6000, 8000)), 10000),
     (BoundingBox((100, 100), (200, 200)), BoundingBox((0, 500), (6000, 8000)), 0),
@@ -298,6 +373,297 @@ def test_read_alto_groundtype_exists():
     assert 'article' == ocr_data.get_type_groundtruth()
 
 
+@pytest.mark.parametrize(['file_name', 'expected'],
+                         [('page01.gt.art1.xml', 'art1'), ('page01.ann.gt.xml', 'ann'),
+                          ('page01.gt.table.txt', 'table'), ('page01.gt.xml', None),
+                          ('page01.txt', None)])
+def test_gt_type_from_filename(file_name, expected):
+    """Type label either after or before 'gt'"""
+
+    assert gt_type_from_filename(file_name) == expected
+
+
+@pytest.mark.parametrize(['label', 'expected'],
+                         [('article', 'article'), ('art1', 'article'), ('annx', 'announcement'),
+                          ('Tabelle', 'table'), ('advertisement', 'advertisement'),
+                          ('ger', 'n.a.'), ('n.a.', 'n.a.'), ('ta', 'n.a.')])
+def test_normalize_gt_type(label, expected):
+    """Labels match known types by first chars"""
+
+    assert _normalize_gt_type(label) == expected
+
+
+def test_gt_type_annotated_alto_other_tag(tmp_path):
+    """ALTO OtherTag with ID instead of LABEL"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace('</Description>', '</Description>\n  <Tags>\n    '
+                                    '<OtherTag ID="ulb_groundtruth_type" VALUE="advertisement"/>\n  </Tags>'),
+                       encoding='utf-8')
+
+    # act
+    (gt_type, _, _) = ocr_to_text(str(path_gt))
+
+    # assert
+    assert gt_type == 'advertisement'
+
+
+def test_gt_type_annotated_page_metadata(tmp_path):
+    """PAGE MetadataItem named like ALTO OtherTag ID"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace('</pc:LastChange>', '</pc:LastChange>\n    '
+                                    '<pc:MetadataItem type="other" name="ulb_groundtruth_type" value="table"/>'),
+                       encoding='utf-8')
+
+    # act
+    (gt_type, _, _) = ocr_to_text(str(path_gt))
+
+    # assert
+    assert gt_type == 'table'
+
+
+def test_ocr_to_text_collects_parse_warnings(tmp_path):
+    """ALTO String without CONTENT and line left
+    without words are skipped, but not silently"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs'], ['springt']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace(' CONTENT="Fuchs"', '').replace('CONTENT="springt"', 'CONTENT=" "'),
+                       encoding='utf-8')
+    warnings = []
+
+    # act
+    (_, text, _) = ocr_to_text(str(path_gt), oneliner=True, warnings=warnings)
+
+    # assert
+    assert text == 'der faule'
+    assert len(warnings) == 3
+    assert 'without CONTENT, skipped' in warnings[0]
+    assert 'with empty CONTENT, skipped' in warnings[1]
+    assert 'without words, skipped' in warnings[2]
+
+
+def test_alto_reader_invalid_word_confidence(tmp_path):
+    """WC with comma is no confidence, but word
+    gets read nevertheless and problem counted"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace(' CONTENT="faule"', ' WC="0,95" CONTENT="faule"'), encoding='utf-8')
+    (reader, warnings) = (AltoReader(), [])
+
+    # act
+    (_, lines) = reader.read(str(path_gt), warnings=warnings)
+
+    # assert
+    assert reader.line_text(lines[0]) == 'der faule Fuchs'
+    assert [_w.confidence for _w in lines[0].words] == [None, None, None]
+    assert parse_warning_counts(warnings) == {'String with invalid WC, confidence ignored': 1}
+
+
+@pytest.mark.parametrize("mode,expected,warning", [
+    (MISSING_CONTENT_EMPTY, 'der faule springt', 'read as empty'),
+    (MISSING_CONTENT_SUBS, 'der faule Fuchs springt', 'read SUBS_CONTENT')])
+def test_alto_reader_missing_content(tmp_path, mode, expected, warning):
+    """String without CONTENT kept as word without
+    text or read by SUBS_CONTENT, but counted"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs', 'springt']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace(' CONTENT="Fuchs"', ' SUBS_CONTENT="Fuchs"'), encoding='utf-8')
+    (reader, warnings) = (AltoReader(mode), [])
+
+    # act
+    (_, lines) = reader.read(str(path_gt), warnings=warnings)
+
+    # assert
+    assert [reader.line_text(_l) for _l in lines] == [expected]
+    assert len(lines[0].words) == 4
+    assert parse_warning_counts(warnings) == {f'String without CONTENT, {warning}': 1}
+
+
+def test_alto_reader_missing_content_error(tmp_path):
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace(' CONTENT="Fuchs"', ''), encoding='utf-8')
+
+    # act
+    with pytest.raises(EvalParseError) as err:
+        AltoReader(MISSING_CONTENT_ERROR).read(str(path_gt))
+
+    # assert
+    assert 'without CONTENT' in str(err.value)
+
+
+def test_evaluate_pair_parse_warnings(tmp_path):
+    """Parse warnings of either file get surfaced
+    by role, problem-free files have none"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (path_c, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_c.read_text(encoding='utf-8')
+    path_c.write_text(_xml.replace(' CONTENT="faule"', ''), encoding='utf-8')
+
+    # act
+    result = evaluate_pair(path_c, path_gt)
+    result_clean = evaluate_pair(path_gt, path_gt)
+
+    # assert
+    assert len(result.parse_warnings) == 1
+    assert result.parse_warnings[0].startswith('candidate: String@ID=')
+    assert result_clean.parse_warnings == []
+
+
+def test_evaluate_pair_document_statistics(tmp_path):
+    """Chars and words of lines with text counted,
+    without line breaks, and regions containing them"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs'], ['springt']], [['über']]])
+    candidate = SyntheticPage([[['der', 'faule', 'Fuchs', 'springt']]])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001', 'page')
+
+    # act
+    result = evaluate_pair(path_c, path_gt)
+
+    # assert
+    assert result.statistics_reference == DocumentStatistics(26, 3, 5, 2)
+    assert result.statistics_candidate == DocumentStatistics(23, 1, 4, 1)
+
+
+@pytest.mark.parametrize("units,n_chars", [('codepoints', 10), ('graphemes', 9)])
+def test_evaluate_pair_document_statistics_units(tmp_path, units, n_chars):
+    """Reference chars counted as codepoints or as
+    user-perceived chars, like 'u' with combining 'e',
+    by statistics and character metrics alike"""
+
+    # arrange
+    path_gt = tmp_path / 'page0001.gt.txt'
+    path_c = tmp_path / 'page0001.txt'
+    path_gt.write_text('die Mu\u0364hle', encoding='utf-8')
+    path_c.write_text('die Muhle', encoding='utf-8')
+
+    # act
+    result = evaluate_pair(path_c, path_gt, EvalConfig(count_units=units))
+
+    # assert
+    assert result.statistics_reference.n_chars == n_chars
+    assert result.statistics_candidate.n_chars == 9
+    assert result.diagnostics['CCA']['n_ref'] == n_chars
+
+
+def test_evaluate_pair_fingerprint(tmp_path):
+    """Same configuration same fingerprint, regardless
+    of defaults passed explicitly, any setting of any
+    metric changes it"""
+
+    # arrange
+    path_gt = tmp_path / 'page0001.gt.txt'
+    path_c = tmp_path / 'page0001.txt'
+    path_gt.write_text('der faule Fuchs', encoding='utf-8')
+    path_c.write_text('der faule Fnchs', encoding='utf-8')
+
+    # act
+    result = evaluate_pair(path_c, path_gt)
+    result_explicit = evaluate_pair(path_c, path_gt, EvalConfig(normalizations=[], equivalences={}))
+    result_case = evaluate_pair(path_c, path_gt, EvalConfig(metrics=[metric_from_spec('CCA:case=ignore')]))
+
+    # assert
+    assert result.fingerprint == Evaluator(str(tmp_path)).fingerprint()
+    assert result_explicit.fingerprint == result.fingerprint
+    assert result_case.fingerprint != evaluate_pair(path_c, path_gt,
+                                                    EvalConfig(metrics=[metric_from_spec('CCA')])).fingerprint
+
+
+def test_evaluator_fingerprint_optional_settings(tmp_path):
+    """Settings added later count only if set, so
+    unchanged configuration keeps it's fingerprint"""
+
+    # arrange
+    evaluator = Evaluator(str(tmp_path))
+    fingerprint = evaluator.fingerprint()
+
+    # act
+    evaluator.min_word_confidence = 0.5
+    fingerprint_set = evaluator.fingerprint()
+    evaluator.min_word_confidence = None
+
+    # assert
+    assert fingerprint_set != fingerprint
+    assert evaluator.fingerprint() == fingerprint
+
+
+def test_evaluate_pair_strict_parse(tmp_path):
+    """Strict parsing fails pair with any parse
+    warning, but not problem-free pair"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (path_c, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_c.read_text(encoding='utf-8')
+    path_c.write_text(_xml.replace(' CONTENT="faule"', ''), encoding='utf-8')
+    config = EvalConfig(strict_parse=True)
+
+    # act
+    with pytest.raises(EvalParseError) as err:
+        evaluate_pair(path_c, path_gt, config)
+    result_clean = evaluate_pair(path_gt, path_gt, config)
+
+    # assert
+    assert '1 parse warnings' in str(err.value)
+    assert result_clean.parse_warnings == []
+
+
+def test_aggregate_by_gt_type(tmp_path):
+    """Plain text groundtruth typed by file name gets
+    aggregated per type besides the whole set"""
+
+    # arrange
+    dir_gt = tmp_path / 'groundtruth' / 'odem'
+    dir_c = tmp_path / 'media' / 'odem'
+    dir_gt.mkdir(parents=True)
+    dir_c.mkdir(parents=True)
+    entries = []
+    for (_name, _type, _text) in [('p1', 'art', 'der faule Fuchs'), ('p2', 'table', 'springt auf den Zaun'),
+                                  ('p3', 'table', 'der Zaun')]:
+        (dir_gt / f'{_name}.gt.{_type}.txt').write_text(_text, encoding='utf-8')
+        (dir_c / f'{_name}.txt').write_text(_text if _name != 'p3' else 'der Zuun', encoding='utf-8')
+        _entry = EvalEntry(str(dir_c / f'{_name}.txt'))
+        _entry.path_g = str(dir_gt / f'{_name}.gt.{_type}.txt')
+        entries.append(_entry)
+    evaluator = Evaluator(str(dir_gt))
+    evaluator.metrics = [metric_from_spec('CCA')]
+
+    # act
+    evaluator.eval_all(entries, sequential=True)
+    evaluator.aggregate(by_type=True, by_metrics=[0])
+    evaluator.eval_map()
+
+    # assert
+    assert [_e.gt_type for _e in evaluator.evaluation_entries] == ['article', 'table', 'table']
+    results = {_r.eval_key: _r for _r in evaluator.get_results()}
+    assert results['CCA@odem'].n_total == 3
+    assert results['CCA@odem@article'].n_total == 1
+    assert results['CCA@odem@table'].n_total == 2
+    assert results['CCA@odem@table'].mean == approx((100 + 100 * 7 / 8) / 2)
+
+
 def test_alto_page_dimensions():
     ocr_data = OCRData(OCR_DATA[0])
     page_dim = ocr_data.get_page_dimensions()
@@ -398,6 +764,31 @@ def test_line_repr():
     assert line.__repr__().startswith('[test_line][500:100]')
 
 
+def test_line_drop_words_below():
+    """Drop ALTO words with word confidence WC
+    below threshold, keep words without WC"""
+
+    # arrange
+    line = OCRWordLine('test_line')
+    the_xml = """
+    <alto xmlns="http://www.loc.gov/standards/alto/ns-v3#">
+        <String ID="s1" HPOS="100" VPOS="100" WIDTH="100" HEIGHT="100" WC="0.9" CONTENT="der"/>
+        <String ID="s2" HPOS="200" VPOS="100" WIDTH="100" HEIGHT="100" WC="0.3" CONTENT="faule"/>
+        <String ID="s3" HPOS="300" VPOS="100" WIDTH="100" HEIGHT="100" CONTENT="Fuchs"/>
+    </alto>
+    """
+    the_dom = parseString(the_xml)
+    for _i, _el in enumerate(the_dom.getElementsByTagName('String')):
+        line.add_word(OCRWord(f'w_0{_i}', _el))
+
+    # act
+    n_dropped = line.drop_words_below(0.5)
+
+    # assert
+    assert n_dropped == 1
+    assert line.get_text() == 'der Fuchs'
+
+
 def test_no_groundtruth_at_all(tmp_path):
     """
     Behavior if no groundtruth found:
@@ -508,3 +899,645 @@ def test_handle_table_text_groundtruth():
 
     # assert
     assert 5.82 == approx(eval_entry.metrics[0].value, 1e-3)
+
+
+@pytest.mark.parametrize("label,expected", [
+    ('3/8', (3, 8)), (' 1 / 1 ', (1, 1))])
+def test_parse_shard(label, expected):
+    assert expected == parse_shard(label)
+
+
+@pytest.mark.parametrize("label", ['0/8', '9/8', '3', 'a/b', '1/0'])
+def test_parse_shard_invalid(label):
+    with pytest.raises(ValueError):
+        parse_shard(label)
+
+
+@pytest.mark.parametrize("label,expected", [
+    ('60s', 60.0), ('2m', 120.0), ('90', 90.0), ('0.5s', 0.5)])
+def test_parse_timeout(label, expected):
+    assert expected == parse_timeout(label)
+
+
+@pytest.mark.parametrize("label", ['0s', '-1s', '60x', 's', ''])
+def test_parse_timeout_invalid(label):
+    with pytest.raises(ValueError):
+        parse_timeout(label)
+
+
+def test_filter_shard_partitions_candidates():
+    """Shards 1/n .. n/n must cover all candidates exactly once,
+    independent from the location of the candidates root"""
+
+    # arrange
+    rel_paths = [f'ger/page{i:03d}.xml' for i in range(50)]
+    entries_a = [EvalEntry(os.path.join('/mnt/a/odem', p)) for p in rel_paths]
+    entries_b = [EvalEntry(os.path.join('/data/b/odem', p)) for p in reversed(rel_paths)]
+
+    # act
+    shards_a = [filter_shard(entries_a, '/mnt/a/odem', i, 4) for i in range(1, 5)]
+    shards_b = [filter_shard(entries_b, '/data/b/odem', i, 4) for i in range(1, 5)]
+
+    # assert
+    picked = [e.path_c for shard in shards_a for e in shard]
+    assert sorted(picked) == sorted(e.path_c for e in entries_a)
+    for shard_a, shard_b in zip(shards_a, shards_b):
+        assert sorted(os.path.relpath(e.path_c, '/mnt/a/odem') for e in shard_a) == \
+            sorted(os.path.relpath(e.path_c, '/data/b/odem') for e in shard_b)
+
+
+def test_eval_entries_own_metrics_and_score():
+    """Sequential evaluation must not share metric
+    objects between entries, and each entry gets
+    it's own composite score appended"""
+
+    # arrange
+    res_can = os.path.join(TEST_RES_DIR, 'candidate', 'frk_page')
+    res_ref = os.path.join(TEST_RES_DIR, 'groundtruth', 'page')
+    entries = []
+    for _name in ['urn+nbn+de+gbv+3+1-115907-p0042-0_ger', 'urn+nbn+de+gbv+3+1-126343-p0285-7_ger']:
+        _entry = EvalEntry(os.path.join(res_can, f'{_name}.xml'))
+        _entry.path_g = os.path.join(res_ref, f'{_name}.gt.xml')
+        entries.append(_entry)
+    evaluator = Evaluator(res_ref)
+    evaluator.score = score_from_spec('0.6*CCA + 0.4*WWA', 'Q')
+
+    # act
+    evaluator.eval_all(entries, sequential=True)
+
+    # assert
+    (entry1, entry2) = evaluator.evaluation_entries
+    assert entry1.metrics[0] is not entry2.metrics[0]
+    assert entry1.metrics[0].value > entry2.metrics[0].value
+    for _entry in [entry1, entry2]:
+        _score = _entry.metrics[-1]
+        assert _score.label == 'Q'
+        assert _score.value == approx(0.6 * _entry.metrics[0].value + 0.4 * _entry.metrics[2].value)
+
+
+def test_eval_all_tfidf_weights_by_corpus(tmp_path):
+    """Missing a word rare in groundtruth corpus hurts
+    weighted recall more than missing a frequent one"""
+
+    # arrange
+    pages = [('Fuchs springt Zaun', 'Fuchs springt'),
+             ('Fuchs Hase Wiese', 'Hase Wiese'),
+             ('Fuchs Hund Baum', 'Fuchs Hund Baum')]
+    entries = []
+    for (i, (txt_gt, txt_c)) in enumerate(pages):
+        (tmp_path / f'page0{i}.gt.txt').write_text(txt_gt, encoding='utf-8')
+        (tmp_path / f'page0{i}.txt').write_text(txt_c, encoding='utf-8')
+        _entry = EvalEntry(str(tmp_path / f'page0{i}.txt'))
+        _entry.path_g = str(tmp_path / f'page0{i}.gt.txt')
+        entries.append(_entry)
+    evaluator = Evaluator(str(tmp_path), extras='ignore_geometry')
+    evaluator.metrics = [metric_from_spec('IRRec'), metric_from_spec('IRRec:weighting=tfidf')]
+
+    # act
+    evaluator.eval_all(entries, sequential=True)
+
+    # assert
+    (rare, frequent, _) = [[_m.value for _m in _e.metrics] for _e in evaluator.evaluation_entries]
+    assert rare[0] == approx(frequent[0])
+    assert rare[1] == approx(2.693 / 4.386, abs=0.001)
+    assert frequent[1] == approx(3.386 / 4.386, abs=0.001)
+
+
+def test_eval_entry_line_metric_gets_lines(tmp_path):
+    """Line metric compares lines even if texts
+    get joined by spaces for other metrics"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der faule Fuchs\nspringt', encoding='utf-8')
+    path_cd = tmp_path / 'page01.txt'
+    path_cd.write_text('der faule Fuchs\nsprang', encoding='utf-8')
+    eval_entry = EvalEntry(str(path_cd))
+    eval_entry.path_g = str(path_gt)
+    evaluator = Evaluator(str(tmp_path), extras='ignore_geometry')
+    evaluator.metrics = [metric_from_spec('LCA')]
+
+    # act
+    evaluator.eval_entry(eval_entry)
+
+    # assert
+    assert eval_entry.txt_reference == 'der faule Fuchs springt'
+    assert eval_entry.metrics[0].n_ref == 2
+    assert eval_entry.metrics[0].diagnostics['accuracies'] == [approx(100.0), approx(100 * 5 / 7)]
+
+
+def test_eval_entry_best_window_snippet_groundtruth(tmp_path):
+    """Snippet groundtruth gets evaluated only against
+    best matching window of candidate tokens"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der faule Fuchs springt über den Zaun', encoding='utf-8')
+    path_cd = tmp_path / 'page01.txt'
+    path_cd.write_text('Kapitel 1\nEs war einmal der faule Fnchs springt über den Zaun\nund lief davon',
+                       encoding='utf-8')
+    eval_entry = EvalEntry(str(path_cd))
+    eval_entry.path_g = str(path_gt)
+    evaluator = Evaluator(str(tmp_path), extras='ignore_geometry,best_window')
+    evaluator.metrics = [MetricWA()]
+
+    # act
+    evaluator.eval_entry(eval_entry)
+
+    # assert
+    assert evaluator.text_mode
+    assert eval_entry.window == (5, 12)
+    assert eval_entry.txt_candidate == 'der faule Fnchs springt über den Zaun'
+    assert eval_entry.metrics[0].value == approx(100 * 6 / 7)
+
+
+def test_eval_all_reports_each_entry_when_done(capsys):
+    """Verbose evaluation prints one line per
+    entry with it's metrics, in order of paths"""
+
+    # arrange
+    res_can = os.path.join(TEST_RES_DIR, 'candidate', 'frk_page')
+    res_ref = os.path.join(TEST_RES_DIR, 'groundtruth', 'page')
+    entries = []
+    for _name in ['urn+nbn+de+gbv+3+1-126343-p0285-7_ger', 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger']:
+        _entry = EvalEntry(os.path.join(res_can, f'{_name}.xml'))
+        _entry.path_g = os.path.join(res_ref, f'{_name}.gt.xml')
+        entries.append(_entry)
+    evaluator = Evaluator(res_ref, verbosity=1)
+    evaluator.metrics = [MetricWA()]
+
+    # act
+    evaluator.eval_all(entries, sequential=True)
+
+    # assert
+    lines = [_l for _l in capsys.readouterr().out.splitlines() if _l.startswith('[DEBUG] (')]
+    assert len(lines) == 2
+    assert lines[0].startswith('[DEBUG] (1/2) [urn:nbn:de:gbv:3:1-115907-p0042-0_ger] [WWA:')
+    assert lines[1].startswith('[DEBUG] (2/2) [urn:nbn:de:gbv:3:1-126343-p0285-7_ger] [WWA:')
+
+
+def test_eval_entry_warns_no_reference_units_verbose_only(tmp_path, capsys):
+    """Missing reference units always recorded
+    with entry, but only printed when verbose"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der und die', encoding='utf-8')
+    path_cd = tmp_path / 'page01.txt'
+    path_cd.write_text('der und die', encoding='utf-8')
+    entries = [EvalEntry(str(path_cd)), EvalEntry(str(path_cd))]
+    for _entry in entries:
+        _entry.path_g = str(path_gt)
+
+    outputs = []
+
+    # act
+    for (_entry, _verbosity) in zip(entries, [0, 1]):
+        _evaluator = Evaluator(str(tmp_path), verbosity=_verbosity)
+        _evaluator.metrics = [metric_from_spec('IRRec')]
+        _evaluator.eval_entry(_entry)
+        outputs.append(capsys.readouterr().out)
+
+    # assert
+    assert entries[0].warnings == entries[1].warnings == ['no reference units for IRRec']
+    assert '[WARN ]' not in outputs[0]
+    assert '[WARN ] page01.gt.txt: no reference units for IRRec' in outputs[1]
+
+
+def test_get_region_text_page_groundtruth():
+    """Text and frame of single region by it's ID"""
+
+    # arrange
+    path_gt = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger.gt.xml')
+
+    # act
+    (text, frame) = get_region_text(path_gt, ['region0003'])
+
+    # assert
+    assert text
+    assert len(frame) == 2
+    assert frame[0][0] < frame[1][0]
+    assert frame[0][1] < frame[1][1]
+    assert get_region_text(path_gt, ['region9999']) == (None, None)
+
+
+def test_eval_entry_region_text_as_read(monkeypatch):
+    """Text of regions requested is taken from lines of
+    groundtruth as read, file doesn't get parsed again"""
+
+    # arrange
+    _name = 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger'
+    eval_entry = EvalEntry(os.path.join(TEST_RES_DIR, 'candidate', 'frk_page', f'{_name}.xml'))
+    eval_entry.path_g = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', f'{_name}.gt.xml')
+    (text, _) = get_region_text(eval_entry.path_g, ['region0003'])
+    evaluator = Evaluator(os.path.join(TEST_RES_DIR, 'groundtruth', 'page'))
+    evaluator.metrics = [MetricWA()]
+    evaluator.region_ids = ['region0003']
+
+    def _to_pieces(path):
+        raise AssertionError(f'{path} parsed again')
+
+    monkeypatch.setattr(evaluation_module, 'to_pieces', _to_pieces)
+
+    # act
+    result = evaluator.eval_entry(eval_entry)
+
+    # assert
+    assert result.metrics[0].n_ref == len(text.split())
+
+
+def test_eval_entry_skips_groundtruth_without_region():
+    """Pages lacking all requested regions
+    are skipped rather than evaluated"""
+
+    # arrange
+    _name = 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger'
+    eval_entry = EvalEntry(os.path.join(TEST_RES_DIR, 'candidate', 'frk_page', f'{_name}.xml'))
+    eval_entry.path_g = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', f'{_name}.gt.xml')
+    evaluator = Evaluator(os.path.join(TEST_RES_DIR, 'groundtruth', 'page'))
+    evaluator.metrics = [MetricWA()]
+    evaluator.region_ids = ['region9999']
+
+    # act
+    result = evaluator.eval_entry(eval_entry)
+
+    # assert
+    assert result is None
+    assert not eval_entry.metrics
+
+
+def test_evaluate_pair_values_diagnostics_timings():
+    """Single pair evaluation exposes all metric
+    values, their details and time spent"""
+
+    # arrange
+    _name = 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger'
+    path_cd = os.path.join(TEST_RES_DIR, 'candidate', 'frk_page', f'{_name}.xml')
+    path_gt = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', f'{_name}.gt.xml')
+    config = EvalConfig(metrics=[MetricWA()], score=score_from_spec('1.0*WWA', 'Q'))
+
+    # act
+    result = evaluate_pair(path_cd, path_gt, config)
+
+    # assert
+    assert list(result.values) == ['WWA', 'Q']
+    assert result.values['Q'] == approx(result.values['WWA'])
+    assert result.diagnostics['WWA']['n_ref'] > 0
+    assert not result.diagnostics['WWA']['gaps_applied']
+    assert set(result.timings) == {'read', 'metrics', 'total'}
+    assert result.timings['total'] >= result.timings['read'] + result.timings['metrics']
+    assert config.metrics[0].value is None
+
+
+def test_eval_entry_word_alignment(tmp_path):
+    """Word accuracy's aligned tokens kept per entry,
+    errors thereof as many as accuracy counts"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs'], ['springt', 'hoch']]])
+    candidate = SyntheticPage([[['der', 'fanle', 'Fuchs'], ['springt']]])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    evaluator = Evaluator(str(tmp_path))
+    evaluator.metrics = [MetricWA()]
+    entry = EvalEntry(str(path_c))
+    entry.path_g = str(path_gt)
+
+    # act
+    evaluator.eval_entry(entry)
+
+    # assert
+    errors = [_a for _a in entry.word_alignment if _a[0] != 'match']
+    assert errors == [('substitution', 'faule', 'fanle'), ('deletion', 'hoch', None)]
+    assert entry.metrics[0].value == approx(60.0)
+
+
+def test_evaluate_pair_separators(tmp_path):
+    """Regions joined by their own separator, metrics
+    compare texts as joined"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs'], ['springt']], [['die', 'Hecke']]])
+    (path_c, path_gt) = write_pair(reference, reference, tmp_path, tmp_path, 'page0001')
+    config = EvalConfig(metrics=[MetricCA()], separators='line=newline:region= | ')
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.txt_reference == 'der faule Fuchs\nspringt | die Hecke'
+    assert result.txt_candidate == result.txt_reference
+    assert result.values['CCA'] == approx(100.0)
+    assert result.diagnostics['CCA']['n_ref'] == len(result.txt_reference)
+
+
+def test_evaluate_pair_separators_region_newline(tmp_path):
+    """Region separated by line break, which
+    isn't taken as line break itself"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs'], ['springt']], [['die', 'Hecke']]])
+    (path_c, path_gt) = write_pair(reference, reference, tmp_path, tmp_path, 'page0001')
+    config = EvalConfig(metrics=[MetricCA()], separators='line=space:region=newline')
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.txt_reference == 'der faule Fuchs springt\ndie Hecke'
+    assert result.txt_candidate == result.txt_reference
+
+
+def test_ocr_to_text_separators_by_default_spaces(tmp_path):
+    """Lines and regions joined by line_separator alike,
+    unless separators are given"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule'], ['Fuchs']], [['springt']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+
+    # act
+    (_, text_default, _) = ocr_to_text(str(path_gt), oneliner=True)
+    (_, text_breaks, _) = ocr_to_text(str(path_gt), oneliner=True, line_separator='\n')
+    (_, text_custom, _) = ocr_to_text(str(path_gt), oneliner=True,
+                                      separators=separators_from_spec('word=tab:region=newline'))
+
+    # assert
+    assert text_default == 'der faule Fuchs springt'
+    assert text_breaks == 'der faule\nFuchs\nspringt'
+    assert text_custom == 'der\tfaule Fuchs\nspringt'
+
+
+def test_evaluate_pair_missing_file_raises_io_error(tmp_path):
+    path_gt = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger.gt.xml')
+
+    with pytest.raises(EvalIOError):
+        evaluate_pair(str(tmp_path / 'missing.xml'), path_gt)
+
+
+def test_evaluate_pair_broken_xml_raises_parse_error_with_position(tmp_path):
+    """Malformed XML isn't taken as plain text"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der faule Fuchs', encoding='utf-8')
+    path_cd = tmp_path / 'page01.xml'
+    path_cd.write_text('<alto>\n<Layout>\n</alto>', encoding='utf-8')
+
+    # act
+    with pytest.raises(EvalParseError) as err:
+        evaluate_pair(str(path_cd), str(path_gt))
+
+    # assert
+    assert err.value.data_format == 'xml'
+    assert err.value.position == (3, 2)
+    assert str(path_cd) in str(err.value)
+
+
+class _MetricBroken(MetricCA):
+    """Metric missing a char even if texts are equal"""
+
+    def calculate(self, ctx):
+        return MetricOutcome(99.0, 1, len(ctx.reference))
+
+
+def test_self_check_groundtruth_perfect():
+    """Each groundtruth file evaluated against
+    itself yields perfect values for all metrics"""
+
+    # act
+    (n_checked, deviations) = self_check(os.path.join(TEST_RES_DIR, 'groundtruth', 'txt'))
+
+    # assert
+    assert n_checked == 2
+    assert deviations == []
+
+
+def test_self_check_reports_deviations(tmp_path):
+    """Imperfect values and groundtruth which can't
+    be evaluated get reported with their path"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der faule Fuchs', encoding='utf-8')
+    (tmp_path / 'page02.gt.xml').write_text('<alto>\n<Layout>\n</alto>', encoding='utf-8')
+    config = EvalConfig(metrics=[metric_from_spec('WWA'), _MetricBroken()])
+
+    # act
+    (n_checked, deviations) = self_check(str(tmp_path), config)
+
+    # assert
+    assert n_checked == 2
+    assert deviations[0] == (str(path_gt), 'CCA 99.0 instead of 100.0')
+    assert deviations[1][0] == str(tmp_path / 'page02.gt.xml')
+    assert deviations[1][1].startswith("can't evaluate")
+    assert len(deviations) == 2
+
+
+@pytest.mark.parametrize(['spec', 'expected'], [
+    ('CCA', 100.0), ('CCA:report=error-rate', 0.0), ('IRFM', 1.0), ('CaseER', 0.0)])
+def test_perfect_value(spec, expected):
+    assert perfect_value(metric_from_spec(spec)) == expected
+
+
+def test_eval_entry_empty_groundtruth_raises_metric_error(tmp_path):
+    """Empty groundtruth is told apart from broken
+    groundtruth, but still handled as before"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('', encoding='utf-8')
+    path_cd = tmp_path / 'page01.txt'
+    path_cd.write_text('der faule Fuchs', encoding='utf-8')
+
+    # act
+    with pytest.raises(EvalMetricError) as err:
+        evaluate_pair(str(path_cd), str(path_gt))
+
+    # assert
+    assert isinstance(err.value, EvalError)
+    assert isinstance(err.value, RuntimeError)
+    assert not isinstance(err.value, EvalParseError)
+
+
+def test_eval_entry_normalizes_by_publication_era(tmp_path):
+    """Era from METS date adds it's normalizations
+    and gets aggregated separately"""
+
+    # arrange
+    path_root = tmp_path / 'odem'
+    path_root.mkdir()
+    (path_root / 'work01.xml').write_text(
+        '<mets:mets xmlns:mets="http://www.loc.gov/METS/" xmlns:mods="http://www.loc.gov/mods/v3">'
+        '<mods:originInfo><mods:dateIssued>1789</mods:dateIssued></mods:originInfo></mets:mets>',
+        encoding='utf-8')
+    path_gt = path_root / 'page01.gt.txt'
+    path_gt.write_text('Geschichte des Hauses', encoding='utf-8')
+    path_cd = path_root / 'page01.txt'
+    path_cd.write_text('Geſchichte des Hauſes', encoding='utf-8')
+    eval_entry = EvalEntry(str(path_cd))
+    eval_entry.path_g = str(path_gt)
+    evaluator = Evaluator(str(path_root))
+    evaluator.metrics = [MetricCA()]
+    evaluator.eras = [Era('pre-1900', end=1899, normalizations=['long-s']), Era('since-1900', start=1900)]
+
+    # act
+    evaluator.eval_entry(eval_entry)
+    evaluator.aggregate(by_metrics=[0])
+
+    # assert
+    assert eval_entry.era == 'pre-1900'
+    assert eval_entry.metrics[0].value == approx(100.0)
+    assert 'CCA@odem@era:pre-1900' in evaluator.evaluation_map
+
+
+class _MetricSlow(MetricCA):
+    """Metric stuck on texts with a certain word"""
+
+    def calculate(self, ctx):
+        if 'Zaun' in ctx.reference:
+            time.sleep(5)
+        return super().calculate(ctx)
+
+
+def test_eval_all_timeout_per_file(tmp_path):
+    """Pair exceeding time budget is marked and kept
+    aside, evaluation continues with other pairs"""
+
+    # arrange
+    entries = []
+    for (i, _text) in enumerate(['Fuchs springt', 'Fuchs springt über den Zaun', 'Hase Wiese']):
+        (tmp_path / f'page0{i}.gt.txt').write_text(_text, encoding='utf-8')
+        (tmp_path / f'page0{i}.txt').write_text(_text, encoding='utf-8')
+        _entry = EvalEntry(str(tmp_path / f'page0{i}.txt'))
+        _entry.path_g = str(tmp_path / f'page0{i}.gt.txt')
+        entries.append(_entry)
+    evaluator = Evaluator(str(tmp_path))
+    evaluator.metrics = [_MetricSlow()]
+    evaluator.timeout_per_file = 0.2
+
+    # act
+    _started = time.perf_counter()
+    evaluator.eval_all(entries, sequential=True)
+
+    # assert
+    assert time.perf_counter() - _started < 2
+    assert [os.path.basename(_e.path_c) for _e in evaluator.evaluation_entries] == ['page00.txt', 'page02.txt']
+    (timed_out,) = evaluator.timed_out_entries
+    assert timed_out.path_c.endswith('page01.txt')
+    assert timed_out.timed_out
+    assert timed_out.metrics == []
+
+
+def test_evaluate_pair_timeout(tmp_path):
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der Zaun', encoding='utf-8')
+
+    # act
+    with pytest.raises(EvalTimeoutError):
+        evaluate_pair(str(path_gt), str(path_gt), EvalConfig(metrics=[_MetricSlow()], timeout=0.2))
+
+
+def test_evaluate_pair_timeout_reading_regions(tmp_path, monkeypatch):
+    """Timeout while files get parsed for regions aborts
+    pair instead of evaluation going on without regions"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'den', 'Zaun']]])
+    (path_c, path_gt) = write_pair(reference, reference, tmp_path, tmp_path, 'page0001')
+    _to_pieces = evaluation_module.to_pieces
+
+    def _to_pieces_slow(path):
+        time.sleep(5)
+        return _to_pieces(path)
+
+    monkeypatch.setattr(evaluation_module, 'to_pieces', _to_pieces_slow)
+
+    # act
+    with pytest.raises(EvalTimeoutError):
+        evaluate_pair(str(path_c), str(path_gt),
+                      EvalConfig(metrics=[metric_from_spec('CCA')], by_region=True, timeout=0.2))
+
+
+def test_resource_usage():
+    """Wall time since start, CPU time and memory
+    of the run so far"""
+
+    # arrange
+    _started = time.perf_counter() - 2
+
+    # act
+    usage = resource_usage(_started)
+
+    # assert
+    assert usage['wall_seconds'] >= 2
+    assert usage['cpu_seconds'] > 0
+    if os.name == 'posix':
+        assert usage['peak_rss_mib'] > 1
+
+
+def test_evaluate_pair_by_region(tmp_path):
+    """Each groundtruth region gets evaluated against
+    matched candidate region, missing ones against
+    empty text"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'den', 'Zaun']],
+                               [['Hase', 'Wiese']]])
+    candidate = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'dcn', 'Zaun']]])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    config = EvalConfig(metrics=[metric_from_spec('CCA'), metric_from_spec('WWA'), metric_from_spec('RegRec')],
+                        by_region=True)
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert [(_r.id, _r.candidate_id) for _r in result.regions] == [('r1', 'r1'), ('r2', 'r2'), ('r3', None)]
+    assert [[_m.label for _m in _r.metrics] for _r in result.regions] == [['CCA', 'WWA']] * 3
+    (first, second, third) = [[_m.value for _m in _r.metrics] for _r in result.regions]
+    assert first == [100.0, 100.0]
+    assert second == [approx(95.0), 75.0]
+    assert third == [0.0, 0.0]
+    assert result.regions[0].iou == approx(1.0)
+
+
+def test_eval_entry_layout_boxes(tmp_path):
+    """Groundtruth regions and lines keep their bounding
+    boxes with region metrics and line accuracies"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'den', 'Zaun']]])
+    candidate = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'dcn', 'Zaun']]])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    entry = EvalEntry(str(path_c))
+    entry.path_g = str(path_gt)
+    evaluator = Evaluator(str(tmp_path))
+    evaluator.metrics = [metric_from_spec('WWA'), metric_from_spec('LCA')]
+    evaluator.by_region = True
+    evaluator.layout_boxes = True
+
+    # act
+    evaluator.eval_entry(entry)
+
+    # assert
+    assert [(_b.kind, _b.id, _b.parent) for _b in entry.boxes] == [
+        ('region', 'r1', None), ('region', 'r2', None), ('line', 'r1_l1', 'r1'), ('line', 'r2_l1', 'r2')]
+    assert entry.boxes[1].scores == {'WWA': 75.0, 'LCA': approx(95.0)}
+    assert entry.boxes[3].scores == {'LCA': approx(95.0)}
+    assert entry.boxes[0].bbox.p1 < entry.boxes[0].bbox.p2
+
+
+def test_evaluate_pair_by_region_plain_text(tmp_path):
+    """Plain text lacks regions, page metrics still work"""
+
+    # arrange
+    path_gt = tmp_path / 'page01.gt.txt'
+    path_gt.write_text('der faule Fuchs', encoding='utf-8')
+
+    # act
+    result = evaluate_pair(str(path_gt), str(path_gt), EvalConfig(metrics=[metric_from_spec('CCA')], by_region=True))
+
+    # assert
+    assert result.values == {'CCA': 100.0}
+    assert result.regions is None
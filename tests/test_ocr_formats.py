@@ -0,0 +1,143 @@
+# -*- coding: utf-8 -*-
+"""OCR Format Readers Test Module"""
+
+import os
+
+import pytest
+
+from digital_eval import formats
+from digital_eval.errors import (
+    EvalUnsupportedError,
+)
+from digital_eval.evaluation import (
+    ocr_to_text,
+)
+from digital_eval.formats import (
+    FormatReader,
+    detect_reader,
+    get_reader,
+    reader_names,
+    register_reader,
+)
+
+from .conftest import (
+    TEST_RES_DIR
+)
+
+HOCR = """<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><meta name="ocr-system" content="tesseract"/></head>
+<body>
+<div class="ocr_page" id="page_1" title="bbox 0 0 1000 1000">
+<span class="ocr_line" id="line_1" title="bbox 100 100 900 150">
+<span class="ocrx_word" id="word_1" title="bbox 100 100 300 150; x_wconf 90">der</span>
+<span class="ocrx_word" id="word_2" title="bbox 350 100 600 150; x_wconf 80"><strong>faule</strong></span>
+</span>
+<span class="ocr_line" id="line_2" title="bbox 100 600 900 650">
+<span class="ocrx_word" id="word_3" title="bbox 100 600 400 650">Fuchs</span>
+</span>
+</div>
+</body>
+</html>
+"""
+
+
+@pytest.mark.parametrize("path_data,expected", [
+    (os.path.join('candidate', 'frk_alto', '1667522809_J_0073_0512_01.xml'), 'alto'),
+    (os.path.join('candidate', 'frk_page', 'urn+nbn+de+gbv+3+1-115907-p0042-0_ger.xml'), 'page'),
+    (os.path.join('groundtruth', 'txt', '1246734.gt.txt'), 'text')])
+def test_detect_reader_builtin_formats(path_data, expected):
+    assert detect_reader(os.path.join(TEST_RES_DIR, path_data)).name == expected
+
+
+def test_ocr_to_text_hocr_lines_within_frame(tmp_path):
+    """hOCR words get framed like ALTO or PAGE words"""
+
+    # arrange
+    path_hocr = tmp_path / 'page01.hocr'
+    path_hocr.write_text(HOCR, encoding='utf-8')
+
+    # act
+    (_, all_text, n_all) = ocr_to_text(str(path_hocr), oneliner=True)
+    (_, framed_text, n_framed) = ocr_to_text(str(path_hocr), ((0, 0), (1000, 500)), oneliner=True)
+
+    # assert
+    assert detect_reader(str(path_hocr)).name == 'hocr'
+    assert (all_text, n_all) == ('der faule Fuchs', 2)
+    assert (framed_text, n_framed) == ('der faule', 1)
+
+
+def test_ocr_to_text_hocr_min_confidence(tmp_path):
+    """Words below minimal confidence are dropped,
+    words without any confidence are kept"""
+
+    # arrange
+    path_hocr = tmp_path / 'page01.hocr'
+    path_hocr.write_text(HOCR, encoding='utf-8')
+
+    # act
+    (_, text, n_lines) = ocr_to_text(str(path_hocr), oneliner=True, min_confidence=0.85)
+
+    # assert
+    assert (text, n_lines) == ('der Fuchs', 2)
+
+
+def test_ocr_to_text_forced_format(tmp_path):
+    """Forced format skips detection, i.e.
+    to take markup as plain text"""
+
+    # arrange
+    path_data = tmp_path / 'page01.xml'
+    path_data.write_text('<b>der faule Fuchs</b>', encoding='utf-8')
+
+    # act
+    (_, text, _) = ocr_to_text(str(path_data), oneliner=True, data_format='text')
+
+    # assert
+    assert text == '<b>der faule Fuchs</b>'
+    with pytest.raises(EvalUnsupportedError):
+        ocr_to_text(str(path_data), oneliner=True)
+
+
+def test_register_reader_takes_precedence(tmp_path, monkeypatch):
+    """Readers registered later sniff first"""
+
+    # arrange
+    monkeypatch.setattr(formats, '_READERS', list(formats._READERS))
+
+    class UpperTextReader(FormatReader):
+        name = 'upper'
+
+        def sniff(self, path_in, head):
+            return str(path_in).endswith('.upper.txt')
+
+        def read(self, path_in, coords=None):
+            with open(path_in, mode='r', encoding='utf-8') as _handle:
+                return ('n.a.', [_handle.read().upper()])
+
+    path_data = tmp_path / 'page01.upper.txt'
+    path_data.write_text('der faule Fuchs', encoding='utf-8')
+
+    # act
+    register_reader(UpperTextReader())
+
+    # assert
+    assert reader_names()[0] == 'upper'
+    assert get_reader('upper').name == 'upper'
+    assert ocr_to_text(str(path_data), oneliner=True)[1] == 'DER FAULE FUCHS'
+
+
+def test_detect_reader_text_starting_like_markup(tmp_path):
+    """Text files no markup reader claims are read as
+    plain text, even if starting with '<'"""
+
+    # arrange
+    path_data = tmp_path / 'page01.gt.txt'
+    path_data.write_text('<Titel> der faule Fuchs', encoding='utf-8')
+
+    # act
+    (_, text, _) = ocr_to_text(str(path_data), oneliner=True)
+
+    # assert
+    assert detect_reader(str(path_data)).name == 'text'
+    assert text == '<Titel> der faule Fuchs'
@@ -0,0 +1,30 @@
+# -*- coding: utf-8 -*-
+"""Report Labels Test Module"""
+
+import pytest
+
+from digital_eval.i18n import (
+    LABELS,
+    label,
+)
+
+
+def test_labels_translated_completely():
+    """Each language provides all labels"""
+
+    assert set(LABELS['de']) == set(LABELS['en'])
+
+
+def test_label_with_placeholders():
+    assert label('items', 'de') == 'Dokumente'
+    assert label('summary', 'de', candidates='media/odem', reference='gt/odem',
+                 date='2026-10-16') == 'Auswertung von "media/odem" gegen "gt/odem" (2026-10-16)'
+
+
+def test_label_unknown_key_and_language():
+    """Unknown keys pass as they are, unknown languages fail"""
+
+    assert label('WERoi', 'de') == 'WERoi'
+    with pytest.raises(ValueError) as err:
+        label('items', 'fr')
+    assert 'unknown report language' in str(err.value)
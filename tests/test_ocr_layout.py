@@ -0,0 +1,481 @@
+# -*- coding: utf-8 -*-
+"""Layout Evaluation Profile Test Module"""
+
+import os
+
+from types import (
+    SimpleNamespace,
+)
+
+import pytest
+
+from digital_eval.evaluation import (
+    EvalConfig,
+    evaluate_pair,
+    read_regions,
+)
+from digital_eval.layout import (
+    LAYOUT_DEFAULT_WEIGHT,
+    TABLE_CONTENT_IGNORE,
+    LayoutProfile,
+    iou,
+    load_layout_profile,
+    match_regions,
+    order_regions,
+    project_lines,
+    read_tables,
+    table_distance,
+)
+from digital_eval.metrics import (
+    metric_from_spec,
+)
+from digital_eval.model import (
+    BoundingBox,
+    to_pieces,
+)
+
+from .conftest import (
+    TEST_RES_DIR
+)
+from .synthetic import (
+    SyntheticPage,
+    write_pair,
+)
+
+
+def test_load_layout_profile():
+    """Weights from profile, everything else by default"""
+
+    # arrange
+    path_profile = os.path.join(TEST_RES_DIR, 'layout', 'profile-newspaper.xml')
+
+    # act
+    profile = load_layout_profile(path_profile)
+
+    # assert
+    assert profile.name == 'newspaper'
+    assert profile.error_weight('merge') == 1.5
+    assert profile.error_weight('miss') == LAYOUT_DEFAULT_WEIGHT
+    assert profile.region_weight('TextRegion', 'heading') == 2.0
+    assert profile.region_weight('TextRegion', 'paragraph') == 1.0
+    assert profile.region_weight('TableRegion') == LAYOUT_DEFAULT_WEIGHT
+    assert profile.weight('split', 'ImageRegion') == pytest.approx(0.125)
+
+
+def test_load_layout_profile_unknown_error(tmp_path):
+    """Reject error kinds PRImA doesn't know"""
+
+    # arrange
+    path_profile = tmp_path / 'profile.xml'
+    path_profile.write_text('<Profile><ErrorTypeWeights><Weight type="blur" value="1"/>'
+                            '</ErrorTypeWeights></Profile>')
+
+    # act
+    with pytest.raises(RuntimeError) as err:
+        load_layout_profile(path_profile)
+
+    # assert
+    assert "unknown layout error 'blur'" in str(err.value)
+
+
+def test_layout_profile_default():
+    """Default profile weights all errors equal"""
+
+    profile = LayoutProfile()
+
+    assert profile.weight('miss', 'TextRegion', 'heading') == LAYOUT_DEFAULT_WEIGHT
+    with pytest.raises(ValueError):
+        profile.error_weight('blur')
+
+
+def test_iou():
+    """Half overlapping boxes of same size share a third"""
+
+    assert iou(BoundingBox((0, 0), (100, 100)), BoundingBox((50, 0), (150, 100))) == pytest.approx(1 / 3)
+    assert iou(BoundingBox((0, 0), (100, 100)), BoundingBox((200, 0), (300, 100))) == 0.0
+
+
+def test_match_regions_one_to_one():
+    """Greatest overlap goes first, each box matches
+    at most once, pairs below threshold don't match"""
+
+    # arrange
+    reference = [BoundingBox((0, 0), (100, 100)), BoundingBox((0, 200), (100, 300))]
+    candidate = [BoundingBox((0, 0), (100, 110)), BoundingBox((0, 0), (100, 100)),
+                 BoundingBox((0, 250), (100, 350))]
+
+    # act
+    matches = match_regions(reference, candidate, threshold=0.5)
+
+    # assert
+    assert matches == [(0, 1, 1.0)]
+    assert match_regions(reference, candidate, threshold=0.3)[1] == (1, 2, pytest.approx(1 / 3))
+
+
+@pytest.mark.parametrize("authority,expected", [
+    ('reference', (['r1', 'r2', 'r3'], ['c2', 'c1', 'c4', 'c3'])),
+    ('candidate', (['r2', 'r1', 'r3'], ['c1', 'c4', 'c2', 'c3'])),
+    ('each', (['r1', 'r2', 'r3'], ['c1', 'c4', 'c2', 'c3']))])
+def test_order_regions(authority, expected):
+    """Regions follow their counterparts, unmatched
+    ones stay behind their predecessor"""
+
+    # arrange
+    reference = [('r1', BoundingBox((0, 0), (100, 100)), 'a'), ('r2', BoundingBox((0, 200), (100, 300)), 'b'),
+                 ('r3', BoundingBox((0, 400), (100, 500)), 'c')]
+    candidate = [('c1', BoundingBox((0, 200), (100, 300)), 'b'), ('c4', BoundingBox((200, 0), (300, 50)), 'x'),
+                 ('c2', BoundingBox((0, 0), (100, 100)), 'a'), ('c3', BoundingBox((0, 400), (100, 500)), 'c')]
+
+    # act
+    (ordered_reference, ordered_candidate) = order_regions(reference, candidate, authority)
+
+    # assert
+    assert ([_r[0] for _r in ordered_reference], [_r[0] for _r in ordered_candidate]) == expected
+
+
+@pytest.mark.parametrize("authority,expected", [('each', 0.0), ('reference', 100.0), ('candidate', 100.0)])
+def test_evaluate_pair_order_authority(tmp_path, authority, expected):
+    """Candidate with regions in differing reading order
+    matches reference, if serialized in either order"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'Zaun', 'Hase']]])
+    candidate = SyntheticPage([[['springt', 'Zaun', 'Hase']], [['der', 'faule', 'Fuchs']]], slots=[1, 0])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    config = EvalConfig(metrics=[metric_from_spec('WWA')], order_authority=authority)
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.values['WWA'] == pytest.approx(expected)
+
+
+def test_evaluate_pair_region_order(tmp_path):
+    """Region IDs of both texts in order serialized
+    are kept with result, if reordered"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'Zaun', 'Hase']]])
+    candidate = SyntheticPage([[['springt', 'Zaun', 'Hase']], [['der', 'faule', 'Fuchs']]], slots=[1, 0])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+
+    # act
+    result_each = evaluate_pair(str(path_c), str(path_gt), EvalConfig(metrics=[metric_from_spec('WWA')]))
+    result = evaluate_pair(str(path_c), str(path_gt),
+                           EvalConfig(metrics=[metric_from_spec('WWA')], order_authority='reference'))
+
+    # assert
+    assert result_each.region_order is None
+    assert result.region_order == (['r1', 'r2'], ['r2', 'r1'])
+
+
+def test_evaluate_pair_order_authority_words_as_read(tmp_path):
+    """Regions serialized from words as read, so
+    uncertain words stay dropped"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'Fuchs']], [['springt', 'Zaun', 'Hase']]])
+    candidate = SyntheticPage([[['springt', 'Zaun', 'Hase']], [['der', 'faule', 'Fuchs']]], slots=[1, 0])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001', 'alto')
+    path_c.write_text(path_c.read_text(encoding='utf-8').replace('CONTENT="faule"', 'WC="0.3" CONTENT="faule"'),
+                      encoding='utf-8')
+    config = EvalConfig(metrics=[metric_from_spec('WWA')], order_authority='reference', min_word_confidence=0.5)
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.txt_candidate == 'der Fuchs springt Zaun Hase'
+    assert result.values['WWA'] == pytest.approx(100.0)
+
+
+def test_read_regions_without_coordinates():
+    """Regions of lines as read, regions without
+    coordinates kept, but without bounding box"""
+
+    # arrange
+    (region_1, region_2) = (SimpleNamespace(id='r1', p1=(0, 0), p2=(100, 100)),
+                            SimpleNamespace(id='r2', p1=None, p2=None))
+    lines = [('der faule', region_1, None), ('Fuchs', region_1, None), ('springt', region_2, None)]
+
+    # act
+    regions = read_regions(lines)
+
+    # assert
+    assert [(_r[0], _r[2]) for _r in regions] == [('r1', 'der faule\nFuchs'), ('r2', 'springt')]
+    assert regions[0][1].p2 == (100, 100)
+    assert regions[1][1] is None
+    assert not read_regions([('der faule', None, None)])
+
+
+def test_project_lines():
+    """Candidate lines go to reference region covering most
+    of them, regardless of candidate's segmentation, lines
+    outside follow the region of their predecessor"""
+
+    # arrange
+    reference = [('r1', BoundingBox((0, 0), (100, 100)), 'a\nb'), ('r2', BoundingBox((0, 200), (100, 300)), 'c')]
+    candidate = [('l1', BoundingBox((0, 210), (100, 240)), 'c', 'c1'),
+                 ('l2', BoundingBox((0, 10), (100, 40)), 'a', 'c1'),
+                 ('l3', BoundingBox((0, 90), (100, 120)), 'b', 'c1'),
+                 ('l4', BoundingBox((0, 500), (100, 530)), 'x', 'c1')]
+
+    # act
+    projected = project_lines(reference, candidate)
+
+    # assert
+    assert [(_r[0], _r[2]) for _r in projected] == [('r1', 'a\nb'), (None, 'x'), ('r2', 'c')]
+
+
+def test_project_lines_without_coordinates():
+    """Candidate lines without coordinates, or reference
+    regions without, don't get assigned to any region"""
+
+    # arrange
+    reference = [('r1', BoundingBox((0, 0), (100, 100)), 'a'), ('r2', None, 'b')]
+    candidate = [('l1', BoundingBox((0, 10), (100, 40)), 'a', None),
+                 ('l2', None, 'b', None)]
+
+    # act
+    projected = project_lines(reference, candidate)
+
+    # assert
+    assert [(_r[0], _r[2]) for _r in projected] == [('r1', 'a'), (None, 'b'), ('r2', '')]
+
+
+def test_evaluate_pair_project_candidate_words_as_read(tmp_path):
+    """Candidate lines projected as read, so
+    uncertain words stay dropped"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'Fuchs']], [['springt', 'Zaun', 'Hase']]])
+    candidate = SyntheticPage([[['springt', 'Zaun', 'Hase']], [['der', 'faule', 'Fuchs']]], slots=[1, 0])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001', 'alto')
+    path_c.write_text(path_c.read_text(encoding='utf-8').replace('CONTENT="faule"', 'WC="0.3" CONTENT="faule"'),
+                      encoding='utf-8')
+    config = EvalConfig(metrics=[metric_from_spec('WWA')], project_candidate=True, min_word_confidence=0.5)
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.txt_candidate == 'der Fuchs springt Zaun Hase'
+    assert result.region_order == (['r1', 'r2'], ['r1', 'r2'])
+
+
+@pytest.mark.parametrize("project,expected", [(False, 0.0), (True, 100.0)])
+def test_evaluate_pair_project_candidate(tmp_path, project, expected):
+    """Candidate text re-assembled in reference regions
+    matches reference, whatever the candidate's order"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'Zaun', 'Hase']]])
+    candidate = SyntheticPage([[['springt', 'Zaun', 'Hase']], [['der', 'faule', 'Fuchs']]], slots=[1, 0])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    config = EvalConfig(metrics=[metric_from_spec('WWA')], project_candidate=project)
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.values['WWA'] == pytest.approx(expected)
+
+
+def test_evaluate_pair_project_candidate_without_regions(tmp_path):
+    """Candidate lines of hOCR lack regions, but
+    get projected into reference regions anyway"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'Zaun', 'Hase']]])
+    (_, path_gt) = write_pair(reference, reference, tmp_path, tmp_path, 'page0001')
+    _lines = []
+    for (_region, _text) in reversed(list(zip(to_pieces(str(path_gt)).pieces, ['der faule Fuchs',
+                                                                             'springt Zaun Hase']))):
+        (_xs, _ys) = ([_p[0] for _p in _region.dimensions], [_p[1] for _p in _region.dimensions])
+        _bbox = f'bbox {min(_xs)} {min(_ys)} {max(_xs)} {max(_ys)}'
+        _words = ''.join(f'<span class="ocrx_word" title="{_bbox}">{_w}</span>' for _w in _text.split())
+        _lines.append(f'<span class="ocr_line" title="{_bbox}">{_words}</span>')
+    path_c = tmp_path / 'page0001.hocr'
+    path_c.write_text('<html xmlns="http://www.w3.org/1999/xhtml"><body><div class="ocr_page">'
+                      f'{"".join(_lines)}</div></body></html>', encoding='utf-8')
+    config = EvalConfig(metrics=[metric_from_spec('WWA')], project_candidate=True)
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.txt_candidate == 'der faule Fuchs springt Zaun Hase'
+    assert result.values['WWA'] == pytest.approx(100.0)
+
+
+def test_region_metrics(tmp_path):
+    """Candidate misses second region, but
+    locates first one exactly"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'den', 'Zaun']]])
+    candidate = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    config = EvalConfig(metrics=[metric_from_spec(_l) for _l in ['RegPre', 'RegRec', 'RegIoU']])
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.values == {'RegPre': 100.0, 'RegRec': 50.0, 'RegIoU': 100.0}
+    assert result.diagnostics['RegRec'] == {'n_reference': 2, 'n_candidate': 1, 'n_matched': 1, 'n_ref': 2}
+
+
+def test_region_metrics_weighted_by_profile(tmp_path):
+    """Missed heading weighs twice as much as
+    the paragraph found by candidate"""
+
+    # arrange
+    reference = SyntheticPage([[['der', 'faule', 'Fuchs']], [['springt', 'auf', 'den', 'Zaun']]])
+    candidate = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001')
+    _xml = path_gt.read_text(encoding='utf-8')
+    _xml = _xml.replace('<pc:TextRegion id="r1"', '<pc:TextRegion type="paragraph" id="r1"')
+    path_gt.write_text(_xml.replace('<pc:TextRegion id="r2"', '<pc:TextRegion type="heading" id="r2"'),
+                       encoding='utf-8')
+    path_profile = os.path.join(TEST_RES_DIR, 'layout', 'profile-newspaper.xml')
+    config = EvalConfig(metrics=[metric_from_spec(f'{_l}:profile={path_profile}')
+                                 for _l in ['RegPre', 'RegRec', 'RegIoU']])
+
+    # act
+    result = evaluate_pair(str(path_c), str(path_gt), config)
+
+    # assert
+    assert result.values['RegPre'] == 100.0
+    assert result.values['RegRec'] == pytest.approx(100 / 3)
+    assert result.values['RegIoU'] == 100.0
+    assert result.diagnostics['RegRec']['n_matched'] == 1
+
+
+def test_region_metrics_invalid_iou():
+    with pytest.raises(ValueError) as err:
+        metric_from_spec('RegRec:iou=1.5')
+    assert "expected number within (0, 1]" in err.value.args[0]
+
+
+PAGE_TABLE = os.path.join(TEST_RES_DIR, 'groundtruth', 'page', 'urn+nbn+de+gbv+3+1-126343-p0285-7_ger.gt.xml')
+PAGE_TABLE_CANDIDATE = os.path.join(TEST_RES_DIR, 'candidate', 'frk_page', 'urn+nbn+de+gbv+3+1-126343-p0285-7_ger.xml')
+
+
+def _write_table(path_out, rows, cell_role=False):
+    """PAGE with single table, each row as list of cell
+    texts or (text, col_span), cells either Transkribus
+    TableCell or PAGE 2019 TextRegion with TableCellRole"""
+
+    _cells = []
+    for (_r, _row) in enumerate(rows):
+        _col = 0
+        for _cell in _row:
+            (_text, _span) = _cell if isinstance(_cell, tuple) else (_cell, 1)
+            _line = (f'<TextLine id="l{_r}_{_col}"><Coords points="0,0 1,0 1,1 0,1"/>'
+                     f'<TextEquiv><Unicode>{_text}</Unicode></TextEquiv></TextLine>')
+            if cell_role:
+                _cells.append(f'<TextRegion id="c{_r}_{_col}"><Roles><TableCellRole rowIndex="{_r}" '
+                              f'columnIndex="{_col}" rowSpan="1" colSpan="{_span}"/></Roles>'
+                              f'<Coords points="0,0 1,0 1,1 0,1"/>{_line}</TextRegion>')
+            else:
+                _cells.append(f'<TableCell id="c{_r}_{_col}" row="{_r}" col="{_col}" rowSpan="1" '
+                              f'colSpan="{_span}"><Coords points="0,0 1,0 1,1 0,1"/>{_line}</TableCell>')
+            _col += _span
+    path_out.write_text('<PcGts xmlns="http://schema.primaresearch.org/PAGE/gts/pagecontent/2013-07-15">'
+                        '<Page imageFilename="p1.jpg" imageWidth="1000" imageHeight="1000">'
+                        '<TableRegion id="t1"><Coords points="100,100 900,100 900,900 100,900"/>'
+                        f'{"".join(_cells)}</TableRegion></Page></PcGts>', encoding='utf-8')
+    return path_out
+
+
+@pytest.mark.parametrize('cell_role', [False, True])
+def test_read_tables(tmp_path, cell_role):
+    """Cells of both PAGE flavours get grouped by
+    row and ordered by column"""
+
+    # arrange
+    path_page = _write_table(tmp_path / 'page.xml', [['Jahr', ('Anzahl', 2)], ['1789', '12', '3']], cell_role)
+
+    # act
+    (table,) = read_tables(path_page)
+
+    # assert
+    assert table.id == 't1'
+    assert [[_c.text for _c in _row] for _row in table.rows] == [['Jahr', 'Anzahl'], ['1789', '12', '3']]
+    assert table.rows[0][1].col_span == 2
+    assert table.n_nodes == 1 + 2 + 5
+
+
+def test_read_tables_groundtruth():
+    # act
+    (table,) = read_tables(PAGE_TABLE)
+
+    # assert
+    assert len(table.rows) == 46
+    assert table.rows[0][1].text == 'breit 27 Zoll'
+
+
+@pytest.mark.parametrize(['rows', 'expected'], [
+    ([['Jahr', 'Anzahl'], ['1789', '12']], 0.0),
+    ([['Jahr', 'Anzahl'], ['1780', '12']], 0.25),
+    ([['Jahr', 'Anzahl']], 3.0),
+    ([['Jahr'], ['1789', '12']], 1.0),
+    ([[('Jahr', 2)], ['1789', '12']], 2.0)])
+def test_table_distance(tmp_path, rows, expected):
+    """Text edits cost per char, missing rows cost
+    all their nodes, cells spanning differently don't
+    match at all"""
+
+    # arrange
+    reference = read_tables(_write_table(tmp_path / 'page.gt.xml', [['Jahr', 'Anzahl'], ['1789', '12']]))[0]
+    candidate = read_tables(_write_table(tmp_path / 'page.xml', rows))[0]
+
+    # act
+    distance = table_distance(reference, candidate)
+
+    # assert
+    assert distance == pytest.approx(expected)
+
+
+def test_table_distance_structure_only(tmp_path):
+    # arrange
+    reference = read_tables(_write_table(tmp_path / 'page.gt.xml', [['Jahr', 'Anzahl'], ['1789', '12']]))[0]
+    candidate = read_tables(_write_table(tmp_path / 'page.xml', [['Iahr', 'Anzahl'], ['1780', '']]))[0]
+
+    # act
+    distance = table_distance(reference, candidate, TABLE_CONTENT_IGNORE)
+
+    # assert
+    assert distance == 0.0
+
+
+def test_table_metric(tmp_path):
+    """Similarity by edit distance relative to nodes
+    of greater table, candidate without tables fails"""
+
+    # arrange
+    path_gt = _write_table(tmp_path / 'page.gt.xml', [['Jahr', 'Anzahl'], ['1789', '12']])
+    path_c = _write_table(tmp_path / 'page.xml', [['Jahr', 'Anzahl'], ['1780', '12']])
+    config = EvalConfig(metrics=[metric_from_spec('TblSim'), metric_from_spec('TblSim:content=ignore')])
+
+    # act
+    result = evaluate_pair(PAGE_TABLE_CANDIDATE, PAGE_TABLE, EvalConfig(metrics=[metric_from_spec('TblSim')]))
+    metrics = evaluate_pair(str(path_c), str(path_gt), config).metrics
+
+    # assert
+    assert result.values['TblSim'] == 0.0
+    assert result.diagnostics['TblSim']['n_candidate'] == 0
+    assert metrics[0].value == pytest.approx(100 * (1 - 0.25 / 7))
+    assert metrics[0].n_ref == 4
+    assert metrics[1].value == 100.0
+
+
+@pytest.mark.parametrize(['spec', 'message'], [
+    ('TblSim:content=all', "unknown content 'all'"),
+    ('RegRec:content=ignore', "requires table metric 'TblSim'")])
+def test_table_metric_invalid_option(spec, message):
+    with pytest.raises(ValueError) as err:
+        metric_from_spec(spec)
+    assert message in err.value.args[0]
@@ -1,20 +1,54 @@
 # -*- coding: utf-8 -*-
 """OCR Metric Test Module"""
 
+import math
 import random
 
 import unicodedata
 
+from collections import (
+    Counter
+)
+
 import pytest
 
+from pytest import (
+    approx
+)
+
+from digital_eval.compat import (
+    MetricOcrevalWERBag,
+)
 from digital_eval.metrics import (
     character_accuracy,
     bag_of_tokens,
+    document_frequencies,
+    edit_operations,
+    filter_tokens,
+    fuzzy_tokens,
+    idf,
+    ir_weighted,
+    best_window,
+    align_lines,
+    capitalized_tokens,
+    char_ngram_fscore,
+    word_ngram_bleu,
+    metric_from_spec,
+    register_metric,
+    semi_global_distance,
+    score_from_spec,
+    MetricCA,
+    MetricLineCA,
+    MetricWA,
+    MetricBoW,
+    EvalContext,
+    METRICS,
     ir_fmeasure,
     ir_recall,
     ir_precision,
     _ir_preprocess,
     token_based,
+    tokenize,
 )
 
 def test_metric_normalization():
@@ -168,6 +202,60 @@ def test_ir_metrics_german_stopwords_poor_candidate():
     assert _fm == pytest.approx(_pre , 0.28)
 
 
+@pytest.mark.parametrize(['languages', 'expected'], [
+    (['lat'], {'Gallia', 'omnis', 'divisa', 'partes', 'tres'}),
+    (['deu'], {'Gallia', 'est', 'omnis', 'divisa', 'partes', 'tres'}),
+    (['german'], {'Gallia', 'est', 'omnis', 'divisa', 'partes', 'tres'}),
+    (['deu', 'lat'], {'Gallia', 'omnis', 'divisa', 'partes', 'tres'})])
+def test_ir_stopwords_by_iso_code(languages, expected):
+    """Bundled stopwords by ISO 639-2 code, NLTK
+    names denote NLTK's list"""
+
+    # act
+    gt, _ = _ir_preprocess("Gallia est omnis divisa in partes tres", "", languages)
+
+    # assert
+    assert gt == expected
+
+
+@pytest.mark.parametrize(['languages', 'expected'], [
+    (None, {'Fuchs', 'muß', 'springen'}),
+    (['deu'], {'Fuchs', 'springen'})])
+def test_ir_stopwords_bundled_if_requested(languages, expected):
+    """Default stopwords come from NLTK, bundled
+    list only for language requested by code"""
+
+    # act
+    gt, _ = _ir_preprocess("der Fuchs muß springen", "", languages)
+
+    # assert
+    assert gt == expected
+
+
+def test_ir_stopwords_unknown_language():
+    with pytest.raises(ValueError) as _err:
+        metric_from_spec('IRRec:language=deu,xyz')
+    assert "unknown stopword language 'xyz'" in _err.value.args[0]
+
+
+def test_ir_stopwords_language_option():
+    """IR metric with latin stopwords ignores
+    missing 'et', but not missing 'Caesar'"""
+
+    # arrange
+    _metric = metric_from_spec('IRRec:language=lat')
+    _metric.input_reference = 'Caesar et Gallia'
+    _metric.input_candidate = 'Gallia'
+
+    # act
+    _metric.calc()
+
+    # assert
+    assert _metric.languages == ['lat']
+    assert _metric.n_ref == 2
+    assert _metric.value == pytest.approx(0.5)
+
+
 def test_metrics_token_based_more_gt_than_tc():
     """1 fits for text with 7 tokens = 14.28"""
 
@@ -238,3 +326,756 @@ def test_metrics_token_based_no_test_candidate():
     # assert
     assert 0.0 == pytest.approx(ratio)
     assert diff == 5
+
+
+def test_metric_wa_edits_whole_tokens():
+    """Word accuracy is 100 - WER: each token counts
+    as single edit, whatever it's number of chars.
+    Split and merged words count as several edits."""
+
+    # arrange
+    ctx = EvalContext('der überaus faule Fuchs springt', 'der überall faule Fuchsspringt')
+
+    # act
+    outcome = MetricWA().calculate(ctx)
+
+    # assert
+    assert outcome.diff == 3
+    assert outcome.n_ref == 5
+    assert outcome.value == pytest.approx(40.0)
+    assert len(outcome.data_candidate) == 4
+
+
+def test_filter_tokens_punct_and_digits():
+    """Drop tokens consisting only of punctuation or digits,
+    but keep tokens which just contain some"""
+
+    tokens = ['(', 'Sand', 'Gate', ')', ',', '1899', '—', '(Sand', '3.']
+
+    assert filter_tokens(tokens, ['punct']) == ['Sand', 'Gate', '1899', '(Sand', '3.']
+    assert filter_tokens(tokens, ['punct', 'digits']) == ['Sand', 'Gate', '(Sand', '3.']
+    assert filter_tokens(tokens, []) == tokens
+
+
+@pytest.mark.parametrize("mode,expected", [
+    ('whitespace', ['(', 'Sand', 'Gate', ')', ',']),
+    ('split-punct', ['(', 'Sand', 'Gate', ')', ',']),
+    ('strip-punct', ['Sand', 'Gate'])])
+def test_tokenize_modes(mode, expected):
+    """Candidate tokens '( Sand Gate ) ,' match
+    groundtruth '(Sand Gate),' if punctuation
+    gets split off or stripped on both sides"""
+
+    assert tokenize('( Sand Gate ) ,', mode) == expected
+    if mode != 'whitespace':
+        assert tokenize('(Sand Gate),', mode) == expected
+
+
+def test_metric_from_spec_tokenize():
+    """Word accuracy with consistently split punctuation"""
+
+    # arrange
+    metric = metric_from_spec('WWA:tokenize=split-punct')
+    metric.input_reference = 'bei (Sand Gate), 1899'
+    metric.input_candidate = 'bei ( Sand Gate ) , 1899'
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.tokenization == 'split-punct'
+    assert metric.value == approx(100.0)
+    assert metric.n_ref == 7
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('CCA', 20.0),
+    ('CCA:case=ignore', 100.0),
+    ('CLA:case=ignore', 100.0),
+    ('WWA', 0.0),
+    ('WWA:case=ignore', 100.0),
+    ('IRFM:case=ignore', 1.0)])
+def test_metric_from_spec_case_ignore(spec, expected):
+    """Capitalization differences don't count as errors"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    metric.input_reference = 'DER FAULE FUCHS'
+    metric.input_candidate = 'der Faule fuchs'
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.value == approx(expected, abs=0.01)
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('WWA', 0.0),
+    ('WWA:marks=ignore', 100.0),
+    ('CCA:marks=ignore', 100.0),
+    ('CLA:marks=ignore', 100.0),
+    ('CCA:case=ignore:marks=ignore', 100.0),
+    ('WWAs:language=fra:marks=ignore', 100.0)])
+def test_metric_from_spec_marks_ignore(spec, expected):
+    """Accent errors don't count as errors, composed
+    or not, since marks get stripped after NFKD"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    metric.input_reference = 'l\u2019élève à côté'
+    metric.input_candidate = 'l\u2019eleve a cote'
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.value == approx(expected, abs=0.01)
+
+
+def test_fuzzy_tokens():
+    """Exact matches first, then closest candidate
+    token, each matching one reference token only"""
+
+    reference = ['der', 'faule', 'Fuchs', 'Fuchs', 'springt']
+    candidate = ['dcr', 'Fuchs', 'Fnchs', 'Fncks', 'spring']
+
+    assert fuzzy_tokens(reference, candidate, 1) == ['der', 'Fuchs', 'Fuchs', 'Fncks', 'springt']
+    assert fuzzy_tokens(reference, candidate, 0.2) == ['dcr', 'Fuchs', 'Fuchs', 'Fncks', 'springt']
+    assert fuzzy_tokens(reference, candidate, 0) == candidate
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('WBoW', 20.0),
+    ('WBoW:fuzzy=1', 80.0),
+    ('IRRec:fuzzy=1', 0.6667),
+    ('IRPre:fuzzy=0.2', 0.4)])
+def test_metric_from_spec_fuzzy(spec, expected):
+    """Slightly misrecognized words count as found"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    metric.input_reference = 'der faule Fuchs Fuchs springt'
+    metric.input_candidate = 'dcr Fuchs Fnchs Fncks spring'
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.value == approx(expected, abs=0.001)
+
+
+def test_ir_weighted_without_corpus_by_term_frequency():
+    """Without corpus data all terms share same idf"""
+
+    reference = Counter({'Fuchs': 3, 'Zaun': 1})
+    candidate = Counter({'Fuchs': 1, 'Hase': 1})
+
+    assert ir_weighted(reference, candidate) == (approx(0.5), approx(0.75))
+    assert idf('Zaun') == approx(1.0)
+    assert idf('Zaun', document_frequencies([{'Fuchs'}, {'Fuchs', 'Zaun'}])) == approx(math.log(3 / 2) + 1)
+
+
+def test_align_lines_skips_inserted_lines():
+    """Candidate lines without counterpart don't shift
+    alignment, missing lines count all their chars"""
+
+    reference = ['der faule Fuchs', 'springt über', 'den Zaun']
+    candidate = ['Seite 12', 'der faule Fnchs', 'den Zaun']
+
+    assert align_lines(reference, candidate) == [
+        ('der faule Fuchs', 'der faule Fnchs', 1),
+        ('springt über', '', 12),
+        ('den Zaun', 'den Zaun', 0)]
+
+
+def test_metric_line_ca_mean_of_lines():
+    """Line accuracies average per line and keep
+    worst lines, decomposed 'ü' counts two chars"""
+
+    # arrange
+    ctx = EvalContext('der faule Fuchs springt über den Zaun',
+                      'der faule Fuchs springt uber den Zaun',
+                      options={'lines': ('der faule Fuchs\nspringt über\nden Zaun',
+                                         'der faule Fuchs\nspringt xxxx\nden Zaun')})
+
+    # act
+    outcome = MetricLineCA().calculate(ctx)
+
+    # assert
+    assert outcome.n_ref == 3
+    assert outcome.diagnostics['accuracies'] == [approx(100.0), approx(100 * 8 / 13), approx(100.0)]
+    assert outcome.diagnostics['median'] == approx(100.0)
+    assert outcome.diagnostics['worst'][0][1:] == (unicodedata.normalize('NFKD', 'springt über'), 'springt xxxx')
+    assert outcome.value == approx((200 + 100 * 8 / 13) / 3)
+
+
+def test_char_ngram_fscore_averages_orders():
+    """Unigrams match half, bigrams none"""
+
+    (fscore, precision, recall) = char_ngram_fscore('ab', 'ac', order=2)
+
+    assert (precision, recall) == (approx(0.25), approx(0.25))
+    assert fscore == approx(0.25)
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('CCA', 100 * 14 / 15),
+    ('ChrF', 100.0),
+    ('ChrF:n=1:beta=1', 100.0)])
+def test_metric_chrf_ignores_segmentation(spec, expected):
+    """Merged tokens cost char accuracy, but
+    no n-grams since whitespace is ignored"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    ctx = EvalContext('der faule Fuchs', 'derfaule Fuchs')
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert outcome.value == approx(expected)
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('WWA', 100 * 4 / 6),
+    ('WWAs:language=deu', 100 * 2 / 3)])
+def test_metric_wwas_drops_stopwords(spec, expected):
+    """Stopwords dropped regardless of case and
+    normalization, errors of content words remain"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    ctx = EvalContext('Der Fuchs springt über die Mauer', 'Der Fuchs sprlngt über Die Mauer')
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert outcome.value == approx(expected)
+
+
+def test_word_ngram_bleu_clips_and_penalizes():
+    """Repeated candidate token matches only once,
+    candidate one token shorter than reference"""
+
+    # arrange
+    reference = 'der faule Fuchs springt'.split()
+    candidate = 'der der Fuchs'.split()
+
+    # act
+    (value, precisions, penalty) = word_ngram_bleu(reference, candidate, order=2)
+
+    # assert
+    assert precisions == [approx(2 / 3), approx(0.0)]
+    assert penalty == approx(math.exp(1 - 4 / 3))
+    assert value == 0.0
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('BLEU', 0.0),
+    ('BLEU:n=3', 100 * (3 / 4 * 2 / 3 * 1 / 2) ** (1 / 3)),
+    ('BLEU:n=1', 75.0)])
+def test_metric_bleu(spec, expected):
+    """Last word differs, so no 4-gram matches at all,
+    same length, so no brevity penalty"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    ctx = EvalContext('der faule Fuchs springt', 'der faule Fuchs sprang')
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert outcome.value == approx(expected)
+    assert outcome.n_ref == 4
+    assert outcome.diagnostics['brevity_penalty'] == 1.0
+
+
+@pytest.mark.parametrize("reference,candidate,expected", [
+    ('Der große Fuchs springt', 'der Große Fuchs sprang', 50.0),
+    ('die Straße', 'die STRASSE', 50.0),
+    ('der faule Fuchs', 'der fanle Fuchs', 0.0)])
+def test_metric_case_error_rate(reference, candidate, expected):
+    """Only words equal after casefolding count"""
+
+    outcome = metric_from_spec('CaseER').calculate(EvalContext(reference, candidate))
+
+    assert outcome.value == approx(expected)
+
+
+@pytest.mark.parametrize("spec,candidate,expected", [
+    ('DiaER', 'Uber schone Hauser', 100 * 3 / 21),
+    ('DiaER:units=graphemes', 'Uber schone Hauser', 100 * 3 / 18),
+    ('DiaER', 'Übex schöne Häuser', 0.0)])
+def test_metric_diacritics_error_rate(spec, candidate, expected):
+    """Only chars equal after stripping combining marks count,
+    relative to reference's decomposed codepoints or graphemes"""
+
+    outcome = metric_from_spec(spec).calculate(EvalContext('Über schöne Häuser', candidate))
+
+    assert outcome.value == approx(expected)
+
+
+@pytest.mark.parametrize("spec,reference,candidate,expected", [
+    ('WBoW', 'fox', 'fox fox fox', 100.0),
+    ('BoWf', 'fox', 'fox fox fox', 100 / 3),
+    ('BoWf', 'fox fox fox', 'fox', 100 / 3),
+    ('BoWf', 'fox fox fox', 'fox fox fox hen', 75.0),
+    ('BoWf:similarity=cosine', 'fox fox fox', 'fox', 100.0),
+    ('BoWf:similarity=cosine', 'fox fox fox', 'fox fox fox hen', 100 * 3 / math.sqrt(10)),
+    ('BoWf', '', '', 100.0)])
+def test_metric_frequency_bag_of_words(spec, reference, candidate, expected):
+    """Spurious repetitions count by frequencies, unlike
+    with plain bag of words"""
+
+    outcome = metric_from_spec(spec).calculate(EvalContext(reference, candidate))
+
+    assert outcome.value == approx(expected)
+
+
+@pytest.mark.parametrize("spec,label,expected", [
+    ('CCA:report=error-rate', 'CER', 100 * 10 / 15),
+    ('WWA:report=error-rate', 'WER', 100 * 2 / 3),
+    ('WBoW:report=error-rate', 'BoWER', 100 * 1 / 3),
+    ('CCA:report=accuracy', 'CCA', 100 * 5 / 15)])
+def test_metric_from_spec_error_rate(spec, label, expected):
+    """Error rates count edits per reference unit,
+    bag of words the larger number of missing or
+    spurious tokens"""
+
+    # arrange
+    metric = metric_from_spec(spec)
+    ctx = EvalContext('der faule Fuchs', 'der Fuchs faule Hund')
+
+    # act
+    outcome = metric.calculate(ctx)
+
+    # assert
+    assert metric.label == label
+    assert outcome.value == approx(expected)
+
+
+@pytest.mark.parametrize("spec", ['CCA:report=error-rate', 'WWA:report=error-rate'])
+def test_metric_error_rate_without_reference(spec):
+    """Errors without any reference unit count as
+    complete failure, rather than infinite rate"""
+
+    outcome = metric_from_spec(spec).calculate(EvalContext('', 'der Fuchs'))
+
+    assert outcome.value == approx(100.0)
+
+
+def test_metric_error_rate_exceeds_hundred():
+    metric = metric_from_spec('WWA:report=error-rate')
+
+    outcome = metric.calculate(EvalContext('Fuchs', 'der faule Hund'))
+
+    assert outcome.value == approx(300.0)
+
+
+@pytest.mark.parametrize("spec,expected", [
+    ('WWA', 0.0),
+    ('WWA:length=max', 25.0),
+    ('WWA:length=max:report=error-rate', 75.0),
+    ('WWA:report=error-rate', 150.0)])
+def test_metric_from_spec_length(spec, expected):
+    """Normalized by reference, candidate's extra
+    words make errors exceed reference length"""
+
+    outcome = metric_from_spec(spec).calculate(EvalContext('der Fuchs', 'der faule rote Hund'))
+
+    assert outcome.value == approx(expected)
+
+
+def test_metric_segmentation_error_rate():
+    """Split and merged words count with their word
+    edits, other errors don't count at all"""
+
+    # arrange
+    ctx = EvalContext('der faule Fuchs springt über den Zaun', 'derfaule Fu chs sprang über den Zaun')
+
+    # act
+    outcome = metric_from_spec('SegER').calculate(ctx)
+
+    # assert
+    assert outcome.value == approx(100 * 4 / 7)
+    assert outcome.diagnostics == {'n_split': 1, 'n_merge': 1}
+
+
+def test_metric_keyword_recall(tmp_path):
+    """Terms occuring in reference count once each,
+    terms of several words only if found in sequence"""
+
+    # arrange
+    path_terms = tmp_path / 'names.txt'
+    path_terms.write_text('# places\nHalle an der Saale\nMerseburg\nMartin Luther\nLeipzig\n', encoding='utf-8')
+    ctx = EvalContext('Martin Luther kam nach Halle an der Saale, dann nach Merseburg und Halle.',
+                      'Martin Lnther kam nach Halle an der Saale; dann nach Merseburg und Halle')
+
+    # act
+    outcome = metric_from_spec(f'KWRec:terms={path_terms}').calculate(ctx)
+
+    # assert
+    assert outcome.value == approx(100 * 2 / 3)
+    assert outcome.n_ref == 3
+    assert outcome.diagnostics == {'missed': ['Martin Luther']}
+
+
+@pytest.mark.parametrize("spec,candidate,expected", [
+    ('CapRec', 'Dann kam Luther nach halle. Der Rat von Merseburg', 50.0),
+    ('CapRec:case=ignore', 'Dann kam Luther nach halle. Der Rat von Merseburg', 75.0),
+    ('CapRec', 'Luther kam nach Halle in die Stadt Merseburg', 100.0)])
+def test_metric_capitalized_recall(spec, candidate, expected):
+    """Capitalized reference words but those starting
+    sentences, even after closing quotes, must occur
+    in candidate anywhere"""
+
+    # arrange
+    ctx = EvalContext('Dann kam Luther nach Halle. "Es war kalt." Die Stadt Merseburg', candidate)
+
+    # act
+    outcome = metric_from_spec(spec).calculate(ctx)
+
+    # assert
+    assert outcome.n_ref == 4
+    assert outcome.value == approx(expected)
+
+
+def test_capitalized_tokens_abbreviations():
+    """Ordinals, roman numerals and initials don't end
+    sentences, other abbreviations do"""
+
+    # act
+    tokens = capitalized_tokens('Dann kam Heinrich II. Graf von Mansfeld am 3. März mit M. Luther. '
+                                'Röm. Kaiser Karl')
+
+    # assert
+    assert tokens == ['Heinrich', 'II', 'Graf', 'Mansfeld', 'März', 'M', 'Luther', 'Karl']
+
+
+@pytest.mark.parametrize("label,candidate,value", [
+    ('WWA', 'der faule Fuchs springt .', 100.0),
+    ('SegER', 'der faul e Fuchs springt .', 50.0),
+])
+def test_metric_from_spec_drop_punct(label, candidate, value):
+    """Token based metrics ignore punctuation tokens if requested"""
+
+    # arrange
+    metric = metric_from_spec(f'{label}:drop=punct')
+    metric.input_reference = 'der faule Fuchs , springt'
+    metric.input_candidate = candidate
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.label == label
+    assert metric.token_filters == ['punct']
+    assert metric.value == approx(value)
+    assert metric.n_ref == 4
+
+
+@pytest.mark.parametrize("spec,message", [
+    ('XYZ', "unknown metric"),
+    ('CCA:drop=punct', "requires token based metric"),
+    ('WBoW:drop=words', "unknown token class"),
+    ('WBoW:keep=punct', "unknown option"),
+    ('WBoW:punct', "invalid option"),
+    ('WBoW:gaps=free', "requires edit distance metric"),
+    ('CCA:gaps=local', "unknown alignment mode"),
+    ('WWA:units=graphemes', "requires character based metric"),
+    ('CCA:units=bytes', "unknown units"),
+    ('CCA:tokenize=split-punct', "requires token based metric"),
+    ('WWA:tokenize=chars', "unknown tokenization"),
+    ('WWA:case=upper', "unknown case mode"),
+    ('WWA:fuzzy=1', "requires bag of words metric"),
+    ('WBoW:fuzzy=1.5', "invalid fuzzy threshold"),
+    ('IRFM:fuzzy=some', "invalid fuzzy threshold"),
+    ('WBoW:weighting=tfidf', "requires IR metric"),
+    ('IRRec:weighting=bm25', "unknown weighting"),
+    ('CCA:n=3', "requires char n-gram metric"),
+    ('IRFM:report=error-rate', "option 'report' requires one of"),
+    ('CCA:report=ratio', "unknown report mode"),
+    ('WBoW:length=max', "requires edit distance metric"),
+    ('WBoW:similarity=cosine', "requires frequency bag of words metric"),
+    ('BoWf:similarity=dice', "unknown similarity"),
+    ('CCA:length=candidate', "unknown length"),
+    ('ChrF:n=0', "expected positive number"),
+    ('ChrF:beta=high', "expected positive number"),
+    ('BLEU:n=0', "expected positive number"),
+    ('BLEU:beta=1', "requires char n-gram metric"),
+    ('WWA:language=deu', "requires dictionary metric 'DICT' or stopword metric"),
+    ('KWRec', "requires option 'terms'"),
+    ('WWA:terms=names.txt', "requires keyword metric 'KWRec'")])
+def test_metric_from_spec_invalid(spec, message):
+    with pytest.raises(ValueError) as err:
+        metric_from_spec(spec)
+    assert message in str(err.value)
+
+
+def test_register_metric_by_closure():
+    """Registered factory gets picked by label and
+    takes options of builtin metrics"""
+
+    # arrange
+    def _factory():
+        _metric = MetricCA()
+        (_metric.label, _metric.name) = ('CCAx', 'Custom Character Accuracy')
+        return _metric
+    register_metric('CCAx', _factory)
+
+    # act
+    try:
+        metric = metric_from_spec('CCAx:case=ignore')
+    finally:
+        METRICS.pop('CCAx')
+    outcome = metric.calculate(EvalContext('Fuchs', 'FUCHS'))
+
+    # assert
+    assert metric.name == 'Custom Character Accuracy'
+    assert outcome.value == approx(100.0)
+    with pytest.raises(ValueError) as err:
+        register_metric('CCA:x', _factory)
+    assert "invalid metric label 'CCA:x'" in str(err.value)
+
+
+@pytest.mark.parametrize("reference,candidate,expected", [
+    ('der Fuchs', 'der Fnchs', (1, 0, 0)),
+    ('der Fuchs', 'der Fuchs!', (0, 1, 0)),
+    ('der faule Fuchs', 'der Fuchs', (0, 0, 6)),
+    ('', 'Fuchs', (0, 5, 0)),
+    ('der faule Fuchs'.split(), 'der fanle Fuchs springt'.split(), (1, 1, 0))])
+def test_edit_operations(reference, candidate, expected):
+    """Substitutions, insertions and deletions
+    sum up to edit distance"""
+
+    operations = edit_operations(reference, candidate)
+
+    assert (operations['substitutions'], operations['insertions'], operations['deletions']) == expected
+
+
+def test_edit_operations_long_bad_candidate():
+    """Operations of long page with many
+    errors are counted, too"""
+
+    # act
+    operations = edit_operations('Fuchs ' * 200, 'Hecke ' * 200)
+
+    # assert
+    assert operations['substitutions'] == 800
+    assert operations['insertions'] + operations['deletions'] == 0
+
+
+def test_edit_accuracy_outcome_counts():
+    """Edit distance metrics report candidate length and
+    operations, but not for free end gaps applied"""
+
+    # arrange
+    ctx = EvalContext('der faule Fuchs', 'Seite 12 der faule Fnchs')
+
+    # act
+    outcome = MetricCA().calculate(ctx)
+    outcome_gaps = metric_from_spec('CCA:gaps=free').calculate(ctx)
+
+    # assert
+    assert outcome.n_candidate == 24
+    assert sum(outcome.operations.values()) == outcome.diff
+    assert outcome.operations['insertions'] == 9
+    assert outcome_gaps.operations is None
+
+
+def test_semi_global_distance_partial_groundtruth():
+    """Candidate text not covered by groundtruth
+    doesn't count as edits"""
+
+    assert semi_global_distance('faule Fuchs', 'der faule Fnchs springt') == (1, 11)
+    assert semi_global_distance('der faule Fuchs springt', 'faule Fuchs') == (0, 11)
+
+
+@pytest.mark.parametrize("gaps,candidate,expected", [
+    ('global', 'Seite 12 der faule Fuchs springt über den Zaun', 0.0),
+    ('free', 'Seite 12 der faule Fuchs springt über den Zaun', 100.0),
+    ('auto', 'Seite 12 der faule Fuchs springt über den Zaun', 100.0),
+    ('auto', 'der faule Fnchs springt über den Zaun', 95.65),
+    ('auto', 'xxxxxxxxxxxxxx Seite 12 ein grauer Hund lief', 0.0)])
+def test_metric_from_spec_gaps(gaps, candidate, expected):
+    """Free end gaps make partial groundtruth score
+    sensibly, auto only applies them if text
+    is considerably shorter but matches well"""
+
+    # arrange
+    metric = metric_from_spec(f'CCA:gaps={gaps}')
+    metric.input_reference = 'der faule Fuchs springt'
+    metric.input_candidate = candidate
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.gaps == gaps
+    assert metric.value == approx(expected, abs=0.01)
+
+
+@pytest.mark.parametrize("units,expected", [
+    ('codepoints', 60.0),
+    ('graphemes', 75.0)])
+def test_metric_from_spec_units(units, expected):
+    """Decomposed 'ü' counts as two codepoints, but as
+    one user-perceived char only, so a misread 'ü'
+    makes a single error with grapheme clusters"""
+
+    # arrange
+    metric = metric_from_spec(f'CCA:units={units}')
+    metric.input_reference = 'Thür'
+    metric.input_candidate = 'Thir'
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.units == units
+    assert metric.value == approx(expected)
+
+
+def test_best_window_snippet():
+    """Window of candidate tokens matching snippet best"""
+
+    candidate = 'Kapitel 1 der faule Fnchs springt über den Zaun und lief'.split()
+
+    assert best_window('der faule Fuchs springt'.split(), candidate) == (2, 6, 1)
+
+
+def test_metric_word_accuracy_gaps_partial_candidate():
+    """Only covered reference tokens count if
+    candidate transcribes part of reference"""
+
+    # arrange
+    metric = metric_from_spec('WWA:gaps=free')
+    metric.input_reference = 'Kapitel 1 der faule Fuchs springt über den Zaun'
+    metric.input_candidate = 'der faule Fnchs springt'
+
+    # act
+    metric.calc()
+
+    # assert
+    assert metric.gaps_applied
+    assert metric.n_ref == 4
+    assert metric.value == approx(75.0)
+
+
+def test_metrics_share_eval_context():
+    """Metrics with same preprocessing do it only
+    once per entry and calculate same values"""
+
+    # arrange
+    reference = 'der faule Fuchs , springt über den Zaun'
+    candidate = 'der faule Fnchs springt über den Zaun .'
+    ctx = EvalContext(reference, candidate)
+    metrics = [MetricCA(), MetricWA(), MetricBoW(), metric_from_spec('WWA:drop=punct')]
+    uncached = [MetricCA(), MetricWA(), MetricBoW(), metric_from_spec('WWA:drop=punct')]
+
+    # act
+    outcomes = [_m.calculate(ctx) for _m in metrics]
+    for _plain in uncached:
+        _plain.input_reference = reference
+        _plain.input_candidate = candidate
+        _plain.calc()
+
+    # assert
+    assert ctx.n_hits == 1
+    assert [o.value for o in outcomes] == [m.value for m in uncached]
+    assert outcomes[1].data_reference is outcomes[2].data_reference
+
+
+def test_metric_calculate_leaves_metric_untouched():
+    """Stateless calculation, outcome carries value,
+    reference units and diagnostics"""
+
+    # arrange
+    metric = metric_from_spec('CCA:gaps=free')
+    ctx = EvalContext('faule Fuchs', 'der faule Fnchs springt')
+
+    # act
+    outcome = metric.calculate(ctx)
+    entry_metric = metric.with_outcome(outcome)
+
+    # assert
+    assert metric.value is None
+    assert outcome.n_ref == 11
+    assert outcome.diff == 1
+    assert outcome.diagnostics == {'gaps_applied': True}
+    assert entry_metric.value == approx(outcome.value)
+    assert entry_metric.gaps_applied
+
+
+def test_metrics_without_reference_units():
+    """Reference without units (i.e. only dropped tokens)
+    mustn't break calculation"""
+
+    assert character_accuracy('', '') == (100, 0, 0)
+    assert bag_of_tokens([], ['Fuchs']) == (100, 0, 0)
+    assert token_based([], ['Fuchs']) == (0, 1, 0)
+
+
+def test_score_from_spec_combine():
+    """Composite score is weighted sum of metric values"""
+
+    # arrange
+    score = score_from_spec('0.6*CCA + 0.4 * WWA')
+    metric_ca = MetricCA()
+    metric_ca.value = 90.0
+    metric_ca.n_ref = 100
+    metric_wa = MetricWA()
+    metric_wa.value = 50.0
+    metric_wa.n_ref = 20
+
+    # act
+    score.combine([metric_ca, metric_wa])
+
+    # assert
+    assert score.label == 'Score'
+    assert score.weights == {'CCA': 0.6, 'WWA': 0.4}
+    assert score.value == approx(74.0)
+    assert score.n_ref == 100
+
+
+def test_score_from_spec_compat_and_unit_range():
+    """Compatibility metrics can be weighted, IR metrics
+    ranging 0 - 1 are scaled like all others"""
+
+    # arrange
+    score = score_from_spec('0.5*WERoi + 0.5*IRFM')
+    metric_wer = MetricOcrevalWERBag()
+    (metric_wer.value, metric_wer.n_ref) = (10.0, 20)
+    metric_fm = metric_from_spec('IRFM')
+    (metric_fm.value, metric_fm.n_ref) = (0.9, 20)
+
+    # act
+    score.combine([metric_wer, metric_fm])
+
+    # assert
+    assert score.value == approx(50.0)
+
+
+@pytest.mark.parametrize("spec,message", [
+    ('0.6*XYZ', "unknown metric"),
+    ('0.6*CCA + 0.4', "invalid score term"),
+    ('x*CCA', "invalid score weight"),
+    ('0.6*CCA + 0.4*CCA', "weighted twice")])
+def test_score_from_spec_invalid(spec, message):
+    with pytest.raises(ValueError) as err:
+        score_from_spec(spec)
+    assert message in str(err.value)
+
+
+def test_score_missing_metric():
+    """Score can't be combined without weighted metric"""
+
+    score = score_from_spec('0.6*CCA + 0.4*WWA')
+
+    with pytest.raises(ValueError) as err:
+        score.validate(['CCA', 'CLA'])
+    assert "['WWA'] not calculated" in str(err.value)
@@ -0,0 +1,57 @@
+# -*- coding: utf-8 -*-
+"""METS Test Module"""
+
+import pytest
+
+from digital_eval.mets import (
+    find_mets,
+    page_year,
+    read_mets_year,
+)
+
+METS = """<?xml version="1.0" encoding="UTF-8"?>
+<mets:mets xmlns:mets="http://www.loc.gov/METS/" xmlns:mods="http://www.loc.gov/mods/v3">
+<mets:dmdSec ID="dmd1"><mets:mdWrap MDTYPE="MODS"><mets:xmlData><mods:mods>
+<mods:originInfo eventType="digitization"><mods:dateCaptured>2021</mods:dateCaptured></mods:originInfo>
+<mods:originInfo>
+<mods:dateIssued>[ca. 1790]</mods:dateIssued>
+<mods:dateIssued keyDate="yes">1789</mods:dateIssued>
+</mods:originInfo>
+</mods:mods></mets:xmlData></mets:mdWrap></mets:dmdSec>
+</mets:mets>
+"""
+
+
+@pytest.fixture(name="work_dir")
+def _fixture_work_dir(tmp_path):
+    _dir_pages = tmp_path / 'work01' / 'FULLTEXT'
+    _dir_pages.mkdir(parents=True)
+    (tmp_path / 'work01' / 'work01.xml').write_text(METS, encoding='utf-8')
+    (_dir_pages / 'page01.xml').write_text('<alto/>', encoding='utf-8')
+    return tmp_path / 'work01'
+
+
+def test_find_mets_above_page(work_dir):
+    path_page = work_dir / 'FULLTEXT' / 'page01.xml'
+
+    assert find_mets(str(path_page)) == str(work_dir / 'work01.xml')
+    assert find_mets(str(path_page), max_levels=0) is None
+
+
+def test_read_mets_year_prefers_key_date(work_dir):
+    assert read_mets_year(str(work_dir / 'work01.xml')) == 1789
+
+
+def test_read_mets_year_broken_mets(tmp_path, capsys):
+    path_mets = tmp_path / 'work01.xml'
+    path_mets.write_text(METS[:200], encoding='utf-8')
+
+    assert read_mets_year(str(path_mets)) is None
+    assert '[WARN ] work01.xml' in capsys.readouterr().out
+
+
+def test_page_year_without_mets(tmp_path):
+    path_page = tmp_path / 'page01.xml'
+    path_page.write_text('<alto/>', encoding='utf-8')
+
+    assert page_year(str(path_page)) is None
@@ -15,13 +15,29 @@ from digital_eval.evaluation import (
 )
 
 from digital_eval.model import (
+    Separators,
+    separators_from_spec,
     to_pieces,
+    to_alto,
+    to_page,
+    validate_alto,
+    validate_page,
+    write_alto,
+    write_page,
     PieceType
 )
 
+from xml.dom.minidom import (
+    parseString,
+)
+
 from .conftest import (
     TEST_RES_DIR,
 )
+from .synthetic import (
+    SyntheticPage,
+    write_pair,
+)
 
 
 @pytest.fixture(name='page_gt_type_art_filename')
@@ -273,3 +289,242 @@ def test_pieces_type_with_contains_relation():
     assert line1.type == PieceType.LINE
     assert word1 in line1 and word1 in region1
     assert word1.type == PieceType.WORD
+
+
+def test_write_alto_roundtrip_from_page(tmp_path):
+    """PAGE data written as ALTO V4 keeps structure,
+    text and polygon coordinates"""
+
+    # arrange
+    ocr_path = join(TEST_RES_DIR, 'candidate/frk_page/urn+nbn+de+gbv+3+1-115907-p0042-0_ger.xml')
+    page_piece = to_pieces(ocr_path)
+    path_alto = tmp_path / 'page.alto.xml'
+
+    # act
+    write_alto(page_piece, path_alto)
+    alto_piece = to_pieces(str(path_alto))
+
+    # assert
+    assert alto_piece.type == PieceType.PAGE
+    assert len(alto_piece.pieces) == 3
+    assert alto_piece.pieces[2].pieces[0].transcription == 'und erklaͤret die Schrift nicht nur al⸗'
+    assert alto_piece.transcription == page_piece.transcription
+    alto_data = path_alto.read_text(encoding='utf-8')
+    assert 'xmlns="http://www.loc.gov/standards/alto/ns-v4#"' in alto_data
+    assert '<Polygon POINTS="111,1655 81,1655 80,1757 111,1757"/>' in alto_data
+
+
+def test_write_alto_word_confidence(tmp_path):
+    """Word confidences are read from and written as WC"""
+
+    # arrange
+    ocr_path = join(TEST_RES_DIR, 'groundtruth/alto/1667522809_J_0073_0001_375x2050_2325x9550.xml')
+    page_piece = to_pieces(ocr_path)
+    first_word = page_piece.pieces[0].pieces[0].pieces[0]
+    first_word.confidence = 0.875
+    path_alto = tmp_path / 'page.alto.xml'
+
+    # act
+    write_alto(page_piece, path_alto)
+    alto_piece = to_pieces(str(path_alto))
+
+    # assert
+    assert alto_piece.pieces[0].id == 'block_27'
+    assert alto_piece.subject == page_piece.subject
+    assert alto_piece.pieces[0].pieces[0].pieces[0].confidence == 0.875
+    assert alto_piece.pieces[0].pieces[0].pieces[1].confidence == 0.95
+
+
+def test_to_pieces_alto_invalid_word_confidence(tmp_path):
+    """WC with comma is no confidence, but word is kept"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001', 'alto')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace(' CONTENT="faule"', ' WC="0,95" CONTENT="faule"'), encoding='utf-8')
+
+    # act
+    piece = to_pieces(str(path_gt))
+
+    # assert
+    (_, word, _) = piece.pieces[0].pieces[0].pieces
+    assert word.transcription == 'faule'
+    assert word.confidence is None
+
+
+def test_to_pieces_page_invalid_word_confidence(tmp_path):
+    """conf with comma is no confidence, but word is kept"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001')
+    _xml = path_gt.read_text(encoding='utf-8')
+    path_gt.write_text(_xml.replace('<pc:TextEquiv>', '<pc:TextEquiv conf="0,95">', 1), encoding='utf-8')
+
+    # act
+    piece = to_pieces(str(path_gt))
+
+    # assert
+    (word, _, _) = piece.pieces[0].pieces[0].pieces
+    assert word.transcription == 'der'
+    assert word.confidence is None
+
+
+def test_write_page_roundtrip_from_alto(tmp_path):
+    """ALTO data written as PAGE 2019 keeps regions in
+    reading order, line and word text and confidences"""
+
+    # arrange
+    ocr_path = join(TEST_RES_DIR, 'groundtruth/alto/1667522809_J_0073_0001_375x2050_2325x9550.xml')
+    alto_piece = to_pieces(ocr_path)
+    path_page = tmp_path / 'page.xml'
+
+    # act
+    write_page(alto_piece, path_page, image_name='1667522809_J_0073_0001.jpg')
+    page_piece = to_pieces(str(path_page))
+
+    # assert
+    assert page_piece.id == '1667522809_J_0073_0001.jpg'
+    assert [r.id for r in page_piece.pieces] == [r.id for r in alto_piece.pieces]
+    assert page_piece.pieces[0].pieces[0].transcription == 'Neueſte Ereigniſſe.'
+    assert page_piece.pieces[0].pieces[0].pieces[0].confidence == 0.96
+    assert page_piece.transcription == alto_piece.transcription
+    page_data = path_page.read_text(encoding='utf-8')
+    assert '<pc:RegionRefIndexed index="0" regionRef="block_27"/>' in page_data
+    assert '<pc:Unicode>Neueſte Ereigniſſe.</pc:Unicode>' in page_data
+
+
+@pytest.mark.parametrize("ocr_path", [
+    'groundtruth/page/1667522809_J_0001_0002.art.gt.xml',
+    'candidate/frk_alto/1667522809_J_0073_0512_01.xml'])
+def test_written_output_is_valid(ocr_path):
+    """Duplicate IDs and lines without words from
+    source data must not make output invalid"""
+
+    page_piece = to_pieces(join(TEST_RES_DIR, ocr_path))
+
+    assert validate_alto(to_alto(page_piece)) == []
+    assert validate_page(to_page(page_piece)) == []
+
+
+def test_validate_page_errors():
+    """Report structural problems with their location"""
+
+    # arrange
+    document = parseString(
+        '<pc:PcGts xmlns:pc="http://schema.primaresearch.org/PAGE/gts/pagecontent/2019-07-15">'
+        '<pc:Metadata><pc:Creator>me</pc:Creator></pc:Metadata>'
+        '<pc:Page imageFilename="p.jpg" imageWidth="100">'
+        '<pc:ReadingOrder><pc:OrderedGroup id="ro"><pc:RegionRefIndexed index="0" regionRef="r2"/>'
+        '</pc:OrderedGroup></pc:ReadingOrder>'
+        '<pc:TextRegion id="r1"><pc:TextEquiv conf="1.2"><pc:Unicode>a</pc:Unicode></pc:TextEquiv>'
+        '<pc:Coords points="0,0 10,x"/></pc:TextRegion></pc:Page></pc:PcGts>')
+
+    # act
+    errors = validate_page(document)
+
+    # assert
+    assert "Metadata: missing Created" in errors
+    assert "Page: missing attribute imageHeight" in errors
+    assert "ReadingOrder: unknown regionRef 'r2'" in errors
+    assert "TextRegion@id=r1: Coords must come first" in errors
+    assert "TextEquiv: conf='1.2' not in [0,1]" in errors
+    assert "Coords: invalid points='0,0 10,x'" in errors
+
+
+def test_write_alto_validate_rejects_invalid(tmp_path):
+    """Don't write invalid data if validation requested"""
+
+    # arrange
+    page_piece = to_pieces(join(TEST_RES_DIR, 'groundtruth/alto/1667522809_J_0073_0001_375x2050_2325x9550.xml'))
+    page_piece.pieces[0].pieces[0].pieces[0].confidence = 96.0
+    path_alto = tmp_path / 'page.alto.xml'
+
+    # act
+    with pytest.raises(RuntimeError) as err:
+        write_alto(page_piece, path_alto, validate=True)
+
+    # assert
+    assert "String@ID=string_228: WC='96.000' not in [0,1]" in str(err.value)
+    assert not path_alto.exists()
+
+
+def test_write_page_keeps_page_metadata(tmp_path):
+    """PAGE metadata carried over as it is, but
+    LastChange and own processing step updated"""
+
+    # arrange
+    ocr_path = join(TEST_RES_DIR, 'candidate/frk_page/urn+nbn+de+gbv+3+1-115907-p0042-0_ger.xml')
+    page_piece = to_pieces(ocr_path)
+    path_page = tmp_path / 'page.xml'
+
+    # act
+    write_page(page_piece, path_page, step_description='frame')
+    written = to_pieces(str(path_page))
+
+    # assert
+    steps = written.metadata.processing_steps
+    assert steps[0] == ('2022-02-28T09:46:03', 'created', 'OCR-D/core 2.26.0')
+    assert steps[1][1:] == ('preprocessing/optimization/binarization', 'ocrd-olena-binarize')
+    assert steps[-1] == ('n.a.', 'frame', 'digital-eval')
+    page_data = path_page.read_text(encoding='utf-8')
+    assert '<pc:Label value="sauvola-ms-split" type="impl"/>' in page_data
+    assert '<pc:LastChange>2022-02-28T09:46:03</pc:LastChange>' not in page_data
+
+
+def test_write_alto_keeps_alto_description(tmp_path):
+    """ALTO processing information carried over and extended,
+    source image name propagated if converted to PAGE"""
+
+    # arrange
+    ocr_path = join(TEST_RES_DIR, 'candidate/frk_alto/1667522809_J_0001_0002.xml')
+    alto_piece = to_pieces(ocr_path)
+    path_alto = tmp_path / 'page.alto.xml'
+    path_page = tmp_path / 'page.xml'
+
+    # act
+    write_alto(alto_piece, path_alto, step_description='reorder')
+    written = to_pieces(str(path_alto))
+    write_page(written, path_page)
+
+    # assert
+    steps = written.metadata.processing_steps
+    assert steps[0] == ('n.a.', 'ocr', 'tesseract 5.1.0-70-g0df5')
+    assert steps[1][1:] == ('reorder', 'digital-eval')
+    page_data = path_page.read_text(encoding='utf-8')
+    assert 'imageFilename="/eval/media/1667522809_J_0001/1667522809_J_0001_0002.tif"' in page_data
+    assert 'name="ocr" value="tesseract 5.1.0-70-g0df5"' in page_data
+    assert 'name="reorder" value="digital-eval"' in page_data
+
+
+@pytest.mark.parametrize(['spec', 'expected'],
+                         [('line=newline', Separators(line='\n')),
+                          ('word=none:region=tab', Separators(word='', region='\t')),
+                          ('region=\\n\\n', Separators(region='\n\n')),
+                          ('line= | ', Separators(line=' | '))])
+def test_separators_from_spec(spec, expected):
+    assert separators_from_spec(spec) == expected
+
+
+@pytest.mark.parametrize('spec', ['page=newline', 'line', 'line:region=none'])
+def test_separators_from_spec_invalid(spec):
+    with pytest.raises(ValueError):
+        separators_from_spec(spec)
+
+
+def test_piece_text_by_separators(tmp_path):
+    """Words, lines and regions joined by separators
+    of their level, transcription joins all by space"""
+
+    # arrange
+    page = SyntheticPage([[['der', 'faule', 'Fuchs'], ['springt']], [['die', 'Hecke']]])
+    (_, path_gt) = write_pair(page, page, tmp_path, tmp_path, 'page0001')
+    piece = to_pieces(str(path_gt))
+
+    # act
+    text = piece.text(separators_from_spec('word=none:line=newline:region=|'))
+
+    # assert
+    assert text == 'derfauleFuchs\nspringt|dieHecke'
+    assert piece.transcription == 'der faule Fuchs springt die Hecke'
@@ -0,0 +1,134 @@
+# -*- coding: utf-8 -*-
+"""Text Normalization Test Module"""
+
+import pytest
+
+from digital_eval.normalization import (
+    apply_equivalences,
+    expand_abbreviations,
+    normalize_text,
+    parse_normalizations,
+    read_abbreviations,
+    read_equivalences,
+)
+
+
+def test_normalize_long_s():
+    assert normalize_text('Geſchichte des Hauſes', ['long-s']) == 'Geschichte des Hauses'
+
+
+def test_normalize_nothing():
+    assert normalize_text('Geſchichte', []) == 'Geſchichte'
+
+
+@pytest.mark.parametrize("normalization,expected", [
+    ('historical-glyphs', 'Müller soll ﬁnden Äpfel'),
+    ('historical-glyphs-mufi', 'Müller soll finden Äpfel')])
+def test_normalize_historical_glyphs(normalization, expected):
+    """Basic table folds long s, r rotunda and combining
+    e umlauts, MUFI table ligatures, too"""
+
+    assert normalize_text('Mu\u0364lleꝛ ſoll ﬁnden A\u0364pfel', [normalization]) == expected
+
+
+def test_parse_normalizations_unknown():
+    with pytest.raises(ValueError) as err:
+        parse_normalizations('long-s, umlauts')
+    assert "unknown normalization ['umlauts']" in str(err.value)
+
+
+@pytest.mark.parametrize("normalization,expected", [
+    ('superscripts', 'Haus1) und H2O*) Gott†'),
+    ('drop-superscripts', 'Haus) und HO*) Gott†'),
+    ('drop-footnote-markers', 'Haus und H₂O Gott')])
+def test_normalize_superscripts_and_footnote_markers(normalization, expected):
+    assert normalize_text('Haus¹) und H₂O*) Gott†', [normalization]) == expected
+
+
+@pytest.mark.parametrize("normalization,text,expected", [
+    ('roman-ascii', 'Cap. Ⅻ, Seite ⅸ', 'Cap. XII, Seite ix'),
+    ('roman-unicode', 'Cap. XII, Seite ix', 'Cap. Ⅻ, Seite ⅸ'),
+    ('roman-unicode', 'Cap. XIII, Mix', 'Cap. XIII, Mix')])
+def test_normalize_roman_numerals(normalization, text, expected):
+    """Only standalone numerals with unicode
+    counterpart get mapped to unicode"""
+
+    assert normalize_text(text, [normalization]) == expected
+
+
+def test_expand_abbreviations_from_file(tmp_path):
+    """Abbreviations get expanded, longer ones first,
+    regardless of composed or decomposed chars"""
+
+    # arrange
+    path_abbr = tmp_path / 'abbreviations.tsv'
+    path_abbr.write_text('# early modern\n⁊\tetc.\nDr\u0303\tDoctor\n\nD\tDie\n', encoding='utf-8')
+
+    # act
+    abbreviations = read_abbreviations(str(path_abbr))
+
+    # assert
+    assert abbreviations == {'⁊': 'etc.', 'Dr\u0303': 'Doctor', 'D': 'Die'}
+    assert expand_abbreviations('Dr\u0303 Faust ⁊ D Welt', abbreviations) == 'Doctor Faust etc. Die Welt'
+
+
+def test_expand_abbreviations_word_boundaries():
+    """Abbreviations don't match inside words,
+    so 'u.' gets expanded, but not within 'zu.'"""
+
+    # arrange
+    abbreviations = {'u.': 'und', 'D': 'Die', '⁊': 'etc.'}
+
+    # act
+    result = expand_abbreviations('Hase u. Igel kamen zu. D Welt ⁊c.', abbreviations)
+
+    # assert
+    assert result == 'Hase und Igel kamen zu. Die Welt etc.c.'
+
+
+def test_read_abbreviations_malformed(tmp_path):
+    path_abbr = tmp_path / 'abbreviations.tsv'
+    path_abbr.write_text('⁊ etc.\n', encoding='utf-8')
+    with pytest.raises(ValueError) as err:
+        read_abbreviations(str(path_abbr))
+    assert "abbreviations.tsv:1: expected 'abbreviation<TAB>expansion'" in str(err.value)
+
+
+@pytest.mark.parametrize("name,content", [
+    ('equivalences.tsv', '# OCR-D level 2\n„\t"\n–\t-\nß\tss\n&\tund\nvnd\tund\n'),
+    ('equivalences.json', '{"„": "\\"", "–": "-", "ß": "ss", "&": "und", "vnd": "und"}')])
+def test_apply_equivalences_from_file(tmp_path, name, content):
+    """Variants get replaced in TSV or JSON alike, words
+    of several letters as whole tokens only"""
+
+    # arrange
+    path_table = tmp_path / name
+    path_table.write_text(content, encoding='utf-8')
+
+    # act
+    equivalences = read_equivalences(str(path_table))
+
+    # assert
+    assert equivalences == {'„': '"', '–': '-', 'ß': 'ss', '&': 'und', 'vnd': 'und'}
+    assert apply_equivalences('„Straße“ – Haus & Hof vnd vndt', equivalences) == '"Strasse“ - Haus und Hof und vndt'
+
+
+def test_read_equivalences_malformed(tmp_path):
+    path_table = tmp_path / 'equivalences.json'
+    path_table.write_text('["ß", "ss"]', encoding='utf-8')
+    with pytest.raises(ValueError) as err:
+        read_equivalences(str(path_table))
+    assert "expected JSON object of variants" in str(err.value)
+
+
+def test_normalize_line_breaks_only():
+    assert normalize_text('der  faule\r\nFuchs\n\nspringt', ['line-breaks']) == 'der  faule Fuchs springt'
+
+
+@pytest.mark.parametrize("text,expected", [
+    ('der Fu⸗\nchs springt', 'der Fuchs springt'),
+    ('der Fu¬ \r\n chs springt', 'der Fuchs springt'),
+    ('von Nord-\nSüd', 'von Nord-\nSüd'),
+    ('der Fu⸗ chs', 'der Fu⸗ chs')])
+def test_normalize_dehyphenate_line_end_only(text, expected):
+    assert normalize_text(text, ['dehyphenate']) == expected
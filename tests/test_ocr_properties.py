@@ -0,0 +1,143 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation Metric Properties Test Module
+
+Check invariants all metrics must keep, whatever their
+inputs, on random unicode texts with fixed seed: perfect
+value on equal texts, bounds of their range, symmetry
+where applicable and monotonicity under added errors
+"""
+
+import math
+import random
+
+import pytest
+
+from digital_eval.compat import (
+    ERROR_RATES,
+)
+from digital_eval.evaluation import (
+    SELF_CHECK_SKIP,
+    perfect_value,
+)
+from digital_eval.metrics import (
+    METRICS,
+    METRICS_IR,
+    METRICS_UNIT_RANGE,
+    metric_from_spec,
+)
+
+# plain, accented, decomposed and historical chars of
+# several scripts, punctuation and lots of whitespace
+PROPERTY_ALPHABET = (list('abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789')
+                     + list('äöüÄÖÜßſé') + ['uͤ', 'ä', '̈']
+                     + list('αβγδεΩ') + list('אבגד') + list('.,;:!?-()„“⸗')
+                     + [' '] * 12 + ['\n'])
+# char missing in alphabet to put errors with
+PROPERTY_ERROR = '¤'
+PROPERTY_SEED = 4711
+PROPERTY_CASES = 40
+# metrics comparing texts, with some of their options
+PROPERTY_METRICS = [_l for _l in METRICS if _l not in SELF_CHECK_SKIP] + [
+    'CCA:units=graphemes', 'CCA:length=max', 'WWA:tokenize=split-punct', 'BoWf:similarity=cosine',
+    'WBoW:fuzzy=1', 'CCA:report=error-rate', 'WWA:report=error-rate']
+# metrics which don't care which text is reference
+PROPERTY_SYMMETRIC = ['CCA:length=max', 'CLA:length=max', 'WWA:length=max', 'ChrF:beta=1',
+                      'BoWf', 'BoWf:similarity=cosine', 'IRFM']
+# metrics which never get better if errors are added
+PROPERTY_MONOTONE = ['CCA', 'CLA', 'LCA', 'WWA', 'WBoW', 'BoWf', 'ChrF', 'IRRec',
+                     'CCA:units=graphemes', 'WWA:tokenize=split-punct']
+
+
+def _text(rng, max_length=60, min_length=1) -> str:
+    """Random text, never empty unless min_length is 0"""
+
+    _text = ''.join(rng.choice(PROPERTY_ALPHABET) for _ in range(rng.randint(min_length, max_length)))
+    return _text.strip() or ('x' if min_length else '')
+
+
+def _value(spec, reference, candidate) -> float:
+    _metric = metric_from_spec(spec)
+    _metric.input_reference = reference
+    _metric.input_candidate = candidate
+    _metric.calc()
+    return _metric.value
+
+
+def _bounds(metric) -> tuple:
+    if metric.label in METRICS_UNIT_RANGE:
+        return (0.0, 1.0)
+    if metric.label in ERROR_RATES:
+        return (0.0, math.inf)
+    return (0.0, 100.0)
+
+
+@pytest.mark.parametrize('spec', PROPERTY_METRICS)
+def test_metric_identity(spec):
+    """Equal texts get perfect value, except IR metrics
+    for texts without any terms, i.e. only stopwords"""
+
+    rng = random.Random(PROPERTY_SEED)
+    expected = perfect_value(metric_from_spec(spec))
+    for _ in range(PROPERTY_CASES):
+        text = _text(rng)
+        if spec in METRICS_IR and not metric_from_spec(spec).terms(text):
+            continue
+        assert _value(spec, text, text) == pytest.approx(expected), text
+
+
+@pytest.mark.parametrize('spec', PROPERTY_METRICS)
+def test_metric_bounds(spec):
+    """Values stay within metric's range, also for empty
+    candidates, error rates may exceed 100 though"""
+
+    rng = random.Random(PROPERTY_SEED)
+    (lower, upper) = _bounds(metric_from_spec(spec))
+    for _ in range(PROPERTY_CASES):
+        (reference, candidate) = (_text(rng), _text(rng, min_length=0))
+        value = _value(spec, reference, candidate)
+        assert value is not None and not math.isnan(value), (reference, candidate)
+        assert lower <= value <= upper, (reference, candidate)
+
+
+@pytest.mark.parametrize(['reference', 'candidate'], [
+    ('und der die', 'Fuchs'),
+    ('und der die', ''),
+    ('.,;', 'der'),
+    ('\u0308', 'a'),
+    ('x', '')])
+@pytest.mark.parametrize('spec', PROPERTY_METRICS)
+def test_metric_bounds_edge_cases(spec, reference, candidate):
+    """References without terms, punctuation only
+    or single combining mark don't break metrics"""
+
+    (lower, upper) = _bounds(metric_from_spec(spec))
+    value = _value(spec, reference, candidate)
+    assert value is not None and not math.isnan(value)
+    assert lower <= value <= upper
+
+
+@pytest.mark.parametrize('spec', PROPERTY_SYMMETRIC)
+def test_metric_symmetry(spec):
+    rng = random.Random(PROPERTY_SEED)
+    for _ in range(PROPERTY_CASES):
+        (text_a, text_b) = (_text(rng), _text(rng))
+        assert _value(spec, text_a, text_b) == pytest.approx(_value(spec, text_b, text_a)), (text_a, text_b)
+
+
+@pytest.mark.parametrize('spec', PROPERTY_MONOTONE)
+def test_metric_monotone_under_errors(spec):
+    """Substituting more and more chars of reference
+    never improves candidate's value"""
+
+    rng = random.Random(PROPERTY_SEED)
+    for _ in range(PROPERTY_CASES // 4):
+        reference = _text(rng)
+        positions = [_i for (_i, _c) in enumerate(reference) if not _c.isspace()]
+        rng.shuffle(positions)
+        candidate = list(reference)
+        previous = _value(spec, reference, reference)
+        for _position in positions:
+            candidate[_position] = PROPERTY_ERROR
+            value = _value(spec, reference, ''.join(candidate))
+            assert value <= previous + 1e-9, (reference, ''.join(candidate))
+            previous = value
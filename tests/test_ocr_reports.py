@@ -0,0 +1,87 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation Report Formats Test Module
+
+Render reports of fixture corpus and compare them with
+golden files in tests/resources/golden. If output changes
+on purpose, rewrite golden files with
+
+    pytest tests/test_ocr_reports.py --update-golden
+
+and review their diff before commit.
+"""
+
+import os
+import re
+import shutil
+
+import pytest
+
+from digital_eval.cli import (
+    _main,
+)
+
+from digital_eval.sinks import (
+    CsvSink,
+)
+
+from .conftest import (
+    TEST_RES_DIR,
+    assert_golden,
+)
+
+# candidate and groundtruth pages of fixture corpus
+GOLDEN_PAGES = [
+    'urn+nbn+de+gbv+3+1-115907-p0042-0_ger',
+    'urn+nbn+de+gbv+3+1-126343-p0285-7_ger',
+    'urn+nbn+de+gbv+3+1-138193-p0904-0_ger',
+]
+
+
+@pytest.fixture(name='golden_corpus')
+def _fixture_golden_corpus(tmp_path):
+    """Fixture corpus with candidates below a directory
+    named like groundtruth root, as required by aggregation"""
+
+    path_dir_c = tmp_path / 'media' / 'odem' / 'ger'
+    path_dir_c.mkdir(parents=True)
+    path_dir_gt = tmp_path / 'groundtruth' / 'odem' / 'ger'
+    path_dir_gt.mkdir(parents=True)
+    for _name in GOLDEN_PAGES:
+        shutil.copy(os.path.join(TEST_RES_DIR, 'candidate', 'frk_page', f'{_name}.xml'), path_dir_c)
+        shutil.copy(os.path.join(TEST_RES_DIR, 'groundtruth', 'page', f'{_name}.gt.xml'), path_dir_gt)
+    return tmp_path
+
+
+def _stable(text, tmp_path) -> str:
+    """Replace data varying between runs, i.e.
+    temporary paths, date and resource usage"""
+
+    _text = text.replace(str(tmp_path), '<tmp>')
+    _text = re.sub(r'\d+\.\d+ (s|MiB)\b', r'<n> \1', _text)
+    _text = re.sub(r'"(wall_seconds|cpu_seconds|peak_rss_mib|peak_rss_worker_mib)": ([\d.]+|null)',
+                   r'"\1": <n>', _text)
+    return re.sub(r'\d{4}-\d{2}-\d{2}', '<date>', _text)
+
+
+def test_report_formats_golden(golden_corpus, update_golden, capsys):
+    """Summary printed, results file, csv sink and html
+    report of fixture corpus match their golden files"""
+
+    # arrange
+    path_results = golden_corpus / 'results.json'
+    path_html = golden_corpus / 'report.html'
+    path_csv = golden_corpus / 'results.csv'
+
+    # act
+    _main(str(golden_corpus / 'media' / 'odem'), str(golden_corpus / 'groundtruth' / 'odem'), 0, None,
+          path_results=str(path_results), path_html=str(path_html), sinks=[CsvSink(str(path_csv))])
+
+    # assert
+    outputs = {
+        'summary.txt': capsys.readouterr().out,
+        'results.json': path_results.read_text(encoding='utf-8'),
+        'report.html': path_html.read_text(encoding='utf-8'),
+        'results.csv': path_csv.read_text(encoding='utf-8'),
+    }
+    for (_name, _output) in outputs.items():
+        assert_golden(_name, _stable(_output, golden_corpus), update_golden)
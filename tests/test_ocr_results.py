@@ -0,0 +1,467 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation Results Test Module"""
+
+import json
+import math
+
+import pytest
+
+from pytest import (
+    approx
+)
+
+from digital_eval.evaluation import (
+    DocumentStatistics,
+    EvalEntry,
+    EvaluationResult,
+    Evaluator,
+    LayoutBox,
+    RegionEntry,
+)
+
+from digital_eval.metrics import (
+    MetricCA,
+)
+
+from digital_eval.errors import (
+    EvalMatchingError,
+)
+
+from digital_eval.model import (
+    BoundingBox,
+)
+
+from digital_eval.results import (
+    aggregate_baseline,
+    compare_results,
+    merge_results,
+    read_results,
+    write_html_report,
+    write_results,
+)
+
+
+def _entry(path_dir_c, sub_dir, name, value, n_ref, gt_type='n.a.'):
+    _metric = MetricCA()
+    _metric.value = value
+    _metric.diff = 10
+    _metric.n_ref = n_ref
+    entry = EvalEntry(str(path_dir_c / sub_dir / f'{name}.xml'))
+    entry.path_g = f'/data/ocr/groundtruth/odem/{sub_dir}/{name}.gt.xml'
+    entry.gt_type = gt_type
+    entry.metrics = [_metric]
+    return entry
+
+
+def test_results_roundtrip(tmp_path):
+    """Stored entries must be restored with
+    all values required for aggregation"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810, 'article')
+    entry.window = (12, 140)
+    entry.era = 'pre-1900'
+    entry.warnings = ['no reference units for WWA']
+    entry.parse_warnings = ['candidate: TextLine@ID=l1 without words, skipped']
+    path_results = tmp_path / 'results.json'
+
+    resources = {'wall_seconds': 12.5, 'cpu_seconds': 40.2, 'peak_rss_mib': 210.4, 'peak_rss_worker_mib': None}
+
+    # act
+    write_results(path_results, [entry], path_dir_c, '/data/ocr/groundtruth/odem', shard='1/2',
+                  resources=resources)
+    data = read_results(path_results)
+
+    # assert
+    assert data['shard'] == '1/2'
+    assert data['resources'] == resources
+    assert data['root_groundtruth'] == '/data/ocr/groundtruth/odem'
+    restored = data['entries'][0]
+    assert restored.path_c == entry.path_c
+    assert restored.path_g == entry.path_g
+    assert restored.gt_type == 'article'
+    assert restored.metrics[0].label == 'CCA'
+    assert restored.metrics[0].value == approx(95.70)
+    assert restored.metrics[0].n_ref == 810
+    assert restored.window == (12, 140)
+    assert restored.era == 'pre-1900'
+    assert str(restored) == str(entry)
+    assert not restored.timed_out
+    assert restored.regions is None
+    assert restored.warnings == ['no reference units for WWA']
+    assert restored.parse_warnings == ['candidate: TextLine@ID=l1 without words, skipped']
+
+
+def test_results_infinite_value_as_null(tmp_path):
+    """Infinite error rates, like dinglehopper's for
+    empty reference, are stored as valid JSON null"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', math.inf, 0)
+    path_results = tmp_path / 'results.json'
+
+    # act
+    write_results(path_results, [entry], path_dir_c, '/data/ocr/groundtruth/odem')
+    with open(path_results, encoding='utf-8') as _handle:
+        raw = json.load(_handle, parse_constant=lambda c: pytest.fail(f"invalid JSON constant {c}"))
+    data = read_results(path_results)
+
+    # assert
+    assert raw['entries'][0]['metrics'][0]['value'] is None
+    assert data['entries'][0].metrics[0].value == math.inf
+
+
+def test_results_roundtrip_operations(tmp_path):
+    """Candidate length and edit operations restored,
+    pooled over entries by aggregation"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entries = [_entry(path_dir_c, 'ger', f'page0{i}', 95.0, 100, 'article') for i in (1, 2)]
+    for (_i, _entry_i) in enumerate(entries):
+        _entry_i.metrics[0].n_candidate = 98
+        _entry_i.metrics[0].operations = {'substitutions': 2 + _i, 'insertions': 1, 'deletions': 3}
+    path_results = tmp_path / 'results.json'
+
+    # act
+    write_results(path_results, entries, path_dir_c, str(tmp_path / 'odem'))
+    restored = read_results(path_results)['entries']
+    evaluator = Evaluator(str(tmp_path / 'odem'))
+    evaluator.evaluation_entries = restored
+    evaluator.aggregate(by_metrics=[0])
+    evaluator.eval_map()
+
+    # assert
+    assert restored[1].metrics[0].n_candidate == 98
+    assert restored[1].metrics[0].operations == {'substitutions': 3, 'insertions': 1, 'deletions': 3}
+    assert evaluator.get_results()[0].operations == {'substitutions': 5, 'insertions': 2, 'deletions': 6}
+
+
+def test_results_roundtrip_statistics(tmp_path):
+    """Document statistics of both files restored,
+    those of references pooled by aggregation"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entries = [_entry(path_dir_c, 'ger', f'page0{i}', 95.0, 100) for i in (1, 2)]
+    for (_i, _entry_i) in enumerate(entries):
+        _entry_i.statistics_reference = DocumentStatistics(100, 4 + _i, 20, 1)
+        _entry_i.statistics_candidate = DocumentStatistics(98, 4, 19, 2)
+    path_results = tmp_path / 'results.json'
+
+    # act
+    write_results(path_results, entries, path_dir_c, str(tmp_path / 'odem'))
+    restored = read_results(path_results)['entries']
+    evaluator = Evaluator(str(tmp_path / 'odem'))
+    evaluator.evaluation_entries = restored
+    evaluator.aggregate(by_metrics=[0])
+    evaluator.eval_map()
+
+    # assert
+    assert restored[1].statistics_reference == DocumentStatistics(100, 5, 20, 1)
+    assert restored[1].statistics_candidate == DocumentStatistics(98, 4, 19, 2)
+    assert evaluator.get_results()[0].statistics == DocumentStatistics(200, 9, 40, 2)
+    assert evaluator.get_results()[0].n_lines == 9
+
+
+def test_write_html_report_pooled_operations(tmp_path):
+    """Edit operations of all documents summed up
+    per metric, in labels of report language"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entries = [_entry(path_dir_c, 'ger', f'page0{i}', 95.0, 100) for i in (1, 2)]
+    for (_i, _entry_i) in enumerate(entries):
+        _entry_i.metrics[0].operations = {'substitutions': 2 + _i, 'insertions': 1, 'deletions': 3}
+    path_html = tmp_path / 'report.html'
+
+    # act
+    write_html_report(path_html, entries, 'odem', lang='de')
+
+    # assert
+    report = path_html.read_text(encoding='utf-8')
+    assert '<h2>Editieroperationen</h2>' in report
+    assert '<tr><td>CCA</td><td>5</td><td>2</td><td>6</td></tr>' in report
+
+
+def test_results_roundtrip_regions(tmp_path):
+    """Metrics by region get stored and restored
+    and make rows of html report"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810)
+    (_matched, _missed) = (RegionEntry('r1', 'c7', 0.91), RegionEntry('r2'))
+    _matched.metrics = [_entry(path_dir_c, 'ger', 'page01', 97.50, 400).metrics[0]]
+    _missed.metrics = [_entry(path_dir_c, 'ger', 'page01', 0.0, 12).metrics[0]]
+    entry.regions = [_matched, _missed]
+    path_results = tmp_path / 'results.json'
+    path_html = tmp_path / 'report.html'
+
+    # act
+    write_results(path_results, [entry], path_dir_c, '/data/ocr/groundtruth/odem')
+    write_html_report(path_html, [entry])
+    restored = read_results(path_results)['entries'][0]
+
+    # assert
+    assert [(_r.id, _r.candidate_id, _r.iou) for _r in restored.regions] == [('r1', 'c7', 0.91), ('r2', None, 0.0)]
+    assert restored.regions[0].metrics[0].value == approx(97.50)
+    assert restored.regions[1].metrics[0].n_ref == 12
+    html = path_html.read_text(encoding='utf-8')
+    assert '<tr><td>r1</td><td>c7</td><td>0.91</td><td>97.50 (400)</td></tr>' in html
+    assert '<tr><td>r2</td><td>n.a.</td><td>0.00</td><td>0.00 (12)</td></tr>' in html
+
+
+def test_results_roundtrip_layout_boxes(tmp_path):
+    """Layout boxes get stored with their coordinates
+    and scores and restored"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810)
+    _line = LayoutBox('line', 'r1_l1', BoundingBox((10, 20), (310, 60)), 'r1')
+    _line.scores = {'LCA': 93.5}
+    entry.boxes = [LayoutBox('region', 'r1', BoundingBox((10, 20), (320, 400))), _line]
+    path_results = tmp_path / 'results.json'
+
+    # act
+    write_results(path_results, [entry], path_dir_c, '/data/ocr/groundtruth/odem')
+    restored = read_results(path_results)['entries'][0]
+
+    # assert
+    assert [(_b.kind, _b.id, _b.parent, _b.scores) for _b in restored.boxes] == [
+        ('region', 'r1', None, {}), ('line', 'r1_l1', 'r1', {'LCA': 93.5})]
+    assert (restored.boxes[1].bbox.p1, restored.boxes[1].bbox.p2) == ((10, 20), (310, 60))
+
+
+def test_results_roundtrip_timed_out(tmp_path):
+    """Timed out entries keep their mark, but are
+    left out when aggregating baseline"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810)
+    timed_out = EvalEntry(str(path_dir_c / 'ger' / 'page02.xml'))
+    timed_out.path_g = '/data/ocr/groundtruth/odem/ger/page02.gt.xml'
+    timed_out.timed_out = True
+    path_results = tmp_path / 'results.json'
+
+    # act
+    write_results(path_results, [entry, timed_out], path_dir_c, '/data/ocr/groundtruth/odem')
+    data = read_results(path_results)
+    results = aggregate_baseline(path_results)
+
+    # assert
+    assert [_e.timed_out for _e in data['entries']] == [False, True]
+    assert data['entries'][1].metrics == []
+    assert results[0].n_total == 1
+
+
+def test_results_merge_partials_like_single_run(tmp_path):
+    """Aggregate of merged partial results
+    must equal aggregate of one single run"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    root_gt = '/data/ocr/groundtruth/odem'
+    entries = [_entry(path_dir_c, 'eng', 'page01', 95.70, 810),
+               _entry(path_dir_c, 'ger', 'page02', 96.53, 675),
+               _entry(path_dir_c, 'ger', 'page03', 94.91, 1395)]
+    part1 = tmp_path / 'part1.json'
+    part2 = tmp_path / 'part2.json'
+    write_results(part1, entries[:1], path_dir_c, root_gt, shard='1/2')
+    write_results(part2, entries[1:], path_dir_c, root_gt, shard='2/2')
+
+    # act
+    (_, root_merged, merged) = merge_results([part2, part1])
+    evaluator = Evaluator(root_merged)
+    evaluator.evaluation_entries = merged
+    evaluator.aggregate(by_metrics=[0])
+    evaluator.eval_map()
+    single = Evaluator(root_gt)
+    single.evaluation_entries = entries
+    single.aggregate(by_metrics=[0])
+    single.eval_map()
+
+    # assert
+    assert [e.path_c for e in merged] == [e.path_c for e in entries]
+    merged_results = [r.get_defaults() for r in evaluator.get_results()]
+    single_results = [r.get_defaults() for r in single.get_results()]
+    assert merged_results == single_results
+    assert merged_results[0][1] == 3
+
+
+def test_results_merge_duplicate_entries(tmp_path):
+    """Entries contained in several partials count once"""
+
+    path_dir_c = tmp_path / 'media' / 'odem'
+    root_gt = '/data/ocr/groundtruth/odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810)
+    part1 = tmp_path / 'part1.json'
+    part2 = tmp_path / 'part2.json'
+    write_results(part1, [entry], path_dir_c, root_gt)
+    write_results(part2, [entry], path_dir_c, root_gt)
+
+    (_, _, merged) = merge_results([part1, part2])
+
+    assert len(merged) == 1
+
+
+def test_results_merge_fails_different_groundtruth(tmp_path):
+    """Partials from different groundtruth sets can't be merged"""
+
+    path_dir_c = tmp_path / 'media' / 'odem'
+    part1 = tmp_path / 'part1.json'
+    part2 = tmp_path / 'part2.json'
+    write_results(part1, [_entry(path_dir_c, 'ger', 'p1', 90.0, 100)], path_dir_c, '/data/gt/odem')
+    write_results(part2, [_entry(path_dir_c, 'ger', 'p2', 90.0, 100)], path_dir_c, '/data/gt/zd1')
+
+    with pytest.raises(RuntimeError) as err:
+        merge_results([part1, part2])
+
+    assert "doesn't match" in str(err.value)
+    assert isinstance(err.value, EvalMatchingError)
+
+
+def test_results_merge_fails_different_fingerprints(tmp_path):
+    """Partials evaluated with differing configurations
+    can't be merged, unless forced"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    (entry1, entry2) = (_entry(path_dir_c, 'ger', 'p1', 90.0, 100), _entry(path_dir_c, 'ger', 'p2', 90.0, 100))
+    evaluator = Evaluator('/data/gt/odem')
+    entry1.fingerprint = evaluator.fingerprint()
+    evaluator.normalizations = ['long-s']
+    entry2.fingerprint = evaluator.fingerprint()
+    (part1, part2) = (tmp_path / 'part1.json', tmp_path / 'part2.json')
+    write_results(part1, [entry1], path_dir_c, '/data/gt/odem')
+    write_results(part2, [entry2], path_dir_c, '/data/gt/odem')
+
+    # act
+    with pytest.raises(EvalMatchingError) as err:
+        merge_results([part1, part2])
+    (_, _, merged) = merge_results([part1, part2], force=True)
+
+    # assert
+    assert 'differing configurations' in str(err.value)
+    assert entry1.fingerprint != entry2.fingerprint
+    assert [_e.fingerprint for _e in merged] == [entry1.fingerprint, entry2.fingerprint]
+
+
+def test_write_html_report(tmp_path):
+    """Report lists each document with
+    it's metrics and spotted differences"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810)
+    entry.txt_reference = 'der faule <Fuchs>'
+    entry.txt_candidate = 'der fanle <Fuchs>'
+    path_html = tmp_path / 'report.html'
+
+    # act
+    write_html_report(path_html, [entry], 'odem')
+
+    # assert
+    report = path_html.read_text(encoding='utf-8')
+    assert '<th>CCA</th>' in report
+    assert '<td>95.70</td>' in report
+    assert 'fa<span class="diff">u</span>le &lt;Fuchs&gt;' in report
+    assert 'fa<span class="diff">n</span>le &lt;Fuchs&gt;' in report
+
+
+def test_write_html_report_german(tmp_path):
+    """Report labels and metric names translated"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entry = _entry(path_dir_c, 'ger', 'page01', 95.70, 810)
+    path_html = tmp_path / 'report.html'
+
+    # act
+    write_html_report(path_html, [entry], 'odem', lang='de')
+
+    # assert
+    report = path_html.read_text(encoding='utf-8')
+    assert '<h2>Gesamtergebnis</h2>' in report
+    assert '<th>Zeichengenauigkeit</th><td>95.70 (810)</td>' in report
+
+
+def _result(eval_key, mean):
+    _result = EvaluationResult(eval_key)
+    _result.mean = mean
+    return _result
+
+
+def test_compare_results_with_baseline():
+    """Changes below threshold don't count and error
+    rates improve when they decrease"""
+
+    # arrange
+    baseline = [_result('CCA@odem', 95.0), _result('CCA@odem/ger', 95.0),
+                _result('CER@odem', 5.0), _result('WWA@odem', 80.0)]
+    results = [_result('CCA@odem', 94.8), _result('CCA@odem/ger', 93.0),
+               _result('CER@odem', 4.0), _result('CCA@odem/eng', 99.0)]
+
+    # act
+    comparison = compare_results(results, baseline, threshold=0.5)
+
+    # assert
+    assert [(c[0], c[4]) for c in comparison] == [
+        ('CCA@odem', 'unchanged'),
+        ('CCA@odem/ger', 'regression'),
+        ('CER@odem', 'improvement')]
+    assert comparison[1][3] == approx(-2.0)
+
+
+def test_compare_results_unit_range_threshold():
+    """Threshold scales for IR metrics ranging 0 - 1"""
+
+    # arrange
+    baseline = [_result('IRFM@odem', 0.90), _result('IRRec@odem', 0.90)]
+    results = [_result('IRFM@odem', 0.88), _result('IRRec@odem', 0.898)]
+
+    # act
+    comparison = compare_results(results, baseline, threshold=0.5)
+
+    # assert
+    assert [c[4] for c in comparison] == ['regression', 'unchanged']
+
+
+def test_compare_results_micro_average():
+    """Micro means may regress while macro means don't"""
+
+    # arrange
+    baseline = [_result('CCA@odem', 90.0)]
+    results = [_result('CCA@odem', 90.0)]
+    baseline[0].micro_mean = 92.0
+    results[0].micro_mean = 88.0
+
+    # act
+    macro = compare_results(results, baseline, threshold=0.5)
+    micro = compare_results(results, baseline, threshold=0.5, average='micro')
+
+    # assert
+    assert macro[0][4] == 'unchanged'
+    assert micro[0][1:] == (92.0, 88.0, -4.0, 'regression')
+
+
+def test_aggregate_baseline(tmp_path):
+    """Baseline aggregated from stored results like any run"""
+
+    # arrange
+    path_dir_c = tmp_path / 'media' / 'odem'
+    entries = [_entry(path_dir_c, 'ger', 'page01', 90.0, 810),
+               _entry(path_dir_c, 'ger', 'page02', 80.0, 675)]
+    path_baseline = tmp_path / 'baseline.json'
+    write_results(path_baseline, entries, path_dir_c, '/data/ocr/groundtruth/odem')
+
+    # act
+    results = aggregate_baseline(path_baseline)
+
+    # assert
+    assert [r.eval_key for r in results] == ['CCA@odem', 'CCA@odem/ger']
+    assert results[0].mean == approx(85.0)
@@ -0,0 +1,189 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation Result Sinks Test Module"""
+
+import csv
+import json
+import sqlite3
+import threading
+
+import pytest
+
+from digital_eval.evaluation import (
+    EvalEntry,
+    Evaluator,
+    find_groundtruth,
+    gather_candidates,
+)
+from digital_eval.metrics import (
+    metric_from_spec,
+)
+from digital_eval.sinks import (
+    CsvSink,
+    JsonSink,
+    PrometheusSink,
+    SqliteSink,
+    StdoutSink,
+    sink_from_spec,
+)
+
+from .synthetic import (
+    synthetic_corpus,
+)
+
+
+def _evaluate(tmp_path, sinks):
+    """Evaluate synthetic corpus of 3 pages streaming
+    into sinks, like the cli does"""
+
+    synthetic_corpus(tmp_path, [0, 2, 5])
+    root_c = str(tmp_path / 'media' / 'odem')
+    root_gt = str(tmp_path / 'groundtruth' / 'odem')
+    entries = gather_candidates(root_c)
+    for _entry in entries:
+        _entry.path_g = find_groundtruth(_entry.path_c, root_c, root_gt)
+    evaluator = Evaluator(root_gt)
+    evaluator.metrics = [metric_from_spec('CCA')]
+    evaluator.sinks = sinks
+    evaluator.eval_all(entries, sequential=True)
+    evaluator.aggregate(by_metrics=[0])
+    evaluator.eval_map()
+    for _sink in sinks:
+        _sink.aggregate(evaluator.get_results())
+        _sink.close()
+    return evaluator
+
+
+@pytest.mark.parametrize(['spec', 'expected'],
+                         [('stdout', StdoutSink), ('csv:out.csv', CsvSink),
+                          ('json:out.jsonl', JsonSink), ('sqlite:out.db', SqliteSink),
+                          ('prometheus:out.prom', PrometheusSink)])
+def test_sink_from_spec(tmp_path, spec, expected):
+    """Each kind of sink by spec"""
+
+    # arrange
+    if ':' in spec:
+        spec = spec.replace(':', f':{tmp_path}/')
+
+    # act
+    sink = sink_from_spec(spec)
+
+    # assert
+    assert isinstance(sink, expected)
+    sink.close()
+
+
+@pytest.mark.parametrize('spec', ['xlsx:out.xlsx', 'csv', 'csv:', 'stdout:out.txt'])
+def test_sink_from_spec_invalid(spec):
+    """Unknown kinds and missing or needless paths"""
+
+    with pytest.raises(ValueError):
+        sink_from_spec(spec)
+
+
+def test_sink_csv(tmp_path):
+    """Row per entry as it's done, finally aggregate"""
+
+    # arrange
+    path_out = tmp_path / 'out.csv'
+
+    # act
+    _evaluate(tmp_path, [CsvSink(str(path_out))])
+
+    # assert
+    with open(path_out, encoding='utf-8') as _handle:
+        rows = list(csv.DictReader(_handle))
+    assert [_r['kind'] for _r in rows] == ['entry', 'entry', 'entry', 'aggregate', 'aggregate']
+    assert rows[0]['metric'] == 'CCA'
+    assert rows[0]['value'] == '100.0000'
+    assert [_r['key'] for _r in rows[3:]] == ['odem', 'odem/ger']
+    assert rows[3]['n_items'] == '3'
+
+
+def test_sink_json_lines(tmp_path):
+    """Entries keep their index, final event aggregates"""
+
+    # arrange
+    path_out = tmp_path / 'out.jsonl'
+
+    # act
+    _evaluate(tmp_path, [JsonSink(str(path_out))])
+
+    # assert
+    with open(path_out, encoding='utf-8') as _handle:
+        events = [json.loads(_l) for _l in _handle]
+    assert [_e['event'] for _e in events] == ['entry', 'entry', 'entry', 'aggregate']
+    assert [_e['index'] for _e in events[:3]] == [1, 2, 3]
+    assert events[0]['n_entries'] == 3
+    assert events[-1]['results'][0]['eval_key'] == 'CCA@odem'
+
+
+def test_sink_sqlite(tmp_path):
+    """Entries and aggregates queryable by sql"""
+
+    # arrange
+    path_out = tmp_path / 'out.db'
+
+    # act
+    evaluator = _evaluate(tmp_path, [SqliteSink(str(path_out))])
+
+    # assert
+    connection = sqlite3.connect(str(path_out))
+    assert connection.execute('SELECT COUNT(*) FROM entries').fetchone()[0] == 3
+    (mean, n_items) = connection.execute("SELECT mean, n_items FROM aggregates "
+                                         "WHERE eval_key = 'CCA@odem'").fetchone()
+    connection.close()
+    result = [_r for _r in evaluator.get_results() if _r.eval_key == 'CCA@odem'][0]
+    assert mean == pytest.approx(result.mean)
+    assert n_items == 3
+
+
+def test_sink_prometheus(tmp_path):
+    """Progress gauges and means with labels"""
+
+    # arrange
+    path_out = tmp_path / 'out.prom'
+
+    # act
+    _evaluate(tmp_path, [PrometheusSink(str(path_out))])
+
+    # assert
+    lines = path_out.read_text(encoding='utf-8').splitlines()
+    assert 'digital_eval_entries_done 3' in lines
+    assert 'digital_eval_entries_timed_out 0' in lines
+    assert 'digital_eval_items{metric="CCA",set="odem"} 3' in lines
+    assert not (tmp_path / 'out.prom.tmp').exists()
+
+
+def test_sink_stdout(tmp_path, capsys):
+    """Single line per entry and per aggregate"""
+
+    # act
+    _evaluate(tmp_path, [StdoutSink()])
+
+    # assert
+    lines = capsys.readouterr().out.splitlines()
+    assert len([_l for _l in lines if _l.startswith('[INFO ] (')]) == 3
+    assert any('[CCA@odem] mean' in _l for _l in lines)
+
+
+def test_sink_concurrent_entries(tmp_path):
+    """Entries from several threads don't interleave"""
+
+    # arrange
+    path_out = tmp_path / 'out.jsonl'
+    sink = JsonSink(str(path_out))
+    entry = EvalEntry('candidate.xml')
+
+    # act
+    threads = [threading.Thread(target=lambda i=i: [sink.entry(entry, i, 8) for _ in range(50)])
+               for i in range(8)]
+    for _thread in threads:
+        _thread.start()
+    for _thread in threads:
+        _thread.join()
+    sink.close()
+
+    # assert
+    with open(path_out, encoding='utf-8') as _handle:
+        events = [json.loads(_l) for _l in _handle]
+    assert len(events) == 400
@@ -0,0 +1,125 @@
+# -*- coding: utf-8 -*-
+"""OCR Evaluation Synthetic Corpora Test Module
+
+Check metrics and aggregation against scores known
+by construction of synthetic pages (see synthetic)
+"""
+
+import random
+
+import pytest
+
+from pytest import (
+    approx
+)
+
+from digital_eval.evaluation import (
+    EvalConfig,
+    Evaluator,
+    evaluate_pair,
+    find_groundtruth,
+    gather_candidates,
+)
+from digital_eval.metrics import (
+    metric_from_spec,
+)
+
+from .synthetic import (
+    SYNTH_FORMATS,
+    SYNTH_SEED,
+    synthetic_corpus,
+    synthetic_page,
+    with_errors,
+    with_permuted_regions,
+    with_punctuation,
+    write_pair,
+)
+
+
+def _evaluate(tmp_path, reference, candidate, specs, data_format='page'):
+    (path_c, path_gt) = write_pair(reference, candidate, tmp_path, tmp_path, 'page0001', data_format)
+    _config = EvalConfig(metrics=[metric_from_spec(_s) for _s in specs])
+    return [_m.value for _m in evaluate_pair(path_c, path_gt, _config).metrics]
+
+
+@pytest.mark.parametrize("data_format", SYNTH_FORMATS)
+@pytest.mark.parametrize("n_errors", [0, 1, 7, 30])
+def test_synthetic_error_rates(tmp_path, data_format, n_errors):
+    """Each error costs exactly one char and one word"""
+
+    # arrange
+    rng = random.Random(SYNTH_SEED)
+    reference = synthetic_page(rng)
+    candidate = with_errors(reference, n_errors, rng)
+
+    # act
+    (cer, wer) = _evaluate(tmp_path, reference, candidate,
+                           ['CCA:report=error-rate', 'WWA:report=error-rate'], data_format)
+
+    # assert
+    assert cer == approx(100 * n_errors / reference.n_chars)
+    assert wer == approx(100 * n_errors / len(reference.words))
+
+
+def test_synthetic_reading_order_permuted(tmp_path):
+    """Regions read in different order hurt edit
+    distance but not bag of words"""
+
+    # arrange
+    rng = random.Random(SYNTH_SEED)
+    reference = synthetic_page(rng, n_regions=3)
+    candidate = with_permuted_regions(reference, rng)
+
+    # act
+    (cca, wbow, irrec) = _evaluate(tmp_path, reference, candidate, ['CCA', 'WBoW', 'IRRec'])
+
+    # assert
+    assert cca < 100.0
+    assert wbow == approx(100.0)
+    assert irrec == approx(1.0)
+
+
+def test_synthetic_punctuation_split(tmp_path):
+    """Split punctuation costs substitution and insertion
+    per line unless both texts get tokenized alike"""
+
+    # arrange
+    rng = random.Random(SYNTH_SEED)
+    page = synthetic_page(rng)
+    n_lines = sum(len(_region) for _region in page.regions)
+
+    # act
+    (wwa, wwa_split) = _evaluate(tmp_path, with_punctuation(page), with_punctuation(page, split=True),
+                                 ['WWA', 'WWA:tokenize=split-punct'], 'alto')
+
+    # assert
+    assert wwa == approx(100 - 100 * 2 * n_lines / len(page.words))
+    assert wwa_split == approx(100.0)
+
+
+def test_synthetic_corpus_aggregation(tmp_path):
+    """Mean averages accuracy per page, micro
+    mean pools errors and chars of all pages"""
+
+    # arrange
+    pages = synthetic_corpus(tmp_path, [0, 2, 5, 9])
+    root_c = str(tmp_path / 'media' / 'odem')
+    root_gt = str(tmp_path / 'groundtruth' / 'odem')
+    entries = gather_candidates(root_c)
+    for _entry in entries:
+        _entry.path_g = find_groundtruth(_entry.path_c, root_c, root_gt)
+    evaluator = Evaluator(root_gt)
+    evaluator.metrics = [metric_from_spec('CCA')]
+
+    # act
+    evaluator.eval_all(entries, sequential=True)
+    evaluator.aggregate(by_metrics=[0])
+    evaluator.eval_map()
+
+    # assert
+    result = [_r for _r in evaluator.get_results() if _r.eval_key == 'CCA@odem'][0]
+    accuracies = [100 - 100 * _c.n_errors / _r.n_chars for (_r, _c) in pages]
+    assert result.n_total == len(pages)
+    assert result.mean == approx(sum(accuracies) / len(pages))
+    n_errors = sum(_c.n_errors for (_, _c) in pages)
+    assert result.micro_mean == approx(100 - 100 * n_errors / sum(_r.n_chars for (_r, _) in pages))